@@ -0,0 +1,53 @@
+//! Measures tokenizing and parsing every object of `pdfreference1.0.pdf`.
+//!
+//! Run with `cargo bench --bench tokenizer`.
+use pdf_rs::document::PDFDocument;
+use pdf_rs::error::Result;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+const PATH: &str = "document/pdfreference1.0.pdf";
+const ROUNDS: u32 = 10;
+
+/// Reads every object of the document, returning the number of objects read
+/// and the number of objects that failed to parse.
+fn read_all(document: &PDFDocument) -> (usize, usize) {
+    let mut count = 0;
+    let mut failed = 0;
+    for index in 0..document.get_xref_slice().len() {
+        match document.read_object(index) {
+            Ok(Some(_)) => count += 1,
+            Ok(None) => {}
+            Err(_) => failed += 1,
+        }
+    }
+    (count, failed)
+}
+
+fn bench<F>(name: &str, open: F) -> Result<()>
+where
+    F: Fn() -> Result<PDFDocument>,
+{
+    let mut best = Duration::MAX;
+    let mut counts = (0, 0);
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        let document = open()?;
+        counts = read_all(&document);
+        best = best.min(start.elapsed());
+    }
+    println!(
+        "{:<8} {:>6} objects ({} failed)  best of {} rounds: {:?}",
+        name, counts.0, counts.1, ROUNDS, best
+    );
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let bytes = std::fs::read(PATH)?;
+    bench("file", || PDFDocument::open(PathBuf::from(PATH)))?;
+    bench("memory", || PDFDocument::from_bytes(bytes.clone()))?;
+    #[cfg(feature = "mmap")]
+    bench("mmap", || PDFDocument::open_mmap(PathBuf::from(PATH)))?;
+    Ok(())
+}
@@ -0,0 +1,411 @@
+use crate::annotation::{read_annotations, AnnotationKind};
+use crate::constants::{A, AA, D, DEST, DESTS, F, JS, N, NAMES, NEW_WINDOW, NEXT, S, UF, URI, WIN};
+use crate::document::PDFDocument;
+use crate::error::Result;
+use crate::nametree::find_name;
+use crate::objects::{Dictionary, PDFObject, PDFStrKind, PDFString};
+use crate::pstr::decode_text_string;
+use std::collections::HashSet;
+
+/// An action performed when a link is clicked, a page is opened or the document
+/// is opened, with the actions chained after it by `/Next`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Action {
+    kind: ActionKind,
+    /// The actions performed after this one, in order.
+    next: Vec<Action>,
+}
+
+/// The type of an action, with its target.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ActionKind {
+    /// Goes to a destination in the document.
+    GoTo { destination: Destination },
+    /// Goes to a destination in another PDF file, whose page is an index in that
+    /// file and whose name can't be looked up.
+    GoToR { file: Option<String>, destination: Option<Destination>, new_window: Option<bool> },
+    /// Launches an application or opens a file.
+    Launch { file: Option<String>, new_window: Option<bool> },
+    /// Resolves a uniform resource identifier, usually a web link.
+    Uri { uri: String },
+    /// Executes a predefined viewer action such as `NextPage` or `Print`.
+    Named { name: String },
+    /// Executes a JavaScript script.
+    JavaScript { script: String },
+    /// Another type of action, with its `/S` name.
+    Other { subtype: String },
+}
+
+/// A destination: a page and the view of it to display.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Destination {
+    /// The name the destination was referred to by, for named destinations.
+    name: Option<String>,
+    /// The index of the page, None for pages which aren't in the document and
+    /// for named destinations which aren't defined.
+    page: Option<usize>,
+    view: Option<DestinationView>,
+}
+
+/// How a destination page is displayed. Coordinates are in default user space,
+/// None keeping the current value of the viewer.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DestinationView {
+    /// The top-left corner at `(left, top)`, magnified by `zoom`.
+    Xyz { left: Option<f64>, top: Option<f64>, zoom: Option<f64> },
+    /// The whole page fitting the window.
+    Fit,
+    /// The page width fitting the window, `top` at the top of the window.
+    FitH { top: Option<f64> },
+    /// The page height fitting the window, `left` at the left edge of the window.
+    FitV { left: Option<f64> },
+    /// The rectangle fitting the window.
+    FitR { left: f64, bottom: f64, right: f64, top: f64 },
+    /// The bounding box of the page contents fitting the window.
+    FitB,
+    /// The width of the page contents fitting the window.
+    FitBH { top: Option<f64> },
+    /// The height of the page contents fitting the window.
+    FitBV { left: Option<f64> },
+}
+
+/// A link annotation of a page, with its target.
+#[derive(Clone, Debug)]
+pub struct Link {
+    /// The link annotation object reference, None for direct objects in `/Annots`.
+    obj_ref: Option<(u32, u16)>,
+    /// The rectangle on the page, normalized as `[llx, lly, urx, ury]`.
+    rect: [f64; 4],
+    quad_points: Vec<[(f64, f64); 4]>,
+    action: Option<Action>,
+}
+
+impl Action {
+    /// Returns the type of the action, with its target.
+    pub fn get_kind(&self) -> &ActionKind {
+        &self.kind
+    }
+
+    /// Returns the actions performed after this one, `/Next`.
+    pub fn get_next(&self) -> &[Action] {
+        &self.next
+    }
+
+    /// Returns this action followed by the actions chained after it, depth first.
+    pub fn get_chain(&self) -> Vec<&Action> {
+        let mut chain = vec![self];
+        for next in &self.next {
+            chain.extend(next.get_chain());
+        }
+        chain
+    }
+}
+
+impl Destination {
+    /// Returns the name the destination was referred to by, for named destinations.
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the index of the destination page, starting from 0.
+    ///
+    /// For GoTo actions this is a page of the document, None when the page isn't
+    /// found or a named destination isn't defined. For GoToR actions it is a page
+    /// of the remote file.
+    pub fn get_page(&self) -> Option<usize> {
+        self.page
+    }
+
+    /// Returns how the destination page is displayed, None for named
+    /// destinations which aren't resolved.
+    pub fn get_view(&self) -> Option<&DestinationView> {
+        self.view.as_ref()
+    }
+}
+
+impl Link {
+    /// Returns the link annotation object reference, None for annotations written directly in `/Annots`.
+    pub fn get_obj_ref(&self) -> Option<(u32, u16)> {
+        self.obj_ref
+    }
+
+    /// Returns the rectangle of the link on the page, as `[llx, lly, urx, ury]`.
+    pub fn get_rect(&self) -> [f64; 4] {
+        self.rect
+    }
+
+    /// Returns the quadrilaterals activating the link, empty when the whole rectangle does.
+    pub fn get_quad_points(&self) -> &[[(f64, f64); 4]] {
+        &self.quad_points
+    }
+
+    /// Returns the action of the link, with a `/Dest` given as a GoTo action.
+    ///
+    /// # Returns
+    ///
+    /// The action, or None for links without a target
+    pub fn get_action(&self) -> Option<&Action> {
+        self.action.as_ref()
+    }
+}
+
+/// Reads the link annotations listed by the `/Annots` array of a page.
+pub(crate) fn read_links(document: &PDFDocument, page: &Dictionary) -> Result<Vec<Link>> {
+    let mut links = Vec::new();
+    for annotation in read_annotations(document, page)? {
+        if annotation.get_subtype() != "Link" {
+            continue;
+        }
+        let attrs = annotation.get_attrs();
+        let action = match (attrs.get(A), attrs.get(DEST)) {
+            (Some(action), _) => read_action(document, action, &mut HashSet::new())?,
+            (None, Some(destination)) => read_action_or_destination(document, destination)?,
+            (None, None) => None,
+        };
+        let quad_points = match annotation.get_kind() {
+            AnnotationKind::Link { quad_points } => quad_points.clone(),
+            _ => Vec::new(),
+        };
+        links.push(Link { obj_ref: annotation.get_obj_ref(), rect: annotation.get_rect(), quad_points, action });
+    }
+    Ok(links)
+}
+
+/// Reads the additional actions of a page, an annotation or a form field, `/AA`.
+///
+/// # Returns
+///
+/// The actions keyed by their trigger, such as `O` for opening a page, sorted by trigger
+pub(crate) fn read_additional_actions(document: &PDFDocument, attrs: &Dictionary) -> Result<Vec<(String, Action)>> {
+    let Some(object) = attrs.get(AA) else {
+        return Ok(Vec::new());
+    };
+    let object = document.resolve(object)?;
+    let Some(triggers) = object.as_dict() else {
+        return Ok(Vec::new());
+    };
+    let mut actions = Vec::new();
+    for (trigger, action) in triggers.iter() {
+        if let Some(action) = read_action(document, action, &mut HashSet::new())? {
+            actions.push((trigger.clone(), action));
+        }
+    }
+    actions.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(actions)
+}
+
+/// Reads an action dictionary or, as `/OpenAction` and `/Dest` allow, a destination
+/// which is returned as a GoTo action.
+pub(crate) fn read_action_or_destination(document: &PDFDocument, object: &PDFObject) -> Result<Option<Action>> {
+    if document.resolve(object)?.is_dict() {
+        return read_action(document, object, &mut HashSet::new());
+    }
+    Ok(read_destination(document, object, true)?
+        .map(|destination| Action { kind: ActionKind::GoTo { destination }, next: Vec::new() }))
+}
+
+/// Reads an action dictionary and the actions chained after it.
+///
+/// # Arguments
+///
+/// * `document` - The document the action belongs to
+/// * `object` - The action dictionary or a reference to it
+/// * `visited` - The action objects already read, so that cyclic chains end
+///
+/// # Returns
+///
+/// The action, or None if the object isn't a dictionary or was already read
+fn read_action(document: &PDFDocument, object: &PDFObject, visited: &mut HashSet<(u32, u16)>) -> Result<Option<Action>> {
+    if let Some(obj_ref) = object.as_object_ref() && !visited.insert(obj_ref) {
+        return Ok(None);
+    }
+    let object = document.resolve(object)?;
+    let Some(attrs) = object.as_dict() else {
+        return Ok(None);
+    };
+    let new_window = match attrs.get(NEW_WINDOW) {
+        Some(object) => document.resolve(object)?.as_bool(),
+        None => None,
+    };
+    let subtype = match attrs.get(S) {
+        Some(object) => document.resolve(object)?.as_name().cloned().unwrap_or_default(),
+        None => String::new(),
+    };
+    let kind = match subtype.as_str() {
+        "GoTo" => {
+            let destination = match attrs.get(D) {
+                Some(object) => read_destination(document, object, true)?,
+                None => None,
+            };
+            match destination {
+                Some(destination) => ActionKind::GoTo { destination },
+                None => ActionKind::Other { subtype },
+            }
+        }
+        "GoToR" => {
+            let destination = match attrs.get(D) {
+                Some(object) => read_destination(document, object, false)?,
+                None => None,
+            };
+            ActionKind::GoToR { file: read_file_name(document, attrs.get(F))?, destination, new_window }
+        }
+        "Launch" => {
+            let mut file = read_file_name(document, attrs.get(F))?;
+            // The Windows launch parameters name the file when /F is missing
+            if file.is_none() && let Some(win) = attrs.get(WIN) {
+                let win = document.resolve(win)?;
+                file = read_file_name(document, win.as_dict().and_then(|win| win.get(F)))?;
+            }
+            ActionKind::Launch { file, new_window }
+        }
+        "URI" => {
+            // URIs are 7-bit ASCII byte strings rather than text strings
+            let uri = match attrs.get(URI) {
+                Some(object) => document.resolve(object)?.as_string().map(|uri| String::from_utf8_lossy(uri.get_buf()).into_owned()),
+                None => None,
+            };
+            ActionKind::Uri { uri: uri.unwrap_or_default() }
+        }
+        "Named" => {
+            let name = match attrs.get(N) {
+                Some(object) => document.resolve(object)?.as_name().cloned(),
+                None => None,
+            };
+            ActionKind::Named { name: name.unwrap_or_default() }
+        }
+        "JavaScript" => {
+            let script = match attrs.get(JS) {
+                Some(object) => match document.resolve(object)?.as_ref() {
+                    PDFObject::Stream(stream) => {
+                        decode_text_string(&PDFString::new(PDFStrKind::Literal, stream.get_decoded_buf()?))
+                    }
+                    object => object.as_string().map(decode_text_string).unwrap_or_default(),
+                },
+                None => String::new(),
+            };
+            ActionKind::JavaScript { script }
+        }
+        _ => ActionKind::Other { subtype },
+    };
+    let mut next = Vec::new();
+    if let Some(object) = attrs.get(NEXT) {
+        match document.resolve(object)?.as_ref() {
+            PDFObject::Array(actions) => {
+                for action in actions {
+                    next.extend(read_action(document, action, visited)?);
+                }
+            }
+            _ => next.extend(read_action(document, object, visited)?),
+        }
+    }
+    Ok(Some(Action { kind, next }))
+}
+
+/// Reads a destination: an explicit destination array, or the name of a
+/// destination as a name or a string.
+///
+/// # Arguments
+///
+/// * `document` - The document the destination belongs to
+/// * `object` - The destination
+/// * `local` - Whether the destination is in the document, so that page references
+///   and names are resolved, or in a remote file
+fn read_destination(document: &PDFDocument, object: &PDFObject, local: bool) -> Result<Option<Destination>> {
+    let object = document.resolve(object)?;
+    let name = match object.as_ref() {
+        PDFObject::Array(values) => return Ok(Some(read_explicit_destination(document, values, local)?)),
+        PDFObject::Named(name) => name.as_bytes().to_vec(),
+        PDFObject::String(name) => name.get_buf().clone(),
+        _ => return Ok(None),
+    };
+    if local && let Some(destination) = find_named_destination(document, &name)? {
+        return Ok(Some(destination));
+    }
+    let name = Some(String::from_utf8_lossy(&name).into_owned());
+    Ok(Some(Destination { name, page: None, view: None }))
+}
+
+/// Reads an explicit destination, `[page /XYZ left top zoom]` and the like.
+fn read_explicit_destination(document: &PDFDocument, values: &[PDFObject], local: bool) -> Result<Destination> {
+    let page = match values.first() {
+        Some(PDFObject::ObjectRef(obj_num, gen_num)) if local => document.get_page_index((*obj_num, *gen_num)),
+        Some(object) => object.as_i64().and_then(|page| usize::try_from(page).ok()),
+        None => None,
+    };
+    let mut args = Vec::with_capacity(4);
+    for value in values.iter().skip(2) {
+        args.push(document.resolve(value)?.as_f64());
+    }
+    let arg = |index: usize| args.get(index).copied().flatten();
+    let view = match values.get(1).and_then(PDFObject::as_name).map(String::as_str) {
+        Some("XYZ") => Some(DestinationView::Xyz { left: arg(0), top: arg(1), zoom: arg(2).filter(|zoom| *zoom != 0.0) }),
+        Some("Fit") => Some(DestinationView::Fit),
+        Some("FitH") => Some(DestinationView::FitH { top: arg(0) }),
+        Some("FitV") => Some(DestinationView::FitV { left: arg(0) }),
+        Some("FitR") => match (arg(0), arg(1), arg(2), arg(3)) {
+            (Some(left), Some(bottom), Some(right), Some(top)) => Some(DestinationView::FitR { left, bottom, right, top }),
+            _ => None,
+        },
+        Some("FitB") => Some(DestinationView::FitB),
+        Some("FitBH") => Some(DestinationView::FitBH { top: arg(0) }),
+        Some("FitBV") => Some(DestinationView::FitBV { left: arg(0) }),
+        _ => None,
+    };
+    Ok(Destination { name: None, page, view })
+}
+
+/// Looks up a named destination, in the `/Dests` name tree of the name dictionary
+/// or in the `/Dests` dictionary of the catalog used before PDF 1.2.
+///
+/// The value of a named destination is an explicit destination or a dictionary
+/// holding it in `/D`.
+pub(crate) fn find_named_destination(document: &PDFDocument, name: &[u8]) -> Result<Option<Destination>> {
+    let catalog = document.get_catalog();
+    let mut value = None;
+    if let Some(names) = catalog.get(NAMES)
+        && let Some(tree) = document.resolve(names)?.as_dict().and_then(|names| names.get(DESTS))
+    {
+        value = find_name(document, tree, name)?;
+    }
+    if value.is_none() && let Some(dests) = catalog.get(DESTS) {
+        let dests = document.resolve(dests)?;
+        if let Some(object) = dests.as_dict().and_then(|dests| dests.get(&String::from_utf8_lossy(name))) {
+            value = Some(document.resolve(object)?.into_owned());
+        }
+    }
+    let values = match value {
+        Some(PDFObject::Dict(attrs)) => match attrs.get(D) {
+            Some(object) => document.resolve(object)?.into_owned(),
+            None => return Ok(None),
+        },
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let Some(values) = values.as_array() else {
+        return Ok(None);
+    };
+    let mut destination = read_explicit_destination(document, values, true)?;
+    destination.name = Some(String::from_utf8_lossy(name).into_owned());
+    Ok(Some(destination))
+}
+
+/// Reads the name of the file of a file specification, a string or a dictionary
+/// whose `/UF` is preferred to `/F`.
+pub(crate) fn read_file_name(document: &PDFDocument, object: Option<&PDFObject>) -> Result<Option<String>> {
+    let Some(object) = object else {
+        return Ok(None);
+    };
+    let object = document.resolve(object)?;
+    if let Some(name) = object.as_string() {
+        return Ok(Some(decode_text_string(name)));
+    }
+    let Some(attrs) = object.as_dict() else {
+        return Ok(None);
+    };
+    for key in [UF, F] {
+        if let Some(name) = attrs.get(key) && let Some(name) = document.resolve(name)?.as_string() {
+            return Ok(Some(decode_text_string(name)));
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,119 @@
+use crate::font::strip_subset_tag;
+
+/// Metrics of one of the standard 14 fonts, from its AFM file.
+#[derive(Debug)]
+pub(crate) struct StandardFontMetrics {
+    name: &'static str,
+    /// The height above and below the baseline, in glyph space units.
+    ascent: f64,
+    descent: f64,
+    /// The code in the built-in encoding (-1 for unencoded glyphs), width and name of each glyph.
+    glyphs: &'static [(i16, u16, &'static str)],
+}
+
+include!("../afm/Courier");
+include!("../afm/Courier-Bold");
+include!("../afm/Courier-Oblique");
+include!("../afm/Courier-BoldOblique");
+include!("../afm/Helvetica");
+include!("../afm/Helvetica-Bold");
+include!("../afm/Helvetica-Oblique");
+include!("../afm/Helvetica-BoldOblique");
+include!("../afm/Times-Roman");
+include!("../afm/Times-Bold");
+include!("../afm/Times-Italic");
+include!("../afm/Times-BoldItalic");
+include!("../afm/Symbol");
+include!("../afm/ZapfDingbats");
+
+const STANDARD_FONTS: [&StandardFontMetrics; 14] = [
+    &COURIER,
+    &COURIER_BOLD,
+    &COURIER_OBLIQUE,
+    &COURIER_BOLDOBLIQUE,
+    &HELVETICA,
+    &HELVETICA_BOLD,
+    &HELVETICA_OBLIQUE,
+    &HELVETICA_BOLDOBLIQUE,
+    &TIMES_ROMAN,
+    &TIMES_BOLD,
+    &TIMES_ITALIC,
+    &TIMES_BOLDITALIC,
+    &SYMBOL,
+    &ZAPFDINGBATS,
+];
+
+impl StandardFontMetrics {
+    /// Finds the standard font a `/BaseFont` name refers to.
+    ///
+    /// Subset prefixes are ignored, and the alternative names of the standard
+    /// fonts, such as `Arial,Bold` or `TimesNewRomanPSMT`, are recognized.
+    ///
+    /// # Returns
+    ///
+    /// The metrics of the font, or None if the name isn't one of a standard font
+    pub(crate) fn find(base_font: &str) -> Option<&'static StandardFontMetrics> {
+        let name = strip_subset_tag(base_font).unwrap_or(base_font);
+        let (family, style) = match name.find([',', '-']) {
+            Some(index) => (&name[..index], &name[index + 1..]),
+            None => (name, ""),
+        };
+        let bold = style.contains("Bold");
+        let italic = style.contains("Italic") || style.contains("Oblique");
+        let name = match family {
+            "Courier" | "CourierNew" | "CourierNewPSMT" | "CourierNewPS" => match (bold, italic) {
+                (false, false) => "Courier",
+                (true, false) => "Courier-Bold",
+                (false, true) => "Courier-Oblique",
+                (true, true) => "Courier-BoldOblique",
+            },
+            "Helvetica" | "Arial" | "ArialMT" => match (bold, italic) {
+                (false, false) => "Helvetica",
+                (true, false) => "Helvetica-Bold",
+                (false, true) => "Helvetica-Oblique",
+                (true, true) => "Helvetica-BoldOblique",
+            },
+            "Times" | "TimesNewRoman" | "TimesNewRomanPSMT" | "TimesNewRomanPS" => match (bold, italic) {
+                (false, false) => "Times-Roman",
+                (true, false) => "Times-Bold",
+                (false, true) => "Times-Italic",
+                (true, true) => "Times-BoldItalic",
+            },
+            "Symbol" => "Symbol",
+            "ZapfDingbats" => "ZapfDingbats",
+            _ => return None,
+        };
+        STANDARD_FONTS.iter().find(|font| font.name == name).copied()
+    }
+
+    /// Returns the name of the font.
+    pub(crate) fn get_name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns true for Symbol and ZapfDingbats, whose built-in encoding is
+    /// specific to the font.
+    pub(crate) fn is_symbolic(&self) -> bool {
+        matches!(self.name, "Symbol" | "ZapfDingbats")
+    }
+
+    /// Returns the height of the glyphs above the baseline, in glyph space units.
+    pub(crate) fn get_ascent(&self) -> f64 {
+        self.ascent
+    }
+
+    /// Returns the depth of the glyphs below the baseline, in glyph space units.
+    pub(crate) fn get_descent(&self) -> f64 {
+        self.descent
+    }
+
+    /// Gets the width of a glyph in glyph space units.
+    pub(crate) fn get_width(&self, glyph_name: &str) -> Option<f64> {
+        self.glyphs.iter().find(|glyph| glyph.2 == glyph_name).map(|glyph| glyph.1 as f64)
+    }
+
+    /// Gets the glyph name the built-in encoding of the font maps a code to.
+    pub(crate) fn get_glyph_name(&self, code: u8) -> Option<&'static str> {
+        self.glyphs.iter().find(|glyph| glyph.0 == code as i16).map(|glyph| glyph.2)
+    }
+}
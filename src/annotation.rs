@@ -0,0 +1,286 @@
+use crate::colorspace::ColorSpace;
+use crate::constants::{ANNOTS, C, CONTENTS, DA, F, IC, INK_LIST, IRT, M, NAME, NM, OPEN, PARENT, POPUP, QUAD_POINTS, RECT, SUBJ, SUBTYPE, T};
+use crate::document::PDFDocument;
+use crate::error::Result;
+use crate::graphics::Color;
+use crate::objects::{Dictionary, PDFObject};
+use crate::pstr::decode_text_string;
+use std::rc::Rc;
+
+/// An annotation of a page, such as a link, a comment or a form field widget.
+#[derive(Clone, Debug)]
+pub struct Annotation {
+    /// The annotation object reference, None for direct objects in `/Annots`.
+    obj_ref: Option<(u32, u16)>,
+    subtype: String,
+    kind: AnnotationKind,
+    /// The rectangle on the page, normalized as `[llx, lly, urx, ury]`.
+    rect: [f64; 4],
+    contents: Option<String>,
+    /// The unique name, `/NM`.
+    name: Option<String>,
+    modified: Option<String>,
+    color: Option<Color>,
+    flags: u32,
+    /// The text label of markup annotations, usually the author.
+    author: Option<String>,
+    subject: Option<String>,
+    /// The annotation this markup annotation replies to.
+    in_reply_to: Option<(u32, u16)>,
+    /// The pop-up annotation showing the text of this markup annotation.
+    popup: Option<(u32, u16)>,
+    attrs: Dictionary,
+}
+
+/// The type of an annotation, with the entries specific to it.
+///
+/// Quadrilaterals are four points in default user space, in the order of
+/// `/QuadPoints`: usually the lower-left, lower-right, upper-right and
+/// upper-left corners of the text they cover.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AnnotationKind {
+    /// A hypertext link, with the regions activating it.
+    Link { quad_points: Vec<[(f64, f64); 4]> },
+    /// A sticky note, with whether it is initially open and its icon name.
+    Text { open: bool, icon: Option<String> },
+    /// Text displayed directly on the page, with its default appearance string.
+    FreeText { default_appearance: Option<String> },
+    /// Highlighted text.
+    Highlight { quad_points: Vec<[(f64, f64); 4]> },
+    /// Underlined text.
+    Underline { quad_points: Vec<[(f64, f64); 4]> },
+    /// Struck out text.
+    StrikeOut { quad_points: Vec<[(f64, f64); 4]> },
+    /// Text underlined with a jagged line.
+    Squiggly { quad_points: Vec<[(f64, f64); 4]> },
+    /// Freehand scribbles, each path a list of points.
+    Ink { ink_list: Vec<Vec<(f64, f64)>> },
+    /// A rectangle, with the colour it is filled with.
+    Square { interior_color: Option<Color> },
+    /// An ellipse, with the colour it is filled with.
+    Circle { interior_color: Option<Color> },
+    /// A rubber stamp, with its icon name.
+    Stamp { icon: Option<String> },
+    /// A file attached to the page, with its icon name.
+    FileAttachment { icon: Option<String> },
+    /// The widget of an interactive form field.
+    Widget,
+    /// A pop-up window showing the text of its parent annotation.
+    Popup { parent: Option<(u32, u16)>, open: bool },
+    /// Another type of annotation, named by [`Annotation::get_subtype`].
+    Other,
+}
+
+impl Annotation {
+    /// Reads an annotation dictionary.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - The document the annotation belongs to
+    /// * `obj_ref` - The annotation object reference, None for direct objects
+    /// * `attrs` - The annotation dictionary
+    pub(crate) fn new(document: &PDFDocument, obj_ref: Option<(u32, u16)>, attrs: Dictionary) -> Result<Self> {
+        let text = |key: &str| -> Result<Option<String>> {
+            match attrs.get(key) {
+                Some(object) => Ok(document.resolve(object)?.as_string().map(decode_text_string)),
+                None => Ok(None),
+            }
+        };
+        let subtype = match attrs.get(SUBTYPE) {
+            Some(object) => document.resolve(object)?.as_name().cloned().unwrap_or_default(),
+            None => String::new(),
+        };
+        let rect = match get_numbers(document, &attrs, RECT)?.as_slice() {
+            [x0, y0, x1, y1, ..] => [x0.min(*x1), y0.min(*y1), x0.max(*x1), y0.max(*y1)],
+            _ => [0.0; 4],
+        };
+        let flags = match attrs.get(F) {
+            Some(object) => document.resolve(object)?.as_i64().unwrap_or(0) as u32,
+            None => 0,
+        };
+        let name = |key: &str| -> Result<Option<String>> {
+            match attrs.get(key) {
+                Some(object) => Ok(document.resolve(object)?.as_name().cloned()),
+                None => Ok(None),
+            }
+        };
+        let open = match attrs.get(OPEN) {
+            Some(object) => document.resolve(object)?.as_bool().unwrap_or(false),
+            None => false,
+        };
+        let quad_points = || -> Result<Vec<[(f64, f64); 4]>> {
+            let values = get_numbers(document, &attrs, QUAD_POINTS)?;
+            Ok(values.chunks_exact(8)
+                .map(|quad| [(quad[0], quad[1]), (quad[2], quad[3]), (quad[4], quad[5]), (quad[6], quad[7])])
+                .collect())
+        };
+        let kind = match subtype.as_str() {
+            "Link" => AnnotationKind::Link { quad_points: quad_points()? },
+            "Text" => AnnotationKind::Text { open, icon: name(NAME)? },
+            "FreeText" => AnnotationKind::FreeText { default_appearance: text(DA)? },
+            "Highlight" => AnnotationKind::Highlight { quad_points: quad_points()? },
+            "Underline" => AnnotationKind::Underline { quad_points: quad_points()? },
+            "StrikeOut" => AnnotationKind::StrikeOut { quad_points: quad_points()? },
+            "Squiggly" => AnnotationKind::Squiggly { quad_points: quad_points()? },
+            "Ink" => AnnotationKind::Ink { ink_list: get_ink_list(document, &attrs)? },
+            "Square" => AnnotationKind::Square { interior_color: get_color(document, &attrs, IC)? },
+            "Circle" => AnnotationKind::Circle { interior_color: get_color(document, &attrs, IC)? },
+            "Stamp" => AnnotationKind::Stamp { icon: name(NAME)? },
+            "FileAttachment" => AnnotationKind::FileAttachment { icon: name(NAME)? },
+            "Widget" => AnnotationKind::Widget,
+            "Popup" => AnnotationKind::Popup { parent: attrs.get(PARENT).and_then(PDFObject::as_object_ref), open },
+            _ => AnnotationKind::Other,
+        };
+        // Only markup annotations have an author, the /T of widgets is a field name
+        let markup = !matches!(kind, AnnotationKind::Link { .. } | AnnotationKind::Widget | AnnotationKind::Popup { .. })
+            && !matches!(subtype.as_str(), "Movie" | "Screen" | "PrinterMark" | "TrapNet" | "Watermark" | "3D");
+        Ok(Annotation {
+            obj_ref,
+            contents: text(CONTENTS)?,
+            name: text(NM)?,
+            modified: text(M)?,
+            color: get_color(document, &attrs, C)?,
+            author: if markup { text(T)? } else { None },
+            subject: if markup { text(SUBJ)? } else { None },
+            in_reply_to: attrs.get(IRT).and_then(PDFObject::as_object_ref),
+            popup: attrs.get(POPUP).and_then(PDFObject::as_object_ref),
+            subtype,
+            kind,
+            rect,
+            flags,
+            attrs,
+        })
+    }
+
+    /// Returns the annotation object reference, None for annotations written directly in `/Annots`.
+    pub fn get_obj_ref(&self) -> Option<(u32, u16)> {
+        self.obj_ref
+    }
+
+    /// Returns the `/Subtype` of the annotation, such as `Link`, `Text` or `Widget`.
+    pub fn get_subtype(&self) -> &str {
+        &self.subtype
+    }
+
+    /// Returns the type of the annotation, with the entries specific to it.
+    pub fn get_kind(&self) -> &AnnotationKind {
+        &self.kind
+    }
+
+    /// Returns the rectangle of the annotation on the page, as `[llx, lly, urx, ury]`.
+    pub fn get_rect(&self) -> [f64; 4] {
+        self.rect
+    }
+
+    /// Returns the text of the annotation, or its alternate description for
+    /// annotations which don't display text.
+    pub fn get_contents(&self) -> Option<&str> {
+        self.contents.as_deref()
+    }
+
+    /// Returns the name identifying the annotation on its page, `/NM`.
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the date the annotation was last modified, usually in the PDF
+    /// date format `D:YYYYMMDDHHmmSSOHH'mm`.
+    pub fn get_mod_date(&self) -> Option<&str> {
+        self.modified.as_deref()
+    }
+
+    /// Returns the colour of the annotation: the background of its icon, the
+    /// title bar of its pop-up window or the border of a link.
+    pub fn get_color(&self) -> Option<&Color> {
+        self.color.as_ref()
+    }
+
+    /// Returns the annotation flags: 1 invisible, 2 hidden, 4 print, 8 no zoom,
+    /// 16 no rotate, 32 no view, 64 read only, 128 locked, 256 toggle no view
+    /// and 512 locked contents.
+    pub fn get_flags(&self) -> u32 {
+        self.flags
+    }
+
+    /// Returns the author of a markup annotation, its `/T` text label.
+    pub fn get_author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    /// Returns the subject of a markup annotation.
+    pub fn get_subject(&self) -> Option<&str> {
+        self.subject.as_deref()
+    }
+
+    /// Returns the reference of the annotation this markup annotation replies to.
+    pub fn get_in_reply_to(&self) -> Option<(u32, u16)> {
+        self.in_reply_to
+    }
+
+    /// Returns the reference of the pop-up annotation showing the text of this markup annotation.
+    pub fn get_popup(&self) -> Option<(u32, u16)> {
+        self.popup
+    }
+
+    /// Returns the annotation dictionary.
+    pub fn get_attrs(&self) -> &Dictionary {
+        &self.attrs
+    }
+}
+
+/// Reads the annotations listed by the `/Annots` array of a page.
+///
+/// Entries which aren't dictionaries are skipped.
+pub(crate) fn read_annotations(document: &PDFDocument, page: &Dictionary) -> Result<Vec<Annotation>> {
+    let Some(annots) = page.get(ANNOTS) else {
+        return Ok(Vec::new());
+    };
+    let annots = document.resolve(annots)?;
+    let mut annotations = Vec::new();
+    for entry in annots.as_array().unwrap_or_default() {
+        if let Some(attrs) = document.resolve(entry)?.into_owned().to_dict() {
+            annotations.push(Annotation::new(document, entry.as_object_ref(), attrs)?);
+        }
+    }
+    Ok(annotations)
+}
+
+/// Reads an array of numbers, empty if the entry is missing or isn't an array.
+fn get_numbers(document: &PDFDocument, attrs: &Dictionary, key: &str) -> Result<Vec<f64>> {
+    let Some(object) = attrs.get(key) else {
+        return Ok(Vec::new());
+    };
+    let values = document.resolve(object)?;
+    Ok(values.as_array().unwrap_or_default().iter().filter_map(PDFObject::as_f64).collect())
+}
+
+/// Reads a colour array, its number of components choosing DeviceGray, DeviceRGB or DeviceCMYK.
+///
+/// # Returns
+///
+/// The colour, or None for missing or empty arrays, which stand for transparent
+fn get_color(document: &PDFDocument, attrs: &Dictionary, key: &str) -> Result<Option<Color>> {
+    let components = get_numbers(document, attrs, key)?;
+    let color_space = match components.len() {
+        1 => ColorSpace::DeviceGray,
+        3 => ColorSpace::DeviceRGB,
+        4 => ColorSpace::DeviceCMYK,
+        _ => return Ok(None),
+    };
+    Ok(Some(Color::new(Rc::new(color_space), components, None)))
+}
+
+/// Reads the paths of an ink annotation, `/InkList`.
+fn get_ink_list(document: &PDFDocument, attrs: &Dictionary) -> Result<Vec<Vec<(f64, f64)>>> {
+    let Some(object) = attrs.get(INK_LIST) else {
+        return Ok(Vec::new());
+    };
+    let paths = document.resolve(object)?;
+    let mut ink_list = Vec::new();
+    for path in paths.as_array().unwrap_or_default() {
+        let path = document.resolve(path)?;
+        let values = path.as_array().unwrap_or_default().iter().filter_map(PDFObject::as_f64).collect::<Vec<_>>();
+        ink_list.push(values.chunks_exact(2).map(|point| (point[0], point[1])).collect());
+    }
+    Ok(ink_list)
+}
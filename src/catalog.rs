@@ -1,4 +1,4 @@
-use crate::constants::{COUNT, FIRST, KIDS, LAST, NEXT, OUTLINES, PAGES, PREV, TYPE};
+use crate::constants::{COUNT, CROP_BOX, FIRST, KIDS, LAST, MEDIA_BOX, NEXT, OUTLINES, PAGES, PREV, RESOURCES, ROTATE, TYPE};
 use crate::error::PDFError::{ObjectAttrMiss, PDFParseError, XrefEntryNotFound};
 use crate::error::Result;
 use crate::objects::{Dictionary, PDFNumber, PDFObject, XEntry};
@@ -14,6 +14,9 @@ macro_rules! mixture_node_id {
     }};
 }
 
+/// Page attributes which are inherited from the ancestors in the page tree.
+const INHERITABLE_ATTRS: [&str; 4] = [RESOURCES, MEDIA_BOX, CROP_BOX, ROTATE];
+
 /// Type alias for node identifiers in the page tree.
 type NodeId = u64;
 
@@ -27,6 +30,8 @@ pub(crate) struct PageTreeArean {
     root_id: NodeId,
     /// A collection of all nodes in the page tree, indexed by their IDs.
     nodes: HashMap<NodeId, PageNode>,
+    /// The IDs of the page leaf nodes in document order.
+    page_ids: Vec<NodeId>,
 }
 
 /// Represents a node in the page tree structure.
@@ -40,6 +45,7 @@ pub(crate) struct PageNode {
     /// The count of pages or child nodes under this node.
     /// For leaf nodes, this is 0. For intermediate nodes, this is the total
     /// number of leaf nodes under this node.
+    #[allow(dead_code)]
     count: usize,
     /// Optional list of child node IDs for intermediate nodes.
     /// This is None for leaf nodes (actual pages).
@@ -53,6 +59,7 @@ pub(crate) struct PageNode {
 ///
 /// The outline provides a hierarchical navigation structure for the document,
 /// typically displayed in the PDF viewer's sidebar.
+#[allow(dead_code)]
 pub(crate) struct OutlineTreeArean {
     /// The ID of the root node in the outline tree.
     root_id: NodeId,
@@ -63,6 +70,7 @@ pub(crate) struct OutlineTreeArean {
 /// Represents a node in the outline (bookmark) tree.
 ///
 /// Each outline node corresponds to a bookmark entry in the PDF document.
+#[allow(dead_code)]
 pub(crate) struct OutlineNode {
     count: i64,
     /// The title of the bookmark.
@@ -92,15 +100,15 @@ pub(crate) struct OutlineNode {
 ///
 /// # Returns
 ///
-/// A `Result` containing a tuple with the constructed `PageTreeArean` and an optional `Outline`,
-/// or an error if the page catalog cannot be found
+/// A `Result` containing a tuple with the catalog dictionary, the constructed `PageTreeArean`
+/// and an optional `Outline`, or an error if the page catalog cannot be found
 pub(crate) fn decode_catalog_data(
     tokenizer: &mut Tokenizer,
     catalog: (u32, u16),
     xrefs: &[XEntry],
-) -> Result<(PageTreeArean, Option<OutlineTreeArean>)> {
+) -> Result<(Dictionary, PageTreeArean, Option<OutlineTreeArean>)> {
     let entry = xrefs_search(xrefs, catalog)?;
-    let obj = parse_with_offset(tokenizer, entry.value)?;
+    let obj = parse_with_offset(tokenizer, entry.value, xrefs)?;
     let catalog_attr = match obj {
         PDFObject::IndirectObject(_, _, value) => value.to_dict(),
         _ => return Err(ObjectAttrMiss("PDF catalog not found.")),
@@ -125,7 +133,7 @@ pub(crate) fn decode_catalog_data(
                 build_outline_tree(tokenizer, xrefs, obj_num, gen_num, None, &mut map)?;
                 outline = Some(OutlineTreeArean::new(mixture_node_id!(obj_num, gen_num), map));
             }
-            Ok((page_tree_arean, outline))
+            Ok((dict, page_tree_arean, outline))
         }
         _ => Err(ObjectAttrMiss("Catalog attribute not found or not a dict.")),
     }
@@ -156,7 +164,7 @@ fn build_page_tree(
     nodes: &mut HashMap<NodeId, PageNode>,
 ) -> Result<()> {
     let entry = xrefs_search(xrefs, obj_ref)?;
-    let obj = match parse_with_offset(tokenizer, entry.value)? {
+    let obj = match parse_with_offset(tokenizer, entry.value, xrefs)? {
         PDFObject::IndirectObject(_, _, value) => *value,
         _ => return Err(XrefEntryNotFound(obj_ref.0, obj_ref.1)),
     };
@@ -220,7 +228,7 @@ fn build_outline_tree(
     map: &mut HashMap<NodeId, OutlineNode>,
 ) -> Result<()> {
     let entry = xrefs_search(xrefs, (obj_num, gen_num))?;
-    let object = parse_with_offset(tokenizer, entry.value)?;
+    let object = parse_with_offset(tokenizer, entry.value, xrefs)?;
     let (_, _, attrs) = match object.as_indirect_object() {
         Some((obj_num, gen_num, obj)) => match obj.as_dict() {
             Some(dict) => (obj_num, gen_num, dict),
@@ -280,7 +288,9 @@ impl PageTreeArean {
     ///
     /// A new `PageTreeArean` instance
     pub(crate) fn new(root_id: NodeId, nodes: HashMap<NodeId, PageNode>) -> Self {
-        Self { nodes, root_id }
+        let mut page_ids = Vec::new();
+        collect_page_ids(&nodes, root_id, &mut page_ids);
+        Self { nodes, root_id, page_ids }
     }
 
     /// Returns a reference to the root node of the page tree.
@@ -288,6 +298,7 @@ impl PageTreeArean {
     /// # Returns
     ///
     /// A reference to the root `PageNode`
+    #[allow(dead_code)]
     pub fn get_root_node(&self) -> Option<&PageNode> {
         self.nodes.get(&self.root_id)
     }
@@ -301,7 +312,64 @@ impl PageTreeArean {
     ///
     /// The total number of pages in the document
     pub(crate) fn get_page_num(&self) -> usize {
-        self.nodes.values().filter(|node| node.count == 0).count()
+        self.page_ids.len()
+    }
+
+    /// Gets the index of the page with the given object reference.
+    ///
+    /// # Returns
+    ///
+    /// The index of the page in document order, or None if the object isn't a page
+    pub(crate) fn get_page_index(&self, obj_ref: (u32, u16)) -> Option<usize> {
+        let node_id = mixture_node_id!(obj_ref.0, obj_ref.1);
+        self.page_ids.iter().position(|id| *id == node_id)
+    }
+
+    /// Gets the object reference and attributes of the page at `index`.
+    ///
+    /// Attributes which a page inherits from its ancestors (`Resources`, `MediaBox`,
+    /// `CropBox` and `Rotate`) are copied into the returned dictionary when the page
+    /// doesn't define them itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the page in document order
+    ///
+    /// # Returns
+    ///
+    /// The page object reference and attributes, or None if the index is out of bounds
+    pub(crate) fn get_page_attrs(&self, index: usize) -> Option<((u32, u16), Dictionary)> {
+        let node_id = *self.page_ids.get(index)?;
+        let node = self.nodes.get(&node_id)?;
+        let mut attrs = node.attrs.clone();
+        for key in INHERITABLE_ATTRS {
+            let mut parent_id = node.parent_id;
+            while !attrs.contain(key) && let Some(parent) = parent_id.and_then(|id| self.nodes.get(&id)) {
+                if let Some(value) = parent.attrs.get(key) {
+                    attrs.insert(key, value.clone());
+                }
+                parent_id = parent.parent_id;
+            }
+        }
+        let obj_ref = ((node_id >> 16) as u32, node_id as u16);
+        Some((obj_ref, attrs))
+    }
+}
+
+/// Collects the page leaf nodes below `node_id` in document order.
+fn collect_page_ids(nodes: &HashMap<NodeId, PageNode>, node_id: NodeId, page_ids: &mut Vec<NodeId>) {
+    let node = match nodes.get(&node_id) {
+        Some(node) => node,
+        None => return,
+    };
+    match &node.kids {
+        Some(kids) => {
+            for kid in kids {
+                collect_page_ids(nodes, *kid, page_ids);
+            }
+        }
+        None if !node.attrs.named_value_was(TYPE, PAGES) => page_ids.push(node_id),
+        None => {}
     }
 }
 
@@ -0,0 +1,487 @@
+use crate::content::ContentParser;
+use crate::encoding::glyph_name_to_unicode;
+use crate::objects::PDFObject;
+use std::collections::BTreeMap;
+#[cfg(feature = "cjk")]
+use std::sync::OnceLock;
+
+/// The maximum number of bytes of a character code.
+const MAX_CODE_LENGTH: usize = 4;
+
+/// The predefined CMaps of the Adobe-GB1, Adobe-CNS1, Adobe-Japan1 and Adobe-Korea1
+/// character collections, and their CID-to-Unicode CMaps, sorted by name.
+#[cfg(feature = "cjk")]
+static PREDEFINED_CMAPS: [(&str, &str); 63] = [
+    ("83pv-RKSJ-H", include_str!("../cmap/83pv-RKSJ-H")),
+    ("90ms-RKSJ-H", include_str!("../cmap/90ms-RKSJ-H")),
+    ("90ms-RKSJ-V", include_str!("../cmap/90ms-RKSJ-V")),
+    ("90msp-RKSJ-H", include_str!("../cmap/90msp-RKSJ-H")),
+    ("90msp-RKSJ-V", include_str!("../cmap/90msp-RKSJ-V")),
+    ("90pv-RKSJ-H", include_str!("../cmap/90pv-RKSJ-H")),
+    ("Add-RKSJ-H", include_str!("../cmap/Add-RKSJ-H")),
+    ("Add-RKSJ-V", include_str!("../cmap/Add-RKSJ-V")),
+    ("Adobe-CNS1-UCS2", include_str!("../cmap/Adobe-CNS1-UCS2")),
+    ("Adobe-GB1-UCS2", include_str!("../cmap/Adobe-GB1-UCS2")),
+    ("Adobe-Japan1-UCS2", include_str!("../cmap/Adobe-Japan1-UCS2")),
+    ("Adobe-Korea1-UCS2", include_str!("../cmap/Adobe-Korea1-UCS2")),
+    ("B5pc-H", include_str!("../cmap/B5pc-H")),
+    ("B5pc-V", include_str!("../cmap/B5pc-V")),
+    ("CNS-EUC-H", include_str!("../cmap/CNS-EUC-H")),
+    ("CNS-EUC-V", include_str!("../cmap/CNS-EUC-V")),
+    ("ETen-B5-H", include_str!("../cmap/ETen-B5-H")),
+    ("ETen-B5-V", include_str!("../cmap/ETen-B5-V")),
+    ("ETenms-B5-H", include_str!("../cmap/ETenms-B5-H")),
+    ("ETenms-B5-V", include_str!("../cmap/ETenms-B5-V")),
+    ("EUC-H", include_str!("../cmap/EUC-H")),
+    ("EUC-V", include_str!("../cmap/EUC-V")),
+    ("Ext-RKSJ-H", include_str!("../cmap/Ext-RKSJ-H")),
+    ("Ext-RKSJ-V", include_str!("../cmap/Ext-RKSJ-V")),
+    ("GB-EUC-H", include_str!("../cmap/GB-EUC-H")),
+    ("GB-EUC-V", include_str!("../cmap/GB-EUC-V")),
+    ("GBK-EUC-H", include_str!("../cmap/GBK-EUC-H")),
+    ("GBK-EUC-V", include_str!("../cmap/GBK-EUC-V")),
+    ("GBK2K-H", include_str!("../cmap/GBK2K-H")),
+    ("GBK2K-V", include_str!("../cmap/GBK2K-V")),
+    ("GBKp-EUC-H", include_str!("../cmap/GBKp-EUC-H")),
+    ("GBKp-EUC-V", include_str!("../cmap/GBKp-EUC-V")),
+    ("GBpc-EUC-H", include_str!("../cmap/GBpc-EUC-H")),
+    ("GBpc-EUC-V", include_str!("../cmap/GBpc-EUC-V")),
+    ("H", include_str!("../cmap/H")),
+    ("HKscs-B5-H", include_str!("../cmap/HKscs-B5-H")),
+    ("HKscs-B5-V", include_str!("../cmap/HKscs-B5-V")),
+    ("KSC-EUC-H", include_str!("../cmap/KSC-EUC-H")),
+    ("KSC-EUC-V", include_str!("../cmap/KSC-EUC-V")),
+    ("KSCms-UHC-H", include_str!("../cmap/KSCms-UHC-H")),
+    ("KSCms-UHC-HW-H", include_str!("../cmap/KSCms-UHC-HW-H")),
+    ("KSCms-UHC-HW-V", include_str!("../cmap/KSCms-UHC-HW-V")),
+    ("KSCms-UHC-V", include_str!("../cmap/KSCms-UHC-V")),
+    ("KSCpc-EUC-H", include_str!("../cmap/KSCpc-EUC-H")),
+    ("UniCNS-UCS2-H", include_str!("../cmap/UniCNS-UCS2-H")),
+    ("UniCNS-UCS2-V", include_str!("../cmap/UniCNS-UCS2-V")),
+    ("UniCNS-UTF16-H", include_str!("../cmap/UniCNS-UTF16-H")),
+    ("UniCNS-UTF16-V", include_str!("../cmap/UniCNS-UTF16-V")),
+    ("UniGB-UCS2-H", include_str!("../cmap/UniGB-UCS2-H")),
+    ("UniGB-UCS2-V", include_str!("../cmap/UniGB-UCS2-V")),
+    ("UniGB-UTF16-H", include_str!("../cmap/UniGB-UTF16-H")),
+    ("UniGB-UTF16-V", include_str!("../cmap/UniGB-UTF16-V")),
+    ("UniJIS-UCS2-H", include_str!("../cmap/UniJIS-UCS2-H")),
+    ("UniJIS-UCS2-HW-H", include_str!("../cmap/UniJIS-UCS2-HW-H")),
+    ("UniJIS-UCS2-HW-V", include_str!("../cmap/UniJIS-UCS2-HW-V")),
+    ("UniJIS-UCS2-V", include_str!("../cmap/UniJIS-UCS2-V")),
+    ("UniJIS-UTF16-H", include_str!("../cmap/UniJIS-UTF16-H")),
+    ("UniJIS-UTF16-V", include_str!("../cmap/UniJIS-UTF16-V")),
+    ("UniKS-UCS2-H", include_str!("../cmap/UniKS-UCS2-H")),
+    ("UniKS-UCS2-V", include_str!("../cmap/UniKS-UCS2-V")),
+    ("UniKS-UTF16-H", include_str!("../cmap/UniKS-UTF16-H")),
+    ("UniKS-UTF16-V", include_str!("../cmap/UniKS-UTF16-V")),
+    ("V", include_str!("../cmap/V")),
+];
+
+/// The predefined CMaps parsed so far, in the order of [`PREDEFINED_CMAPS`], each
+/// parsed once however many fonts use it.
+#[cfg(feature = "cjk")]
+static PARSED_CMAPS: [OnceLock<CMap>; PREDEFINED_CMAPS.len()] = [const { OnceLock::new() }; PREDEFINED_CMAPS.len()];
+
+/// A CMap, mapping character codes to CIDs or to Unicode text.
+///
+/// The CMap tells how the bytes of a string are split into codes, from its
+/// codespace ranges. The encoding CMap of a composite font maps them to the
+/// CIDs selecting the glyphs, while a `/ToUnicode` CMap maps the codes of any
+/// font to the text they stand for.
+#[derive(Clone, Debug, Default)]
+pub struct CMap {
+    name: Option<String>,
+    /// True for vertical writing.
+    vertical: bool,
+    /// The codespace ranges as the number of bytes and the lowest and highest bytes.
+    codespace: Vec<(usize, [u8; MAX_CODE_LENGTH], [u8; MAX_CODE_LENGTH])>,
+    /// The CID ranges by code length and first code, with the last code and the first CID.
+    cid_ranges: BTreeMap<(usize, u32), (u32, u32)>,
+    /// The ranges of codes mapped to a single `.notdef` CID when they have no CID of their own.
+    notdef_ranges: BTreeMap<(usize, u32), (u32, u32)>,
+    /// The Unicode ranges by code length and first code, with the last code and the text.
+    unicode_ranges: BTreeMap<(usize, u32), (u32, Destination)>,
+    /// True for the Identity CMaps, mapping each two-byte code to the same CID.
+    identity: bool,
+    /// The CMap used by `usecmap`, consulted for the codes this one doesn't map.
+    parent: Option<Box<CMap>>,
+}
+
+/// The Unicode text of a range of codes.
+#[derive(Clone, Debug)]
+enum Destination {
+    /// The UTF-16 code units of the first code, the last of which is incremented through the range.
+    Incrementing(Vec<u16>),
+    /// The text of each code.
+    Text(Vec<Option<String>>),
+}
+
+impl CMap {
+    /// Creates one of the `Identity-H` and `Identity-V` CMaps.
+    pub fn identity(vertical: bool) -> Self {
+        let name = if vertical { "Identity-V" } else { "Identity-H" };
+        CMap {
+            name: Some(name.to_string()),
+            vertical,
+            codespace: vec![(2, [0, 0, 0, 0], [0xFF, 0xFF, 0, 0])],
+            cid_ranges: BTreeMap::new(),
+            notdef_ranges: BTreeMap::new(),
+            unicode_ranges: BTreeMap::new(),
+            identity: true,
+            parent: None,
+        }
+    }
+
+    /// Gets a predefined CMap by name.
+    ///
+    /// The Identity CMaps are always available. The CMaps of the Adobe CJK
+    /// character collections, such as `UniGB-UCS2-H` or `90ms-RKSJ-H`, and their
+    /// CID-to-Unicode CMaps, such as `Adobe-Japan1-UCS2`, require the `cjk` feature.
+    /// They are parsed once, later calls return a copy.
+    ///
+    /// # Returns
+    ///
+    /// The CMap, or None if it isn't known
+    pub fn predefined(name: &str) -> Option<Self> {
+        match name {
+            "Identity-H" => Some(CMap::identity(false)),
+            "Identity-V" => Some(CMap::identity(true)),
+            #[cfg(feature = "cjk")]
+            _ => {
+                let index = PREDEFINED_CMAPS.binary_search_by(|(other, _)| other.cmp(&name)).ok()?;
+                let cmap = PARSED_CMAPS[index].get_or_init(|| CMap::parse(PREDEFINED_CMAPS[index].1.as_bytes().to_vec()));
+                Some(cmap.clone())
+            }
+            #[cfg(not(feature = "cjk"))]
+            _ => None,
+        }
+    }
+
+    /// Parses an embedded CMap stream.
+    ///
+    /// The CMap syntax is close enough to content streams for the content parser
+    /// to split it into operations, each group of mappings ending with its `end`
+    /// keyword. A CMap used by `usecmap` must be predefined; its mappings are
+    /// overridden by the ones of the stream. Parsing stops at the first syntax
+    /// error, keeping the mappings read so far.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The decoded CMap stream data
+    ///
+    /// # Returns
+    ///
+    /// The CMap
+    pub fn parse(data: Vec<u8>) -> Self {
+        let mut cmap = CMap::default();
+        for operation in ContentParser::new(data) {
+            let operation = match operation {
+                Ok(operation) => operation,
+                Err(_) => break,
+            };
+            let operands = operation.get_operands();
+            match operation.get_operator().get_name() {
+                "usecmap" => if let Some(PDFObject::Named(name)) = operands.last()
+                    && let Some(parent) = CMap::predefined(name) {
+                    cmap.use_cmap(parent);
+                },
+                "def" => match operands {
+                    [PDFObject::Named(key), PDFObject::Named(name)] if key == "CMapName" => cmap.name = Some(name.clone()),
+                    [PDFObject::Named(key), mode] if key == "WMode" => cmap.vertical = mode.as_i64() == Some(1),
+                    _ => {}
+                },
+                "endcodespacerange" => for range in operands.chunks_exact(2) {
+                    if let [PDFObject::String(low), PDFObject::String(high)] = range {
+                        cmap.add_codespace_range(low.get_buf(), high.get_buf());
+                    }
+                },
+                "endcidchar" => for mapping in operands.chunks_exact(2) {
+                    if let [PDFObject::String(code), cid] = mapping
+                        && let Some(cid) = cid.as_i64() {
+                        let code = code.get_buf();
+                        cmap.add_cid_range(code.len(), to_code(code), to_code(code), cid as u32);
+                    }
+                },
+                "endcidrange" => for mapping in operands.chunks_exact(3) {
+                    if let [PDFObject::String(low), PDFObject::String(high), cid] = mapping
+                        && let Some(cid) = cid.as_i64() {
+                        let low = low.get_buf();
+                        cmap.add_cid_range(low.len(), to_code(low), to_code(high.get_buf()), cid as u32);
+                    }
+                },
+                "endnotdefchar" => for mapping in operands.chunks_exact(2) {
+                    if let [PDFObject::String(code), cid] = mapping
+                        && let Some(cid) = cid.as_i64() {
+                        let code = code.get_buf();
+                        cmap.add_notdef_range(code.len(), to_code(code), to_code(code), cid as u32);
+                    }
+                },
+                "endnotdefrange" => for mapping in operands.chunks_exact(3) {
+                    if let [PDFObject::String(low), PDFObject::String(high), cid] = mapping
+                        && let Some(cid) = cid.as_i64() {
+                        let low = low.get_buf();
+                        cmap.add_notdef_range(low.len(), to_code(low), to_code(high.get_buf()), cid as u32);
+                    }
+                },
+                "endbfchar" => for mapping in operands.chunks_exact(2) {
+                    if let [PDFObject::String(code), text] = mapping {
+                        let code = code.get_buf();
+                        let text = Destination::Text(vec![destination_text(text)]);
+                        cmap.add_unicode_range(code.len(), to_code(code), to_code(code), text);
+                    }
+                },
+                "endbfrange" => for mapping in operands.chunks_exact(3) {
+                    let (low, high) = match mapping {
+                        [PDFObject::String(low), PDFObject::String(high), _] => (low.get_buf(), high.get_buf()),
+                        _ => continue,
+                    };
+                    let text = match &mapping[2] {
+                        PDFObject::String(text) => Destination::Incrementing(to_utf16(text.get_buf())),
+                        PDFObject::Array(texts) => Destination::Text(texts.iter().map(destination_text).collect()),
+                        _ => continue,
+                    };
+                    cmap.add_unicode_range(low.len(), to_code(low), to_code(high), text);
+                },
+                _ => {}
+            }
+        }
+        cmap
+    }
+
+    /// Returns the name of the CMap.
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns true if the CMap is for vertical writing.
+    pub fn is_vertical(&self) -> bool {
+        self.vertical
+    }
+
+    /// Splits the bytes of a string into character codes, following the codespace ranges.
+    ///
+    /// Bytes not matching any codespace range are consumed as a code of the
+    /// length of the shortest codespace range.
+    ///
+    /// # Returns
+    ///
+    /// Each code together with the number of bytes it was made of
+    pub fn split_codes(&self, bytes: &[u8]) -> Vec<(u32, usize)> {
+        let shortest = self.codespace.iter().map(|range| range.0).min().unwrap_or(1);
+        let mut codes = Vec::new();
+        let mut rest = bytes;
+        while !rest.is_empty() {
+            let length = self.codespace.iter()
+                .filter(|(length, low, high)| {
+                    rest.len() >= *length && (0..*length).all(|i| low[i] <= rest[i] && rest[i] <= high[i])
+                })
+                .map(|range| range.0)
+                .min()
+                .unwrap_or(shortest)
+                .min(rest.len());
+            codes.push((to_code(&rest[..length]), length));
+            rest = &rest[length..];
+        }
+        codes
+    }
+
+    /// Gets the number of bytes of a code, from the codespace range it belongs to.
+    ///
+    /// Codes outside the codespace ranges are given the fewest bytes they fit in.
+    pub fn get_code_length(&self, code: u32) -> usize {
+        self.codespace.iter()
+            .filter(|(length, low, high)| {
+                let bytes = code.to_be_bytes();
+                let bytes = &bytes[MAX_CODE_LENGTH - length..];
+                (*length == MAX_CODE_LENGTH || code >> (8 * length) == 0)
+                    && (0..*length).all(|i| low[i] <= bytes[i] && bytes[i] <= high[i])
+            })
+            .map(|range| range.0)
+            .min()
+            .unwrap_or_else(|| (1..MAX_CODE_LENGTH).find(|length| code >> (8 * length) == 0).unwrap_or(MAX_CODE_LENGTH))
+    }
+
+    /// Gets the CID a code is mapped to.
+    ///
+    /// Codes without a CID of their own may be mapped to a `.notdef` CID by
+    /// `notdefchar` and `notdefrange` mappings.
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - The character code
+    ///
+    /// # Returns
+    ///
+    /// The CID, or None if the code isn't mapped
+    pub fn get_cid(&self, code: u32) -> Option<u32> {
+        let length = self.get_code_length(code);
+        if let Some((&(range_length, first), &(last, cid))) = self.cid_ranges.range(..=(length, code)).next_back()
+            && range_length == length
+            && code <= last {
+            return Some(cid + (code - first));
+        }
+        if let Some(cid) = self.parent.as_ref().and_then(|parent| parent.get_cid(code)) {
+            return Some(cid);
+        }
+        if let Some((&(range_length, _), &(last, cid))) = self.notdef_ranges.range(..=(length, code)).next_back()
+            && range_length == length
+            && code <= last {
+            return Some(cid);
+        }
+        self.identity.then_some(code)
+    }
+
+    /// Gets the Unicode text a code is mapped to by `bfchar` and `bfrange` mappings.
+    ///
+    /// Codes are looked up with the length of their codespace range, or with
+    /// any length when the CMap doesn't declare one for them.
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - The character code
+    ///
+    /// # Returns
+    ///
+    /// The text, or None if the code isn't mapped
+    pub fn get_unicode(&self, code: u32) -> Option<String> {
+        let length = self.get_code_length(code);
+        let found = std::iter::once(length)
+            .chain((1..=MAX_CODE_LENGTH).filter(|other| *other != length))
+            .find_map(|length| {
+                let (&(range_length, first), (last, text)) = self.unicode_ranges.range(..=(length, code)).next_back()?;
+                (range_length == length && code <= *last).then_some((first, text))
+            });
+        let (first, text) = match found {
+            Some(found) => found,
+            None => return self.parent.as_ref().and_then(|parent| parent.get_unicode(code)),
+        };
+        let offset = code - first;
+        match text {
+            Destination::Incrementing(units) => {
+                let mut units = units.clone();
+                if let Some(unit) = units.last_mut() {
+                    *unit = unit.wrapping_add(offset as u16);
+                }
+                Some(String::from_utf16_lossy(&units))
+            }
+            Destination::Text(texts) => texts.get(offset as usize).cloned().flatten(),
+        }
+    }
+
+    /// Returns true if the CMap maps codes to Unicode text.
+    pub fn has_unicode(&self) -> bool {
+        !self.unicode_ranges.is_empty() || self.parent.as_ref().is_some_and(|parent| parent.has_unicode())
+    }
+
+    /// Sets the writing mode from the `/WMode` entry of the CMap stream dictionary.
+    pub(crate) fn set_vertical(&mut self, vertical: bool) {
+        self.vertical = vertical;
+    }
+
+    fn add_codespace_range(&mut self, low: &[u8], high: &[u8]) {
+        let length = low.len();
+        if length == 0 || length > MAX_CODE_LENGTH || high.len() != length {
+            return;
+        }
+        let (mut low_bytes, mut high_bytes) = ([0; MAX_CODE_LENGTH], [0; MAX_CODE_LENGTH]);
+        low_bytes[..length].copy_from_slice(low);
+        high_bytes[..length].copy_from_slice(high);
+        self.codespace.push((length, low_bytes, high_bytes));
+    }
+
+    fn add_cid_range(&mut self, length: usize, first: u32, last: u32, cid: u32) {
+        if first <= last {
+            self.cid_ranges.insert((length, first), (last, cid));
+        }
+    }
+
+    fn add_notdef_range(&mut self, length: usize, first: u32, last: u32, cid: u32) {
+        if first <= last {
+            self.notdef_ranges.insert((length, first), (last, cid));
+        }
+    }
+
+    fn add_unicode_range(&mut self, length: usize, first: u32, last: u32, text: Destination) {
+        if first <= last {
+            self.unicode_ranges.insert((length, first), (last, text));
+        }
+    }
+
+    /// Bases the CMap on another one, inheriting its codespace ranges and the
+    /// mappings of the codes it doesn't map itself.
+    pub(crate) fn use_cmap(&mut self, parent: CMap) {
+        self.codespace.extend(parent.codespace.iter().copied());
+        match &mut self.parent {
+            Some(existing) => existing.use_cmap(parent),
+            None => self.parent = Some(Box::new(parent)),
+        }
+    }
+}
+
+/// Reads a big-endian character code.
+fn to_code(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0u32, |code, b| code << 8 | *b as u32)
+}
+
+/// Splits a UTF-16BE destination string into code units.
+fn to_utf16(bytes: &[u8]) -> Vec<u16> {
+    bytes.chunks(2)
+        .map(|unit| unit.iter().fold(0u16, |unit, b| unit << 8 | *b as u16))
+        .collect()
+}
+
+/// Reads the text of a `bfchar` or `bfrange` destination, a UTF-16BE string
+/// which may hold several characters or surrogate pairs, or a glyph name.
+fn destination_text(object: &PDFObject) -> Option<String> {
+    match object {
+        PDFObject::String(text) => Some(String::from_utf16_lossy(&to_utf16(text.get_buf()))),
+        PDFObject::Named(name) => glyph_name_to_unicode(name),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests `bfchar` and both forms of `bfrange`, with one and two byte source
+    /// codes, ligatures, surrogate pairs and glyph names as destinations.
+    #[test]
+    fn test_to_unicode() {
+        let data = b"/CIDInit /ProcSet findresource begin 12 dict begin begincmap
+            /CMapName /Test-UCS def
+            2 begincodespacerange <00> <7F> <8000> <FFFF> endcodespacerange
+            3 beginbfchar <41> <0061> <8001> <00660069> <8002> <D83DDE00> endbfchar
+            1 beginbfchar <42> /Aacute endbfchar
+            2 beginbfrange <30> <39> <FF10> <9000> <9002> [<0058> <D83DDE01> /eacute] endbfrange
+            1 beginbfrange <80FF> <8101> <00E8> endbfrange
+            endcmap CMapName currentdict /CMap defineresource pop end end";
+        let cmap = CMap::parse(data.to_vec());
+        assert_eq!(cmap.get_name(), Some("Test-UCS"));
+        assert!(cmap.has_unicode());
+        assert_eq!(cmap.split_codes(b"\x41\x80\x01\x42"), [(0x41, 1), (0x8001, 2), (0x42, 1)]);
+        assert_eq!(cmap.get_unicode(0x41).as_deref(), Some("a"));
+        assert_eq!(cmap.get_unicode(0x42).as_deref(), Some("Á"));
+        assert_eq!(cmap.get_unicode(0x8001).as_deref(), Some("fi"));
+        assert_eq!(cmap.get_unicode(0x8002).as_deref(), Some("😀"));
+        assert_eq!(cmap.get_unicode(0x35).as_deref(), Some("５"));
+        assert_eq!(cmap.get_unicode(0x9001).as_deref(), Some("😁"));
+        assert_eq!(cmap.get_unicode(0x9002).as_deref(), Some("é"));
+        // The last code unit is incremented across the range
+        assert_eq!(cmap.get_unicode(0x8101).as_deref(), Some("ê"));
+        assert_eq!(cmap.get_unicode(0x43), None);
+        assert_eq!(cmap.get_unicode(0x9003), None);
+    }
+
+    /// Tests that a CMap based on a predefined one through `usecmap` keeps its
+    /// codespace and mappings, its own mappings taking precedence.
+    #[test]
+    fn test_use_cmap() {
+        let data = b"/Identity-H usecmap
+            1 begincidrange <0100> <01FF> 5000 endcidrange";
+        let cmap = CMap::parse(data.to_vec());
+        assert_eq!(cmap.split_codes(b"\x01\x10\x00\x20"), [(0x0110, 2), (0x20, 2)]);
+        assert_eq!(cmap.get_cid(0x0110), Some(5016));
+        assert_eq!(cmap.get_cid(0x20), Some(0x20));
+    }
+}
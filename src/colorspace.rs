@@ -0,0 +1,519 @@
+use crate::constants::{ALTERNATE, COLOR_SPACE, GAMMA, MATRIX, N, RANGE, WHITE_POINT};
+use crate::document::PDFDocument;
+use crate::error::Result;
+use crate::function::Function;
+use crate::objects::{Dictionary, PDFObject};
+use crate::utils::{read_u16, read_u32};
+
+/// The maximum nesting of colour spaces, guarding against resources naming each other.
+const MAX_COLOR_SPACE_DEPTH: usize = 8;
+
+/// The D50 white point of the ICC profile connection space.
+const D50: [f64; 3] = [0.9642, 1.0, 0.8249];
+
+/// The D65 white point of sRGB.
+const D65: [f64; 3] = [0.9505, 1.0, 1.089];
+
+/// The Bradford cone response matrix and its inverse, for chromatic adaptation.
+const BRADFORD: [f64; 9] = [0.8951, 0.2664, -0.1614, -0.7502, 1.7135, 0.0367, 0.0389, -0.0685, 1.0296];
+const BRADFORD_INVERSE: [f64; 9] = [
+    0.9869929, -0.1470543, 0.1599627, 0.4323053, 0.5183603, 0.0492912, -0.0085287, 0.0400428, 0.9684867,
+];
+
+/// Converts D65 CIE XYZ to linear sRGB.
+const XYZ_TO_SRGB: [f64; 9] = [
+    3.2404542, -1.5371385, -0.4985314, -0.9692660, 1.8760108, 0.0415560, 0.0556434, -0.2040259, 1.0572252,
+];
+
+/// A colour space, with the colour spaces and functions it refers to loaded.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColorSpace {
+    DeviceGray,
+    DeviceRGB,
+    DeviceCMYK,
+    /// A CIE-based gray, with the diffuse white point and the gamma of the gray component.
+    CalGray { white_point: [f64; 3], gamma: f64 },
+    /// A CIE-based RGB, with the gamma of each component and the matrix mapping them to XYZ.
+    CalRGB { white_point: [f64; 3], gamma: [f64; 3], matrix: [f64; 9] },
+    /// CIE L*a*b*, with the ranges of the a* and b* components as `[amin, amax, bmin, bmax]`.
+    Lab { white_point: [f64; 3], range: [f64; 4] },
+    /// An ICC profile with `components` colour components, converted with the
+    /// profile if it is supported and with the alternate colour space otherwise.
+    ICCBased { components: usize, range: Vec<(f64, f64)>, alternate: Box<ColorSpace>, profile: Option<IccProfile> },
+    /// A colour table of `hival + 1` colours in the base colour space, each colour
+    /// taking one byte per component.
+    Indexed { base: Box<ColorSpace>, hival: usize, lookup: Vec<u8> },
+    /// A single colorant, the tint being converted to the alternate colour space by the tint transform.
+    Separation { name: String, alternate: Box<ColorSpace>, tint_transform: Option<Function> },
+    /// Several colorants, their tints being converted to the alternate colour space by the tint transform.
+    DeviceN { names: Vec<String>, alternate: Box<ColorSpace>, tint_transform: Option<Function> },
+    /// Patterns, with the colour space of uncoloured tiling patterns.
+    Pattern { underlying: Option<Box<ColorSpace>> },
+}
+
+/// An ICC profile converting colours to the XYZ profile connection space.
+///
+/// Only gray and RGB matrix/TRC profiles are supported, the ones describing
+/// scanners, cameras and monitors. Other profiles are left to the alternate
+/// colour space.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IccProfile {
+    transform: IccTransform,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum IccTransform {
+    /// The tone curve of the gray component.
+    Gray(Curve),
+    /// The tone curves of the red, green and blue components and the matrix of their XYZ colorants.
+    Rgb([Curve; 3], [f64; 9]),
+}
+
+/// An ICC tone reproduction curve.
+#[derive(Clone, Debug, PartialEq)]
+enum Curve {
+    Identity,
+    Gamma(f64),
+    /// Samples evenly spaced over `[0, 1]`, linearly interpolated.
+    Table(Vec<f64>),
+    /// A parametric curve, with its function type and parameters `[g, a, b, c, d, e, f]`.
+    Parametric(u16, [f64; 7]),
+}
+
+impl ColorSpace {
+    /// Loads a colour space.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - The document used to resolve indirect references
+    /// * `object` - A colour space name or array, or a reference to it
+    /// * `resources` - The resources looking up colour spaces named other than the device ones
+    ///
+    /// # Returns
+    ///
+    /// The colour space, None if it is unknown or malformed
+    pub fn load(document: &PDFDocument, object: &PDFObject, resources: Option<&Dictionary>) -> Result<Option<ColorSpace>> {
+        load_color_space(document, object, resources, 0)
+    }
+
+    /// Returns the name of the colour space family, such as `DeviceRGB` or `Indexed`.
+    pub fn get_family(&self) -> &'static str {
+        match self {
+            ColorSpace::DeviceGray => "DeviceGray",
+            ColorSpace::DeviceRGB => "DeviceRGB",
+            ColorSpace::DeviceCMYK => "DeviceCMYK",
+            ColorSpace::CalGray { .. } => "CalGray",
+            ColorSpace::CalRGB { .. } => "CalRGB",
+            ColorSpace::Lab { .. } => "Lab",
+            ColorSpace::ICCBased { .. } => "ICCBased",
+            ColorSpace::Indexed { .. } => "Indexed",
+            ColorSpace::Separation { .. } => "Separation",
+            ColorSpace::DeviceN { .. } => "DeviceN",
+            ColorSpace::Pattern { .. } => "Pattern",
+        }
+    }
+
+    /// Returns the number of colour components, 0 for coloured patterns.
+    pub fn get_components(&self) -> usize {
+        match self {
+            ColorSpace::DeviceGray | ColorSpace::CalGray { .. } | ColorSpace::Indexed { .. } | ColorSpace::Separation { .. } => 1,
+            ColorSpace::DeviceRGB | ColorSpace::CalRGB { .. } | ColorSpace::Lab { .. } => 3,
+            ColorSpace::DeviceCMYK => 4,
+            ColorSpace::ICCBased { components, .. } => *components,
+            ColorSpace::DeviceN { names, .. } => names.len(),
+            ColorSpace::Pattern { underlying } => underlying.as_ref().map_or(0, |underlying| underlying.get_components()),
+        }
+    }
+
+    /// Returns the interval of each colour component.
+    pub fn get_ranges(&self) -> Vec<(f64, f64)> {
+        match self {
+            ColorSpace::Lab { range, .. } => vec![(0.0, 100.0), (range[0], range[1]), (range[2], range[3])],
+            ColorSpace::ICCBased { range, .. } => range.clone(),
+            ColorSpace::Indexed { hival, .. } => vec![(0.0, *hival as f64)],
+            _ => vec![(0.0, 1.0); self.get_components()],
+        }
+    }
+
+    /// Returns the initial colour set by `CS` or `cs`.
+    ///
+    /// The initial colour is black for the device colour spaces, full tints for
+    /// `Separation` and `DeviceN`, and otherwise 0 clipped to the component ranges.
+    pub fn get_initial_color(&self) -> Vec<f64> {
+        match self {
+            ColorSpace::DeviceCMYK => vec![0.0, 0.0, 0.0, 1.0],
+            ColorSpace::Separation { .. } | ColorSpace::DeviceN { .. } => vec![1.0; self.get_components()],
+            ColorSpace::Pattern { .. } => Vec::new(),
+            _ => self.get_ranges().iter().map(|(min, max)| 0.0f64.clamp(*min, *max)).collect(),
+        }
+    }
+
+    /// Converts colour components to sRGB.
+    ///
+    /// CMYK is converted naively, without a profile, and CIE-based colours are
+    /// adapted from their white point to the D65 white point of sRGB.
+    ///
+    /// # Arguments
+    ///
+    /// * `components` - The colour components, missing components being taken as 0
+    ///
+    /// # Returns
+    ///
+    /// The red, green and blue components, between 0 and 1
+    pub fn to_rgb(&self, components: &[f64]) -> [f64; 3] {
+        let value = |i: usize| components.get(i).copied().unwrap_or(0.0);
+        let rgb = match self {
+            ColorSpace::DeviceGray => [value(0); 3],
+            ColorSpace::DeviceRGB => [value(0), value(1), value(2)],
+            ColorSpace::DeviceCMYK => {
+                let k = 1.0 - value(3);
+                [(1.0 - value(0)) * k, (1.0 - value(1)) * k, (1.0 - value(2)) * k]
+            }
+            ColorSpace::CalGray { white_point, gamma } => {
+                let y = value(0).max(0.0).powf(*gamma);
+                xyz_to_rgb([white_point[0] * y, white_point[1] * y, white_point[2] * y], *white_point)
+            }
+            ColorSpace::CalRGB { white_point, gamma, matrix } => {
+                let [a, b, c] = [0, 1, 2].map(|i| value(i).max(0.0).powf(gamma[i]));
+                // The matrix holds the XYZ of each component, component by component
+                let xyz = [0, 1, 2].map(|i| matrix[i] * a + matrix[3 + i] * b + matrix[6 + i] * c);
+                xyz_to_rgb(xyz, *white_point)
+            }
+            ColorSpace::Lab { white_point, range } => {
+                let l = value(0).clamp(0.0, 100.0);
+                let a = value(1).clamp(range[0], range[1]);
+                let b = value(2).clamp(range[2], range[3]);
+                let m = (l + 16.0) / 116.0;
+                let g = |x: f64| if x >= 6.0 / 29.0 { x * x * x } else { 108.0 / 841.0 * (x - 4.0 / 29.0) };
+                let xyz = [white_point[0] * g(m + a / 500.0), white_point[1] * g(m), white_point[2] * g(m - b / 200.0)];
+                xyz_to_rgb(xyz, *white_point)
+            }
+            ColorSpace::ICCBased { profile: Some(profile), .. } => profile.to_rgb(components),
+            ColorSpace::ICCBased { alternate, .. } => alternate.to_rgb(components),
+            ColorSpace::Indexed { base, hival, lookup } => {
+                let count = base.get_components();
+                let index = (value(0).round().max(0.0) as usize).min(*hival);
+                // Table bytes are scaled to the ranges of the base colour space
+                let color = base.get_ranges().iter().enumerate()
+                    .map(|(i, (min, max))| {
+                        let byte = lookup.get(index * count + i).copied().unwrap_or(0) as f64;
+                        min + byte * (max - min) / 255.0
+                    })
+                    .collect::<Vec<f64>>();
+                base.to_rgb(&color)
+            }
+            ColorSpace::Separation { alternate, tint_transform, .. }
+            | ColorSpace::DeviceN { alternate, tint_transform, .. } => match tint_transform {
+                Some(tint_transform) => alternate.to_rgb(&tint_transform.eval(components)),
+                // Without a usable tint transform, tints are shown as shades of gray
+                None => {
+                    let tint = (0..self.get_components()).map(value).fold(0.0, f64::max);
+                    [1.0 - tint; 3]
+                }
+            },
+            ColorSpace::Pattern { underlying: Some(underlying) } => underlying.to_rgb(components),
+            ColorSpace::Pattern { underlying: None } => [0.0; 3],
+        };
+        rgb.map(|value| if value.is_nan() { 0.0 } else { value.clamp(0.0, 1.0) })
+    }
+
+    /// Converts colour components to 8-bit sRGB.
+    pub fn to_rgb8(&self, components: &[f64]) -> [u8; 3] {
+        self.to_rgb(components).map(|value| (value * 255.0).round() as u8)
+    }
+}
+
+impl IccProfile {
+    /// Parses an ICC profile.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The profile data, the decoded data of an `ICCBased` stream
+    ///
+    /// # Returns
+    ///
+    /// The profile, None if it is malformed or not a gray or RGB matrix/TRC profile
+    pub fn parse(data: &[u8]) -> Option<IccProfile> {
+        if data.get(36..40)? != b"acsp" || data.get(20..24)? != b"XYZ " {
+            return None;
+        }
+        let tag_count = read_u32(data, 128)? as usize;
+        let tag = |signature: &[u8]| -> Option<&[u8]> {
+            (0..tag_count.min(1024)).find_map(|i| {
+                let entry = 132 + i * 12;
+                if data.get(entry..entry + 4)? != signature {
+                    return None;
+                }
+                let offset = read_u32(data, entry + 4)? as usize;
+                let size = read_u32(data, entry + 8)? as usize;
+                data.get(offset..offset.checked_add(size)?)
+            })
+        };
+        let transform = match data.get(16..20)? {
+            b"GRAY" => IccTransform::Gray(Curve::parse(tag(b"kTRC")?)?),
+            b"RGB " => {
+                let curves = [Curve::parse(tag(b"rTRC")?)?, Curve::parse(tag(b"gTRC")?)?, Curve::parse(tag(b"bTRC")?)?];
+                let colorants = [read_xyz(tag(b"rXYZ")?)?, read_xyz(tag(b"gXYZ")?)?, read_xyz(tag(b"bXYZ")?)?];
+                // Colorants are the columns of the matrix
+                let matrix = [0, 1, 2, 3, 4, 5, 6, 7, 8].map(|i| colorants[i % 3][i / 3]);
+                IccTransform::Rgb(curves, matrix)
+            }
+            _ => return None,
+        };
+        Some(IccProfile { transform })
+    }
+
+    /// Returns the number of colour components of the profile.
+    pub fn get_components(&self) -> usize {
+        match self.transform {
+            IccTransform::Gray(_) => 1,
+            IccTransform::Rgb(..) => 3,
+        }
+    }
+
+    /// Converts colour components between 0 and 1 to sRGB.
+    pub fn to_rgb(&self, components: &[f64]) -> [f64; 3] {
+        let value = |i: usize| components.get(i).copied().unwrap_or(0.0).clamp(0.0, 1.0);
+        match &self.transform {
+            // Gray profiles only define the luminance of neutral colours
+            IccTransform::Gray(curve) => [encode_srgb(curve.eval(value(0))); 3],
+            IccTransform::Rgb(curves, matrix) => {
+                let linear = [0, 1, 2].map(|i| curves[i].eval(value(i)));
+                xyz_to_rgb(multiply(matrix, linear), D50)
+            }
+        }
+    }
+}
+
+impl Curve {
+    /// Parses a `curv` or `para` tag.
+    fn parse(tag: &[u8]) -> Option<Curve> {
+        match tag.get(0..4)? {
+            b"curv" => {
+                let count = read_u32(tag, 8)? as usize;
+                match count {
+                    0 => Some(Curve::Identity),
+                    1 => Some(Curve::Gamma(read_u16(tag, 12)? as f64 / 256.0)),
+                    _ => {
+                        let samples = (0..count).map(|i| Some(read_u16(tag, 12 + i * 2)? as f64 / 65535.0));
+                        Some(Curve::Table(samples.collect::<Option<Vec<f64>>>()?))
+                    }
+                }
+            }
+            b"para" => {
+                let function_type = read_u16(tag, 8)?;
+                let count = [1, 3, 4, 5, 7].get(function_type as usize)?;
+                let mut params = [0.0; 7];
+                for (i, param) in params.iter_mut().take(*count).enumerate() {
+                    *param = read_u32(tag, 12 + i * 4)? as i32 as f64 / 65536.0;
+                }
+                Some(Curve::Parametric(function_type, params))
+            }
+            _ => None,
+        }
+    }
+
+    /// Maps a component value between 0 and 1 to its linear value.
+    fn eval(&self, x: f64) -> f64 {
+        match self {
+            Curve::Identity => x,
+            Curve::Gamma(gamma) => x.powf(*gamma),
+            Curve::Table(samples) => {
+                let position = x * (samples.len() - 1) as f64;
+                let i = (position.floor() as usize).min(samples.len() - 2);
+                samples[i] + (position - i as f64) * (samples[i + 1] - samples[i])
+            }
+            Curve::Parametric(function_type, [g, a, b, c, d, e, f]) => {
+                let power = |x: f64| if x > 0.0 { x.powf(*g) } else { 0.0 };
+                match function_type {
+                    0 => power(x),
+                    1 => if x >= -b / a { power(a * x + b) } else { 0.0 },
+                    2 => if x >= -b / a { power(a * x + b) + c } else { *c },
+                    3 => if x >= *d { power(a * x + b) } else { c * x },
+                    _ => if x >= *d { power(a * x + b) + e } else { c * x + f },
+                }
+            }
+        }
+    }
+}
+
+fn load_color_space(
+    document: &PDFDocument,
+    object: &PDFObject,
+    resources: Option<&Dictionary>,
+    depth: usize,
+) -> Result<Option<ColorSpace>> {
+    if depth > MAX_COLOR_SPACE_DEPTH {
+        return Ok(None);
+    }
+    let object = document.resolve(object)?;
+    let (family, params) = match object.as_ref() {
+        PDFObject::Named(name) => (name.as_str(), &[][..]),
+        PDFObject::Array(values) => match values.split_first() {
+            Some((PDFObject::Named(name), params)) => (name.as_str(), params),
+            _ => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+    let load = |object: &PDFObject| load_color_space(document, object, resources, depth + 1);
+    let color_space = match (family, params) {
+        ("DeviceGray" | "G", _) => ColorSpace::DeviceGray,
+        ("DeviceRGB" | "RGB", _) => ColorSpace::DeviceRGB,
+        ("DeviceCMYK" | "CMYK", _) => ColorSpace::DeviceCMYK,
+        ("CalGray", [dict, ..]) => {
+            let dict = resolve_dict(document, dict)?;
+            let white_point = read_array::<3>(document, dict.as_ref(), WHITE_POINT)?.unwrap_or(D65);
+            let gamma = read_array::<1>(document, dict.as_ref(), GAMMA)?.map_or(1.0, |[gamma]| gamma);
+            ColorSpace::CalGray { white_point, gamma }
+        }
+        ("CalRGB", [dict, ..]) => {
+            let dict = resolve_dict(document, dict)?;
+            let white_point = read_array::<3>(document, dict.as_ref(), WHITE_POINT)?.unwrap_or(D65);
+            let gamma = read_array::<3>(document, dict.as_ref(), GAMMA)?.unwrap_or([1.0; 3]);
+            let matrix = read_array::<9>(document, dict.as_ref(), MATRIX)?
+                .unwrap_or([1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0]);
+            ColorSpace::CalRGB { white_point, gamma, matrix }
+        }
+        ("Lab", [dict, ..]) => {
+            let dict = resolve_dict(document, dict)?;
+            let white_point = read_array::<3>(document, dict.as_ref(), WHITE_POINT)?.unwrap_or(D65);
+            let range = read_array::<4>(document, dict.as_ref(), RANGE)?.unwrap_or([-100.0, 100.0, -100.0, 100.0]);
+            ColorSpace::Lab { white_point, range }
+        }
+        ("ICCBased", [stream, ..]) => {
+            let stream = match document.resolve(stream)?.into_owned() {
+                PDFObject::Stream(stream) => stream,
+                _ => return Ok(None),
+            };
+            let dict = stream.get_metadata();
+            let profile = IccProfile::parse(&stream.get_decoded_buf()?);
+            let components = match (dict.get_u64_num(N), &profile) {
+                (Some(n @ 1..=32), _) => n as usize,
+                (_, Some(profile)) => profile.get_components(),
+                _ => return Ok(None),
+            };
+            let alternate = match dict.get(ALTERNATE) {
+                Some(alternate) => load(alternate)?,
+                None => None,
+            };
+            let alternate = match (alternate, components) {
+                (Some(alternate), _) => alternate,
+                (None, 1) => ColorSpace::DeviceGray,
+                (None, 4) => ColorSpace::DeviceCMYK,
+                (None, _) => ColorSpace::DeviceRGB,
+            };
+            let range = match read_numbers(document, Some(dict), RANGE)? {
+                Some(values) if values.len() >= components * 2 => values.chunks_exact(2).take(components).map(|range| (range[0], range[1])).collect(),
+                _ => vec![(0.0, 1.0); components],
+            };
+            // Profiles not matching the number of components are ignored
+            let profile = profile.filter(|profile| profile.get_components() == components);
+            ColorSpace::ICCBased { components, range, alternate: Box::new(alternate), profile }
+        }
+        ("Indexed" | "I", [base, hival, lookup]) => {
+            let base = match load(base)? {
+                Some(base) => base,
+                None => return Ok(None),
+            };
+            let hival = document.resolve(hival)?.as_i64().unwrap_or(0).clamp(0, 255) as usize;
+            let lookup = match document.resolve(lookup)?.as_ref() {
+                PDFObject::String(lookup) => lookup.get_buf().clone(),
+                PDFObject::Stream(lookup) => lookup.get_decoded_buf()?,
+                _ => Vec::new(),
+            };
+            ColorSpace::Indexed { base: Box::new(base), hival, lookup }
+        }
+        ("Separation", [name, alternate, tint_transform, ..]) => {
+            let name = document.resolve(name)?.as_name().cloned().unwrap_or_default();
+            let alternate = match load(alternate)? {
+                Some(alternate) => alternate,
+                None => return Ok(None),
+            };
+            let tint_transform = Function::load(document, tint_transform)?;
+            ColorSpace::Separation { name, alternate: Box::new(alternate), tint_transform }
+        }
+        ("DeviceN", [names, alternate, tint_transform, ..]) => {
+            let names = match document.resolve(names)?.as_array() {
+                Some(names) if !names.is_empty() => names.iter().map(|name| name.as_name().cloned().unwrap_or_default()).collect(),
+                _ => return Ok(None),
+            };
+            let alternate = match load(alternate)? {
+                Some(alternate) => alternate,
+                None => return Ok(None),
+            };
+            let tint_transform = Function::load(document, tint_transform)?;
+            ColorSpace::DeviceN { names, alternate: Box::new(alternate), tint_transform }
+        }
+        ("Pattern", params) => {
+            let underlying = match params.first() {
+                Some(underlying) => load(underlying)?.map(Box::new),
+                None => None,
+            };
+            ColorSpace::Pattern { underlying }
+        }
+        // Other names are looked up in the colour space resources
+        (name, []) => {
+            let color_space = match resources.and_then(|resources| resources.get(COLOR_SPACE)) {
+                Some(color_spaces) => document.resolve(color_spaces)?.as_dict().and_then(|color_spaces| color_spaces.get(name)).cloned(),
+                None => None,
+            };
+            match color_space {
+                Some(color_space) => return load(&color_space),
+                None => return Ok(None),
+            }
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(color_space))
+}
+
+fn resolve_dict(document: &PDFDocument, object: &PDFObject) -> Result<Option<Dictionary>> {
+    Ok(document.resolve(object)?.into_owned().to_dict())
+}
+
+fn read_numbers(document: &PDFDocument, dict: Option<&Dictionary>, key: &str) -> Result<Option<Vec<f64>>> {
+    let values = match dict.and_then(|dict| dict.get(key)) {
+        Some(values) => document.resolve(values)?,
+        None => return Ok(None),
+    };
+    Ok(values.as_array().map(|values| values.iter().map(|value| value.as_f64().unwrap_or(0.0)).collect()))
+}
+
+/// Reads an array of `N` numbers, or a single number for `N = 1`.
+fn read_array<const N: usize>(document: &PDFDocument, dict: Option<&Dictionary>, key: &str) -> Result<Option<[f64; N]>> {
+    if N == 1
+        && let Some(value) = dict.and_then(|dict| dict.get(key))
+        && let Some(value) = document.resolve(value)?.as_f64() {
+        return Ok(Some([value; N]));
+    }
+    Ok(read_numbers(document, dict, key)?.and_then(|values| values.try_into().ok()))
+}
+
+/// Converts CIE XYZ relative to a white point to sRGB, with a Bradford chromatic adaptation to D65.
+fn xyz_to_rgb(xyz: [f64; 3], white_point: [f64; 3]) -> [f64; 3] {
+    let source = multiply(&BRADFORD, white_point);
+    let target = multiply(&BRADFORD, D65);
+    let cone = multiply(&BRADFORD, xyz);
+    let adapted = multiply(&BRADFORD_INVERSE, [0, 1, 2].map(|i| cone[i] * target[i] / source[i]));
+    multiply(&XYZ_TO_SRGB, adapted).map(encode_srgb)
+}
+
+/// Multiplies a 3×3 matrix, given row by row, by a vector.
+fn multiply(matrix: &[f64; 9], vector: [f64; 3]) -> [f64; 3] {
+    [0, 1, 2].map(|row| matrix[row * 3] * vector[0] + matrix[row * 3 + 1] * vector[1] + matrix[row * 3 + 2] * vector[2])
+}
+
+/// Applies the sRGB transfer function to a linear component.
+fn encode_srgb(value: f64) -> f64 {
+    let value = value.clamp(0.0, 1.0);
+    if value <= 0.0031308 { value * 12.92 } else { 1.055 * value.powf(1.0 / 2.4) - 0.055 }
+}
+
+/// Reads the first value of an `XYZ ` tag.
+fn read_xyz(tag: &[u8]) -> Option<[f64; 3]> {
+    if tag.get(0..4)? != b"XYZ " {
+        return None;
+    }
+    let value = |i: usize| Some(read_u32(tag, 8 + i * 4)? as i32 as f64 / 65536.0);
+    Some([value(0)?, value(1)?, value(2)?])
+}
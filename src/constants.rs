@@ -1,7 +1,7 @@
-/// Macro to define PDF key constants and a utility function for key checking.
+/// Macro to define PDF key constants and a utility function for key lookup.
 ///
 /// This macro generates a module containing string constants for common PDF keys
-/// and a function to check if a string is a valid PDF key.
+/// and a function to look up the key matching a string.
 ///
 /// # Arguments
 ///
@@ -16,7 +16,7 @@ macro_rules! pdf_key {
                 pub(crate) const $ident: &str = $value;
             )+
         }
-        /// Checks if a string is a valid PDF key.
+        /// Looks up the PDF key matching a string.
         ///
         /// # Arguments
         ///
@@ -24,13 +24,13 @@ macro_rules! pdf_key {
         ///
         /// # Returns
         ///
-        /// True if the string is a valid PDF key, false otherwise
-        pub(crate) fn is_key(str:&str)->bool{
+        /// The static key constant if the string is a valid PDF key, None otherwise
+        pub(crate) fn as_key(str:&str)->Option<&'static str>{
             match str {
                 $(
-                    $value => true,
+                    $value => Some(pdf_key::$ident),
                 )+
-                _ => false
+                _ => None
             }
         }
     }
@@ -60,11 +60,18 @@ pub(crate) const PREV: &str = "Prev";
 pub(crate) const SIZE: &str = "Size";
 /// Key for document catalog.
 pub(crate) const ROOT: &str = "Root";
+/// Key for file identifiers.
+pub(crate) const ID: &str = "ID";
+/// Key for the encryption dictionary of the trailer.
+pub(crate) const ENCRYPT: &str = "Encrypt";
+/// Key for the cross-reference stream of a hybrid-reference file.
+pub(crate) const XREF_STM: &str = "XRefStm";
 /// Key for count of pages or objects.
 pub(crate) const COUNT: &str = "Count";
 /// Key for pages object type.
 pub(crate) const PAGES: &str = "Pages";
 /// Key for catalog object type.
+#[allow(dead_code)]
 pub(crate) const CATALOG: &str = "Catalog";
 /// Key for outlines.
 pub(crate) const OUTLINES: &str = "Outlines";
@@ -81,4 +88,150 @@ pub(crate) const PRODUCER: &str = "Producer";
 pub(crate) const CREATOR: &str = "Creator";
 pub(crate) const CREATION_DATE: &str = "CreationDate";
 pub(crate) const AUTHOR: &str = "Author";
-pub(crate) const TITLE: &str = "Title";
\ No newline at end of file
+pub(crate) const TITLE: &str = "Title";
+/// Key for stream filters.
+pub(crate) const FILTER: &str = "Filter";
+/// Key for stream filter parameters.
+pub(crate) const DECODE_PARMS: &str = "DecodeParms";
+pub(crate) const PREDICTOR: &str = "Predictor";
+pub(crate) const COLORS: &str = "Colors";
+pub(crate) const BITS_PER_COMPONENT: &str = "BitsPerComponent";
+pub(crate) const COLUMNS: &str = "Columns";
+pub(crate) const EARLY_CHANGE: &str = "EarlyChange";
+/// Key for page resources.
+pub(crate) const RESOURCES: &str = "Resources";
+pub(crate) const MEDIA_BOX: &str = "MediaBox";
+pub(crate) const CROP_BOX: &str = "CropBox";
+pub(crate) const ROTATE: &str = "Rotate";
+/// Key for page content streams.
+pub(crate) const CONTENTS: &str = "Contents";
+pub(crate) const WIDTH: &str = "Width";
+pub(crate) const HEIGHT: &str = "Height";
+pub(crate) const COLOR_SPACE: &str = "ColorSpace";
+pub(crate) const DECODE: &str = "Decode";
+pub(crate) const IMAGE_MASK: &str = "ImageMask";
+pub(crate) const INTERPOLATE: &str = "Interpolate";
+pub(crate) const SMASK: &str = "SMask";
+pub(crate) const MASK: &str = "Mask";
+pub(crate) const N: &str = "N";
+pub(crate) const SUBTYPE: &str = "Subtype";
+pub(crate) const BASE_FONT: &str = "BaseFont";
+pub(crate) const FIRST_CHAR: &str = "FirstChar";
+pub(crate) const WIDTHS: &str = "Widths";
+pub(crate) const FONT_DESCRIPTOR: &str = "FontDescriptor";
+pub(crate) const MISSING_WIDTH: &str = "MissingWidth";
+pub(crate) const ASCENT: &str = "Ascent";
+pub(crate) const DESCENT: &str = "Descent";
+pub(crate) const FONT_NAME: &str = "FontName";
+pub(crate) const FLAGS: &str = "Flags";
+pub(crate) const FONT_BBOX: &str = "FontBBox";
+pub(crate) const ITALIC_ANGLE: &str = "ItalicAngle";
+pub(crate) const CAP_HEIGHT: &str = "CapHeight";
+pub(crate) const X_HEIGHT: &str = "XHeight";
+pub(crate) const STEM_V: &str = "StemV";
+pub(crate) const FONT_MATRIX: &str = "FontMatrix";
+pub(crate) const ENCODING: &str = "Encoding";
+pub(crate) const BASE_ENCODING: &str = "BaseEncoding";
+pub(crate) const DIFFERENCES: &str = "Differences";
+pub(crate) const TO_UNICODE: &str = "ToUnicode";
+pub(crate) const DESCENDANT_FONTS: &str = "DescendantFonts";
+pub(crate) const CID_SYSTEM_INFO: &str = "CIDSystemInfo";
+pub(crate) const REGISTRY: &str = "Registry";
+pub(crate) const ORDERING: &str = "Ordering";
+pub(crate) const SUPPLEMENT: &str = "Supplement";
+pub(crate) const DW: &str = "DW";
+pub(crate) const W: &str = "W";
+pub(crate) const DW2: &str = "DW2";
+pub(crate) const W2: &str = "W2";
+pub(crate) const CID_TO_GID_MAP: &str = "CIDToGIDMap";
+pub(crate) const USE_CMAP: &str = "UseCMap";
+pub(crate) const WMODE: &str = "WMode";
+pub(crate) const CMAP_NAME: &str = "CMapName";
+pub(crate) const FONT_FILE: &str = "FontFile";
+pub(crate) const FONT_FILE2: &str = "FontFile2";
+pub(crate) const FONT_FILE3: &str = "FontFile3";
+pub(crate) const LENGTH1: &str = "Length1";
+pub(crate) const LENGTH2: &str = "Length2";
+/// Keys of the resource dictionary.
+pub(crate) const FONT: &str = "Font";
+pub(crate) const XOBJECT: &str = "XObject";
+pub(crate) const EXT_G_STATE: &str = "ExtGState";
+pub(crate) const SHADING: &str = "Shading";
+pub(crate) const PROPERTIES: &str = "Properties";
+pub(crate) const MATRIX: &str = "Matrix";
+pub(crate) const BBOX: &str = "BBox";
+/// Keys of colour space and function dictionaries.
+pub(crate) const WHITE_POINT: &str = "WhitePoint";
+pub(crate) const GAMMA: &str = "Gamma";
+pub(crate) const RANGE: &str = "Range";
+pub(crate) const ALTERNATE: &str = "Alternate";
+pub(crate) const FUNCTION_TYPE: &str = "FunctionType";
+pub(crate) const DOMAIN: &str = "Domain";
+pub(crate) const C0: &str = "C0";
+pub(crate) const C1: &str = "C1";
+pub(crate) const BITS_PER_SAMPLE: &str = "BitsPerSample";
+pub(crate) const ENCODE: &str = "Encode";
+pub(crate) const FUNCTIONS: &str = "Functions";
+pub(crate) const BOUNDS: &str = "Bounds";
+/// Keys of annotation dictionaries.
+pub(crate) const ANNOTS: &str = "Annots";
+pub(crate) const RECT: &str = "Rect";
+pub(crate) const NM: &str = "NM";
+pub(crate) const M: &str = "M";
+pub(crate) const F: &str = "F";
+pub(crate) const C: &str = "C";
+pub(crate) const T: &str = "T";
+pub(crate) const SUBJ: &str = "Subj";
+pub(crate) const IRT: &str = "IRT";
+pub(crate) const POPUP: &str = "Popup";
+pub(crate) const PARENT: &str = "Parent";
+pub(crate) const OPEN: &str = "Open";
+pub(crate) const NAME: &str = "Name";
+pub(crate) const DA: &str = "DA";
+pub(crate) const IC: &str = "IC";
+pub(crate) const QUAD_POINTS: &str = "QuadPoints";
+pub(crate) const INK_LIST: &str = "InkList";
+/// Keys of actions, destinations and name trees.
+pub(crate) const A: &str = "A";
+pub(crate) const AA: &str = "AA";
+pub(crate) const S: &str = "S";
+pub(crate) const D: &str = "D";
+pub(crate) const DEST: &str = "Dest";
+pub(crate) const DESTS: &str = "Dests";
+pub(crate) const NAMES: &str = "Names";
+pub(crate) const LIMITS: &str = "Limits";
+pub(crate) const URI: &str = "URI";
+pub(crate) const JS: &str = "JS";
+pub(crate) const WIN: &str = "Win";
+pub(crate) const UF: &str = "UF";
+pub(crate) const NEW_WINDOW: &str = "NewWindow";
+pub(crate) const OPEN_ACTION: &str = "OpenAction";
+/// Keys of interactive forms and their fields.
+pub(crate) const ACRO_FORM: &str = "AcroForm";
+pub(crate) const FIELDS: &str = "Fields";
+pub(crate) const NEED_APPEARANCES: &str = "NeedAppearances";
+pub(crate) const SIG_FLAGS: &str = "SigFlags";
+pub(crate) const FT: &str = "FT";
+pub(crate) const FF: &str = "Ff";
+pub(crate) const TU: &str = "TU";
+pub(crate) const V: &str = "V";
+pub(crate) const DV: &str = "DV";
+pub(crate) const OPT: &str = "Opt";
+pub(crate) const MAX_LEN: &str = "MaxLen";
+pub(crate) const AP: &str = "AP";
+pub(crate) const AS: &str = "AS";
+pub(crate) const P: &str = "P";
+pub(crate) const Q: &str = "Q";
+pub(crate) const DR: &str = "DR";
+pub(crate) const I: &str = "I";
+pub(crate) const XFA: &str = "XFA";
+pub(crate) const NEEDS_RENDERING: &str = "NeedsRendering";
+/// Keys of file specifications and embedded files.
+pub(crate) const EMBEDDED_FILES: &str = "EmbeddedFiles";
+pub(crate) const FS: &str = "FS";
+pub(crate) const EF: &str = "EF";
+pub(crate) const DESC: &str = "Desc";
+pub(crate) const PARAMS: &str = "Params";
+pub(crate) const MOD_DATE: &str = "ModDate";
+pub(crate) const CHECK_SUM: &str = "CheckSum";
+pub(crate) const AF_RELATIONSHIP: &str = "AFRelationship";
@@ -0,0 +1,553 @@
+use crate::constants::pdf_key::{FALSE, NULL, TURE};
+use crate::constants::{
+    BITS_PER_COMPONENT, COLOR_SPACE, DECODE, DECODE_PARMS, FILTER, HEIGHT, IMAGE_MASK, INTERPOLATE,
+    LENGTH, WIDTH,
+};
+use crate::error::PDFError::{EOFError, PDFParseError, PDFParseError0};
+use crate::error::Result;
+use crate::objects::{Dictionary, PDFObject, Stream};
+use crate::parser::parse;
+use crate::sequence::MemorySequence;
+use crate::tokenizer::Token::{Delimiter, Eof, Id, Key, Name};
+use crate::tokenizer::Tokenizer;
+use crate::utils::is_whitespace;
+use std::collections::HashMap;
+
+/// The number of bytes after a candidate `EI` checked to look like text.
+const INLINE_IMAGE_LOOKAHEAD: usize = 32;
+
+/// Defines the content stream operators and the mapping between operators and
+/// their names.
+///
+/// # Arguments
+///
+/// * `$ident` - The variant of the operator
+/// * `$value` - The name of the operator in content streams
+macro_rules! operators {
+    ($($(#[$meta:meta])* ($ident:ident, $value:literal)),+$(,)?) => {
+        /// A content stream operator.
+        ///
+        /// Operators not defined by the specification are kept as `Unknown`, so
+        /// that consumers can report them instead of failing.
+        #[derive(Clone, Debug, PartialEq)]
+        pub enum Operator {
+            $(
+                $(#[$meta])*
+                $ident,
+            )+
+            /// An operator not defined by the specification.
+            Unknown(String),
+        }
+
+        impl Operator {
+            /// Looks up the operator with the given name.
+            pub fn from_name(name: &str) -> Operator {
+                match name {
+                    $($value => Operator::$ident,)+
+                    _ => Operator::Unknown(name.to_string()),
+                }
+            }
+
+            /// Returns the name of the operator as written in content streams.
+            pub fn get_name(&self) -> &str {
+                match self {
+                    $(Operator::$ident => $value,)+
+                    Operator::Unknown(name) => name,
+                }
+            }
+        }
+    };
+}
+
+operators!(
+    // General graphics state
+    /// `w`: Set the line width.
+    (SetLineWidth, "w"),
+    /// `J`: Set the line cap style.
+    (SetLineCap, "J"),
+    /// `j`: Set the line join style.
+    (SetLineJoin, "j"),
+    /// `M`: Set the miter limit.
+    (SetMiterLimit, "M"),
+    /// `d`: Set the line dash pattern.
+    (SetDash, "d"),
+    /// `ri`: Set the colour rendering intent.
+    (SetRenderingIntent, "ri"),
+    /// `i`: Set the flatness tolerance.
+    (SetFlatness, "i"),
+    /// `gs`: Set parameters from a graphics state parameter dictionary.
+    (SetExtGState, "gs"),
+    // Special graphics state
+    /// `q`: Save the graphics state.
+    (SaveState, "q"),
+    /// `Q`: Restore the graphics state.
+    (RestoreState, "Q"),
+    /// `cm`: Concatenate a matrix to the current transformation matrix.
+    (ConcatMatrix, "cm"),
+    // Path construction
+    /// `m`: Begin a new subpath.
+    (MoveTo, "m"),
+    /// `l`: Append a straight line segment.
+    (LineTo, "l"),
+    /// `c`: Append a cubic Bézier curve with two control points.
+    (CurveTo, "c"),
+    /// `v`: Append a cubic Bézier curve whose first control point is the current point.
+    (CurveToV, "v"),
+    /// `y`: Append a cubic Bézier curve whose second control point is the end point.
+    (CurveToY, "y"),
+    /// `h`: Close the current subpath.
+    (ClosePath, "h"),
+    /// `re`: Append a rectangle as a complete subpath.
+    (Rectangle, "re"),
+    // Path painting
+    /// `S`: Stroke the path.
+    (StrokePath, "S"),
+    /// `s`: Close and stroke the path.
+    (CloseStrokePath, "s"),
+    /// `f`: Fill the path using the nonzero winding number rule.
+    (FillPath, "f"),
+    /// `F`: Equivalent to `f`, kept for compatibility.
+    (FillPathObsolete, "F"),
+    /// `f*`: Fill the path using the even-odd rule.
+    (FillPathEvenOdd, "f*"),
+    /// `B`: Fill using the nonzero winding number rule, then stroke the path.
+    (FillStrokePath, "B"),
+    /// `B*`: Fill using the even-odd rule, then stroke the path.
+    (FillStrokePathEvenOdd, "B*"),
+    /// `b`: Close, fill using the nonzero winding number rule and stroke the path.
+    (CloseFillStrokePath, "b"),
+    /// `b*`: Close, fill using the even-odd rule and stroke the path.
+    (CloseFillStrokePathEvenOdd, "b*"),
+    /// `n`: End the path without filling or stroking it.
+    (EndPath, "n"),
+    // Clipping paths
+    /// `W`: Intersect the clipping path using the nonzero winding number rule.
+    (Clip, "W"),
+    /// `W*`: Intersect the clipping path using the even-odd rule.
+    (ClipEvenOdd, "W*"),
+    // Text objects
+    /// `BT`: Begin a text object.
+    (BeginText, "BT"),
+    /// `ET`: End a text object.
+    (EndText, "ET"),
+    // Text state
+    /// `Tc`: Set the character spacing.
+    (SetCharSpacing, "Tc"),
+    /// `Tw`: Set the word spacing.
+    (SetWordSpacing, "Tw"),
+    /// `Tz`: Set the horizontal scaling.
+    (SetHorizontalScaling, "Tz"),
+    /// `TL`: Set the text leading.
+    (SetLeading, "TL"),
+    /// `Tf`: Set the text font and size.
+    (SetFont, "Tf"),
+    /// `Tr`: Set the text rendering mode.
+    (SetTextRendering, "Tr"),
+    /// `Ts`: Set the text rise.
+    (SetTextRise, "Ts"),
+    // Text positioning
+    /// `Td`: Move to the start of the next line, offset from the current line.
+    (MoveText, "Td"),
+    /// `TD`: Move to the next line and set the leading.
+    (MoveTextSetLeading, "TD"),
+    /// `Tm`: Set the text matrix and text line matrix.
+    (SetTextMatrix, "Tm"),
+    /// `T*`: Move to the start of the next line.
+    (NextLine, "T*"),
+    // Text showing
+    /// `Tj`: Show a text string.
+    (ShowText, "Tj"),
+    /// `TJ`: Show text strings, allowing individual glyph positioning.
+    (ShowTextAdjusted, "TJ"),
+    /// `'`: Move to the next line and show a text string.
+    (NextLineShowText, "'"),
+    /// `"`: Set word and character spacing, move to the next line and show a text string.
+    (NextLineSetSpacingShowText, "\""),
+    // Type 3 fonts
+    /// `d0`: Set the glyph width of a Type 3 glyph.
+    (SetCharWidth, "d0"),
+    /// `d1`: Set the glyph width and bounding box of a Type 3 glyph.
+    (SetCacheDevice, "d1"),
+    // Colour
+    /// `CS`: Set the colour space for stroking operations.
+    (SetStrokeColorSpace, "CS"),
+    /// `cs`: Set the colour space for nonstroking operations.
+    (SetFillColorSpace, "cs"),
+    /// `SC`: Set the colour for stroking operations.
+    (SetStrokeColor, "SC"),
+    /// `SCN`: Set the colour for stroking operations, supporting patterns and special colour spaces.
+    (SetStrokeColorN, "SCN"),
+    /// `sc`: Set the colour for nonstroking operations.
+    (SetFillColor, "sc"),
+    /// `scn`: Set the colour for nonstroking operations, supporting patterns and special colour spaces.
+    (SetFillColorN, "scn"),
+    /// `G`: Set the stroking colour space to DeviceGray and set the gray level.
+    (SetStrokeGray, "G"),
+    /// `g`: Set the nonstroking colour space to DeviceGray and set the gray level.
+    (SetFillGray, "g"),
+    /// `RG`: Set the stroking colour space to DeviceRGB and set the colour.
+    (SetStrokeRGB, "RG"),
+    /// `rg`: Set the nonstroking colour space to DeviceRGB and set the colour.
+    (SetFillRGB, "rg"),
+    /// `K`: Set the stroking colour space to DeviceCMYK and set the colour.
+    (SetStrokeCMYK, "K"),
+    /// `k`: Set the nonstroking colour space to DeviceCMYK and set the colour.
+    (SetFillCMYK, "k"),
+    // Shading patterns
+    /// `sh`: Paint a shading.
+    (PaintShading, "sh"),
+    // Inline images
+    /// `BI`: Begin an inline image.
+    (BeginInlineImage, "BI"),
+    /// `ID`: Begin the inline image data.
+    (BeginInlineImageData, "ID"),
+    /// `EI`: End an inline image.
+    (EndInlineImage, "EI"),
+    // XObjects
+    /// `Do`: Paint an XObject.
+    (PaintXObject, "Do"),
+    // Marked content
+    /// `MP`: Designate a marked-content point.
+    (MarkedContentPoint, "MP"),
+    /// `DP`: Designate a marked-content point with a property list.
+    (MarkedContentPointWithProperties, "DP"),
+    /// `BMC`: Begin a marked-content sequence.
+    (BeginMarkedContent, "BMC"),
+    /// `BDC`: Begin a marked-content sequence with a property list.
+    (BeginMarkedContentWithProperties, "BDC"),
+    /// `EMC`: End a marked-content sequence.
+    (EndMarkedContent, "EMC"),
+    // Compatibility
+    /// `BX`: Begin a compatibility section, where unknown operators are ignored.
+    (BeginCompatibility, "BX"),
+    /// `EX`: End a compatibility section.
+    (EndCompatibility, "EX"),
+);
+
+/// An operator together with the operands preceding it in a content stream.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Operation {
+    operator: Operator,
+    operands: Vec<PDFObject>,
+}
+
+/// Parses a content stream into operations.
+///
+/// Parsing never stops at unknown operators, they are returned as
+/// [`Operator::Unknown`]. A keyword or delimiter which can't start an operand is
+/// treated as an unknown operator as well. Iteration ends after the first
+/// error, such as a string that isn't closed.
+///
+/// Inline images are returned as a single `BI` operation holding the image,
+/// see [`Operation::get_inline_image`].
+pub struct ContentParser {
+    tokenizer: Tokenizer,
+    done: bool,
+}
+
+impl Operation {
+    /// Creates an operation from its operator and operands.
+    pub fn new(operator: Operator, operands: Vec<PDFObject>) -> Self {
+        Operation { operator, operands }
+    }
+
+    /// Returns the operator of the operation.
+    pub fn get_operator(&self) -> &Operator {
+        &self.operator
+    }
+
+    /// Returns the operands of the operation in the order they were written.
+    pub fn get_operands(&self) -> &[PDFObject] {
+        &self.operands
+    }
+
+    /// Returns true if the operator is not defined by the specification.
+    pub fn is_unknown(&self) -> bool {
+        matches!(self.operator, Operator::Unknown(_))
+    }
+
+    /// Returns the inline image of a `BI` operation.
+    ///
+    /// The `BI`, `ID` and `EI` operators of an inline image are parsed into a
+    /// single `BI` operation, whose only operand is the image as a stream with
+    /// its dictionary abbreviations expanded.
+    pub fn get_inline_image(&self) -> Option<&Stream> {
+        match (&self.operator, self.operands.as_slice()) {
+            (Operator::BeginInlineImage, [PDFObject::Stream(image)]) => Some(image),
+            _ => None,
+        }
+    }
+}
+
+impl ContentParser {
+    /// Creates a parser over decoded content stream data.
+    ///
+    /// The data of a page made of several content streams is the concatenation
+    /// of the streams, as returned by [`PDFPage::get_contents`](crate::page::PDFPage::get_contents).
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The decoded content stream data
+    ///
+    /// # Returns
+    ///
+    /// A new `ContentParser` positioned at the start of the data
+    pub fn new(data: Vec<u8>) -> Self {
+        let tokenizer = Tokenizer::new(MemorySequence::new(data));
+        ContentParser { tokenizer, done: false }
+    }
+
+    /// Parses the next operation.
+    ///
+    /// Operands left at the end of the data without an operator are discarded.
+    fn next_operation(&mut self) -> Result<Option<Operation>> {
+        let mut operands = Vec::new();
+        loop {
+            let operator = match self.tokenizer.peek_token(0)? {
+                Id(name) => Some(Operator::from_name(name)),
+                Key(key) if key != NULL && key != TURE && key != FALSE => Some(Operator::from_name(key)),
+                Delimiter(delimiter) if delimiter != "<<" && delimiter != "[" => Some(Operator::from_name(delimiter)),
+                Eof => return Ok(None),
+                _ => None,
+            };
+            match operator {
+                Some(Operator::BeginInlineImage) => {
+                    self.tokenizer.next_token()?;
+                    let image = self.parse_inline_image()?;
+                    return Ok(Some(Operation::new(Operator::BeginInlineImage, vec![PDFObject::Stream(image)])));
+                }
+                Some(operator) => {
+                    self.tokenizer.next_token()?;
+                    return Ok(Some(Operation::new(operator, operands)));
+                }
+                None => operands.push(parse(&mut self.tokenizer, &[])?),
+            }
+        }
+    }
+
+    /// Parses an inline image following the `BI` operator, up to and including `EI`.
+    ///
+    /// The abbreviated keys and names of the image dictionary are expanded, so
+    /// the image is decoded like an image XObject. The data length is computed
+    /// from the image dictionary when the data isn't filtered, otherwise the data
+    /// ends at the first `EI` surrounded by whitespace and followed by content
+    /// that looks like operators.
+    fn parse_inline_image(&mut self) -> Result<Stream> {
+        let mut dict = Dictionary::new(HashMap::new());
+        loop {
+            let key = match self.tokenizer.next_token()? {
+                Id("ID") => break,
+                Name(name) => expand_abbreviation(&name, INLINE_IMAGE_KEYS).to_string(),
+                Eof => return Err(EOFError),
+                token => return Err(PDFParseError0(format!("Except a name in inline image dictionary but it is '{}'", token.to_text()))),
+            };
+            let value = expand_inline_image_value(parse(&mut self.tokenizer, &[])?);
+            dict.insert(&key, value);
+        }
+        let data = self.tokenizer.get_unread()
+            .ok_or(PDFParseError("Inline image data is not in memory."))?;
+        // A single whitespace character separates `ID` from the data
+        let start = usize::from(data.first().is_some_and(|b| is_whitespace(*b)));
+        let length = inline_image_length(&dict)
+            .filter(|length| ends_with_ei(&data[start..], *length))
+            .or_else(|| find_ei(&data[start..]))
+            .ok_or(PDFParseError("Inline image data is not terminated by EI."))?;
+        self.tokenizer.read_bytes(start)?;
+        let buf = self.tokenizer.read_bytes(length)?;
+        self.tokenizer.next_token()?.except(|token| *token == Id("EI"))?;
+        Ok(Stream::new(dict, buf))
+    }
+}
+
+/// The abbreviated keys of inline image dictionaries.
+const INLINE_IMAGE_KEYS: &[(&str, &str)] = &[
+    ("BPC", BITS_PER_COMPONENT),
+    ("CS", COLOR_SPACE),
+    ("D", DECODE),
+    ("DP", DECODE_PARMS),
+    ("F", FILTER),
+    ("H", HEIGHT),
+    ("IM", IMAGE_MASK),
+    ("I", INTERPOLATE),
+    ("L", LENGTH),
+    ("W", WIDTH),
+];
+
+/// The abbreviated colour space and filter names of inline images.
+const INLINE_IMAGE_NAMES: &[(&str, &str)] = &[
+    ("G", "DeviceGray"),
+    ("RGB", "DeviceRGB"),
+    ("CMYK", "DeviceCMYK"),
+    ("I", "Indexed"),
+    ("AHx", "ASCIIHexDecode"),
+    ("A85", "ASCII85Decode"),
+    ("LZW", "LZWDecode"),
+    ("Fl", "FlateDecode"),
+    ("RL", "RunLengthDecode"),
+    ("CCF", "CCITTFaxDecode"),
+    ("DCT", "DCTDecode"),
+];
+
+fn expand_abbreviation<'a>(name: &'a str, table: &[(&str, &'static str)]) -> &'a str {
+    table.iter()
+        .find(|(abbreviation, _)| *abbreviation == name)
+        .map_or(name, |(_, full)| full)
+}
+
+/// Expands the abbreviated names in an inline image dictionary value.
+fn expand_inline_image_value(value: PDFObject) -> PDFObject {
+    match value {
+        PDFObject::Named(name) => PDFObject::Named(expand_abbreviation(&name, INLINE_IMAGE_NAMES).to_string()),
+        PDFObject::Array(values) => PDFObject::Array(values.into_iter().map(expand_inline_image_value).collect()),
+        value => value,
+    }
+}
+
+/// Computes the length of unfiltered inline image data.
+///
+/// Returns None when the data is filtered, when the colour space is a named
+/// resource whose number of components isn't known here, or when the length
+/// overflows.
+fn inline_image_length(dict: &Dictionary) -> Option<usize> {
+    if let Some(length) = dict.get_u64_num(LENGTH) {
+        return Some(length as usize);
+    }
+    if dict.contain(FILTER) {
+        return None;
+    }
+    let width = dict.get_u64_num(WIDTH)? as usize;
+    let height = dict.get_u64_num(HEIGHT)? as usize;
+    let (components, bpc) = if dict.get(IMAGE_MASK).and_then(|mask| mask.as_bool()) == Some(true) {
+        (1, 1)
+    } else {
+        let components = match dict.get(COLOR_SPACE)? {
+            PDFObject::Named(name) => color_space_components(name)?,
+            PDFObject::Array(values) => color_space_components(values.first()?.as_name()?)?,
+            _ => return None,
+        };
+        (components, dict.get_u64_num(BITS_PER_COMPONENT)? as usize)
+    };
+    // Lengths overflowing come from broken dictionaries, the data is then searched for EI
+    width.checked_mul(components)?.checked_mul(bpc)?.div_ceil(8).checked_mul(height)
+}
+
+fn color_space_components(name: &str) -> Option<usize> {
+    match name {
+        "DeviceGray" | "CalGray" | "Indexed" => Some(1),
+        "DeviceRGB" | "CalRGB" | "Lab" => Some(3),
+        "DeviceCMYK" => Some(4),
+        _ => None,
+    }
+}
+
+/// Returns true if `EI`, optionally preceded by whitespace, follows `length` bytes of data.
+fn ends_with_ei(data: &[u8], length: usize) -> bool {
+    match data.get(length..) {
+        Some(rest) => {
+            let n = rest.iter().take_while(|b| is_whitespace(**b)).count();
+            is_ei_at(rest, n)
+        }
+        None => false,
+    }
+}
+
+/// Returns true if `data` holds an `EI` operator at `pos`.
+fn is_ei_at(data: &[u8], pos: usize) -> bool {
+    data[pos..].starts_with(b"EI") && data.get(pos + 2).is_none_or(|b| is_whitespace(*b))
+}
+
+/// Searches the end of inline image data, returning the data length.
+///
+/// The data must be followed by whitespace and `EI`, and the content after `EI`
+/// must look like text, since binary data may contain `EI` as well. The
+/// whitespace before `EI` isn't part of the data.
+fn find_ei(data: &[u8]) -> Option<usize> {
+    (1..data.len()).find(|i| {
+        is_whitespace(data[i - 1]) && is_ei_at(data, *i) && data[i + 2..].iter()
+            .take(INLINE_IMAGE_LOOKAHEAD)
+            .all(|b| is_whitespace(*b) || (0x20..0x7F).contains(b))
+    }).map(|i| i - 1)
+}
+
+impl Iterator for ContentParser {
+    type Item = Result<Operation>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.next_operation() {
+            Ok(Some(operation)) => Some(Ok(operation)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::PDFNumber;
+
+    /// Tests that operands are grouped with their operator and that unknown
+    /// operators don't stop parsing.
+    #[test]
+    fn test_content_parser() -> Result<()> {
+        let data = b"q 1 0 0 1 72 720 cm BT /F1 12 Tf [(A) -120 (B)] TJ ET foo 0.5 g T* ' Q";
+        let operations = ContentParser::new(data.to_vec()).collect::<Result<Vec<Operation>>>()?;
+        let names = operations.iter().map(|op| op.get_operator().get_name()).collect::<Vec<&str>>();
+        assert_eq!(names, ["q", "cm", "BT", "Tf", "TJ", "ET", "foo", "g", "T*", "'", "Q"]);
+        assert_eq!(operations[1].get_operands().len(), 6);
+        assert_eq!(operations[3].get_operands()[0], PDFObject::Named("F1".to_string()));
+        assert_eq!(operations[3].get_operands()[1], PDFObject::Number(PDFNumber::Unsigned(12)));
+        assert!(operations[4].get_operands()[0].is_array());
+        assert_eq!(*operations[6].get_operator(), Operator::Unknown("foo".to_string()));
+        assert_eq!(*operations[8].get_operator(), Operator::NextLine);
+        Ok(())
+    }
+
+    /// Tests inline images whose data length is computed from the dictionary,
+    /// even though the binary data contains `EI`.
+    #[test]
+    fn test_inline_image_length() -> Result<()> {
+        let mut data = b"q BI /W 2 /H 2 /CS /G /BPC 8 ID ".to_vec();
+        data.extend_from_slice(b" EI\n");
+        data.extend_from_slice(b"\nEI Q");
+        let operations = ContentParser::new(data).collect::<Result<Vec<Operation>>>()?;
+        assert_eq!(operations.len(), 3);
+        let image = operations[1].get_inline_image().unwrap();
+        assert_eq!(image.get_buf(), b" EI\n");
+        assert_eq!(image.get_metadata().get_named_value(COLOR_SPACE).unwrap(), "DeviceGray");
+        assert_eq!(image.get_metadata().get_u64_num(WIDTH), Some(2));
+        assert_eq!(*operations[2].get_operator(), Operator::RestoreState);
+        Ok(())
+    }
+
+    /// Tests inline images whose data ends at the first `EI` followed by text,
+    /// used when the data is filtered, the colour space is a named resource or
+    /// the computed length overflows.
+    #[test]
+    fn test_inline_image_search() -> Result<()> {
+        let data = b"BI /W 2 /H 1 /CS /CS0 /BPC 8 ID A EI \x80B\x80EI\nEI\nQ".to_vec();
+        let operations = ContentParser::new(data).collect::<Result<Vec<Operation>>>()?;
+        assert_eq!(operations.len(), 2);
+        assert_eq!(operations[0].get_inline_image().unwrap().get_buf(), b"A EI \x80B\x80EI");
+
+        let data = b"BI /W 4294967296 /H 4294967296 /CS /RGB /BPC 16 ID AB EI Q".to_vec();
+        let operations = ContentParser::new(data).collect::<Result<Vec<Operation>>>()?;
+        assert_eq!(operations[0].get_inline_image().unwrap().get_buf(), b"AB");
+
+        let data = b"BI /W 3 /H 1 /CS /RGB /BPC 8 /F [/AHx] ID 414243> EI".to_vec();
+        let operations = ContentParser::new(data).collect::<Result<Vec<Operation>>>()?;
+        let image = operations[0].get_inline_image().unwrap();
+        assert_eq!(image.get_filters(), ["ASCIIHexDecode"]);
+        assert_eq!(image.get_decoded_buf()?, b"ABC");
+        Ok(())
+    }
+}
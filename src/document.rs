@@ -1,21 +1,34 @@
+use crate::action::{self, Action, Destination};
 use crate::catalog::{OutlineTreeArean, PageTreeArean, decode_catalog_data};
 use crate::constants::pdf_key::{START_XREF, XREF};
-use crate::constants::{AUTHOR, CREATION_DATE, CREATOR, INFO, PREV, PRODUCER, ROOT, TITLE};
+use crate::constants::{AUTHOR, CREATION_DATE, CREATOR, INFO, OPEN_ACTION, PREV, PRODUCER, ROOT, TITLE};
+use crate::embedded::{self, EmbeddedFile};
 use crate::encoding::PreDefinedEncoding;
 use crate::error::PDFError::{
-    InvalidPDFDocument, ObjectAttrMiss, PDFParseError, XrefTableNotFound,
+    InvalidPDFDocument, ObjectAttrMiss, PDFParseError, XrefEntryNotFound, XrefTableNotFound,
 };
 use crate::error::Result;
 use crate::objects::{Dictionary, PDFNumber, PDFObject, XEntry};
 use crate::parser::{parse, parse_text_xref, parse_with_offset};
 use crate::pstr::convert_glyph_text;
-use crate::sequence::{FileSequence, Sequence};
+use crate::sequence::{FileSequence, MemorySequence, Sequence};
+#[cfg(feature = "mmap")]
+use crate::sequence::MmapSequence;
 use crate::tokenizer::Tokenizer;
 use crate::utils::{count_leading_line_endings, line_ending, literal_to_u64, xrefs_search};
+use crate::page::PDFPage;
+use crate::font::{self, FontInfo};
+use crate::form::{self, AcroForm};
+use crate::xfa::{self, Xfa};
 use crate::vpdf::PDFVersion;
+use std::borrow::Cow;
+use std::cell::RefCell;
 use std::path::PathBuf;
 use crate::convert_glyph_from_dict;
 
+/// The maximum number of references followed when resolving a chain of references.
+const MAX_REF_DEPTH: usize = 32;
+
 pub struct PDFDescribe {
     /// (Optional) The name of the application that converted the document from its native format to
     /// PDF.
@@ -42,11 +55,21 @@ pub struct PDFDocument {
     xrefs: Vec<XEntry>,
     /// PDF version information.
     version: PDFVersion,
+    /// The document catalog, the root of the object hierarchy.
+    catalog: Dictionary,
+    /// The trailer of the last cross-reference section.
+    trailer: Dictionary,
+    /// The offset of the last cross-reference section.
+    xref_offset: u64,
     /// Tokenizer for parsing the PDF content.
-    tokenizer: Tokenizer,
+    ///
+    /// Objects are loaded lazily through a shared document, so the tokenizer is
+    /// borrowed mutably only while an object is parsed.
+    tokenizer: RefCell<Tokenizer>,
     /// Page tree arena containing the hierarchical page structure.
     page_tree_arena: PageTreeArean,
     /// Outline tree arena containing the hierarchical outline structure.
+    #[allow(dead_code)]
     outline_tree_arean: Option<OutlineTreeArean>,
     /// Document info
     describe: Option<PDFDescribe>,
@@ -71,6 +94,45 @@ impl PDFDocument {
         Self::new(sequence)
     }
 
+    /// Opens a PDF document by mapping the file into memory.
+    ///
+    /// Objects are tokenized in place from the mapping, which suits large files where
+    /// only some objects are loaded. The file must not be modified while the document
+    /// is open.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the PDF file to open
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the parsed `PDFDocument` or an error if the file cannot be mapped
+    /// or parsed correctly
+    #[cfg(feature = "mmap")]
+    pub fn open_mmap(path: PathBuf) -> Result<PDFDocument> {
+        let sequence = MmapSequence::open(path)?;
+        Self::new(sequence)
+    }
+
+    /// Opens a PDF document from bytes held in memory.
+    ///
+    /// This is useful when the PDF doesn't live on disk, e.g. it was received over the
+    /// network into a `Vec<u8>`.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The PDF bytes, such as a `Vec<u8>`, `&'static [u8]` or `bytes::Bytes`
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the parsed `PDFDocument` or an error if parsing fails
+    pub fn from_bytes<T>(data: T) -> Result<PDFDocument>
+    where
+        T: AsRef<[u8]> + 'static,
+    {
+        Self::new(MemorySequence::new(data))
+    }
+
     /// Creates a PDF document from a sequence of bytes.
     ///
     /// This function parses a sequence of bytes representing a PDF document and constructs
@@ -89,8 +151,8 @@ impl PDFDocument {
         let mut tokenizer = Tokenizer::new(sequence);
         tokenizer.seek(offset)?;
         // Merge all xref table
-        let (xrefs, catalog, info) = merge_xref_table(&mut tokenizer)?;
-        let (page_tree_arena, outline_tree_arean) = match catalog {
+        let (xrefs, catalog, info, trailer) = merge_xref_table(&mut tokenizer)?;
+        let (catalog, page_tree_arena, outline_tree_arean) = match catalog {
             Some(catalog) => decode_catalog_data(&mut tokenizer, catalog, &xrefs)?,
             None => return Err(ObjectAttrMiss("Trailer can't found catalog attr.")),
         };
@@ -99,17 +161,19 @@ impl PDFDocument {
         if let Some(obj) = info {
             let entry = xrefs_search(&xrefs, obj)?;
             if let PDFObject::IndirectObject(_, _, value) =
-                parse_with_offset(&mut tokenizer, entry.value)?
+                parse_with_offset(&mut tokenizer, entry.value, &xrefs)?
+                && let PDFObject::Dict(dict) = *value
             {
-                if let PDFObject::Dict(dict) = *value {
-                    describe = Some(PDFDescribe::new(dict));
-                }
+                describe = Some(PDFDescribe::new(dict));
             }
         }
         let document = PDFDocument {
             xrefs,
             version,
-            tokenizer,
+            catalog,
+            trailer,
+            xref_offset: offset,
+            tokenizer: RefCell::new(tokenizer),
             page_tree_arena,
             outline_tree_arean,
             describe,
@@ -161,7 +225,7 @@ impl PDFDocument {
     ///
     /// A `Result` containing an optional PDFObject (None if the index is out of bounds
     /// or the object is freed) or an error if reading/parsing fails
-    pub fn read_object(&mut self, index: usize) -> Result<Option<PDFObject>> {
+    pub fn read_object(&self, index: usize) -> Result<Option<PDFObject>> {
         if index >= self.xrefs.len() {
             return Ok(None);
         }
@@ -169,11 +233,197 @@ impl PDFDocument {
         if entry.is_freed() {
             return Ok(None);
         }
-        self.tokenizer.seek(entry.get_value())?;
-        let object = parse(&mut self.tokenizer)?;
+        let object = parse_with_offset(&mut self.tokenizer.borrow_mut(), entry.get_value(), &self.xrefs)?;
         Ok(Some(object))
     }
 
+    /// Reads the object with the given object number and generation number.
+    ///
+    /// # Arguments
+    ///
+    /// * `obj_ref` - A tuple containing the object number and generation number
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the object without its `obj`/`endobj` wrapper, None if the
+    /// object doesn't exist or is freed, or an error if reading/parsing fails
+    pub fn get_object(&self, obj_ref: (u32, u16)) -> Result<Option<PDFObject>> {
+        let entry = match xrefs_search(&self.xrefs, obj_ref) {
+            Ok(entry) if entry.is_using() => entry,
+            _ => return Ok(None),
+        };
+        let object = parse_with_offset(&mut self.tokenizer.borrow_mut(), entry.get_value(), &self.xrefs)?;
+        match object {
+            PDFObject::IndirectObject(_, _, value) => Ok(Some(*value)),
+            _ => Err(XrefEntryNotFound(obj_ref.0, obj_ref.1)),
+        }
+    }
+
+    /// Resolves an indirect reference to the object it refers to.
+    ///
+    /// Direct objects are returned borrowed as they are. A reference to an object
+    /// that doesn't exist resolves to the null object, as required by the specification.
+    ///
+    /// # Arguments
+    ///
+    /// * `object` - The object to resolve
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the resolved object or an error if reading/parsing fails
+    pub fn resolve<'a>(&self, object: &'a PDFObject) -> Result<Cow<'a, PDFObject>> {
+        let mut obj_ref = match object {
+            PDFObject::ObjectRef(obj_num, gen_num) => (*obj_num, *gen_num),
+            _ => return Ok(Cow::Borrowed(object)),
+        };
+        // A reference may point to another reference, bounded to avoid cycles
+        for _ in 0..MAX_REF_DEPTH {
+            match self.get_object(obj_ref)? {
+                Some(PDFObject::ObjectRef(obj_num, gen_num)) => obj_ref = (obj_num, gen_num),
+                Some(value) => return Ok(Cow::Owned(value)),
+                None => break,
+            }
+        }
+        Ok(Cow::Owned(PDFObject::Null))
+    }
+
+    /// Gets a page by its index, starting from 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the page in the page tree
+    ///
+    /// # Returns
+    ///
+    /// The page, or None if the index is out of bounds
+    pub fn get_page(&self, index: usize) -> Option<PDFPage<'_>> {
+        let (obj_ref, attrs) = self.page_tree_arena.get_page_attrs(index)?;
+        Some(PDFPage::new(self, index, obj_ref, attrs))
+    }
+
+    /// Gets all pages of the document in order.
+    ///
+    /// # Returns
+    ///
+    /// The pages of the document
+    pub fn get_pages(&self) -> Vec<PDFPage<'_>> {
+        (0..self.get_page_num()).filter_map(|index| self.get_page(index)).collect()
+    }
+
+    /// Gets the font inventory of the document.
+    ///
+    /// The fonts are listed in the order of the first page using them, with
+    /// their name, type, encoding, embedding and the pages using them.
+    ///
+    /// # Returns
+    ///
+    /// The fonts used by the pages of the document
+    pub fn get_fonts(&self) -> Result<Vec<FontInfo<'_>>> {
+        font::collect_fonts(self)
+    }
+
+    /// Gets the index of the page with the given object reference, such as the
+    /// target of a destination.
+    ///
+    /// # Arguments
+    ///
+    /// * `obj_ref` - The object number and generation number of the page object
+    ///
+    /// # Returns
+    ///
+    /// The index of the page in document order, or None if the object isn't a page
+    pub fn get_page_index(&self, obj_ref: (u32, u16)) -> Option<usize> {
+        self.page_tree_arena.get_page_index(obj_ref)
+    }
+
+    /// Gets the document catalog, the dictionary at the root of the object hierarchy.
+    pub fn get_catalog(&self) -> &Dictionary {
+        &self.catalog
+    }
+
+    /// Gets the trailer of the last cross-reference section.
+    pub fn get_trailer(&self) -> &Dictionary {
+        &self.trailer
+    }
+
+    /// Returns the offset of the last cross-reference section, which an
+    /// incremental update refers to as `/Prev`.
+    pub(crate) fn get_xref_offset(&self) -> u64 {
+        self.xref_offset
+    }
+
+    /// Reads the bytes of the whole document.
+    ///
+    /// Data kept in memory or mapped is copied directly, file and reader
+    /// sequences are read from the start.
+    pub(crate) fn read_bytes(&self) -> Result<Vec<u8>> {
+        let mut tokenizer = self.tokenizer.borrow_mut();
+        if let Some(data) = tokenizer.get_data() {
+            return Ok(data.to_vec());
+        }
+        let size = tokenizer.size()?;
+        tokenizer.seek(0)?;
+        tokenizer.read_bytes(size as usize)
+    }
+
+    /// Gets the action performed when the document is opened, `/OpenAction`.
+    ///
+    /// A destination given instead of an action is returned as a GoTo action.
+    ///
+    /// # Returns
+    ///
+    /// The action, or None if the catalog doesn't define one
+    pub fn get_open_action(&self) -> Result<Option<Action>> {
+        match self.catalog.get(OPEN_ACTION) {
+            Some(object) => action::read_action_or_destination(self, object),
+            None => Ok(None),
+        }
+    }
+
+    /// Looks up a named destination, in the `/Dests` name tree of the name
+    /// dictionary or in the `/Dests` dictionary of the catalog.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the destination
+    ///
+    /// # Returns
+    ///
+    /// The destination, or None if the document doesn't define it
+    pub fn get_named_destination(&self, name: &str) -> Result<Option<Destination>> {
+        action::find_named_destination(self, name.as_bytes())
+    }
+
+    /// Gets the interactive form of the document, with its fields.
+    ///
+    /// # Returns
+    ///
+    /// The form, or None if the document doesn't have one
+    pub fn get_form(&self) -> Result<Option<AcroForm>> {
+        form::read_form(self)
+    }
+
+    /// Gets the XFA description of the interactive form of the document, with
+    /// its packets and whether the form is static or dynamic.
+    ///
+    /// # Returns
+    ///
+    /// The XFA description, or None if the document doesn't have an XFA form
+    pub fn get_xfa(&self) -> Result<Option<Xfa>> {
+        xfa::read_xfa(self)
+    }
+
+    /// Gets the files embedded in the document, listed by `/Names /EmbeddedFiles`.
+    ///
+    /// Files attached to pages are read by [`PDFPage::get_file_attachments`].
+    ///
+    /// # Returns
+    ///
+    /// The files, in the order of the name tree
+    pub fn get_embedded_files(&self) -> Result<Vec<EmbeddedFile>> {
+        embedded::read_embedded_files(self)
+    }
+
     /// Gets the total number of pages in the PDF document.
     ///
     /// # Returns
@@ -182,6 +432,15 @@ impl PDFDocument {
     pub fn get_page_num(&self) -> usize {
         self.page_tree_arena.get_page_num()
     }
+
+    /// Gets the document information dictionary.
+    ///
+    /// # Returns
+    ///
+    /// The document info, or None if the trailer doesn't reference an info dictionary
+    pub fn get_describe(&self) -> Option<&PDFDescribe> {
+        self.describe.as_ref()
+    }
 }
 
 /// Parses the PDF version from the beginning of the document.
@@ -213,9 +472,13 @@ fn parse_version(sequence: &mut impl Sequence) -> Result<PDFVersion> {
         return Err(InvalidPDFDocument);
     }
     let version = String::from_utf8(buf[5..8].to_vec())?;
-    Ok(version.try_into()?)
+    version.try_into()
 }
 
+/// Merged cross-reference entries with the catalog and info references found in
+/// the trailer, and the trailer of the last cross-reference section.
+type MergedXref = (Vec<XEntry>, Option<(u32, u16)>, Option<(u32, u16)>, Dictionary);
+
 /// Merges cross-reference tables from a PDF document.
 ///
 /// This function parses and merges multiple cross-reference tables that may exist
@@ -228,17 +491,16 @@ fn parse_version(sequence: &mut impl Sequence) -> Result<PDFVersion> {
 ///
 /// # Returns
 ///
-/// A `Result` containing a tuple with the merged vector of XEntry objects and
-/// a tuple of the catalog object number and generation number, or an error if
+/// A `Result` containing a tuple with the merged vector of XEntry objects,
+/// the catalog and info references and the last trailer, or an error if
 /// parsing fails
-fn merge_xref_table(
-    mut tokenizer: &mut Tokenizer,
-) -> Result<(Vec<XEntry>, Option<(u32, u16)>, Option<(u32, u16)>)> {
+fn merge_xref_table(tokenizer: &mut Tokenizer) -> Result<MergedXref> {
     let mut xrefs = Vec::<XEntry>::new();
     let mut info = None;
     let mut catalog = None;
+    let mut trailer = None;
     loop {
-        let is_xref = tokenizer.check_next_token0(false, |token| token.key_was(XREF))?;
+        let is_xref = tokenizer.next_token_if(|token| token.key_was(XREF))?;
         if !is_xref {
             return Err(XrefTableNotFound);
         }
@@ -247,12 +509,15 @@ fn merge_xref_table(
             xrefs.extend_from_slice(&entries);
         } else {
             for entry in entries {
-                if let None = xrefs.iter().find(|it| it.obj_num == entry.obj_num) {
+                if !xrefs.iter().any(|it| it.obj_num == entry.obj_num) {
                     xrefs.push(entry);
                 }
             }
         }
-        if let PDFObject::Dict(mut dictionary) = parse(&mut tokenizer)? {
+        if let PDFObject::Dict(dictionary) = parse(tokenizer, &xrefs)? {
+            if trailer.is_none() {
+                trailer = Some(dictionary.clone());
+            }
             if let Some(PDFObject::ObjectRef(obj_num, gen_num)) = dictionary.get(ROOT) {
                 catalog = Some((*obj_num, *gen_num));
                 if let Some(PDFObject::ObjectRef(obj_num, gen_num)) = dictionary.get(INFO) {
@@ -264,7 +529,7 @@ fn merge_xref_table(
                 tokenizer.seek(*prev)?;
                 continue;
             }
-            return Ok((xrefs, catalog, info));
+            return Ok((xrefs, catalog, info, trailer.unwrap_or(dictionary)));
         }
         return Err(PDFParseError("Xref table broken."));
     }
@@ -285,7 +550,7 @@ fn merge_xref_table(
 /// startxref keyword cannot be found or the offset cannot be parsed
 fn cal_xref_table_offset(sequence: &mut impl Sequence) -> Result<u64> {
     let size = sequence.size()?;
-    let pos = if size > 1024 { size - 1024 } else { 0 };
+    let pos = size.saturating_sub(1024);
     let mut buf = [0u8; 1024];
     sequence.seek(pos)?;
     let n = sequence.read(&mut buf)?;
@@ -306,16 +571,13 @@ fn cal_xref_table_offset(sequence: &mut impl Sequence) -> Result<u64> {
     if index == n {
         return Err(InvalidPDFDocument);
     }
-    index = index + chars.len();
+    index += chars.len();
     let crlf_num = count_leading_line_endings(&buf[index..n]);
     let start = index + (crlf_num as usize);
-    let mut end = 0usize;
-    for i in start..n {
-        if line_ending(buf[i]) {
-            end = i;
-            break;
-        }
-    }
+    let end = buf[start..n]
+        .iter()
+        .position(|b| line_ending(*b))
+        .map_or(0, |i| start + i);
     if end == 0 || start == end {
         return Err(InvalidPDFDocument);
     }
@@ -324,6 +586,31 @@ fn cal_xref_table_offset(sequence: &mut impl Sequence) -> Result<u64> {
 }
 
 impl PDFDescribe {
+    /// Returns the name of the application that produced the PDF.
+    pub fn get_producer(&self) -> Option<&str> {
+        self.producer.as_deref()
+    }
+    /// Returns the name of the application that created the original document.
+    pub fn get_creator(&self) -> Option<&str> {
+        self.creator.as_deref()
+    }
+    /// Returns the date the document was created.
+    pub fn get_creation_date(&self) -> Option<&str> {
+        self.creation_date.as_deref()
+    }
+    /// Returns the name of the person who created the document.
+    pub fn get_author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+    /// Returns the document title.
+    pub fn get_title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+    /// Returns the date the document was last modified.
+    pub fn get_mod_date(&self) -> Option<&str> {
+        self.mod_date.as_deref()
+    }
+
     pub(crate) fn new(dictionary: Dictionary) -> PDFDescribe {
         let encoding = PreDefinedEncoding::PDFDoc;
         let producer = convert_glyph_from_dict!(dictionary, PRODUCER, &encoding);
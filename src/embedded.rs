@@ -0,0 +1,218 @@
+use crate::action::read_file_name;
+use crate::annotation::{read_annotations, AnnotationKind};
+use crate::constants::{
+    AF_RELATIONSHIP, CHECK_SUM, CREATION_DATE, DESC, EF, EMBEDDED_FILES, F, FS, MOD_DATE, NAMES, PARAMS, SIZE, SUBTYPE, UF,
+};
+use crate::document::PDFDocument;
+use crate::error::Result;
+use crate::nametree::collect_names;
+use crate::objects::{Dictionary, PDFObject, PDFStrKind, PDFString, Stream};
+use crate::pstr::decode_text_string;
+use std::collections::HashMap;
+
+/// A file embedded in a document, listed by `/Names /EmbeddedFiles` or
+/// attached to a page by a file attachment annotation.
+#[derive(Clone, Debug)]
+pub struct EmbeddedFile {
+    /// The key of the file in the embedded files name tree.
+    key: Option<String>,
+    /// The reference of the file attachment annotation.
+    annotation: Option<(u32, u16)>,
+    /// The file name of the file specification, `/UF` preferred to `/F`.
+    file_name: Option<String>,
+    description: Option<String>,
+    /// The MIME type of the file, `/Subtype` of the embedded file stream.
+    mime_type: Option<String>,
+    /// The size of the decoded file, `/Params /Size`.
+    size: Option<u64>,
+    creation_date: Option<String>,
+    mod_date: Option<String>,
+    /// The MD5 digest of the decoded file, `/Params /CheckSum`.
+    checksum: Option<Vec<u8>>,
+    /// How the file relates to the document, `/AFRelationship`.
+    relationship: Option<String>,
+    /// The embedded file stream, still encoded.
+    stream: Stream,
+    /// The file specification dictionary.
+    attrs: Dictionary,
+}
+
+impl EmbeddedFile {
+    /// Reads the embedded file of a file specification.
+    ///
+    /// # Returns
+    ///
+    /// The file, or None if the file specification doesn't embed a file
+    fn read(document: &PDFDocument, file_spec: &PDFObject) -> Result<Option<Self>> {
+        let Some(attrs) = document.resolve(file_spec)?.into_owned().to_dict() else {
+            return Ok(None);
+        };
+        let Some(files) = attrs.get(EF) else {
+            return Ok(None);
+        };
+        let files = document.resolve(files)?;
+        // The stream of the Unicode file name, else of the byte string one
+        let mut stream = None;
+        for key in [UF, F] {
+            if let Some(file) = files.as_dict().and_then(|files| files.get(key))
+                && let PDFObject::Stream(file) = document.resolve(file)?.into_owned()
+            {
+                stream = Some(file);
+                break;
+            }
+        }
+        let Some(stream) = stream else {
+            return Ok(None);
+        };
+        let text = |attrs: &Dictionary, key: &str| -> Result<Option<String>> {
+            match attrs.get(key) {
+                Some(object) => Ok(document.resolve(object)?.as_string().map(decode_text_string)),
+                None => Ok(None),
+            }
+        };
+        let params = match stream.get_metadata().get(PARAMS) {
+            Some(params) => document.resolve(params)?.into_owned().to_dict(),
+            None => None,
+        };
+        let params = params.unwrap_or_else(|| Dictionary::new(HashMap::new()));
+        let size = match params.get(SIZE) {
+            Some(object) => document.resolve(object)?.as_i64().and_then(|size| u64::try_from(size).ok()),
+            None => None,
+        };
+        let checksum = match params.get(CHECK_SUM) {
+            Some(object) => document.resolve(object)?.as_string().map(|checksum| checksum.get_buf().clone()),
+            None => None,
+        };
+        let mime_type = match stream.get_metadata().get(SUBTYPE) {
+            Some(object) => document.resolve(object)?.as_name().cloned(),
+            None => None,
+        };
+        let relationship = match attrs.get(AF_RELATIONSHIP) {
+            Some(object) => document.resolve(object)?.as_name().cloned(),
+            None => None,
+        };
+        Ok(Some(EmbeddedFile {
+            key: None,
+            annotation: None,
+            file_name: read_file_name(document, Some(file_spec))?,
+            description: text(&attrs, DESC)?,
+            mime_type,
+            size,
+            creation_date: text(&params, CREATION_DATE)?,
+            mod_date: text(&params, MOD_DATE)?,
+            checksum,
+            relationship,
+            stream,
+            attrs,
+        }))
+    }
+
+    /// Returns the key of the file in the embedded files name tree, None for
+    /// files attached to a page.
+    pub fn get_key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    /// Returns the reference of the file attachment annotation, None for files
+    /// of the embedded files name tree.
+    pub fn get_annotation(&self) -> Option<(u32, u16)> {
+        self.annotation
+    }
+
+    /// Returns the name of the file, the Unicode `/UF` preferred to `/F`.
+    pub fn get_file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    /// Returns the description of the file, `/Desc`.
+    pub fn get_description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Returns the MIME type of the file, such as `text/xml`.
+    pub fn get_mime_type(&self) -> Option<&str> {
+        self.mime_type.as_deref()
+    }
+
+    /// Returns the size of the file in bytes, as recorded by the document.
+    pub fn get_size(&self) -> Option<u64> {
+        self.size
+    }
+
+    /// Returns the size of the file as stored in the document, after compression.
+    pub fn get_stored_size(&self) -> u64 {
+        self.stream.get_buf().len() as u64
+    }
+
+    /// Returns the date the file was created, usually in the PDF date format
+    /// `D:YYYYMMDDHHmmSSOHH'mm`.
+    pub fn get_creation_date(&self) -> Option<&str> {
+        self.creation_date.as_deref()
+    }
+
+    /// Returns the date the file was last modified.
+    pub fn get_mod_date(&self) -> Option<&str> {
+        self.mod_date.as_deref()
+    }
+
+    /// Returns the 16 byte MD5 digest of the file, as recorded by the document.
+    pub fn get_checksum(&self) -> Option<&[u8]> {
+        self.checksum.as_deref()
+    }
+
+    /// Returns how the file relates to the document, `/AFRelationship`: `Source`,
+    /// `Data`, `Alternative`, `Supplement`, `EncryptedPayload`, `FormData`,
+    /// `Schema` or `Unspecified`.
+    pub fn get_relationship(&self) -> Option<&str> {
+        self.relationship.as_deref()
+    }
+
+    /// Returns the file specification dictionary.
+    pub fn get_attrs(&self) -> &Dictionary {
+        &self.attrs
+    }
+
+    /// Extracts the content of the file, decoding the stream filters.
+    pub fn get_bytes(&self) -> Result<Vec<u8>> {
+        self.stream.get_decoded_buf()
+    }
+}
+
+/// Reads the files of the embedded files name tree of a document.
+///
+/// File specifications which don't embed a file are skipped.
+pub(crate) fn read_embedded_files(document: &PDFDocument) -> Result<Vec<EmbeddedFile>> {
+    let Some(names) = document.get_catalog().get(NAMES) else {
+        return Ok(Vec::new());
+    };
+    let names = document.resolve(names)?;
+    let Some(tree) = names.as_dict().and_then(|names| names.get(EMBEDDED_FILES)) else {
+        return Ok(Vec::new());
+    };
+    let mut files = Vec::new();
+    for (key, file_spec) in collect_names(document, tree)? {
+        if let Some(mut file) = EmbeddedFile::read(document, &file_spec)? {
+            file.key = Some(decode_text_string(&PDFString::new(PDFStrKind::Literal, key)));
+            files.push(file);
+        }
+    }
+    Ok(files)
+}
+
+/// Reads the files attached to a page by file attachment annotations.
+pub(crate) fn read_file_attachments(document: &PDFDocument, page: &Dictionary) -> Result<Vec<EmbeddedFile>> {
+    let mut files = Vec::new();
+    for annotation in read_annotations(document, page)? {
+        if !matches!(annotation.get_kind(), AnnotationKind::FileAttachment { .. }) {
+            continue;
+        }
+        let Some(file_spec) = annotation.get_attrs().get(FS) else {
+            continue;
+        };
+        if let Some(mut file) = EmbeddedFile::read(document, file_spec)? {
+            file.annotation = annotation.get_obj_ref();
+            files.push(file);
+        }
+    }
+    Ok(files)
+}
@@ -1,4 +1,5 @@
 /// Enum for pdf predefined encodings
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub(crate) enum PreDefinedEncoding {
     MacRoman,
     Standard,
@@ -13,12 +14,14 @@ include!("../encoding/Standard");
 include!("../encoding/WinAnsi");
 include!("../encoding/PDFDoc");
 include!("../encoding/MacExpert");
+include!("../encoding/GlyphList");
+include!("../encoding/ZapfDingbatsGlyphList");
 
 
 pub(crate) fn mapper_chr_from_u8(bytes: u8, encoding: &PreDefinedEncoding) -> Option<char> {
     match encoding {
         PreDefinedEncoding::PDFDoc => {
-            PDF_DOC_ENCODING[bytes as usize]
+            PDF_DOC_ENCODING.get(bytes as usize).copied().flatten()
         }
         _ => {
             let look_table = match encoding {
@@ -34,4 +37,98 @@ pub(crate) fn mapper_chr_from_u8(bytes: u8, encoding: &PreDefinedEncoding) -> Op
                 .next()?
         }
     }
-}
\ No newline at end of file
+}
+
+impl PreDefinedEncoding {
+    /// Gets the encoding named by a font's `/Encoding` or `/BaseEncoding` entry.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "MacRomanEncoding" => Some(PreDefinedEncoding::MacRoman),
+            "StandardEncoding" => Some(PreDefinedEncoding::Standard),
+            "WinAnsiEncoding" => Some(PreDefinedEncoding::WinAnsi),
+            "PDFDocEncoding" => Some(PreDefinedEncoding::PDFDoc),
+            "MacExpertEncoding" => Some(PreDefinedEncoding::MacExpert),
+            _ => None,
+        }
+    }
+}
+
+/// Gets the glyph name a predefined encoding maps a code to.
+///
+/// # Returns
+///
+/// The glyph name, or None for unused codes and `PDFDocEncoding`, which isn't a font encoding
+pub(crate) fn glyph_name_from_u8(code: u8, encoding: &PreDefinedEncoding) -> Option<&'static str> {
+    let table = match encoding {
+        PreDefinedEncoding::MacRoman => MAC_ROMAN_ENCODING,
+        PreDefinedEncoding::Standard => STANDARD_ENCODING,
+        PreDefinedEncoding::WinAnsi => WIN_ANSI_ENCODING,
+        PreDefinedEncoding::MacExpert => MAC_EXPERT_ENCODING,
+        PreDefinedEncoding::PDFDoc => return None,
+    };
+    table.iter()
+        .find(|entry| entry.0 == code)
+        .map(|entry| entry.1)
+        .filter(|name| *name != ".notdef")
+}
+
+/// Maps a glyph name to its Unicode text, following the Adobe Glyph List specification.
+///
+/// A suffix after the first period is ignored, and ligature names joining
+/// components with underscores map to the text of each component. Components
+/// are looked up in the Adobe Glyph List or read as `uniXXXX` (one or more
+/// UTF-16 code units) or `uXXXX[XX]`.
+///
+/// # Returns
+///
+/// The text, or None if none of the components is known
+pub(crate) fn glyph_name_to_unicode(name: &str) -> Option<String> {
+    let name = name.split('.').next().unwrap_or_default();
+    let text = name.split('_').filter_map(component_to_unicode).collect::<String>();
+    if text.is_empty() { None } else { Some(text) }
+}
+
+/// Maps a glyph name of the ZapfDingbats font to its Unicode text.
+pub(crate) fn zapf_dingbats_glyph_name_to_unicode(name: &str) -> Option<String> {
+    ZAPF_DINGBATS_GLYPH_LIST.binary_search_by(|entry| entry.0.cmp(name))
+        .ok()
+        .map(|index| ZAPF_DINGBATS_GLYPH_LIST[index].1.to_string())
+        .or_else(|| glyph_name_to_unicode(name))
+}
+
+fn component_to_unicode(component: &str) -> Option<String> {
+    if let Ok(index) = GLYPH_LIST.binary_search_by(|entry| entry.0.cmp(component)) {
+        return Some(GLYPH_LIST[index].1.to_string());
+    }
+    let is_hex = |hex: &str| !hex.is_empty() && hex.bytes().all(|b| b.is_ascii_hexdigit() && !b.is_ascii_lowercase());
+    if let Some(hex) = component.strip_prefix("uni") && hex.len() % 4 == 0 && is_hex(hex) {
+        let units = hex.as_bytes()
+            .chunks(4)
+            .map(|unit| u16::from_str_radix(std::str::from_utf8(unit).unwrap_or_default(), 16).unwrap_or(0))
+            .collect::<Vec<u16>>();
+        return String::from_utf16(&units).ok();
+    }
+    if let Some(hex) = component.strip_prefix('u') && (4..=6).contains(&hex.len()) && is_hex(hex) {
+        return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32).map(String::from);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glyph_name_to_unicode() {
+        assert_eq!(glyph_name_to_unicode("Adieresis").as_deref(), Some("Ä"));
+        assert_eq!(glyph_name_to_unicode("uni20AC").as_deref(), Some("€"));
+        assert_eq!(glyph_name_to_unicode("uniD83DDE00").as_deref(), Some("😀"));
+        assert_eq!(glyph_name_to_unicode("u1F600").as_deref(), Some("😀"));
+        assert_eq!(glyph_name_to_unicode("f_f_i").as_deref(), Some("ffi"));
+        assert_eq!(glyph_name_to_unicode("one.oldstyle").as_deref(), Some("1"));
+        // Lowercase hexadecimal digits aren't allowed
+        assert_eq!(glyph_name_to_unicode("uni20ac"), None);
+        assert_eq!(glyph_name_to_unicode("g42"), None);
+        assert_eq!(zapf_dingbats_glyph_name_to_unicode("a1").as_deref(), Some("✁"));
+    }
+}
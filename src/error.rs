@@ -34,5 +34,13 @@ pub enum PDFError {
     #[error("{0}")]
     FloatParseError(#[from] ParseFloatError),
     #[error("{0}")]
-    PDFObjectCastError(&'static str)
+    PDFObjectCastError(&'static str),
+    #[error("Stream decode error:{0}")]
+    StreamDecodeError(String),
+    #[error("Image decode error:{0}")]
+    ImageDecodeError(String),
+    #[error("Render error:{0}")]
+    RenderError(String),
+    #[error("Form error:{0}")]
+    FormError(String),
 }
@@ -0,0 +1,727 @@
+use crate::constants::{
+    ACRO_FORM, ANNOTS, AP, AS, BASE_FONT, BBOX, CONTENTS, DR, ENCODING, F, FIELDS, FONT, I, MATRIX, N,
+    NEED_APPEARANCES, RECT, RESOURCES, ROOT, SUBTYPE, TYPE, V, XOBJECT,
+};
+use crate::content::{ContentParser, Operator};
+use crate::document::PDFDocument;
+use crate::error::PDFError::FormError;
+use crate::error::Result;
+use crate::font::Font;
+use crate::form::{AcroForm, FieldType, FieldWidget, FormField};
+use crate::graphics::Matrix;
+use crate::objects::{Dictionary, PDFNumber, PDFObject, PDFStrKind, PDFString, Stream};
+use crate::pstr::encode_text_string;
+use crate::writer::{format_number, write_object, IncrementalUpdate};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::rc::Rc;
+
+/// The space between the border of a widget and its text.
+const PADDING: f64 = 2.0;
+/// The font size of automatically sized multiline text and list boxes.
+const AUTO_FONT_SIZE: f64 = 12.0;
+/// The colour highlighting the selected options of list boxes.
+const SELECTION_COLOR: &str = "0.6 0.75 0.86 rg";
+/// The check mark of ZapfDingbats, drawn by the check boxes without an appearance.
+const CHECK_MARK: u8 = b'4';
+
+/// Field flags changing how text is laid out and which values are accepted.
+const FLAG_MULTILINE: u32 = 1 << 12;
+const FLAG_PASSWORD: u32 = 1 << 13;
+const FLAG_EDIT: u32 = 1 << 18;
+const FLAG_MULTI_SELECT: u32 = 1 << 21;
+const FLAG_COMB: u32 = 1 << 24;
+/// Annotation flag of widgets which aren't displayed.
+const FLAG_HIDDEN: u32 = 2;
+
+/// Fills the fields of an interactive form and saves the document with an
+/// incremental update.
+///
+/// The normal appearances of the changed widgets are regenerated from their
+/// default appearance string and justification, so that the values display in
+/// viewers which don't construct appearances themselves.
+///
+/// ```no_run
+/// # use pdf_rs::document::PDFDocument;
+/// # use pdf_rs::fill::FormFiller;
+/// # fn main() -> pdf_rs::error::Result<()> {
+/// let document = PDFDocument::open("form.pdf".into())?;
+/// let mut filler = FormFiller::new(&document)?;
+/// filler.set_text("name", "Alice")?;
+/// filler.set_checked("agree", true)?;
+/// filler.save("filled.pdf")?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct FormFiller<'a> {
+    document: &'a PDFDocument,
+    form: AcroForm,
+    update: IncrementalUpdate<'a>,
+    /// The fonts of the default resources by name, None for fonts which can't be used.
+    fonts: HashMap<String, Option<Rc<FieldFont>>>,
+    flatten: bool,
+}
+
+/// A simple font drawing the text of fields.
+struct FieldFont {
+    /// The font dictionary or a reference to it, for the appearance resources.
+    resource: PDFObject,
+    font: Font,
+    /// The code of each character the encoding of the font covers.
+    codes: HashMap<char, u8>,
+}
+
+/// The font, size and colour of a default appearance string, `/DA`.
+struct DefaultAppearance {
+    font_name: String,
+    /// The font size, 0 to fit the text to the widget.
+    font_size: f64,
+    /// The operator setting the fill colour.
+    color: String,
+}
+
+impl<'a> FormFiller<'a> {
+    /// Creates a filler for the interactive form of a document.
+    ///
+    /// # Returns
+    ///
+    /// The filler, or a FormError if the document doesn't have a form, is
+    /// encrypted or has a cross-reference stream
+    pub fn new(document: &'a PDFDocument) -> Result<Self> {
+        let form = document.get_form()?.ok_or_else(|| FormError("the document has no interactive form".to_string()))?;
+        Ok(FormFiller { document, form, update: IncrementalUpdate::new(document)?, fonts: HashMap::new(), flatten: false })
+    }
+
+    /// Sets whether the fields are flattened when the document is saved: their
+    /// appearances are drawn into the page contents and the widgets and fields
+    /// are removed, so the values can't be edited anymore.
+    pub fn set_flatten(&mut self, flatten: bool) {
+        self.flatten = flatten;
+    }
+
+    /// Sets the text of a text field, truncated to its maximum length.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The fully qualified name of the field
+    /// * `text` - The text, with line breaks for multiline fields
+    pub fn set_text(&mut self, name: &str, text: &str) -> Result<()> {
+        let field = self.find_field(name, &[FieldType::Text])?;
+        let text = match field.get_max_len() {
+            Some(max_len) => text.chars().take(max_len as usize).collect(),
+            None => text.to_string(),
+        };
+        self.set_field_entry(&field, V, PDFObject::String(encode_text_string(&text)))?;
+        let display = match field.get_flags() & FLAG_PASSWORD {
+            0 => text,
+            _ => "*".repeat(text.chars().count()),
+        };
+        for widget in field.get_widgets() {
+            self.write_text_appearance(&field, widget, &display)?;
+        }
+        Ok(())
+    }
+
+    /// Checks or unchecks a check box.
+    ///
+    /// Checked widgets take their own on state, and a check mark appearance is
+    /// generated for widgets which have none.
+    pub fn set_checked(&mut self, name: &str, checked: bool) -> Result<()> {
+        let field = self.find_field(name, &[FieldType::CheckBox])?;
+        let on_state = field.get_widgets().iter().find_map(FieldWidget::get_on_state).unwrap_or("Yes").to_string();
+        let value = if checked { on_state.as_str() } else { "Off" };
+        self.set_field_entry(&field, V, PDFObject::Named(value.to_string()))?;
+        for widget in field.get_widgets() {
+            let state = match widget.get_on_state() {
+                Some(state) => state.to_string(),
+                None => self.write_check_appearance(widget)?,
+            };
+            self.set_appearance_state(widget, if checked && state == on_state { &state } else { "Off" })?;
+        }
+        Ok(())
+    }
+
+    /// Selects a radio button.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The fully qualified name of the field
+    /// * `state` - The on state of the widget to select, or `Off` to clear the selection
+    pub fn set_radio(&mut self, name: &str, state: &str) -> Result<()> {
+        let field = self.find_field(name, &[FieldType::RadioButton])?;
+        if state != "Off" && !field.get_widgets().iter().any(|widget| widget.get_on_state() == Some(state)) {
+            return Err(FormError(format!("{} is not a state of the field {}", state, name)));
+        }
+        self.set_field_entry(&field, V, PDFObject::Named(state.to_string()))?;
+        for widget in field.get_widgets() {
+            let selected = widget.get_on_state() == Some(state);
+            self.set_appearance_state(widget, if selected { state } else { "Off" })?;
+        }
+        Ok(())
+    }
+
+    /// Selects the options of a combo box or a list box.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The fully qualified name of the field
+    /// * `values` - The export values of the options, several only for multiple
+    ///   selection lists. Editable combo boxes also accept other text.
+    pub fn set_choice(&mut self, name: &str, values: &[&str]) -> Result<()> {
+        let field = self.find_field(name, &[FieldType::ComboBox, FieldType::ListBox])?;
+        if values.len() > 1 && field.get_flags() & FLAG_MULTI_SELECT == 0 {
+            return Err(FormError(format!("the field {} doesn't allow multiple selection", name)));
+        }
+        let options = field.get_options();
+        let editable = field.get_field_type() == FieldType::ComboBox && field.get_flags() & FLAG_EDIT != 0;
+        let mut indices = Vec::new();
+        for value in values {
+            match options.iter().position(|(export, _)| export == value) {
+                Some(index) => indices.push(index),
+                None if editable || options.is_empty() => {}
+                None => return Err(FormError(format!("{} is not an option of the field {}", value, name))),
+            }
+        }
+        let value = match values {
+            [] => PDFObject::Null,
+            [value] => PDFObject::String(encode_text_string(value)),
+            values => PDFObject::Array(values.iter().map(|value| PDFObject::String(encode_text_string(value))).collect()),
+        };
+        self.set_field_entry(&field, V, value)?;
+        if field.get_field_type() == FieldType::ListBox {
+            indices.sort_unstable();
+            let indices = indices.iter().map(|index| PDFObject::Number(PDFNumber::Unsigned(*index as u64))).collect();
+            self.set_field_entry(&field, I, PDFObject::Array(indices))?;
+        }
+        for widget in field.get_widgets() {
+            match field.get_field_type() {
+                FieldType::ComboBox => {
+                    // The combo box shows the text of the selected option
+                    let text = values.first().map(|value| match options.iter().find(|(export, _)| export == value) {
+                        Some((_, text)) => text.clone(),
+                        None => value.to_string(),
+                    });
+                    self.write_text_appearance(&field, widget, &text.unwrap_or_default())?;
+                }
+                _ => self.write_list_appearance(&field, widget, values)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the document with the filled fields to a file.
+    pub fn save(self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, self.into_bytes()?)?;
+        Ok(())
+    }
+
+    /// Writes the document with the filled fields: the original bytes followed
+    /// by an incremental update holding the changed objects.
+    pub fn into_bytes(mut self) -> Result<Vec<u8>> {
+        if self.flatten {
+            self.flatten_fields()?;
+        }
+        self.update.into_bytes()
+    }
+
+    /// Finds a field of one of the given types which can be updated.
+    fn find_field(&self, name: &str, types: &[FieldType]) -> Result<FormField> {
+        let field = self.form.get_field(name).ok_or_else(|| FormError(format!("the field {} doesn't exist", name)))?;
+        if !types.contains(&field.get_field_type()) {
+            return Err(FormError(format!("the field {} is a {:?} field", name, field.get_field_type())));
+        }
+        if field.get_obj_ref().is_none() {
+            return Err(FormError(format!("the field {} isn't an indirect object", name)));
+        }
+        Ok(field.clone())
+    }
+
+    /// Gets a dictionary as changed by the update.
+    fn get_dict(&self, obj_ref: (u32, u16)) -> Result<Dictionary> {
+        self.update
+            .get(obj_ref)?
+            .and_then(PDFObject::to_dict)
+            .ok_or_else(|| FormError(format!("the object {} {} isn't a dictionary", obj_ref.0, obj_ref.1)))
+    }
+
+    /// Sets an entry of a field dictionary, removing it for the null object.
+    fn set_field_entry(&mut self, field: &FormField, key: &str, value: PDFObject) -> Result<()> {
+        let Some(obj_ref) = field.get_obj_ref() else {
+            return Ok(());
+        };
+        let mut attrs = self.get_dict(obj_ref)?;
+        match value {
+            PDFObject::Null => {
+                attrs.remove(key);
+            }
+            value => {
+                attrs.insert(key, value);
+            }
+        }
+        self.update.set(obj_ref, PDFObject::Dict(attrs));
+        Ok(())
+    }
+
+    /// Sets the appearance state of a check box or radio button widget, `/AS`.
+    fn set_appearance_state(&mut self, widget: &FieldWidget, state: &str) -> Result<()> {
+        let Some(obj_ref) = widget.get_obj_ref() else {
+            return Ok(());
+        };
+        let mut attrs = self.get_dict(obj_ref)?;
+        attrs.insert(AS, PDFObject::Named(state.to_string()));
+        self.update.set(obj_ref, PDFObject::Dict(attrs));
+        Ok(())
+    }
+
+    /// Sets the normal appearance of a widget, `/AP /N`, replacing its other appearances.
+    fn set_normal_appearance(&mut self, widget: &FieldWidget, appearance: PDFObject) -> Result<()> {
+        let Some(obj_ref) = widget.get_obj_ref() else {
+            return Ok(());
+        };
+        let mut attrs = self.get_dict(obj_ref)?;
+        let mut appearances = Dictionary::new(HashMap::new());
+        appearances.insert(N, appearance);
+        attrs.insert(AP, PDFObject::Dict(appearances));
+        self.update.set(obj_ref, PDFObject::Dict(attrs));
+        Ok(())
+    }
+
+    /// Adds a form XObject of the size of a widget.
+    fn add_form_xobject(&mut self, widget: &FieldWidget, resources: Dictionary, content: Vec<u8>) -> PDFObject {
+        let [x0, y0, x1, y1] = widget.get_rect();
+        let mut metadata = Dictionary::new(HashMap::new());
+        metadata.insert(TYPE, PDFObject::Named(XOBJECT.to_string()));
+        metadata.insert(SUBTYPE, PDFObject::Named("Form".to_string()));
+        metadata.insert(BBOX, PDFObject::Array(vec![number(0.0), number(0.0), number(x1 - x0), number(y1 - y0)]));
+        metadata.insert(RESOURCES, PDFObject::Dict(resources));
+        let (obj_num, gen_num) = self.update.add(PDFObject::Stream(Stream::new(metadata, content)));
+        PDFObject::ObjectRef(obj_num, gen_num)
+    }
+
+    /// Gets the font a default appearance string names, from the default resources
+    /// of the form, falling back to Helvetica for missing and composite fonts.
+    fn get_font(&mut self, name: &str) -> Result<Rc<FieldFont>> {
+        if !self.fonts.contains_key(name) {
+            let font = self.load_resource_font(name)?.map(Rc::new);
+            self.fonts.insert(name.to_string(), font);
+        }
+        if let Some(Some(font)) = self.fonts.get(name) {
+            return Ok(font.clone());
+        }
+        Ok(Rc::new(self.load_standard_font("Helvetica")?))
+    }
+
+    fn load_resource_font(&self, name: &str) -> Result<Option<FieldFont>> {
+        let Some(resources) = self.form.get_attrs().get(DR) else {
+            return Ok(None);
+        };
+        let resources = self.document.resolve(resources)?;
+        let Some(fonts) = resources.as_dict().and_then(|resources| resources.get(FONT)) else {
+            return Ok(None);
+        };
+        let fonts = self.document.resolve(fonts)?;
+        let Some(resource) = fonts.as_dict().and_then(|fonts| fonts.get(name)) else {
+            return Ok(None);
+        };
+        let Some(dict) = self.document.resolve(resource)?.into_owned().to_dict() else {
+            return Ok(None);
+        };
+        let font = Font::load(self.document, &dict)?;
+        if font.get_cmap().is_some() {
+            return Ok(None);
+        }
+        Ok(Some(FieldFont::new(resource.clone(), font)))
+    }
+
+    /// Loads a standard 14 font, in WinAnsiEncoding except for the symbolic fonts.
+    fn load_standard_font(&self, base_font: &str) -> Result<FieldFont> {
+        let mut dict = Dictionary::new(HashMap::new());
+        dict.insert(TYPE, PDFObject::Named(FONT.to_string()));
+        dict.insert(SUBTYPE, PDFObject::Named("Type1".to_string()));
+        dict.insert(BASE_FONT, PDFObject::Named(base_font.to_string()));
+        if base_font != "ZapfDingbats" && base_font != "Symbol" {
+            dict.insert(ENCODING, PDFObject::Named("WinAnsiEncoding".to_string()));
+        }
+        let font = Font::load(self.document, &dict)?;
+        Ok(FieldFont::new(PDFObject::Dict(dict), font))
+    }
+
+    /// Writes the appearance of a text field or a combo box showing a single text.
+    fn write_text_appearance(&mut self, field: &FormField, widget: &FieldWidget, text: &str) -> Result<()> {
+        let appearance = DefaultAppearance::parse(field.get_default_appearance().unwrap_or_default());
+        let font = self.get_font(&appearance.font_name)?;
+        let [x0, y0, x1, y1] = widget.get_rect();
+        let (width, height) = (x1 - x0, y1 - y0);
+        let (ascent, descent) = (font.font.get_ascent(), font.font.get_descent());
+        let flags = field.get_flags();
+        let mut lines = Vec::new();
+        let font_size;
+        if flags & FLAG_MULTILINE != 0 {
+            font_size = if appearance.font_size > 0.0 { appearance.font_size } else { AUTO_FONT_SIZE };
+            let line_height = (ascent - descent) * font_size;
+            let mut y = height - PADDING - ascent * font_size;
+            for line in text.split('\n') {
+                for codes in font.wrap(line, (width - 2.0 * PADDING) / font_size) {
+                    let x = align(field.get_alignment(), width, font.get_width(&codes) * font_size);
+                    lines.push((x, y, codes));
+                    y -= line_height;
+                }
+            }
+        } else {
+            let codes = font.encode(text);
+            let text_width = font.get_width(&codes);
+            font_size = match appearance.font_size {
+                size if size > 0.0 => size,
+                // Fit the text to the height of the widget, and to its width if it's wider
+                _ => {
+                    let size = (height - 2.0 * PADDING) / (ascent - descent);
+                    let size = if text_width > 0.0 { size.min((width - 2.0 * PADDING) / text_width) } else { size };
+                    size.max(1.0)
+                }
+            };
+            let y = (height - (ascent - descent) * font_size) / 2.0 - descent * font_size;
+            match field.get_max_len() {
+                // Comb fields spread the characters in equal cells
+                Some(max_len) if flags & FLAG_COMB != 0 && max_len > 0 => {
+                    let cell = width / max_len as f64;
+                    for (i, code) in codes.iter().enumerate() {
+                        let x = cell * i as f64 + (cell - font.get_width(&[*code]) * font_size) / 2.0;
+                        lines.push((x, y, vec![*code]));
+                    }
+                }
+                _ => lines.push((align(field.get_alignment(), width, text_width * font_size), y, codes)),
+            }
+        }
+        let mut content = Vec::new();
+        content.extend_from_slice(b"/Tx BMC\nq\n");
+        content.extend_from_slice(format!("1 1 {} {} re W n\n", format_number(width - 2.0), format_number(height - 2.0)).as_bytes());
+        write_lines(&mut content, &appearance, font_size, &lines);
+        content.extend_from_slice(b"Q\nEMC\n");
+        let resources = font_resources(&appearance.font_name, &font);
+        let appearance = self.add_form_xobject(widget, resources, content);
+        self.set_normal_appearance(widget, appearance)
+    }
+
+    /// Writes the appearance of a list box, the selected options highlighted.
+    fn write_list_appearance(&mut self, field: &FormField, widget: &FieldWidget, values: &[&str]) -> Result<()> {
+        let appearance = DefaultAppearance::parse(field.get_default_appearance().unwrap_or_default());
+        let font = self.get_font(&appearance.font_name)?;
+        let [x0, y0, x1, y1] = widget.get_rect();
+        let (width, height) = (x1 - x0, y1 - y0);
+        let (ascent, descent) = (font.font.get_ascent(), font.font.get_descent());
+        let font_size = if appearance.font_size > 0.0 { appearance.font_size } else { AUTO_FONT_SIZE };
+        let line_height = (ascent - descent) * font_size;
+        let mut content = Vec::new();
+        content.extend_from_slice(b"/Tx BMC\nq\n");
+        content.extend_from_slice(format!("1 1 {} {} re W n\n", format_number(width - 2.0), format_number(height - 2.0)).as_bytes());
+        let mut lines = Vec::new();
+        for (i, (export, text)) in field.get_options().iter().enumerate() {
+            let top = height - PADDING - line_height * i as f64;
+            if values.contains(&export.as_str()) {
+                content.extend_from_slice(format!(
+                    "{} 1 {} {} {} re f\n",
+                    SELECTION_COLOR,
+                    format_number(top - line_height),
+                    format_number(width - 2.0),
+                    format_number(line_height)
+                ).as_bytes());
+            }
+            let codes = font.encode(text);
+            lines.push((PADDING, top - ascent * font_size, codes));
+        }
+        write_lines(&mut content, &appearance, font_size, &lines);
+        content.extend_from_slice(b"Q\nEMC\n");
+        let resources = font_resources(&appearance.font_name, &font);
+        let appearance = self.add_form_xobject(widget, resources, content);
+        self.set_normal_appearance(widget, appearance)
+    }
+
+    /// Writes check mark and empty appearances for a check box widget without any.
+    ///
+    /// # Returns
+    ///
+    /// The on state of the widget, `Yes`
+    fn write_check_appearance(&mut self, widget: &FieldWidget) -> Result<String> {
+        let font = self.load_standard_font("ZapfDingbats")?;
+        let [x0, y0, x1, y1] = widget.get_rect();
+        let (width, height) = (x1 - x0, y1 - y0);
+        let font_size = width.min(height) * 0.8;
+        let x = (width - font.get_width(&[CHECK_MARK]) * font_size) / 2.0;
+        let y = (height - (font.font.get_ascent() + font.font.get_descent()) * font_size) / 2.0;
+        let appearance = DefaultAppearance { font_name: "ZaDb".to_string(), font_size, color: "0 g".to_string() };
+        let mut content = Vec::new();
+        write_lines(&mut content, &appearance, font_size, &[(x, y, vec![CHECK_MARK])]);
+        let on = self.add_form_xobject(widget, font_resources("ZaDb", &font), content);
+        let off = self.add_form_xobject(widget, Dictionary::new(HashMap::new()), Vec::new());
+        let mut states = Dictionary::new(HashMap::new());
+        states.insert("Yes", on);
+        states.insert("Off", off);
+        self.set_normal_appearance(widget, PDFObject::Dict(states))?;
+        Ok("Yes".to_string())
+    }
+
+    /// Draws the normal appearances of the widgets into the contents of their
+    /// pages, then removes the widgets from the pages and the fields from the form.
+    fn flatten_fields(&mut self) -> Result<()> {
+        let mut pages = BTreeMap::<usize, Vec<(u32, u16)>>::new();
+        for field in self.form.get_fields() {
+            for widget in field.get_widgets() {
+                if let (Some(obj_ref), Some(page)) = (widget.get_obj_ref(), widget.get_page()) {
+                    pages.entry(page).or_default().push(obj_ref);
+                }
+            }
+        }
+        for (index, widgets) in pages {
+            self.flatten_page(index, &widgets)?;
+        }
+        // Remove the fields, keeping the rest of the form
+        let Some(root) = self.document.get_trailer().get(ROOT).and_then(PDFObject::as_object_ref) else {
+            return Ok(());
+        };
+        let mut catalog = self.get_dict(root)?;
+        let mut form = self.form.get_attrs().clone();
+        form.insert(FIELDS, PDFObject::Array(Vec::new()));
+        form.remove(NEED_APPEARANCES);
+        match catalog.get(ACRO_FORM).and_then(PDFObject::as_object_ref) {
+            Some(obj_ref) => self.update.set(obj_ref, PDFObject::Dict(form)),
+            None => {
+                catalog.insert(ACRO_FORM, PDFObject::Dict(form));
+                self.update.set(root, PDFObject::Dict(catalog));
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws the appearances of widgets into the contents of a page and removes them from `/Annots`.
+    fn flatten_page(&mut self, index: usize, widgets: &[(u32, u16)]) -> Result<()> {
+        let Some(page) = self.document.get_page(index) else {
+            return Ok(());
+        };
+        let mut attrs = self.get_dict(page.get_obj_ref())?;
+        // The resources may be inherited or shared, the page gets its own copy
+        let mut resources = match page.get_attrs().get(RESOURCES) {
+            Some(resources) => self.update.resolve(resources)?.to_dict().unwrap_or_else(|| Dictionary::new(HashMap::new())),
+            None => Dictionary::new(HashMap::new()),
+        };
+        let mut xobjects = match resources.get(XOBJECT) {
+            Some(xobjects) => self.update.resolve(xobjects)?.to_dict().unwrap_or_else(|| Dictionary::new(HashMap::new())),
+            None => Dictionary::new(HashMap::new()),
+        };
+        let mut content = b"Q\n".to_vec();
+        for obj_ref in widgets {
+            let widget = self.get_dict(*obj_ref)?;
+            if widget.get(F).and_then(PDFObject::as_i64).is_some_and(|flags| flags as u32 & FLAG_HIDDEN != 0) {
+                continue;
+            }
+            let Some(appearance) = self.get_normal_appearance(&widget)? else {
+                continue;
+            };
+            let Some(PDFObject::Stream(stream)) = self.update.get(appearance)? else {
+                continue;
+            };
+            let rect = get_rect(&widget);
+            let Some(matrix) = fit_appearance(stream.get_metadata(), rect) else {
+                continue;
+            };
+            let mut name = format!("Fm{}", appearance.0);
+            while xobjects.contain(&name) {
+                name.push('_');
+            }
+            xobjects.insert(&name, PDFObject::ObjectRef(appearance.0, appearance.1));
+            content.extend_from_slice(b"q ");
+            for value in [matrix.a, matrix.b, matrix.c, matrix.d, matrix.e, matrix.f] {
+                content.extend_from_slice(format_number(value).as_bytes());
+                content.push(b' ');
+            }
+            content.extend_from_slice(b"cm ");
+            write_object(&mut content, &PDFObject::Named(name));
+            content.extend_from_slice(b" Do Q\n");
+        }
+        resources.insert(XOBJECT, PDFObject::Dict(xobjects));
+        // The original contents are wrapped in q and Q, so their state doesn't leak
+        let mut contents = Vec::new();
+        let (obj_num, gen_num) = self.update.add(PDFObject::Stream(Stream::new(Dictionary::new(HashMap::new()), b"q\n".to_vec())));
+        contents.push(PDFObject::ObjectRef(obj_num, gen_num));
+        match attrs.get(CONTENTS) {
+            Some(PDFObject::Array(streams)) => contents.extend(streams.iter().cloned()),
+            Some(stream) => match self.update.resolve(stream)? {
+                PDFObject::Array(streams) => contents.extend(streams),
+                _ => contents.push(stream.clone()),
+            },
+            None => {}
+        }
+        let (obj_num, gen_num) = self.update.add(PDFObject::Stream(Stream::new(Dictionary::new(HashMap::new()), content)));
+        contents.push(PDFObject::ObjectRef(obj_num, gen_num));
+        let flattened = widgets.iter().copied().collect::<HashSet<_>>();
+        if let Some(annots) = attrs.get(ANNOTS) {
+            let annots = self.update.resolve(annots)?;
+            let annots = annots.as_array().unwrap_or_default().iter()
+                .filter(|annot| annot.as_object_ref().is_none_or(|obj_ref| !flattened.contains(&obj_ref)))
+                .cloned()
+                .collect();
+            attrs.insert(ANNOTS, PDFObject::Array(annots));
+        }
+        attrs.insert(CONTENTS, PDFObject::Array(contents));
+        attrs.insert(RESOURCES, PDFObject::Dict(resources));
+        self.update.set(page.get_obj_ref(), PDFObject::Dict(attrs));
+        Ok(())
+    }
+
+    /// Gets the reference of the normal appearance of a widget, chosen by `/AS`
+    /// when the appearance has several states.
+    fn get_normal_appearance(&self, widget: &Dictionary) -> Result<Option<(u32, u16)>> {
+        let Some(appearances) = widget.get(AP) else {
+            return Ok(None);
+        };
+        let appearances = self.update.resolve(appearances)?;
+        let Some(normal) = appearances.as_dict().and_then(|appearances| appearances.get(N)) else {
+            return Ok(None);
+        };
+        match self.update.resolve(normal)? {
+            PDFObject::Dict(states) => {
+                let state = widget.get(AS).and_then(PDFObject::as_name);
+                Ok(state.and_then(|state| states.get(state)).and_then(PDFObject::as_object_ref))
+            }
+            _ => Ok(normal.as_object_ref()),
+        }
+    }
+}
+
+impl FieldFont {
+    fn new(resource: PDFObject, font: Font) -> Self {
+        let mut codes = HashMap::new();
+        for code in 0..=255u8 {
+            let text = font.get_unicode(code as u32).unwrap_or_default();
+            let mut chars = text.chars();
+            if let (Some(chr), None) = (chars.next(), chars.next()) {
+                codes.entry(chr).or_insert(code);
+            }
+        }
+        FieldFont { resource, font, codes }
+    }
+
+    /// Encodes text with the font, dropping the characters it can't show.
+    fn encode(&self, text: &str) -> Vec<u8> {
+        text.chars().filter_map(|chr| self.codes.get(&chr).copied()).collect()
+    }
+
+    /// Gets the width of encoded text, in unscaled text space units.
+    fn get_width(&self, codes: &[u8]) -> f64 {
+        codes.iter().map(|code| self.font.get_advance(*code as u32)).sum()
+    }
+
+    /// Wraps a line of text at spaces to fit a width, in unscaled text space units.
+    fn wrap(&self, text: &str, width: f64) -> Vec<Vec<u8>> {
+        let mut lines = Vec::new();
+        let mut line = Vec::new();
+        for word in text.split(' ') {
+            let word = self.encode(word);
+            let mut candidate = line.clone();
+            if !candidate.is_empty() {
+                candidate.extend(self.encode(" "));
+            }
+            candidate.extend_from_slice(&word);
+            if !line.is_empty() && self.get_width(&candidate) > width {
+                lines.push(std::mem::replace(&mut line, word));
+            } else {
+                line = candidate;
+            }
+        }
+        lines.push(line);
+        lines
+    }
+}
+
+impl DefaultAppearance {
+    /// Parses a default appearance string such as `/Helv 12 Tf 0 0 1 rg`.
+    fn parse(default_appearance: &str) -> Self {
+        let mut appearance = DefaultAppearance { font_name: "Helv".to_string(), font_size: 0.0, color: "0 g".to_string() };
+        for operation in ContentParser::new(default_appearance.as_bytes().to_vec()).map_while(Result::ok) {
+            let operands = operation.get_operands();
+            match operation.get_operator() {
+                Operator::SetFont => {
+                    if let Some(name) = operands.first().and_then(PDFObject::as_name) {
+                        appearance.font_name = name.clone();
+                    }
+                    appearance.font_size = operands.get(1).and_then(PDFObject::as_f64).unwrap_or(0.0);
+                }
+                operator @ (Operator::SetFillGray | Operator::SetFillRGB | Operator::SetFillCMYK) => {
+                    let mut color = operands.iter().filter_map(PDFObject::as_f64).map(format_number).collect::<Vec<_>>();
+                    color.push(operator.get_name().to_string());
+                    appearance.color = color.join(" ");
+                }
+                _ => {}
+            }
+        }
+        appearance
+    }
+}
+
+/// Writes lines of encoded text, each at its own baseline origin.
+fn write_lines(content: &mut Vec<u8>, appearance: &DefaultAppearance, font_size: f64, lines: &[(f64, f64, Vec<u8>)]) {
+    content.extend_from_slice(b"BT\n");
+    write_object(content, &PDFObject::Named(appearance.font_name.clone()));
+    content.extend_from_slice(format!(" {} Tf\n{}\n", format_number(font_size), appearance.color).as_bytes());
+    for (x, y, codes) in lines {
+        content.extend_from_slice(format!("1 0 0 1 {} {} Tm ", format_number(*x), format_number(*y)).as_bytes());
+        write_object(content, &PDFObject::String(PDFString::new(PDFStrKind::Literal, codes.clone())));
+        content.extend_from_slice(b" Tj\n");
+    }
+    content.extend_from_slice(b"ET\n");
+}
+
+/// Creates the resources of an appearance using a font.
+fn font_resources(name: &str, font: &FieldFont) -> Dictionary {
+    let mut fonts = Dictionary::new(HashMap::new());
+    fonts.insert(name, font.resource.clone());
+    let mut resources = Dictionary::new(HashMap::new());
+    resources.insert(FONT, PDFObject::Dict(fonts));
+    resources
+}
+
+/// Computes the horizontal position of text by its justification, `/Q`.
+fn align(alignment: u32, width: f64, text_width: f64) -> f64 {
+    match alignment {
+        1 => (width - text_width) / 2.0,
+        2 => width - PADDING - text_width,
+        _ => PADDING,
+    }
+}
+
+/// Reads the normalized rectangle of an annotation.
+fn get_rect(attrs: &Dictionary) -> [f64; 4] {
+    let values = attrs.get_array_value(RECT).unwrap_or_default();
+    match values.iter().filter_map(PDFObject::as_f64).collect::<Vec<_>>().as_slice() {
+        [x0, y0, x1, y1, ..] => [x0.min(*x1), y0.min(*y1), x0.max(*x1), y0.max(*y1)],
+        _ => [0.0; 4],
+    }
+}
+
+/// Computes the matrix drawing an appearance in the rectangle of its annotation.
+///
+/// The bounding box transformed by the matrix of the appearance is mapped to
+/// the rectangle, as viewers draw annotation appearances.
+///
+/// # Returns
+///
+/// The matrix, or None if the bounding box is empty
+fn fit_appearance(metadata: &Dictionary, rect: [f64; 4]) -> Option<Matrix> {
+    let bbox = metadata.get_array_value(BBOX)?.iter().filter_map(PDFObject::as_f64).collect::<Vec<_>>();
+    let [bx0, by0, bx1, by1] = bbox.as_slice().try_into().ok()?;
+    let matrix = metadata.get_array_value(MATRIX).and_then(Matrix::from_objects).unwrap_or_else(Matrix::identity);
+    let corners = [(bx0, by0), (bx1, by0), (bx1, by1), (bx0, by1)].map(|(x, y)| matrix.transform(x, y));
+    let min_x = corners.iter().map(|c| c.0).fold(f64::INFINITY, f64::min);
+    let max_x = corners.iter().map(|c| c.0).fold(f64::NEG_INFINITY, f64::max);
+    let min_y = corners.iter().map(|c| c.1).fold(f64::INFINITY, f64::min);
+    let max_y = corners.iter().map(|c| c.1).fold(f64::NEG_INFINITY, f64::max);
+    if max_x - min_x <= 0.0 || max_y - min_y <= 0.0 {
+        return None;
+    }
+    let sx = (rect[2] - rect[0]) / (max_x - min_x);
+    let sy = (rect[3] - rect[1]) / (max_y - min_y);
+    Some(Matrix::new(sx, 0.0, 0.0, sy, rect[0] - min_x * sx, rect[1] - min_y * sy))
+}
+
+fn number(value: f64) -> PDFObject {
+    PDFObject::Number(PDFNumber::Real(value))
+}
@@ -0,0 +1,377 @@
+use crate::constants::{BITS_PER_COMPONENT, COLORS, COLUMNS, EARLY_CHANGE, PREDICTOR};
+use crate::error::PDFError::StreamDecodeError;
+use crate::error::Result;
+use crate::objects::Dictionary;
+use crate::utils::is_whitespace;
+use flate2::read::{DeflateDecoder, ZlibDecoder};
+use std::io::Read;
+
+/// Filters whose output is an image format rather than plain bytes.
+///
+/// Decoding stops at these filters, the data encoded by them is returned as is.
+const IMAGE_FILTERS: [&str; 6] = ["DCTDecode", "DCT", "JPXDecode", "CCITTFaxDecode", "CCF", "JBIG2Decode"];
+
+/// Returns true if the filter produces image data, such as `DCTDecode`.
+pub(crate) fn is_image_filter(name: &str) -> bool {
+    IMAGE_FILTERS.contains(&name)
+}
+
+/// Applies the filters to `buf` in order.
+///
+/// Both full and abbreviated filter names (as used by inline images) are
+/// accepted. Decoding stops at the first image filter since its data is the
+/// image itself.
+///
+/// # Arguments
+///
+/// * `buf` - The encoded data
+/// * `filters` - The filter names in the order they are applied for decoding
+/// * `params` - The decode parameters of each filter, if any
+///
+/// # Returns
+///
+/// The decoded data or an error if a filter is unsupported or the data is broken
+pub(crate) fn decode(buf: &[u8], filters: &[&str], params: &[Option<&Dictionary>]) -> Result<Vec<u8>> {
+    let mut data = buf.to_vec();
+    for (i, filter) in filters.iter().enumerate() {
+        let params = params.get(i).copied().flatten();
+        data = match *filter {
+            "FlateDecode" | "Fl" => predict(flate_decode(&data)?, params)?,
+            "LZWDecode" | "LZW" => {
+                let early_change = params.and_then(|p| p.get_u64_num(EARLY_CHANGE)).unwrap_or(1);
+                predict(lzw_decode(&data, early_change != 0)?, params)?
+            }
+            "ASCIIHexDecode" | "AHx" => ascii_hex_decode(&data)?,
+            "ASCII85Decode" | "A85" => ascii85_decode(&data)?,
+            "RunLengthDecode" | "RL" => run_length_decode(&data),
+            "Crypt" => data,
+            name if is_image_filter(name) => return Ok(data),
+            name => return Err(StreamDecodeError(format!("Unsupported filter: {}", name))),
+        };
+    }
+    Ok(data)
+}
+
+/// Inflates zlib data.
+///
+/// Broken streams are common, so the data inflated before an error is kept.
+/// Data without a zlib header is inflated as raw deflate data.
+fn flate_decode(buf: &[u8]) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    let result = ZlibDecoder::new(buf).read_to_end(&mut data);
+    if result.is_ok() || !data.is_empty() {
+        return Ok(data);
+    }
+    let mut data = Vec::new();
+    match DeflateDecoder::new(buf).read_to_end(&mut data) {
+        Ok(_) => Ok(data),
+        Err(_) if !data.is_empty() => Ok(data),
+        Err(e) => Err(StreamDecodeError(format!("FlateDecode: {}", e))),
+    }
+}
+
+/// Decodes LZW data with variable code lengths from 9 to 12 bits.
+///
+/// With `early_change` the code length grows one code early, which is the default.
+fn lzw_decode(buf: &[u8], early_change: bool) -> Result<Vec<u8>> {
+    const CLEAR: usize = 256;
+    const EOD: usize = 257;
+    let early = early_change as usize;
+    let mut table: Vec<Vec<u8>> = (0..=255u8).map(|b| vec![b]).collect();
+    table.push(Vec::new());
+    table.push(Vec::new());
+    let mut data = Vec::new();
+    let mut code_len = 9;
+    let mut prev: Option<usize> = None;
+    let mut bits = 0u32;
+    let mut bit_count = 0;
+    for b in buf {
+        bits = (bits << 8) | *b as u32;
+        bit_count += 8;
+        while bit_count >= code_len {
+            let code = ((bits >> (bit_count - code_len)) & ((1 << code_len) - 1)) as usize;
+            bit_count -= code_len;
+            match code {
+                CLEAR => {
+                    table.truncate(258);
+                    code_len = 9;
+                    prev = None;
+                    continue;
+                }
+                EOD => return Ok(data),
+                _ => {}
+            }
+            let entry = match (table.get(code), prev) {
+                (Some(entry), _) => entry.clone(),
+                // The code being defined by this very step
+                (None, Some(prev)) if code == table.len() => {
+                    let mut entry = table[prev].clone();
+                    entry.push(table[prev][0]);
+                    entry
+                }
+                _ => return Err(StreamDecodeError(format!("LZWDecode: invalid code {}", code))),
+            };
+            if let Some(prev) = prev {
+                let mut new_entry = table[prev].clone();
+                new_entry.push(entry[0]);
+                table.push(new_entry);
+            }
+            data.extend_from_slice(&entry);
+            prev = Some(code);
+            if table.len() + early >= (1 << code_len) && code_len < 12 {
+                code_len += 1;
+            }
+        }
+    }
+    Ok(data)
+}
+
+/// Decodes hexadecimal digits up to the `>` end marker, ignoring whitespace.
+fn ascii_hex_decode(buf: &[u8]) -> Result<Vec<u8>> {
+    let mut data = Vec::with_capacity(buf.len() / 2);
+    let mut high: Option<u8> = None;
+    for b in buf {
+        if *b == b'>' {
+            break;
+        }
+        if is_whitespace(*b) {
+            continue;
+        }
+        let value = match *b {
+            b'0'..=b'9' => b - b'0',
+            b'a'..=b'f' => b - b'a' + 10,
+            b'A'..=b'F' => b - b'A' + 10,
+            _ => return Err(StreamDecodeError(format!("ASCIIHexDecode: invalid byte {:02x}", b))),
+        };
+        match high.take() {
+            Some(h) => data.push(h << 4 | value),
+            None => high = Some(value),
+        }
+    }
+    // An odd final digit is followed by an implied 0
+    if let Some(h) = high {
+        data.push(h << 4);
+    }
+    Ok(data)
+}
+
+/// Decodes base-85 data up to the `~>` end marker.
+fn ascii85_decode(buf: &[u8]) -> Result<Vec<u8>> {
+    let mut data = Vec::with_capacity(buf.len() * 4 / 5);
+    let mut group = [0u8; 5];
+    let mut n = 0;
+    let mut bytes = buf.iter();
+    if buf.starts_with(b"<~") {
+        bytes.nth(1);
+    }
+    for b in bytes {
+        match *b {
+            b'~' => break,
+            b'z' if n == 0 => data.extend_from_slice(&[0; 4]),
+            b'!'..=b'u' => {
+                group[n] = b - b'!';
+                n += 1;
+                if n == 5 {
+                    data.extend_from_slice(&ascii85_group(&group).to_be_bytes());
+                    n = 0;
+                }
+            }
+            b if is_whitespace(b) => {}
+            b => return Err(StreamDecodeError(format!("ASCII85Decode: invalid byte {:02x}", b))),
+        }
+    }
+    // A final partial group of n characters is padded with 'u' and yields n - 1 bytes
+    if n > 1 {
+        group[n..].fill(b'u' - b'!');
+        data.extend_from_slice(&ascii85_group(&group).to_be_bytes()[..n - 1]);
+    }
+    Ok(data)
+}
+
+fn ascii85_group(group: &[u8; 5]) -> u32 {
+    group.iter().fold(0u32, |value, digit| value.wrapping_mul(85).wrapping_add(*digit as u32))
+}
+
+fn run_length_decode(buf: &[u8]) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut i = 0usize;
+    while i < buf.len() {
+        let length = buf[i] as usize;
+        i += 1;
+        match length {
+            128 => break,
+            0..=127 => {
+                let end = (i + length + 1).min(buf.len());
+                data.extend_from_slice(&buf[i..end]);
+                i = end;
+            }
+            _ => {
+                if let Some(b) = buf.get(i) {
+                    data.extend(std::iter::repeat_n(*b, 257 - length));
+                }
+                i += 1;
+            }
+        }
+    }
+    data
+}
+
+/// Reverses the predictor applied before a Flate or LZW encoding.
+fn predict(data: Vec<u8>, params: Option<&Dictionary>) -> Result<Vec<u8>> {
+    let params = match params {
+        Some(params) => params,
+        None => return Ok(data),
+    };
+    let predictor = params.get_u64_num(PREDICTOR).unwrap_or(1);
+    if predictor == 1 {
+        return Ok(data);
+    }
+    let colors = params.get_u64_num(COLORS).unwrap_or(1) as usize;
+    let bpc = params.get_u64_num(BITS_PER_COMPONENT).unwrap_or(8) as usize;
+    let columns = params.get_u64_num(COLUMNS).unwrap_or(1) as usize;
+    // The bit depth selects the unpacking of the components
+    if !matches!(bpc, 1 | 2 | 4 | 8 | 16) {
+        return Err(StreamDecodeError(format!("Unsupported predictor bits per component: {}", bpc)));
+    }
+    let row_bits = colors.checked_mul(bpc).and_then(|bits| bits.checked_mul(columns));
+    let Some(row_bits) = row_bits else {
+        return Err(StreamDecodeError(format!("Predictor row too long: {} colors of {} columns", colors, columns)));
+    };
+    let bpp = (colors * bpc).div_ceil(8).max(1);
+    let row_len = row_bits.div_ceil(8);
+    if row_len == 0 || data.is_empty() {
+        return Ok(data);
+    }
+    // The rows are allocated, so they can't be longer than the data
+    if row_len > data.len() {
+        return Err(StreamDecodeError(format!("Predictor row of {} bytes longer than the data", row_len)));
+    }
+    match predictor {
+        2 => Ok(tiff_predict(data, row_len, colors, bpc)),
+        10..=15 => png_predict(&data, row_len, bpp),
+        _ => Err(StreamDecodeError(format!("Unsupported predictor: {}", predictor))),
+    }
+}
+
+/// Reverses the TIFF horizontal differencing predictor.
+fn tiff_predict(mut data: Vec<u8>, row_len: usize, colors: usize, bpc: usize) -> Vec<u8> {
+    for row in data.chunks_mut(row_len) {
+        match bpc {
+            8 => {
+                for i in colors..row.len() {
+                    row[i] = row[i].wrapping_add(row[i - colors]);
+                }
+            }
+            16 => {
+                for i in (colors * 2..row.len() - 1).step_by(2) {
+                    let left = u16::from_be_bytes([row[i - colors * 2], row[i + 1 - colors * 2]]);
+                    let value = u16::from_be_bytes([row[i], row[i + 1]]).wrapping_add(left);
+                    row[i..i + 2].copy_from_slice(&value.to_be_bytes());
+                }
+            }
+            _ => {
+                // Sub-byte components are unpacked, summed and repacked
+                let mask = (1u32 << bpc) - 1;
+                let count = row.len() * 8 / bpc;
+                let mut prev = vec![0u32; colors];
+                for i in 0..count {
+                    let bit = i * bpc;
+                    let shift = 8 - bpc - bit % 8;
+                    let byte = &mut row[bit / 8];
+                    let value = ((*byte as u32 >> shift) & mask).wrapping_add(prev[i % colors]) & mask;
+                    prev[i % colors] = value;
+                    *byte = (*byte & !((mask as u8) << shift)) | ((value as u8) << shift);
+                }
+            }
+        }
+    }
+    data
+}
+
+/// Reverses the PNG predictors, where each row starts with its own filter type.
+fn png_predict(data: &[u8], row_len: usize, bpp: usize) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut prev = vec![0u8; row_len];
+    for chunk in data.chunks(row_len + 1) {
+        let filter = chunk[0];
+        let mut row = chunk[1..].to_vec();
+        row.resize(row_len, 0);
+        for i in 0..row_len {
+            let left = if i >= bpp { row[i - bpp] } else { 0 };
+            let up = prev[i];
+            let up_left = if i >= bpp { prev[i - bpp] } else { 0 };
+            row[i] = row[i].wrapping_add(match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return Err(StreamDecodeError(format!("Unknown PNG filter type: {}", filter))),
+            });
+        }
+        out.extend_from_slice(&row);
+        prev = row;
+    }
+    Ok(out)
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::{PDFNumber, PDFObject};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_ascii_filters() -> Result<()> {
+        assert_eq!(ascii_hex_decode(b"48 65 6c6C 6f7>")?, b"Hello\x70");
+        assert_eq!(ascii85_decode(b"<~87cURD]i,\"Ebo80~>")?, b"Hello World!");
+        assert_eq!(ascii85_decode(b"z!!~>")?, [0, 0, 0, 0, 0]);
+        assert_eq!(run_length_decode(&[2, b'a', b'b', b'c', 254, b'x', 128]), b"abcxxx");
+        Ok(())
+    }
+
+    #[test]
+    fn test_lzw_decode() -> Result<()> {
+        // The example of the PDF reference, which decodes to "-----A---B"
+        let buf = [0x80, 0x0B, 0x60, 0x50, 0x22, 0x0C, 0x0C, 0x85, 0x01];
+        assert_eq!(lzw_decode(&buf, true)?, b"-----A---B");
+        Ok(())
+    }
+
+    #[test]
+    fn test_tiff_predictor() -> Result<()> {
+        let params = |bpc: u64, columns: u64| {
+            let mut params = Dictionary::new(HashMap::new());
+            params.insert(PREDICTOR, PDFObject::Number(PDFNumber::Unsigned(2)));
+            params.insert(BITS_PER_COMPONENT, PDFObject::Number(PDFNumber::Unsigned(bpc)));
+            params.insert(COLUMNS, PDFObject::Number(PDFNumber::Unsigned(columns)));
+            params
+        };
+        // Four 4-bit samples 1, +1, +2, +15 wrapping to 3
+        assert_eq!(predict(vec![0x11, 0x2F], Some(&params(4, 4)))?, [0x12, 0x43]);
+        // Bit depths which can't be unpacked and rows overflowing are rejected
+        assert!(matches!(predict(vec![0; 4], Some(&params(3, 4))), Err(StreamDecodeError(_))));
+        assert!(matches!(predict(vec![0; 4], Some(&params(32, 4))), Err(StreamDecodeError(_))));
+        assert!(matches!(predict(vec![0; 4], Some(&params(16, u64::MAX))), Err(StreamDecodeError(_))));
+        // Rows longer than the data aren't allocated
+        let mut png = params(8, 1 << 40);
+        png.insert(PREDICTOR, PDFObject::Number(PDFNumber::Unsigned(12)));
+        assert!(matches!(predict(vec![2, 1, 2, 3], Some(&png)), Err(StreamDecodeError(_))));
+        assert!(matches!(predict(vec![0; 4], Some(&params(8, 1 << 40))), Err(StreamDecodeError(_))));
+        Ok(())
+    }
+}
@@ -0,0 +1,1040 @@
+use crate::afm::StandardFontMetrics;
+use crate::cmap::CMap;
+use crate::constants::{
+    ASCENT, BASE_ENCODING, BASE_FONT, CAP_HEIGHT, CID_SYSTEM_INFO, CID_TO_GID_MAP, CMAP_NAME, DESCENDANT_FONTS,
+    DESCENT, DIFFERENCES, DW, DW2, ENCODING, FIRST_CHAR, FLAGS, FONT, FONT_BBOX, FONT_DESCRIPTOR, FONT_FILE,
+    FONT_FILE2, FONT_FILE3, FONT_MATRIX, FONT_NAME, ITALIC_ANGLE, LENGTH1, LENGTH2, MISSING_WIDTH, ORDERING,
+    REGISTRY, RESOURCES, STEM_V, SUBTYPE, SUPPLEMENT, TO_UNICODE, USE_CMAP, W, W2, WIDTHS, WMODE, XOBJECT, X_HEIGHT,
+};
+use crate::document::PDFDocument;
+use crate::encoding::{
+    glyph_name_from_u8, glyph_name_to_unicode, mapper_chr_from_u8, zapf_dingbats_glyph_name_to_unicode,
+    PreDefinedEncoding,
+};
+use crate::error::Result;
+use crate::graphics::Matrix;
+use crate::objects::{Dictionary, PDFObject};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// The default width of glyphs in composite fonts, in glyph space units.
+const DEFAULT_CID_WIDTH: f64 = 1000.0;
+/// The default vertical origin and vertical displacement of glyphs in composite fonts.
+const DEFAULT_CID_VERTICAL: [f64; 2] = [880.0, -1000.0];
+/// The maximum depth of CMaps based on other CMaps through `/UseCMap`.
+const MAX_CMAP_DEPTH: usize = 8;
+/// The ascent and descent of fonts without metrics, in glyph space units.
+const DEFAULT_ASCENT: f64 = 800.0;
+const DEFAULT_DESCENT: f64 = -200.0;
+/// The flag of font descriptors marking fonts with glyphs outside the standard Latin character set.
+const SYMBOLIC_FLAG: u32 = 1 << 2;
+/// The keyword ending the clear text part of a Type 1 font program.
+const EEXEC: &[u8] = b"eexec";
+
+/// A font loaded from a font dictionary.
+///
+/// The font knows how to split the bytes of a string into character codes, the
+/// width of each code, which is needed to position the glyphs, and the Unicode
+/// text of each code.
+#[derive(Debug)]
+pub struct Font {
+    /// The font type, such as `Type1`, `TrueType`, `Type3` or `Type0`.
+    subtype: String,
+    /// The PostScript name of the font.
+    base_font: Option<String>,
+    /// The code of the first entry of `widths`.
+    first_char: u32,
+    /// The glyph widths in glyph space units, None if the font has no `/Widths`.
+    widths: Option<Vec<f64>>,
+    /// The width of codes not covered by `widths`.
+    missing_width: f64,
+    descriptor: Option<FontDescriptor>,
+    /// The built-in metrics of standard 14 fonts.
+    standard: Option<&'static StandardFontMetrics>,
+    /// The matrix mapping glyph space to text space.
+    font_matrix: Matrix,
+    /// The bounding box of the glyphs of a Type3 font, in glyph space units.
+    type3_bbox: Option<[f64; 4]>,
+    /// The CMap of a composite font, splitting strings into codes and mapping them to CIDs.
+    cmap: Option<CMap>,
+    /// The descendant font of a composite font.
+    cid_font: Option<CIDFont>,
+    /// The base encoding of a simple font.
+    encoding: Option<PreDefinedEncoding>,
+    /// The glyph names of the codes of a simple font, from its encoding and `/Differences` array.
+    glyph_names: HashMap<u32, String>,
+    /// The `/ToUnicode` CMap, mapping codes to their Unicode text.
+    to_unicode: Option<CMap>,
+    /// The CID-to-Unicode CMap of the character collection of a composite font.
+    cid_to_unicode: Option<CMap>,
+}
+
+/// The descendant of a composite font, a CIDFont holding the glyph metrics by CID.
+#[derive(Debug)]
+pub struct CIDFont {
+    /// The font type, `CIDFontType0` for CFF glyphs or `CIDFontType2` for TrueType glyphs.
+    subtype: String,
+    /// The PostScript name of the font.
+    base_font: Option<String>,
+    /// The character collection of the CIDs, such as `Adobe-Japan1-6`.
+    registry: Option<String>,
+    ordering: Option<String>,
+    supplement: i64,
+    /// The width of CIDs not covered by `widths`.
+    default_width: f64,
+    /// The widths by first CID, with the last CID of the range.
+    widths: BTreeMap<u32, (u32, f64)>,
+    /// The vertical origin and displacement of CIDs not covered by `vertical_metrics`.
+    default_vertical: [f64; 2],
+    /// The vertical displacement and origin by first CID, with the last CID of the range.
+    vertical_metrics: BTreeMap<u32, (u32, [f64; 3])>,
+    /// The glyph index of each CID of a TrueType font, None for the identity mapping.
+    cid_to_gid: Option<Vec<u16>>,
+}
+
+/// The metrics and attributes of a font other than its glyph widths.
+#[derive(Clone, Debug)]
+pub struct FontDescriptor {
+    font_name: Option<String>,
+    flags: u32,
+    font_bbox: Option<[f64; 4]>,
+    italic_angle: f64,
+    ascent: f64,
+    descent: f64,
+    cap_height: f64,
+    x_height: f64,
+    stem_v: f64,
+    missing_width: f64,
+    /// The key of the embedded font program, `/FontFile`, `/FontFile2` or `/FontFile3`, and its stream.
+    font_file: Option<(&'static str, PDFObject)>,
+}
+
+/// The format of an embedded font program.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FontFormat {
+    /// A Type 1 font program, from `/FontFile`.
+    Type1,
+    /// A TrueType font program, from `/FontFile2`.
+    TrueType,
+    /// A bare CFF font program, from `/FontFile3` of subtype `Type1C` or `CIDFontType0C`.
+    Cff,
+    /// An OpenType font program, from `/FontFile3` of subtype `OpenType`.
+    OpenType,
+}
+
+/// A font program embedded in a document.
+#[derive(Debug)]
+pub struct FontProgram {
+    format: FontFormat,
+    /// The font file data, Type 1 programs being converted to PFB.
+    data: Vec<u8>,
+}
+
+/// A font of the font inventory of a document.
+pub struct FontInfo<'a> {
+    document: &'a PDFDocument,
+    /// The reference of the font dictionary, None for direct font dictionaries.
+    obj_ref: Option<(u32, u16)>,
+    /// The PostScript name of the font.
+    name: Option<String>,
+    /// The font type, such as `Type1`, `TrueType`, `Type3` or `Type0`.
+    subtype: String,
+    /// The name of the encoding or CMap.
+    encoding: Option<String>,
+    /// The font descriptor, the one of the descendant font for composite fonts.
+    descriptor: Option<FontDescriptor>,
+    /// The indexes of the pages using the font.
+    pages: Vec<usize>,
+}
+
+impl Font {
+    /// Loads a font from its font dictionary.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - The document used to resolve indirect references
+    /// * `dict` - The font dictionary
+    ///
+    /// # Returns
+    ///
+    /// The font, or an error if an object of the font can't be read
+    pub(crate) fn load(document: &PDFDocument, dict: &Dictionary) -> Result<Font> {
+        let subtype = dict.get_named_value(SUBTYPE).cloned().unwrap_or_else(|| "Type1".to_string());
+        let base_font = dict.get_named_value(BASE_FONT).cloned();
+        let first_char = match dict.get(FIRST_CHAR) {
+            Some(first_char) => document.resolve(first_char)?.as_i64().unwrap_or(0) as u32,
+            None => 0,
+        };
+        let widths = match dict.get(WIDTHS) {
+            Some(object) => match document.resolve(object)?.as_ref() {
+                PDFObject::Array(values) => {
+                    let mut widths = Vec::with_capacity(values.len());
+                    for value in values {
+                        widths.push(document.resolve(value)?.as_f64().unwrap_or(0.0));
+                    }
+                    Some(widths)
+                }
+                _ => None,
+            },
+            None => None,
+        };
+        let mut descriptor = FontDescriptor::load_entry(document, dict)?;
+        let (mut cmap, mut cid_font) = (None, None);
+        if subtype == "Type0" {
+            // Unknown CMaps fall back to two-byte codes used as CIDs
+            cmap = match dict.get(ENCODING) {
+                Some(object) => load_cmap(document, object, 0)?,
+                None => None,
+            };
+            cmap = cmap.or_else(|| Some(CMap::identity(false)));
+            if let Some(object) = dict.get(DESCENDANT_FONTS)
+                && let Some(descendant) = document.resolve(object)?.as_array().and_then(|fonts| fonts.first())
+                && let PDFObject::Dict(descendant) = document.resolve(descendant)?.as_ref() {
+                descriptor = FontDescriptor::load_entry(document, descendant)?;
+                cid_font = Some(CIDFont::load(document, descendant)?);
+            }
+        }
+        let standard = match subtype.as_str() {
+            "Type0" | "Type3" => None,
+            _ => base_font.as_deref().and_then(StandardFontMetrics::find),
+        };
+        let mut font_matrix = Matrix::scale(0.001, 0.001);
+        let mut type3_bbox = None;
+        if subtype == "Type3" {
+            if let Some(object) = dict.get(FONT_MATRIX)
+                && let Some(values) = document.resolve(object)?.as_array() {
+                font_matrix = Matrix::from_objects(values).unwrap_or(font_matrix);
+            }
+            if let Some(object) = dict.get(FONT_BBOX) {
+                type3_bbox = read_rectangle(document.resolve(object)?.as_ref()).filter(|bbox| bbox[3] > bbox[1]);
+            }
+        }
+        let missing_width = descriptor.as_ref().map_or(0.0, |descriptor| descriptor.missing_width);
+
+        // Simple fonts default to their built-in encoding. It is known for the standard
+        // fonts, others are assumed to use the standard encoding, or the Windows one for
+        // TrueType fonts, which are usually laid out for Windows.
+        let mut encoding = match subtype.as_str() {
+            "Type0" | "Type3" => None,
+            _ if standard.is_some_and(|standard| standard.is_symbolic()) => None,
+            "TrueType" => Some(PreDefinedEncoding::WinAnsi),
+            _ => Some(PreDefinedEncoding::Standard),
+        };
+        let mut differences = Vec::new();
+        if subtype != "Type0" && let Some(object) = dict.get(ENCODING) {
+            match document.resolve(object)?.as_ref() {
+                PDFObject::Named(name) => encoding = PreDefinedEncoding::from_name(name).or(encoding),
+                PDFObject::Dict(dict) => {
+                    if let Some(name) = dict.get_named_value(BASE_ENCODING) {
+                        encoding = PreDefinedEncoding::from_name(name).or(encoding);
+                    }
+                    if let Some(values) = dict.get(DIFFERENCES) {
+                        let values = document.resolve(values)?;
+                        let mut code = 0;
+                        for value in values.as_array().unwrap_or_default() {
+                            match value {
+                                PDFObject::Named(name) => {
+                                    differences.push((code, name.clone()));
+                                    code += 1;
+                                }
+                                value => code = value.as_i64().unwrap_or(0) as u32,
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        let mut glyph_names = HashMap::new();
+        if subtype != "Type0" {
+            for code in 0..=255u8 {
+                let name = match encoding {
+                    Some(encoding) => glyph_name_from_u8(code, &encoding),
+                    None => standard.and_then(|standard| standard.get_glyph_name(code)),
+                };
+                if let Some(name) = name {
+                    glyph_names.insert(code as u32, name.to_string());
+                }
+            }
+            glyph_names.extend(differences);
+        }
+
+        // The CIDs of the Adobe CJK character collections have known Unicode values
+        let cid_to_unicode = match cid_font.as_ref().and_then(|cid_font| cid_font.registry.as_deref().zip(cid_font.ordering.as_deref())) {
+            Some(("Adobe", ordering @ ("GB1" | "CNS1" | "Japan1" | "Korea1"))) => CMap::predefined(&format!("Adobe-{ordering}-UCS2")),
+            _ => None,
+        };
+        // Only embedded CMaps map codes to text, predefined ones are ignored
+        let to_unicode = match dict.get(TO_UNICODE) {
+            Some(object) => load_cmap(document, object, 0)?.filter(|cmap| cmap.has_unicode()),
+            None => None,
+        };
+        Ok(Font {
+            subtype,
+            base_font,
+            first_char,
+            widths,
+            missing_width,
+            descriptor,
+            standard,
+            font_matrix,
+            type3_bbox,
+            cmap,
+            cid_font,
+            encoding,
+            glyph_names,
+            to_unicode,
+            cid_to_unicode,
+        })
+    }
+
+    /// Returns the font type, such as `Type1`, `TrueType`, `Type3` or `Type0`.
+    pub fn get_subtype(&self) -> &str {
+        &self.subtype
+    }
+
+    /// Returns the PostScript name of the font.
+    pub fn get_base_font(&self) -> Option<&str> {
+        self.base_font.as_deref()
+    }
+
+    /// Returns the font descriptor, which standard 14 fonts may omit.
+    pub fn get_descriptor(&self) -> Option<&FontDescriptor> {
+        self.descriptor.as_ref()
+    }
+
+    /// Returns the name of the standard 14 font the font is, or is an alias of,
+    /// such as `Helvetica-Bold` for `Arial,Bold`.
+    pub fn get_standard_font(&self) -> Option<&str> {
+        self.standard.map(|standard| standard.get_name())
+    }
+
+    /// Returns the matrix mapping glyph space to text space.
+    pub fn get_font_matrix(&self) -> &Matrix {
+        &self.font_matrix
+    }
+
+    /// Returns the CMap of a composite font.
+    pub fn get_cmap(&self) -> Option<&CMap> {
+        self.cmap.as_ref()
+    }
+
+    /// Returns the descendant font of a composite font.
+    pub fn get_cid_font(&self) -> Option<&CIDFont> {
+        self.cid_font.as_ref()
+    }
+
+    /// Returns the `/ToUnicode` CMap of the font.
+    pub fn get_to_unicode(&self) -> Option<&CMap> {
+        self.to_unicode.as_ref()
+    }
+
+    /// Returns true if the font is a composite font written vertically.
+    pub fn is_vertical(&self) -> bool {
+        self.cmap.as_ref().is_some_and(|cmap| cmap.is_vertical())
+    }
+
+    /// Gets the CID of a code of a composite font.
+    ///
+    /// Codes the CMap doesn't map select CID 0, the `.notdef` glyph.
+    ///
+    /// # Returns
+    ///
+    /// The CID, or None if the font isn't a composite font
+    pub fn get_cid(&self, code: u32) -> Option<u32> {
+        self.cmap.as_ref().map(|cmap| cmap.get_cid(code).unwrap_or(0))
+    }
+
+    /// Gets the width of a code in glyph space units.
+    ///
+    /// The width comes from `/Widths`, or from `/W` for composite fonts.
+    /// Standard 14 fonts without `/Widths` use the widths of their AFM file.
+    pub fn get_width(&self, code: u32) -> f64 {
+        if let Some(cid_font) = &self.cid_font {
+            return cid_font.get_width(self.get_cid(code).unwrap_or(0));
+        }
+        if self.cmap.is_some() {
+            return DEFAULT_CID_WIDTH;
+        }
+        let widths = match &self.widths {
+            Some(widths) => widths,
+            None => return self.standard
+                .zip(self.glyph_names.get(&code))
+                .and_then(|(standard, name)| standard.get_width(name))
+                .unwrap_or(self.missing_width),
+        };
+        code.checked_sub(self.first_char)
+            .and_then(|index| widths.get(index as usize))
+            .copied()
+            .unwrap_or(self.missing_width)
+    }
+
+    /// Returns the horizontal displacement of a code in unscaled text space units.
+    pub fn get_advance(&self, code: u32) -> f64 {
+        self.font_matrix.transform_vector(self.get_width(code), 0.0).0
+    }
+
+    /// Gets the vertical metrics of a code of a composite font, in unscaled text space units.
+    ///
+    /// # Returns
+    ///
+    /// The vertical displacement, and the position of the vertical origin
+    /// relative to the horizontal one, or None if the font isn't a composite font
+    pub fn get_vertical_metrics(&self, code: u32) -> Option<(f64, (f64, f64))> {
+        let cid = self.get_cid(code)?;
+        let [w1, vx, vy] = match &self.cid_font {
+            Some(cid_font) => cid_font.get_vertical_metrics(cid),
+            None => [DEFAULT_CID_VERTICAL[1], DEFAULT_CID_WIDTH / 2.0, DEFAULT_CID_VERTICAL[0]],
+        };
+        Some((self.font_matrix.transform_vector(0.0, w1).1, self.font_matrix.transform_vector(vx, vy)))
+    }
+
+    /// Gets the glyph name of a code of a simple font, from its encoding.
+    pub fn get_glyph_name(&self, code: u32) -> Option<&str> {
+        self.glyph_names.get(&code).map(|name| name.as_str())
+    }
+
+    /// Gets the Unicode text of a code.
+    ///
+    /// The `/ToUnicode` CMap takes precedence. Composite fonts of the Adobe CJK
+    /// character collections then map the CID of the code with the CID-to-Unicode
+    /// CMap of the collection, which requires the `cjk` feature, while simple
+    /// fonts map the glyph names of their encoding with the Adobe Glyph List.
+    ///
+    /// # Returns
+    ///
+    /// The text, or None if the font doesn't tell which characters the code stands for
+    pub fn get_unicode(&self, code: u32) -> Option<String> {
+        if let Some(text) = self.to_unicode.as_ref().and_then(|cmap| cmap.get_unicode(code)) {
+            return Some(text);
+        }
+        if let Some(cid_to_unicode) = &self.cid_to_unicode {
+            return self.get_cid(code).and_then(|cid| cid_to_unicode.get_unicode(cid));
+        }
+        if let Some(name) = self.glyph_names.get(&code) {
+            let text = match self.get_standard_font() {
+                Some("ZapfDingbats") => zapf_dingbats_glyph_name_to_unicode(name),
+                _ => glyph_name_to_unicode(name),
+            };
+            if text.is_some() {
+                return text;
+            }
+        }
+        let code = u8::try_from(code).ok()?;
+        mapper_chr_from_u8(code, self.encoding.as_ref()?).map(String::from)
+    }
+
+    /// Returns the height of the glyphs above the baseline, in unscaled text space units.
+    pub fn get_ascent(&self) -> f64 {
+        let ascent = match (&self.descriptor, self.standard) {
+            (Some(descriptor), _) if descriptor.ascent > 0.0 => descriptor.ascent,
+            (_, Some(standard)) => standard.get_ascent(),
+            _ => self.type3_bbox.map_or(DEFAULT_ASCENT, |bbox| bbox[3]),
+        };
+        self.font_matrix.transform_vector(0.0, ascent).1
+    }
+
+    /// Returns the depth of the glyphs below the baseline as a negative number,
+    /// in unscaled text space units.
+    pub fn get_descent(&self) -> f64 {
+        let descent = match (&self.descriptor, self.standard) {
+            (Some(descriptor), _) if descriptor.descent < 0.0 => descriptor.descent,
+            (_, Some(standard)) => standard.get_descent(),
+            _ => self.type3_bbox.map_or(DEFAULT_DESCENT, |bbox| bbox[1]),
+        };
+        self.font_matrix.transform_vector(0.0, descent).1
+    }
+
+    /// Splits the bytes of a string into character codes.
+    ///
+    /// Simple fonts have single-byte codes, while the codes of composite fonts
+    /// follow the codespace ranges of their CMap.
+    ///
+    /// # Returns
+    ///
+    /// Each code together with the number of bytes it was made of
+    pub fn split_codes(&self, bytes: &[u8]) -> Vec<(u32, usize)> {
+        match &self.cmap {
+            Some(cmap) => cmap.split_codes(bytes),
+            None => bytes.iter().map(|b| (*b as u32, 1)).collect(),
+        }
+    }
+}
+
+impl CIDFont {
+    fn load(document: &PDFDocument, dict: &Dictionary) -> Result<CIDFont> {
+        let (mut registry, mut ordering, mut supplement) = (None, None, 0);
+        if let Some(object) = dict.get(CID_SYSTEM_INFO)
+            && let PDFObject::Dict(info) = document.resolve(object)?.as_ref() {
+            let text = |key: &str| -> Result<Option<String>> {
+                match info.get(key) {
+                    Some(value) => Ok(document.resolve(value)?.as_string().map(|value| String::from_utf8_lossy(value.get_buf()).into_owned())),
+                    None => Ok(None),
+                }
+            };
+            registry = text(REGISTRY)?;
+            ordering = text(ORDERING)?;
+            if let Some(value) = info.get(SUPPLEMENT) {
+                supplement = document.resolve(value)?.as_i64().unwrap_or(0);
+            }
+        }
+        let default_width = match dict.get(DW) {
+            Some(value) => document.resolve(value)?.as_f64().unwrap_or(DEFAULT_CID_WIDTH),
+            None => DEFAULT_CID_WIDTH,
+        };
+        let widths = read_cid_metrics::<1>(document, dict.get(W))?
+            .into_iter()
+            .map(|(first, (last, [width]))| (first, (last, width)))
+            .collect();
+        let mut default_vertical = DEFAULT_CID_VERTICAL;
+        if let Some(object) = dict.get(DW2)
+            && let Some(values) = document.resolve(object)?.as_array()
+            && let [vy, w1] = values
+            && let (Some(vy), Some(w1)) = (vy.as_f64(), w1.as_f64()) {
+            default_vertical = [vy, w1];
+        }
+        let vertical_metrics = read_cid_metrics::<3>(document, dict.get(W2))?;
+        let cid_to_gid = match dict.get(CID_TO_GID_MAP) {
+            Some(object) => match document.resolve(object)?.as_ref() {
+                PDFObject::Stream(stream) => Some(
+                    stream.get_decoded_buf()?
+                        .chunks_exact(2)
+                        .map(|gid| u16::from_be_bytes([gid[0], gid[1]]))
+                        .collect(),
+                ),
+                _ => None,
+            },
+            None => None,
+        };
+        Ok(CIDFont {
+            subtype: dict.get_named_value(SUBTYPE).cloned().unwrap_or_else(|| "CIDFontType2".to_string()),
+            base_font: dict.get_named_value(BASE_FONT).cloned(),
+            registry,
+            ordering,
+            supplement,
+            default_width,
+            widths,
+            default_vertical,
+            vertical_metrics,
+            cid_to_gid,
+        })
+    }
+
+    /// Returns the font type, `CIDFontType0` for CFF glyphs or `CIDFontType2` for TrueType glyphs.
+    pub fn get_subtype(&self) -> &str {
+        &self.subtype
+    }
+
+    /// Returns the PostScript name of the font.
+    pub fn get_base_font(&self) -> Option<&str> {
+        self.base_font.as_deref()
+    }
+
+    /// Returns the issuer of the character collection, such as `Adobe`.
+    pub fn get_registry(&self) -> Option<&str> {
+        self.registry.as_deref()
+    }
+
+    /// Returns the name of the character collection, such as `Japan1` or `Identity`.
+    pub fn get_ordering(&self) -> Option<&str> {
+        self.ordering.as_deref()
+    }
+
+    /// Returns the supplement number of the character collection.
+    pub fn get_supplement(&self) -> i64 {
+        self.supplement
+    }
+
+    /// Returns the width of CIDs not covered by `/W`, in glyph space units.
+    pub fn get_default_width(&self) -> f64 {
+        self.default_width
+    }
+
+    /// Gets the width of a CID in glyph space units.
+    pub fn get_width(&self, cid: u32) -> f64 {
+        match self.widths.range(..=cid).next_back() {
+            Some((_, &(last, width))) if cid <= last => width,
+            _ => self.default_width,
+        }
+    }
+
+    /// Gets the vertical metrics of a CID, from `/W2` or `/DW2`, in glyph space units.
+    ///
+    /// # Returns
+    ///
+    /// The vertical displacement, and the position of the vertical origin
+    /// relative to the horizontal one
+    pub fn get_vertical_metrics(&self, cid: u32) -> [f64; 3] {
+        match self.vertical_metrics.range(..=cid).next_back() {
+            Some((_, &(last, metrics))) if cid <= last => metrics,
+            _ => [self.default_vertical[1], self.get_width(cid) / 2.0, self.default_vertical[0]],
+        }
+    }
+
+    /// Gets the glyph index of a CID in the embedded TrueType font program.
+    pub fn get_gid(&self, cid: u32) -> u32 {
+        match &self.cid_to_gid {
+            Some(gids) => gids.get(cid as usize).map_or(0, |gid| *gid as u32),
+            None => cid,
+        }
+    }
+}
+
+impl FontDescriptor {
+    /// Loads the descriptor of a font dictionary, if it has one.
+    fn load_entry(document: &PDFDocument, dict: &Dictionary) -> Result<Option<FontDescriptor>> {
+        match dict.get(FONT_DESCRIPTOR) {
+            Some(object) => match document.resolve(object)?.as_ref() {
+                PDFObject::Dict(descriptor) => Ok(Some(FontDescriptor::load(document, descriptor)?)),
+                _ => Ok(None),
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn load(document: &PDFDocument, dict: &Dictionary) -> Result<FontDescriptor> {
+        let number = |key: &str| -> Result<f64> {
+            match dict.get(key) {
+                Some(value) => Ok(document.resolve(value)?.as_f64().unwrap_or(0.0)),
+                None => Ok(0.0),
+            }
+        };
+        let font_bbox = match dict.get(FONT_BBOX) {
+            Some(object) => read_rectangle(document.resolve(object)?.as_ref()),
+            None => None,
+        };
+        Ok(FontDescriptor {
+            font_name: dict.get_named_value(FONT_NAME).cloned(),
+            flags: number(FLAGS)? as u32,
+            font_bbox,
+            italic_angle: number(ITALIC_ANGLE)?,
+            ascent: number(ASCENT)?,
+            descent: number(DESCENT)?,
+            cap_height: number(CAP_HEIGHT)?,
+            x_height: number(X_HEIGHT)?,
+            stem_v: number(STEM_V)?,
+            missing_width: number(MISSING_WIDTH)?,
+            font_file: [FONT_FILE, FONT_FILE2, FONT_FILE3].into_iter()
+                .find_map(|key| dict.get(key).map(|object| (key, object.clone()))),
+        })
+    }
+
+    /// Returns true if the font program is embedded in the document.
+    pub fn is_embedded(&self) -> bool {
+        self.font_file.is_some()
+    }
+
+    /// Loads the embedded font program.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - The document the descriptor belongs to
+    ///
+    /// # Returns
+    ///
+    /// The font program, or None if the font isn't embedded
+    pub fn get_font_program(&self, document: &PDFDocument) -> Result<Option<FontProgram>> {
+        let (key, object) = match &self.font_file {
+            Some(font_file) => font_file,
+            None => return Ok(None),
+        };
+        let stream = match document.resolve(object)?.into_owned() {
+            PDFObject::Stream(stream) => stream,
+            _ => return Ok(None),
+        };
+        let metadata = stream.get_metadata();
+        let data = stream.get_decoded_buf()?;
+        let (format, data) = match *key {
+            FONT_FILE => {
+                let length = |key: &str| -> Result<Option<usize>> {
+                    match metadata.get(key) {
+                        Some(value) => Ok(document.resolve(value)?.as_i64().and_then(|value| usize::try_from(value).ok())),
+                        None => Ok(None),
+                    }
+                };
+                (FontFormat::Type1, to_pfb(data, length(LENGTH1)?, length(LENGTH2)?))
+            }
+            FONT_FILE2 => (FontFormat::TrueType, data),
+            _ => match metadata.get_named_value(SUBTYPE).map(|subtype| subtype.as_str()) {
+                Some("OpenType") => (FontFormat::OpenType, data),
+                _ => (FontFormat::Cff, data),
+            },
+        };
+        Ok(Some(FontProgram { format, data }))
+    }
+
+    /// Returns the PostScript name of the font.
+    pub fn get_font_name(&self) -> Option<&str> {
+        self.font_name.as_deref()
+    }
+
+    /// Returns the flags of the font, such as fixed pitch, serif, symbolic or italic.
+    pub fn get_flags(&self) -> u32 {
+        self.flags
+    }
+
+    /// Returns true if the font has glyphs outside the standard Latin character set.
+    pub fn is_symbolic(&self) -> bool {
+        self.flags & SYMBOLIC_FLAG != 0
+    }
+
+    /// Returns the bounding box of all glyphs, in glyph space units.
+    pub fn get_font_bbox(&self) -> Option<[f64; 4]> {
+        self.font_bbox
+    }
+
+    /// Returns the angle of the vertical strokes, in degrees counterclockwise from the vertical.
+    pub fn get_italic_angle(&self) -> f64 {
+        self.italic_angle
+    }
+
+    /// Returns the height above the baseline of accented characters, in glyph space units.
+    pub fn get_ascent(&self) -> f64 {
+        self.ascent
+    }
+
+    /// Returns the depth below the baseline of descenders, as a negative number in glyph space units.
+    pub fn get_descent(&self) -> f64 {
+        self.descent
+    }
+
+    /// Returns the height of flat capital letters, in glyph space units.
+    pub fn get_cap_height(&self) -> f64 {
+        self.cap_height
+    }
+
+    /// Returns the height of flat lowercase letters, in glyph space units.
+    pub fn get_x_height(&self) -> f64 {
+        self.x_height
+    }
+
+    /// Returns the thickness of the vertical stems.
+    pub fn get_stem_v(&self) -> f64 {
+        self.stem_v
+    }
+
+    /// Returns the width of codes not covered by `/Widths`, in glyph space units.
+    pub fn get_missing_width(&self) -> f64 {
+        self.missing_width
+    }
+}
+
+impl FontFormat {
+    /// Returns the usual file extension of the format, without the dot.
+    pub fn get_extension(&self) -> &'static str {
+        match self {
+            FontFormat::Type1 => "pfb",
+            FontFormat::TrueType => "ttf",
+            FontFormat::Cff => "cff",
+            FontFormat::OpenType => "otf",
+        }
+    }
+}
+
+impl FontProgram {
+    /// Returns the format of the font program.
+    pub fn get_format(&self) -> FontFormat {
+        self.format
+    }
+
+    /// Returns the font file data.
+    pub fn get_data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Writes the font file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file, usually with the extension of the format
+    ///
+    /// # Returns
+    ///
+    /// An error if the file can't be written
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, &self.data)?;
+        Ok(())
+    }
+}
+
+impl<'a> FontInfo<'a> {
+    fn load(document: &'a PDFDocument, obj_ref: Option<(u32, u16)>, dict: &Dictionary) -> Result<FontInfo<'a>> {
+        let subtype = dict.get_named_value(SUBTYPE).cloned().unwrap_or_else(|| "Type1".to_string());
+        let encoding = match dict.get(ENCODING) {
+            Some(object) => match document.resolve(object)?.as_ref() {
+                PDFObject::Named(name) => Some(name.clone()),
+                PDFObject::Dict(encoding) => Some(encoding.get_named_value(BASE_ENCODING).map_or("Custom", |name| name.as_str()).to_string()),
+                PDFObject::Stream(cmap) => Some(cmap.get_metadata().get_named_value(CMAP_NAME).map_or("Custom", |name| name.as_str()).to_string()),
+                _ => None,
+            },
+            None => None,
+        };
+        let mut descriptor = FontDescriptor::load_entry(document, dict)?;
+        if subtype == "Type0"
+            && let Some(object) = dict.get(DESCENDANT_FONTS)
+            && let Some(descendant) = document.resolve(object)?.as_array().and_then(|fonts| fonts.first())
+            && let PDFObject::Dict(descendant) = document.resolve(descendant)?.as_ref() {
+            descriptor = FontDescriptor::load_entry(document, descendant)?;
+        }
+        Ok(FontInfo {
+            document,
+            obj_ref,
+            name: dict.get_named_value(BASE_FONT).cloned(),
+            subtype,
+            encoding,
+            descriptor,
+            pages: Vec::new(),
+        })
+    }
+
+    /// Returns the reference of the font dictionary, None for direct font dictionaries.
+    pub fn get_obj_ref(&self) -> Option<(u32, u16)> {
+        self.obj_ref
+    }
+
+    /// Returns the PostScript name of the font, including any subset prefix.
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the font type, such as `Type1`, `TrueType`, `Type3` or `Type0`.
+    pub fn get_subtype(&self) -> &str {
+        &self.subtype
+    }
+
+    /// Returns the name of the encoding or CMap, `Custom` for embedded ones without a name.
+    pub fn get_encoding(&self) -> Option<&str> {
+        self.encoding.as_deref()
+    }
+
+    /// Returns the font descriptor, the one of the descendant font for composite fonts.
+    pub fn get_descriptor(&self) -> Option<&FontDescriptor> {
+        self.descriptor.as_ref()
+    }
+
+    /// Returns true if the font program is embedded. Type 3 fonts are always
+    /// embedded, their glyphs being content streams of the document.
+    pub fn is_embedded(&self) -> bool {
+        self.subtype == "Type3" || self.descriptor.as_ref().is_some_and(|descriptor| descriptor.is_embedded())
+    }
+
+    /// Returns true if the font is a subset, its name starting with a tag such as `ABCDEF+`.
+    pub fn is_subset(&self) -> bool {
+        self.name.as_deref().is_some_and(|name| strip_subset_tag(name).is_some())
+    }
+
+    /// Returns the indexes of the pages using the font.
+    pub fn get_pages(&self) -> &[usize] {
+        &self.pages
+    }
+
+    /// Loads the embedded font program.
+    ///
+    /// # Returns
+    ///
+    /// The font program, or None if the font isn't embedded or is a Type 3 font
+    pub fn get_font_program(&self) -> Result<Option<FontProgram>> {
+        match &self.descriptor {
+            Some(descriptor) => descriptor.get_font_program(self.document),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Lists the fonts of the pages of a document, in the order of the first page using them.
+///
+/// A page uses the fonts listed by its resources and by the resources of the
+/// Form XObjects they contain. Fonts are told apart by the reference of their
+/// dictionary.
+pub(crate) fn collect_fonts(document: &PDFDocument) -> Result<Vec<FontInfo<'_>>> {
+    let mut fonts = Vec::new();
+    for page in document.get_pages() {
+        if let Some(resources) = page.get_resources()? {
+            collect_resource_fonts(document, &resources, page.get_index(), &mut Vec::new(), &mut fonts)?;
+        }
+    }
+    Ok(fonts)
+}
+
+fn collect_resource_fonts<'a>(
+    document: &'a PDFDocument,
+    resources: &Dictionary,
+    page: usize,
+    forms: &mut Vec<(u32, u16)>,
+    fonts: &mut Vec<FontInfo<'a>>,
+) -> Result<()> {
+    for (_, font) in sorted_entries(document, resources.get(FONT))? {
+        let obj_ref = match font {
+            PDFObject::ObjectRef(number, generation) => Some((number, generation)),
+            _ => None,
+        };
+        if let Some(info) = fonts.iter_mut().find(|info| obj_ref.is_some() && info.obj_ref == obj_ref) {
+            if info.pages.last() != Some(&page) {
+                info.pages.push(page);
+            }
+            continue;
+        }
+        if let PDFObject::Dict(dict) = document.resolve(&font)?.as_ref() {
+            let mut info = FontInfo::load(document, obj_ref, dict)?;
+            info.pages.push(page);
+            fonts.push(info);
+        }
+    }
+    for (_, xobject) in sorted_entries(document, resources.get(XOBJECT))? {
+        // Forms painted several times, or painting themselves, are only visited once
+        if let PDFObject::ObjectRef(number, generation) = xobject {
+            if forms.contains(&(number, generation)) {
+                continue;
+            }
+            forms.push((number, generation));
+        }
+        if let PDFObject::Stream(form) = document.resolve(&xobject)?.as_ref()
+            && form.get_metadata().get_named_value(SUBTYPE).is_some_and(|subtype| subtype == "Form")
+            && let Some(object) = form.get_metadata().get(RESOURCES)
+            && let PDFObject::Dict(form_resources) = document.resolve(object)?.as_ref() {
+            collect_resource_fonts(document, form_resources, page, forms, fonts)?;
+        }
+    }
+    Ok(())
+}
+
+/// Gets the entries of a resource category sorted by name.
+fn sorted_entries(document: &PDFDocument, category: Option<&PDFObject>) -> Result<Vec<(String, PDFObject)>> {
+    let mut entries = match category {
+        Some(object) => match document.resolve(object)?.as_ref() {
+            PDFObject::Dict(dict) => dict.iter().map(|(name, value)| (name.clone(), value.clone())).collect(),
+            _ => Vec::new(),
+        },
+        None => Vec::new(),
+    };
+    entries.sort_by(|a: &(String, PDFObject), b| a.0.cmp(&b.0));
+    Ok(entries)
+}
+
+/// Strips the subset tag of a font name, six uppercase letters followed by `+`.
+///
+/// # Returns
+///
+/// The name without the tag, or None if the name has no subset tag
+pub(crate) fn strip_subset_tag(name: &str) -> Option<&str> {
+    match name.split_once('+') {
+        Some((tag, name)) if tag.len() == 6 && tag.bytes().all(|b| b.is_ascii_uppercase()) => Some(name),
+        _ => None,
+    }
+}
+
+/// Converts a Type 1 font program to the PFB format, splitting it into its
+/// clear text, encrypted and trailer segments.
+///
+/// The lengths of the first two segments come from `/Length1` and `/Length2`.
+/// When they are missing or wrong, the clear text part is taken to end after
+/// the `eexec` keyword and the encrypted part to run to the end of the data.
+fn to_pfb(data: Vec<u8>, length1: Option<usize>, length2: Option<usize>) -> Vec<u8> {
+    if data.starts_with(&[0x80, 0x01]) {
+        return data;
+    }
+    let length1 = length1.filter(|length| *length > 0 && *length <= data.len()).unwrap_or_else(|| {
+        data.windows(EEXEC.len())
+            .position(|window| window == EEXEC)
+            .map(|index| index + EEXEC.len())
+            .map(|index| index + data[index..].iter().take_while(|b| b.is_ascii_whitespace()).count())
+            .unwrap_or(data.len())
+    });
+    let length2 = length2.unwrap_or(data.len()).min(data.len() - length1);
+    let segments = [
+        (1, &data[..length1]),
+        (2, &data[length1..length1 + length2]),
+        (1, &data[length1 + length2..]),
+    ];
+    let mut pfb = Vec::with_capacity(data.len() + 24);
+    for (kind, segment) in segments {
+        if segment.is_empty() {
+            continue;
+        }
+        pfb.extend_from_slice(&[0x80, kind]);
+        pfb.extend_from_slice(&(segment.len() as u32).to_le_bytes());
+        pfb.extend_from_slice(segment);
+    }
+    pfb.extend_from_slice(&[0x80, 0x03]);
+    pfb
+}
+
+/// Reads a rectangle of four numbers.
+fn read_rectangle(object: &PDFObject) -> Option<[f64; 4]> {
+    let values = object.as_array()?.iter().filter_map(|value| value.as_f64()).collect::<Vec<f64>>();
+    <[f64; 4]>::try_from(values).ok()
+}
+
+/// Loads a CMap given by a predefined CMap name or an embedded CMap stream.
+///
+/// # Returns
+///
+/// The CMap, or None if the CMap is neither predefined nor embedded
+fn load_cmap(document: &PDFDocument, object: &PDFObject, depth: usize) -> Result<Option<CMap>> {
+    let stream = match document.resolve(object)?.as_ref() {
+        PDFObject::Named(name) => return Ok(CMap::predefined(name)),
+        PDFObject::Stream(stream) => stream.clone(),
+        _ => return Ok(None),
+    };
+    let metadata = stream.get_metadata();
+    let mut cmap = CMap::parse(stream.get_decoded_buf()?);
+    if let Some(mode) = metadata.get(WMODE) {
+        cmap.set_vertical(document.resolve(mode)?.as_i64() == Some(1));
+    }
+    if depth < MAX_CMAP_DEPTH
+        && let Some(parent) = metadata.get(USE_CMAP)
+        && let Some(parent) = load_cmap(document, parent, depth + 1)? {
+        cmap.use_cmap(parent);
+    }
+    Ok(Some(cmap))
+}
+
+/// Reads a `/W` or `/W2` array of a CIDFont, with `N` numbers per CID.
+///
+/// The array mixes two forms: `c [m1 m2 ...]` gives the metrics of consecutive
+/// CIDs starting at `c`, while `cfirst clast m` gives the same metrics to a range.
+///
+/// # Returns
+///
+/// The metrics by first CID, with the last CID of the range
+fn read_cid_metrics<const N: usize>(
+    document: &PDFDocument,
+    object: Option<&PDFObject>,
+) -> Result<BTreeMap<u32, (u32, [f64; N])>> {
+    let mut metrics = BTreeMap::new();
+    let object = match object {
+        Some(object) => document.resolve(object)?,
+        None => return Ok(metrics),
+    };
+    let mut values = Vec::new();
+    for value in object.as_array().unwrap_or_default() {
+        values.push(document.resolve(value)?.into_owned());
+    }
+    let mut index = 0;
+    while index + 1 < values.len() {
+        let first = match values[index].as_i64() {
+            Some(first) if first >= 0 => first as u32,
+            _ => break,
+        };
+        match &values[index + 1] {
+            PDFObject::Array(array) => {
+                let mut numbers = Vec::with_capacity(array.len());
+                for value in array {
+                    numbers.push(document.resolve(value)?.as_f64().unwrap_or(0.0));
+                }
+                for (cid, chunk) in (first..).zip(numbers.chunks_exact(N)) {
+                    metrics.insert(cid, (cid, <[f64; N]>::try_from(chunk).unwrap_or([0.0; N])));
+                }
+                index += 2;
+            }
+            last => {
+                let numbers = values[index + 2..].iter().take(N).filter_map(|value| value.as_f64()).collect::<Vec<f64>>();
+                if let (Some(last), Ok(numbers)) = (last.as_i64(), <[f64; N]>::try_from(numbers)) {
+                    metrics.insert(first, (last.max(first as i64) as u32, numbers));
+                }
+                index += 2 + N;
+            }
+        }
+    }
+    Ok(metrics)
+}
@@ -0,0 +1,514 @@
+use crate::constants::{
+    ACRO_FORM, ANNOTS, AP, AS, DA, DV, FF, FIELDS, FT, KIDS, MAX_LEN, N, NEED_APPEARANCES, OPT, P, Q, RECT,
+    SIG_FLAGS, SUBTYPE, T, TU, V,
+};
+use crate::document::PDFDocument;
+use crate::error::Result;
+use crate::objects::{Dictionary, PDFObject};
+use crate::pstr::decode_text_string;
+use std::collections::{HashMap, HashSet};
+
+/// The maximum depth of the field hierarchies walked.
+const MAX_FIELD_DEPTH: usize = 32;
+
+/// Field flags selecting the kind of button and choice fields.
+const FLAG_RADIO: u32 = 1 << 15;
+const FLAG_PUSHBUTTON: u32 = 1 << 16;
+const FLAG_COMBO: u32 = 1 << 17;
+
+/// The interactive form of a document, `/AcroForm`.
+#[derive(Clone, Debug)]
+pub struct AcroForm {
+    /// The terminal fields, in the order of the field hierarchy.
+    fields: Vec<FormField>,
+    need_appearances: bool,
+    sig_flags: u32,
+    default_appearance: Option<String>,
+    attrs: Dictionary,
+}
+
+/// A terminal field of an interactive form, with the entries it inherits from
+/// its ancestors resolved.
+#[derive(Clone, Debug)]
+pub struct FormField {
+    /// The field object reference, None for direct objects.
+    obj_ref: Option<(u32, u16)>,
+    /// The fully qualified name, the partial names of the field and its ancestors joined by periods.
+    name: String,
+    partial_name: Option<String>,
+    /// The name shown in the user interface, `/TU`.
+    alternate_name: Option<String>,
+    field_type: FieldType,
+    flags: u32,
+    value: Option<FieldValue>,
+    default_value: Option<FieldValue>,
+    /// The options of choice fields, as export values and texts.
+    options: Vec<(String, String)>,
+    max_len: Option<u32>,
+    /// The default appearance string of variable text, `/DA`.
+    default_appearance: Option<String>,
+    /// The justification of variable text, `/Q`.
+    alignment: u32,
+    widgets: Vec<FieldWidget>,
+    attrs: Dictionary,
+}
+
+/// The type of a form field, `/FT` refined by the field flags.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldType {
+    /// A text box, `Tx`.
+    Text,
+    /// A check box, `Btn`.
+    CheckBox,
+    /// A set of radio buttons, `Btn` with the radio flag.
+    RadioButton,
+    /// A push button which keeps no value, `Btn` with the pushbutton flag.
+    PushButton,
+    /// A drop-down list, `Ch` with the combo flag.
+    ComboBox,
+    /// A scrollable list, `Ch`.
+    ListBox,
+    /// A digital signature, `Sig`.
+    Signature,
+    /// A field without a known type.
+    Unknown,
+}
+
+/// The value of a form field.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldValue {
+    /// The text of a text field or the option of a choice field.
+    Text(String),
+    /// The appearance state of a check box or a radio button, `Off` when unchecked.
+    Name(String),
+    /// The options selected in a multiple selection list.
+    Array(Vec<String>),
+    /// Another value, such as the signature dictionary of a signature field.
+    Other(PDFObject),
+}
+
+/// A widget annotation displaying a form field on a page.
+#[derive(Clone, Debug)]
+pub struct FieldWidget {
+    /// The widget annotation object reference, None for direct objects.
+    obj_ref: Option<(u32, u16)>,
+    /// The rectangle on the page, normalized as `[llx, lly, urx, ury]`.
+    rect: [f64; 4],
+    page: Option<usize>,
+    appearance_state: Option<String>,
+    on_state: Option<String>,
+}
+
+/// The entries a field inherits from its ancestors.
+#[derive(Clone, Default)]
+struct Inherited {
+    name: String,
+    field_type: Option<String>,
+    flags: u32,
+    value: Option<PDFObject>,
+    default_value: Option<PDFObject>,
+    max_len: Option<u32>,
+    default_appearance: Option<String>,
+    alignment: u32,
+}
+
+impl AcroForm {
+    /// Returns the terminal fields of the form, in the order of the field hierarchy.
+    pub fn get_fields(&self) -> &[FormField] {
+        &self.fields
+    }
+
+    /// Finds a field by its fully qualified name.
+    pub fn get_field(&self, name: &str) -> Option<&FormField> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    /// Returns whether viewers should construct the appearances of the fields, `/NeedAppearances`.
+    pub fn is_need_appearances(&self) -> bool {
+        self.need_appearances
+    }
+
+    /// Returns the signature flags: 1 when the document contains signatures and
+    /// 2 when it should only be saved by incremental updates.
+    pub fn get_sig_flags(&self) -> u32 {
+        self.sig_flags
+    }
+
+    /// Returns the default appearance string of the variable text fields, `/DA`.
+    pub fn get_default_appearance(&self) -> Option<&str> {
+        self.default_appearance.as_deref()
+    }
+
+    /// Returns the interactive form dictionary.
+    pub fn get_attrs(&self) -> &Dictionary {
+        &self.attrs
+    }
+}
+
+impl FormField {
+    /// Returns the field object reference, None for fields written directly in their parent.
+    pub fn get_obj_ref(&self) -> Option<(u32, u16)> {
+        self.obj_ref
+    }
+
+    /// Returns the fully qualified name of the field, such as `address.city`.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the partial name of the field, `/T`.
+    pub fn get_partial_name(&self) -> Option<&str> {
+        self.partial_name.as_deref()
+    }
+
+    /// Returns the name of the field shown in the user interface, `/TU`.
+    pub fn get_alternate_name(&self) -> Option<&str> {
+        self.alternate_name.as_deref()
+    }
+
+    /// Returns the type of the field.
+    pub fn get_field_type(&self) -> FieldType {
+        self.field_type
+    }
+
+    /// Returns the field flags, `/Ff`.
+    ///
+    /// Bits 1 to 3 mark read only, required and no export fields. The others
+    /// depend on the type: multiline (bit 13), password (14), file select (21),
+    /// do not spell check (23), do not scroll (24), comb (25) and rich text (26)
+    /// for text fields; no toggle to off (15), radio (16), pushbutton (17) and
+    /// radios in unison (26) for buttons; combo (18), edit (19), sort (20),
+    /// multiple selection (22) and commit on selection change (27) for choices.
+    pub fn get_flags(&self) -> u32 {
+        self.flags
+    }
+
+    /// Returns whether the user may not change the value of the field.
+    pub fn is_read_only(&self) -> bool {
+        self.flags & 1 != 0
+    }
+
+    /// Returns whether the field must have a value when the form is submitted.
+    pub fn is_required(&self) -> bool {
+        self.flags & 2 != 0
+    }
+
+    /// Returns the value of the field, `/V`.
+    pub fn get_value(&self) -> Option<&FieldValue> {
+        self.value.as_ref()
+    }
+
+    /// Returns the value the field takes when the form is reset, `/DV`.
+    pub fn get_default_value(&self) -> Option<&FieldValue> {
+        self.default_value.as_ref()
+    }
+
+    /// Returns the options of a choice field, or the export values of the widgets
+    /// of a button field, as `(export value, text)` pairs.
+    pub fn get_options(&self) -> &[(String, String)] {
+        &self.options
+    }
+
+    /// Returns the maximum length of the text of a text field, `/MaxLen`.
+    pub fn get_max_len(&self) -> Option<u32> {
+        self.max_len
+    }
+
+    /// Returns the default appearance string of the text of the field, `/DA`,
+    /// such as `/Helv 12 Tf 0 g`.
+    pub fn get_default_appearance(&self) -> Option<&str> {
+        self.default_appearance.as_deref()
+    }
+
+    /// Returns the justification of the text of the field, `/Q`: 0 left,
+    /// 1 centred and 2 right.
+    pub fn get_alignment(&self) -> u32 {
+        self.alignment
+    }
+
+    /// Returns the widget annotations displaying the field.
+    pub fn get_widgets(&self) -> &[FieldWidget] {
+        &self.widgets
+    }
+
+    /// Returns the field dictionary.
+    pub fn get_attrs(&self) -> &Dictionary {
+        &self.attrs
+    }
+}
+
+impl FieldWidget {
+    /// Returns the widget annotation object reference, None for widgets written directly in their field.
+    pub fn get_obj_ref(&self) -> Option<(u32, u16)> {
+        self.obj_ref
+    }
+
+    /// Returns the rectangle of the widget on its page, as `[llx, lly, urx, ury]`.
+    pub fn get_rect(&self) -> [f64; 4] {
+        self.rect
+    }
+
+    /// Returns the index of the page the widget is on, None if no page lists it.
+    pub fn get_page(&self) -> Option<usize> {
+        self.page
+    }
+
+    /// Returns the current appearance state of a check box or radio button widget, `/AS`.
+    pub fn get_appearance_state(&self) -> Option<&str> {
+        self.appearance_state.as_deref()
+    }
+
+    /// Returns the appearance state of a check box or radio button widget when
+    /// it is checked, its export value.
+    pub fn get_on_state(&self) -> Option<&str> {
+        self.on_state.as_deref()
+    }
+}
+
+/// Reads the interactive form of a document.
+///
+/// # Returns
+///
+/// The form, or None if the catalog doesn't have an `/AcroForm` dictionary
+pub(crate) fn read_form(document: &PDFDocument) -> Result<Option<AcroForm>> {
+    let Some(form) = document.get_catalog().get(ACRO_FORM) else {
+        return Ok(None);
+    };
+    let Some(attrs) = document.resolve(form)?.into_owned().to_dict() else {
+        return Ok(None);
+    };
+    let need_appearances = match attrs.get(NEED_APPEARANCES) {
+        Some(object) => document.resolve(object)?.as_bool().unwrap_or(false),
+        None => false,
+    };
+    let sig_flags = match attrs.get(SIG_FLAGS) {
+        Some(object) => document.resolve(object)?.as_i64().unwrap_or(0) as u32,
+        None => 0,
+    };
+    let default_appearance = match attrs.get(DA) {
+        Some(object) => document.resolve(object)?.as_string().map(decode_text_string),
+        None => None,
+    };
+    // The form holds the defaults of the variable text entries
+    let alignment = match attrs.get(Q) {
+        Some(object) => document.resolve(object)?.as_i64().unwrap_or(0) as u32,
+        None => 0,
+    };
+    let root = Inherited { default_appearance: default_appearance.clone(), alignment, ..Inherited::default() };
+    let pages = get_annotation_pages(document)?;
+    let mut fields = Vec::new();
+    if let Some(roots) = attrs.get(FIELDS) {
+        let roots = document.resolve(roots)?;
+        let mut visited = HashSet::new();
+        for field in roots.as_array().unwrap_or_default() {
+            walk_field(document, field, &root, &pages, &mut visited, 0, &mut fields)?;
+        }
+    }
+    Ok(Some(AcroForm { fields, need_appearances, sig_flags, default_appearance, attrs }))
+}
+
+/// Maps the annotations listed by the pages to the index of their page.
+fn get_annotation_pages(document: &PDFDocument) -> Result<HashMap<(u32, u16), usize>> {
+    let mut pages = HashMap::new();
+    for page in document.get_pages() {
+        let Some(annots) = page.get_attrs().get(ANNOTS) else {
+            continue;
+        };
+        for annot in document.resolve(annots)?.as_array().unwrap_or_default() {
+            if let Some(obj_ref) = annot.as_object_ref() {
+                pages.entry(obj_ref).or_insert(page.get_index());
+            }
+        }
+    }
+    Ok(pages)
+}
+
+/// Walks a field and its descendants, collecting the terminal fields.
+///
+/// Kids with a partial name, a field type or kids of their own are fields, the
+/// others are the widgets of their parent. A terminal field without kids is
+/// merged with its only widget.
+fn walk_field(
+    document: &PDFDocument,
+    object: &PDFObject,
+    parent: &Inherited,
+    pages: &HashMap<(u32, u16), usize>,
+    visited: &mut HashSet<(u32, u16)>,
+    depth: usize,
+    fields: &mut Vec<FormField>,
+) -> Result<()> {
+    let obj_ref = object.as_object_ref();
+    if let Some(obj_ref) = obj_ref && !visited.insert(obj_ref) {
+        return Ok(());
+    }
+    let Some(attrs) = document.resolve(object)?.into_owned().to_dict() else {
+        return Ok(());
+    };
+    let text = |key: &str| -> Result<Option<String>> {
+        match attrs.get(key) {
+            Some(object) => Ok(document.resolve(object)?.as_string().map(decode_text_string)),
+            None => Ok(None),
+        }
+    };
+    let partial_name = text(T)?;
+    let mut inherited = parent.clone();
+    if let Some(partial_name) = &partial_name {
+        if !inherited.name.is_empty() {
+            inherited.name.push('.');
+        }
+        inherited.name.push_str(partial_name);
+    }
+    if let Some(object) = attrs.get(FT) {
+        inherited.field_type = document.resolve(object)?.as_name().cloned();
+    }
+    if let Some(object) = attrs.get(FF) {
+        inherited.flags = document.resolve(object)?.as_i64().unwrap_or(0) as u32;
+    }
+    if let Some(object) = attrs.get(V) {
+        inherited.value = Some(document.resolve(object)?.into_owned());
+    }
+    if let Some(object) = attrs.get(DV) {
+        inherited.default_value = Some(document.resolve(object)?.into_owned());
+    }
+    if let Some(object) = attrs.get(MAX_LEN) {
+        inherited.max_len = document.resolve(object)?.as_i64().and_then(|len| u32::try_from(len).ok());
+    }
+    if let Some(default_appearance) = text(DA)? {
+        inherited.default_appearance = Some(default_appearance);
+    }
+    if let Some(object) = attrs.get(Q) {
+        inherited.alignment = document.resolve(object)?.as_i64().unwrap_or(0) as u32;
+    }
+    let mut kid_fields = Vec::new();
+    let mut widgets = Vec::new();
+    if let Some(kids) = attrs.get(KIDS) {
+        for kid in document.resolve(kids)?.as_array().unwrap_or_default() {
+            let kid_attrs = document.resolve(kid)?;
+            let Some(kid_attrs) = kid_attrs.as_dict() else {
+                continue;
+            };
+            if [T, FT, KIDS].iter().any(|key| kid_attrs.contain(key)) {
+                kid_fields.push(kid.clone());
+            } else {
+                widgets.push(read_widget(document, kid.as_object_ref(), kid_attrs, pages)?);
+            }
+        }
+    }
+    if !kid_fields.is_empty() {
+        if depth < MAX_FIELD_DEPTH {
+            for kid in &kid_fields {
+                walk_field(document, kid, &inherited, pages, visited, depth + 1, fields)?;
+            }
+        }
+        return Ok(());
+    }
+    if !attrs.contain(KIDS) && (attrs.contain(RECT) || attrs.get_named_value(SUBTYPE).is_some_and(|subtype| subtype == "Widget")) {
+        widgets.push(read_widget(document, obj_ref, &attrs, pages)?);
+    }
+    let field_type = match (inherited.field_type.as_deref(), inherited.flags) {
+        (Some("Tx"), _) => FieldType::Text,
+        (Some("Btn"), flags) if flags & FLAG_PUSHBUTTON != 0 => FieldType::PushButton,
+        (Some("Btn"), flags) if flags & FLAG_RADIO != 0 => FieldType::RadioButton,
+        (Some("Btn"), _) => FieldType::CheckBox,
+        (Some("Ch"), flags) if flags & FLAG_COMBO != 0 => FieldType::ComboBox,
+        (Some("Ch"), _) => FieldType::ListBox,
+        (Some("Sig"), _) => FieldType::Signature,
+        _ => FieldType::Unknown,
+    };
+    let options = match attrs.get(OPT) {
+        Some(object) => read_options(document, object)?,
+        None => Vec::new(),
+    };
+    fields.push(FormField {
+        obj_ref,
+        name: inherited.name,
+        partial_name,
+        alternate_name: text(TU)?,
+        field_type,
+        flags: inherited.flags,
+        value: inherited.value.and_then(read_value),
+        default_value: inherited.default_value.and_then(read_value),
+        options,
+        max_len: inherited.max_len,
+        default_appearance: inherited.default_appearance,
+        alignment: inherited.alignment,
+        widgets,
+        attrs,
+    });
+    Ok(())
+}
+
+/// Reads a widget annotation of a field.
+///
+/// The page is looked up in the `/Annots` arrays of the pages, then from the `/P` entry.
+fn read_widget(
+    document: &PDFDocument,
+    obj_ref: Option<(u32, u16)>,
+    attrs: &Dictionary,
+    pages: &HashMap<(u32, u16), usize>,
+) -> Result<FieldWidget> {
+    let mut rect = [0.0; 4];
+    if let Some(object) = attrs.get(RECT) {
+        let values = document.resolve(object)?;
+        let values = values.as_array().unwrap_or_default().iter().filter_map(PDFObject::as_f64).collect::<Vec<_>>();
+        if let [x0, y0, x1, y1, ..] = values.as_slice() {
+            rect = [x0.min(*x1), y0.min(*y1), x0.max(*x1), y0.max(*y1)];
+        }
+    }
+    let page = obj_ref.and_then(|obj_ref| pages.get(&obj_ref).copied()).or_else(|| {
+        attrs.get(P).and_then(PDFObject::as_object_ref).and_then(|page| document.get_page_index(page))
+    });
+    let appearance_state = match attrs.get(AS) {
+        Some(object) => document.resolve(object)?.as_name().cloned(),
+        None => None,
+    };
+    // The states of the normal appearance are Off and the export value
+    let mut on_state = None;
+    if let Some(object) = attrs.get(AP) {
+        let appearances = document.resolve(object)?;
+        if let Some(normal) = appearances.as_dict().and_then(|appearances| appearances.get(N)) {
+            let normal = document.resolve(normal)?;
+            if let Some(states) = normal.as_dict() {
+                on_state = states.iter().map(|(state, _)| state).find(|state| *state != "Off").cloned();
+            }
+        }
+    }
+    Ok(FieldWidget { obj_ref, rect, page, appearance_state, on_state })
+}
+
+/// Reads the options of a field, `/Opt`, each a text or an `[export value, text]` pair.
+fn read_options(document: &PDFDocument, object: &PDFObject) -> Result<Vec<(String, String)>> {
+    let object = document.resolve(object)?;
+    let mut options = Vec::new();
+    for option in object.as_array().unwrap_or_default() {
+        let option = document.resolve(option)?;
+        match option.as_ref() {
+            PDFObject::String(text) => {
+                let text = decode_text_string(text);
+                options.push((text.clone(), text));
+            }
+            PDFObject::Array(pair) => {
+                let mut pair = pair.iter().filter_map(PDFObject::as_string).map(decode_text_string);
+                if let Some(export) = pair.next() {
+                    let text = pair.next().unwrap_or_else(|| export.clone());
+                    options.push((export, text));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(options)
+}
+
+/// Converts a resolved field value, None for the null object.
+fn read_value(value: PDFObject) -> Option<FieldValue> {
+    match value {
+        PDFObject::Null => None,
+        PDFObject::String(text) => Some(FieldValue::Text(decode_text_string(&text))),
+        PDFObject::Named(name) => Some(FieldValue::Name(name)),
+        PDFObject::Array(values) if values.iter().all(PDFObject::is_string) => {
+            Some(FieldValue::Array(values.iter().filter_map(PDFObject::as_string).map(decode_text_string).collect()))
+        }
+        value => Some(FieldValue::Other(value)),
+    }
+}
@@ -0,0 +1,606 @@
+use crate::constants::pdf_key::{FALSE, TURE};
+use crate::constants::{BITS_PER_SAMPLE, BOUNDS, C0, C1, DECODE, DOMAIN, ENCODE, FUNCTIONS, FUNCTION_TYPE, N, RANGE, SIZE};
+use crate::document::PDFDocument;
+use crate::error::Result;
+use crate::objects::{Dictionary, PDFObject};
+use crate::sequence::MemorySequence;
+use crate::tokenizer::Token::{Delimiter, Id, Key, Number};
+use crate::tokenizer::Tokenizer;
+
+/// The maximum nesting of stitching functions, guarding against cyclic functions.
+const MAX_FUNCTION_DEPTH: usize = 8;
+
+/// The maximum number of input values, bounding the corners a sampled function interpolates between.
+const MAX_FUNCTION_INPUTS: usize = 16;
+
+/// The maximum size of the operand stack of PostScript calculator functions.
+const MAX_STACK_SIZE: usize = 100;
+
+/// A PDF function, mapping input values to output values.
+///
+/// Functions compute the tint transforms of `Separation` and `DeviceN`
+/// colour spaces, the colours of shadings and transfer functions. Inputs are
+/// clipped to the domain and outputs to the range.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Function {
+    /// The interval of each input value.
+    domain: Vec<(f64, f64)>,
+    /// The interval of each output value, optional for some function types.
+    range: Option<Vec<(f64, f64)>>,
+    kind: FunctionKind,
+}
+
+/// The function types and their parameters.
+#[derive(Clone, Debug, PartialEq)]
+enum FunctionKind {
+    /// Type 0, a table of samples multilinearly interpolated.
+    Sampled {
+        /// The number of samples in each input dimension.
+        size: Vec<usize>,
+        bits_per_sample: u32,
+        /// The mapping of each input value to the sample indexes of its dimension.
+        encode: Vec<(f64, f64)>,
+        /// The mapping of sample values to each output value.
+        decode: Vec<(f64, f64)>,
+        /// The samples, the first dimension varying fastest, with the output values of each sample in order.
+        samples: Vec<u32>,
+    },
+    /// Type 2, an exponential interpolation `C0 + x^N * (C1 - C0)` of one input value.
+    Exponential { c0: Vec<f64>, c1: Vec<f64>, exponent: f64 },
+    /// Type 3, functions of one input value each applied to a subdomain.
+    Stitching {
+        functions: Vec<Function>,
+        /// The bounds between the subdomains, in increasing order.
+        bounds: Vec<f64>,
+        /// The mapping of each subdomain to the domain of its function.
+        encode: Vec<(f64, f64)>,
+    },
+    /// Type 4, a PostScript calculator program.
+    PostScript(Vec<PsOperation>),
+}
+
+/// An operation of a PostScript calculator program.
+#[derive(Clone, Debug, PartialEq)]
+enum PsOperation {
+    Push(PsValue),
+    Operator(PsOperator),
+    If(Vec<PsOperation>),
+    IfElse(Vec<PsOperation>, Vec<PsOperation>),
+}
+
+/// A value of the operand stack of a PostScript calculator program.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PsValue {
+    Number(f64),
+    Bool(bool),
+}
+
+macro_rules! ps_operators {
+    ($(($ident:ident, $name:literal)),+$(,)?) => {
+        /// The operators of PostScript calculator programs.
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        enum PsOperator {
+            $($ident,)+
+        }
+
+        impl PsOperator {
+            fn from_name(name: &str) -> Option<PsOperator> {
+                match name {
+                    $($name => Some(PsOperator::$ident),)+
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+ps_operators!(
+    // Arithmetic operators
+    (Abs, "abs"), (Add, "add"), (Atan, "atan"), (Ceiling, "ceiling"), (Cos, "cos"),
+    (Cvi, "cvi"), (Cvr, "cvr"), (Div, "div"), (Exp, "exp"), (Floor, "floor"),
+    (Idiv, "idiv"), (Ln, "ln"), (Log, "log"), (Mod, "mod"), (Mul, "mul"), (Neg, "neg"),
+    (Round, "round"), (Sin, "sin"), (Sqrt, "sqrt"), (Sub, "sub"), (Truncate, "truncate"),
+    // Relational, boolean and bitwise operators
+    (And, "and"), (Bitshift, "bitshift"), (Eq, "eq"), (Ge, "ge"), (Gt, "gt"), (Le, "le"),
+    (Lt, "lt"), (Ne, "ne"), (Not, "not"), (Or, "or"), (Xor, "xor"),
+    // Stack operators
+    (Copy, "copy"), (Dup, "dup"), (Exch, "exch"), (Index, "index"), (Pop, "pop"), (Roll, "roll"),
+);
+
+impl Function {
+    /// Loads a function from its dictionary or stream.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - The document used to resolve indirect references
+    /// * `object` - The function dictionary or stream, or a reference to it
+    ///
+    /// # Returns
+    ///
+    /// The function, None if it is malformed or of an unknown type
+    pub fn load(document: &PDFDocument, object: &PDFObject) -> Result<Option<Function>> {
+        load_function(document, object, 0)
+    }
+
+    /// Returns the number of input values.
+    pub fn get_input_count(&self) -> usize {
+        self.domain.len()
+    }
+
+    /// Returns the number of output values.
+    pub fn get_output_count(&self) -> usize {
+        match (&self.range, &self.kind) {
+            (Some(range), _) => range.len(),
+            (None, FunctionKind::Exponential { c0, .. }) => c0.len(),
+            (None, FunctionKind::Stitching { functions, .. }) => functions.first().map_or(0, |function| function.get_output_count()),
+            // Sampled and PostScript calculator functions require a range
+            (None, _) => 0,
+        }
+    }
+
+    /// Evaluates the function.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input values, missing values being taken as 0
+    ///
+    /// # Returns
+    ///
+    /// The output values, clipped to the range of the function. Outputs of
+    /// PostScript calculator programs failing with an error are 0.
+    pub fn eval(&self, input: &[f64]) -> Vec<f64> {
+        let input = self.domain.iter().enumerate()
+            .map(|(i, (min, max))| input.get(i).copied().unwrap_or(0.0).clamp(*min, *max))
+            .collect::<Vec<f64>>();
+        let mut output = match &self.kind {
+            FunctionKind::Sampled { size, bits_per_sample, encode, decode, samples } => {
+                eval_sampled(&input, size, *bits_per_sample, encode, decode, samples, &self.domain)
+            }
+            FunctionKind::Exponential { c0, c1, exponent } => {
+                let x = input[0].powf(*exponent);
+                c0.iter().zip(c1).map(|(c0, c1)| c0 + x * (c1 - c0)).collect::<Vec<f64>>()
+            }
+            FunctionKind::Stitching { functions, bounds, encode } => {
+                let (min, max) = self.domain[0];
+                let x = input[0];
+                // Subdomains include their lower bound, the last one both bounds
+                let k = bounds.iter().position(|bound| x < *bound).unwrap_or(bounds.len());
+                let low = if k == 0 { min } else { bounds[k - 1] };
+                let high = if k == bounds.len() { max } else { bounds[k] };
+                let x = interpolate(x, low, high, encode[k].0, encode[k].1);
+                functions[k].eval(&[x])
+            }
+            FunctionKind::PostScript(program) => {
+                let mut stack = input.iter().map(|value| PsValue::Number(*value)).collect::<Vec<PsValue>>();
+                let count = self.get_output_count();
+                match execute(program, &mut stack) {
+                    Some(()) if stack.len() >= count => {
+                        stack[stack.len() - count..].iter().map(|value| value.as_number().unwrap_or(0.0)).collect()
+                    }
+                    _ => vec![0.0; count],
+                }
+            }
+        };
+        if let Some(range) = &self.range {
+            for (value, (min, max)) in output.iter_mut().zip(range) {
+                *value = value.clamp(*min, *max);
+            }
+        }
+        output
+    }
+}
+
+impl PsValue {
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            PsValue::Number(value) => Some(*value),
+            PsValue::Bool(_) => None,
+        }
+    }
+}
+
+fn load_function(document: &PDFDocument, object: &PDFObject, depth: usize) -> Result<Option<Function>> {
+    if depth > MAX_FUNCTION_DEPTH {
+        return Ok(None);
+    }
+    let object = document.resolve(object)?;
+    let (dict, stream) = match object.as_ref() {
+        PDFObject::Dict(dict) => (dict, None),
+        PDFObject::Stream(stream) => (stream.get_metadata(), Some(stream)),
+        _ => return Ok(None),
+    };
+    let domain = match read_ranges(document, dict, DOMAIN)? {
+        Some(domain) if !domain.is_empty() && domain.len() <= MAX_FUNCTION_INPUTS => domain,
+        _ => return Ok(None),
+    };
+    let range = read_ranges(document, dict, RANGE)?;
+    let function_type = match dict.get(FUNCTION_TYPE) {
+        Some(function_type) => document.resolve(function_type)?.as_i64(),
+        None => None,
+    };
+    let kind = match (function_type, stream, &range) {
+        (Some(0), Some(stream), Some(range)) => {
+            let size = read_numbers(document, dict, SIZE)?.unwrap_or_default()
+                .into_iter().map(|size| size.max(1.0) as usize).collect::<Vec<usize>>();
+            let bits_per_sample = match dict.get(BITS_PER_SAMPLE) {
+                Some(bits) => document.resolve(bits)?.as_i64().unwrap_or(0),
+                None => 0,
+            };
+            if size.len() != domain.len() || ![1, 2, 4, 8, 12, 16, 24, 32].contains(&bits_per_sample) {
+                return Ok(None);
+            }
+            let bits_per_sample = bits_per_sample as u32;
+            let encode = read_ranges(document, dict, ENCODE)?
+                .filter(|encode| encode.len() == size.len())
+                .unwrap_or_else(|| size.iter().map(|size| (0.0, (size - 1) as f64)).collect());
+            let decode = read_ranges(document, dict, DECODE)?
+                .filter(|decode| decode.len() == range.len())
+                .unwrap_or_else(|| range.clone());
+            // The samples are allocated, so the data must hold them all
+            let data = stream.get_decoded_buf()?;
+            let count = size.iter().try_fold(range.len(), |count, size| count.checked_mul(*size));
+            let samples = match count {
+                Some(count) if count.checked_mul(bits_per_sample as usize).is_some_and(|bits| bits.div_ceil(8) <= data.len()) => {
+                    read_samples(&data, count, bits_per_sample)
+                }
+                _ => return Ok(None),
+            };
+            FunctionKind::Sampled { size, bits_per_sample, encode, decode, samples }
+        }
+        (Some(2), _, _) => {
+            let c0 = read_numbers(document, dict, C0)?.unwrap_or_else(|| vec![0.0]);
+            let c1 = read_numbers(document, dict, C1)?.unwrap_or_else(|| vec![1.0]);
+            let exponent = match dict.get(N) {
+                Some(exponent) => document.resolve(exponent)?.as_f64(),
+                None => None,
+            };
+            match exponent {
+                Some(exponent) if c0.len() == c1.len() => FunctionKind::Exponential { c0, c1, exponent },
+                _ => return Ok(None),
+            }
+        }
+        (Some(3), _, _) => {
+            let mut functions = Vec::new();
+            if let Some(object) = dict.get(FUNCTIONS)
+                && let Some(objects) = document.resolve(object)?.as_array() {
+                for object in objects {
+                    match load_function(document, object, depth + 1)? {
+                        Some(function) => functions.push(function),
+                        None => return Ok(None),
+                    }
+                }
+            }
+            let bounds = read_numbers(document, dict, BOUNDS)?.unwrap_or_default();
+            let encode = read_ranges(document, dict, ENCODE)?.unwrap_or_default();
+            if functions.is_empty() || bounds.len() + 1 != functions.len() || encode.len() != functions.len() {
+                return Ok(None);
+            }
+            FunctionKind::Stitching { functions, bounds, encode }
+        }
+        (Some(4), Some(stream), Some(_)) => match parse_program(stream.get_decoded_buf()?)? {
+            Some(program) => FunctionKind::PostScript(program),
+            None => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+    Ok(Some(Function { domain, range, kind }))
+}
+
+/// Reads an array of numbers of a dictionary.
+fn read_numbers(document: &PDFDocument, dict: &Dictionary, key: &str) -> Result<Option<Vec<f64>>> {
+    let values = match dict.get(key) {
+        Some(values) => document.resolve(values)?,
+        None => return Ok(None),
+    };
+    let numbers = values.as_array().map(|values| values.iter().map(|value| value.as_f64().unwrap_or(0.0)).collect());
+    Ok(numbers)
+}
+
+/// Reads an array of intervals of a dictionary, such as `/Domain` or `/Range`.
+///
+/// The bounds of `/Encode` and `/Decode` intervals may be in decreasing order,
+/// those of `/Domain` and `/Range` are made increasing.
+fn read_ranges(document: &PDFDocument, dict: &Dictionary, key: &str) -> Result<Option<Vec<(f64, f64)>>> {
+    let increasing = key == DOMAIN || key == RANGE;
+    let ranges = read_numbers(document, dict, key)?.map(|values| {
+        values.chunks_exact(2)
+            .map(|range| if increasing { (range[0], range[1].max(range[0])) } else { (range[0], range[1]) })
+            .collect()
+    });
+    Ok(ranges)
+}
+
+/// Reads `count` samples of `bits` bits each.
+fn read_samples(data: &[u8], count: usize, bits: u32) -> Vec<u32> {
+    let mut samples = Vec::with_capacity(count);
+    for i in 0..count {
+        let bit = i * bits as usize;
+        let sample = match bits {
+            1 | 2 | 4 => {
+                let byte = data.get(bit / 8).copied().unwrap_or(0) as u32;
+                (byte >> (8 - bits as usize - bit % 8)) & ((1 << bits) - 1)
+            }
+            // 12-bit samples start on a byte or half byte boundary
+            12 => {
+                let high = data.get(bit / 8).copied().unwrap_or(0) as u32;
+                let low = data.get(bit / 8 + 1).copied().unwrap_or(0) as u32;
+                ((high << 8 | low) >> (4 - bit % 8)) & 0xfff
+            }
+            _ => (0..bits as usize / 8).fold(0, |sample, j| sample << 8 | data.get(bit / 8 + j).copied().unwrap_or(0) as u32),
+        };
+        samples.push(sample);
+    }
+    samples
+}
+
+/// Maps `x` from `[x0, x1]` to `[y0, y1]`.
+fn interpolate(x: f64, x0: f64, x1: f64, y0: f64, y1: f64) -> f64 {
+    if x1 == x0 {
+        return y0;
+    }
+    y0 + (x - x0) * (y1 - y0) / (x1 - x0)
+}
+
+/// Evaluates a sampled function by multilinear interpolation between the
+/// samples surrounding the encoded input.
+fn eval_sampled(
+    input: &[f64],
+    size: &[usize],
+    bits_per_sample: u32,
+    encode: &[(f64, f64)],
+    decode: &[(f64, f64)],
+    samples: &[u32],
+    domain: &[(f64, f64)],
+) -> Vec<f64> {
+    let outputs = decode.len();
+    // The lower sample index and the interpolation weight in each dimension
+    let positions = input.iter().enumerate()
+        .map(|(i, x)| {
+            let e = interpolate(*x, domain[i].0, domain[i].1, encode[i].0, encode[i].1).clamp(0.0, (size[i] - 1) as f64);
+            let index = (e.floor() as usize).min(size[i].saturating_sub(2));
+            (index, e - index as f64)
+        })
+        .collect::<Vec<(usize, f64)>>();
+    let max = (((1u64 << bits_per_sample) - 1) as f64).max(1.0);
+    let mut output = vec![0.0; outputs];
+    // Each corner of the surrounding cell contributes by the product of its weights
+    for corner in 0..1usize << positions.len() {
+        let (mut offset, mut stride, mut weight) = (0, 1, 1.0);
+        for (i, (index, t)) in positions.iter().enumerate() {
+            let upper = corner >> i & 1 == 1;
+            if upper && size[i] == 1 {
+                weight = 0.0;
+            }
+            offset += (index + upper as usize) * stride;
+            stride *= size[i];
+            weight *= if upper { *t } else { 1.0 - t };
+        }
+        if weight == 0.0 {
+            continue;
+        }
+        for (j, value) in output.iter_mut().enumerate() {
+            *value += weight * samples.get(offset * outputs + j).copied().unwrap_or(0) as f64;
+        }
+    }
+    output.iter().zip(decode).map(|(sample, (d0, d1))| interpolate(*sample, 0.0, max, *d0, *d1)).collect()
+}
+
+/// Parses a PostScript calculator program, a procedure enclosed in braces.
+///
+/// # Returns
+///
+/// The operations of the procedure, None if the program is malformed
+fn parse_program(data: Vec<u8>) -> Result<Option<Vec<PsOperation>>> {
+    let mut tokenizer = Tokenizer::new(MemorySequence::new(data));
+    if tokenizer.next_token()? != Delimiter("{") {
+        return Ok(None);
+    }
+    parse_procedure(&mut tokenizer, 0)
+}
+
+/// Parses the operations of a procedure up to its closing brace.
+fn parse_procedure(tokenizer: &mut Tokenizer, depth: usize) -> Result<Option<Vec<PsOperation>>> {
+    let mut operations = Vec::new();
+    // The procedures read but not yet consumed by `if` or `ifelse`
+    let mut procedures: Vec<Vec<PsOperation>> = Vec::new();
+    loop {
+        let operation = match tokenizer.next_token()? {
+            Delimiter("}") => break,
+            Delimiter("{") if depth < MAX_FUNCTION_DEPTH * 4 => {
+                match parse_procedure(tokenizer, depth + 1)? {
+                    Some(procedure) => procedures.push(procedure),
+                    None => return Ok(None),
+                }
+                continue;
+            }
+            Number(number) => PsOperation::Push(PsValue::Number(number.as_f64())),
+            Key(TURE) => PsOperation::Push(PsValue::Bool(true)),
+            Key(FALSE) => PsOperation::Push(PsValue::Bool(false)),
+            Id("if") => match procedures.pop() {
+                Some(procedure) => PsOperation::If(procedure),
+                None => return Ok(None),
+            },
+            Id("ifelse") => match (procedures.pop(), procedures.pop()) {
+                (Some(otherwise), Some(then)) => PsOperation::IfElse(then, otherwise),
+                _ => return Ok(None),
+            },
+            Id(name) => match PsOperator::from_name(name) {
+                Some(operator) => PsOperation::Operator(operator),
+                None => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        // Procedures are only operands of `if` and `ifelse`
+        if !procedures.is_empty() {
+            return Ok(None);
+        }
+        operations.push(operation);
+    }
+    Ok(procedures.is_empty().then_some(operations))
+}
+
+/// Executes the operations of a PostScript calculator program.
+///
+/// # Returns
+///
+/// None if an operator fails, on a stack underflow or overflow or an operand of the wrong type
+fn execute(operations: &[PsOperation], stack: &mut Vec<PsValue>) -> Option<()> {
+    for operation in operations {
+        match operation {
+            PsOperation::Push(value) => stack.push(*value),
+            PsOperation::Operator(operator) => apply(*operator, stack)?,
+            PsOperation::If(then) => {
+                if pop_bool(stack)? {
+                    execute(then, stack)?;
+                }
+            }
+            PsOperation::IfElse(then, otherwise) => {
+                let procedure = if pop_bool(stack)? { then } else { otherwise };
+                execute(procedure, stack)?;
+            }
+        }
+        if stack.len() > MAX_STACK_SIZE {
+            return None;
+        }
+    }
+    Some(())
+}
+
+fn pop_number(stack: &mut Vec<PsValue>) -> Option<f64> {
+    stack.pop()?.as_number()
+}
+
+fn pop_bool(stack: &mut Vec<PsValue>) -> Option<bool> {
+    match stack.pop()? {
+        PsValue::Bool(value) => Some(value),
+        PsValue::Number(_) => None,
+    }
+}
+
+/// Pops an integer, real numbers being truncated.
+fn pop_integer(stack: &mut Vec<PsValue>) -> Option<i64> {
+    Some(pop_number(stack)?.trunc() as i64)
+}
+
+/// Applies an operator to the operand stack.
+fn apply(operator: PsOperator, stack: &mut Vec<PsValue>) -> Option<()> {
+    use PsOperator::*;
+    let value = match operator {
+        Abs | Ceiling | Cos | Cvi | Cvr | Floor | Ln | Log | Neg | Round | Sin | Sqrt | Truncate => {
+            let x = pop_number(stack)?;
+            PsValue::Number(match operator {
+                Abs => x.abs(),
+                Ceiling => x.ceil(),
+                // Angles are in degrees
+                Cos => x.to_radians().cos(),
+                Sin => x.to_radians().sin(),
+                Cvi | Truncate => x.trunc(),
+                Floor => x.floor(),
+                Ln if x > 0.0 => x.ln(),
+                Log if x > 0.0 => x.log10(),
+                Neg => -x,
+                // Halves are rounded up, as in PostScript
+                Round => (x + 0.5).floor(),
+                Sqrt if x >= 0.0 => x.sqrt(),
+                _ => return None,
+            })
+        }
+        Add | Atan | Div | Exp | Mul | Sub => {
+            let y = pop_number(stack)?;
+            let x = pop_number(stack)?;
+            PsValue::Number(match operator {
+                Add => x + y,
+                // The angle of the vector (y, x) in degrees, between 0 and 360
+                Atan => x.atan2(y).to_degrees().rem_euclid(360.0),
+                Div if y != 0.0 => x / y,
+                Exp => x.powf(y),
+                Mul => x * y,
+                Sub => x - y,
+                _ => return None,
+            })
+        }
+        Idiv | Mod | Bitshift => {
+            let y = pop_integer(stack)?;
+            let x = pop_integer(stack)?;
+            PsValue::Number(match operator {
+                Idiv => x.checked_div(y)?,
+                Mod => x.checked_rem(y)?,
+                Bitshift if y >= 0 => x.checked_shl(y.min(63) as u32)?,
+                _ => x.checked_shr((-y).min(63) as u32)?,
+            } as f64)
+        }
+        Eq | Ne | Ge | Gt | Le | Lt => {
+            let y = stack.pop()?;
+            let x = stack.pop()?;
+            let result = match (x, y) {
+                (PsValue::Number(x), PsValue::Number(y)) => match operator {
+                    Eq => x == y,
+                    Ne => x != y,
+                    Ge => x >= y,
+                    Gt => x > y,
+                    Le => x <= y,
+                    _ => x < y,
+                },
+                (PsValue::Bool(x), PsValue::Bool(y)) if matches!(operator, Eq | Ne) => (x == y) == (operator == Eq),
+                _ => return None,
+            };
+            PsValue::Bool(result)
+        }
+        // Boolean operators apply to booleans, bitwise ones to integers
+        And | Or | Xor => match (stack.pop()?, stack.pop()?) {
+            (PsValue::Bool(y), PsValue::Bool(x)) => PsValue::Bool(match operator {
+                And => x & y,
+                Or => x | y,
+                _ => x ^ y,
+            }),
+            (PsValue::Number(y), PsValue::Number(x)) => {
+                let (x, y) = (x.trunc() as i64, y.trunc() as i64);
+                PsValue::Number(match operator {
+                    And => x & y,
+                    Or => x | y,
+                    _ => x ^ y,
+                } as f64)
+            }
+            _ => return None,
+        },
+        Not => match stack.pop()? {
+            PsValue::Bool(x) => PsValue::Bool(!x),
+            PsValue::Number(x) => PsValue::Number(!(x.trunc() as i64) as f64),
+        },
+        Copy => {
+            let n = usize::try_from(pop_integer(stack)?).ok()?;
+            let start = stack.len().checked_sub(n)?;
+            stack.extend_from_within(start..);
+            return Some(());
+        }
+        Dup => *stack.last()?,
+        Exch => {
+            let len = stack.len();
+            if len < 2 {
+                return None;
+            }
+            stack.swap(len - 1, len - 2);
+            return Some(());
+        }
+        Index => {
+            let n = usize::try_from(pop_integer(stack)?).ok()?;
+            *stack.get(stack.len().checked_sub(n + 1)?)?
+        }
+        Pop => {
+            stack.pop()?;
+            return Some(());
+        }
+        Roll => {
+            let j = pop_integer(stack)?;
+            let n = usize::try_from(pop_integer(stack)?).ok()?;
+            let start = stack.len().checked_sub(n)?;
+            if n > 0 {
+                // Positive amounts roll towards the top of the stack
+                stack[start..].rotate_right(j.rem_euclid(n as i64) as usize);
+            }
+            return Some(());
+        }
+    };
+    stack.push(value);
+    Some(())
+}
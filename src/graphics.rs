@@ -0,0 +1,554 @@
+use crate::colorspace::ColorSpace;
+use crate::font::Font;
+use crate::objects::PDFObject;
+use std::rc::Rc;
+
+/// A transformation matrix `[a b c d e f]`, mapping `(x, y)` to
+/// `(a*x + c*y + e, b*x + d*y + f)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Matrix {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+}
+
+/// A segment of a path.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathSegment {
+    /// Begins a new subpath at the point.
+    MoveTo(f64, f64),
+    /// A straight line to the point.
+    LineTo(f64, f64),
+    /// A cubic Bézier curve with two control points and the end point.
+    CurveTo(f64, f64, f64, f64, f64, f64),
+    /// Closes the current subpath.
+    Close,
+}
+
+/// A path in device space.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Path {
+    segments: Vec<PathSegment>,
+}
+
+/// The rule deciding which points are inside a path.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FillRule {
+    NonZero,
+    EvenOdd,
+}
+
+/// A path intersected with the clipping region.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClipPath {
+    path: Path,
+    rule: FillRule,
+}
+
+/// The shape of the ends of open stroked subpaths.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineCap {
+    Butt,
+    Round,
+    ProjectingSquare,
+}
+
+/// The shape of the corners of stroked paths.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+/// A colour together with the colour space it is expressed in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Color {
+    color_space: Rc<ColorSpace>,
+    /// The colour components, empty for an uncoloured pattern without components.
+    components: Vec<f64>,
+    /// The name of the pattern resource, for the Pattern colour space.
+    pattern: Option<String>,
+}
+
+/// The text state parameters.
+#[derive(Clone, Debug)]
+pub struct TextState {
+    char_spacing: f64,
+    word_spacing: f64,
+    /// The horizontal scaling as a factor, where 1.0 is normal width.
+    horizontal_scaling: f64,
+    leading: f64,
+    font: Option<Rc<Font>>,
+    font_size: f64,
+    render_mode: i64,
+    rise: f64,
+}
+
+/// The graphics state parameters which content stream operators act on.
+///
+/// The state is saved by `q` and restored by `Q`.
+#[derive(Clone, Debug)]
+pub struct GraphicsState {
+    /// The current transformation matrix, mapping user space to device space.
+    ctm: Matrix,
+    /// The paths intersected to form the clipping region, in device space.
+    clip: Vec<ClipPath>,
+    stroke_color: Color,
+    fill_color: Color,
+    text: TextState,
+    line_width: f64,
+    line_cap: LineCap,
+    line_join: LineJoin,
+    miter_limit: f64,
+    /// The dash array and dash phase, an empty array being a solid line.
+    dash: (Vec<f64>, f64),
+    rendering_intent: String,
+    flatness: f64,
+    /// The soft mask dictionary set by a graphics state parameter dictionary.
+    soft_mask: Option<PDFObject>,
+    stroke_alpha: f64,
+    fill_alpha: f64,
+    blend_mode: String,
+}
+
+impl Matrix {
+    pub fn new(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Self {
+        Matrix { a, b, c, d, e, f }
+    }
+
+    /// Returns the identity matrix.
+    pub fn identity() -> Self {
+        Matrix::new(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)
+    }
+
+    /// Returns a matrix translating by `(tx, ty)`.
+    pub fn translate(tx: f64, ty: f64) -> Self {
+        Matrix::new(1.0, 0.0, 0.0, 1.0, tx, ty)
+    }
+
+    /// Returns a matrix scaling by `sx` and `sy`.
+    pub fn scale(sx: f64, sy: f64) -> Self {
+        Matrix::new(sx, 0.0, 0.0, sy, 0.0, 0.0)
+    }
+
+    /// Creates a matrix from six numbers, such as the operands of `cm`.
+    ///
+    /// # Returns
+    ///
+    /// The matrix, or None if there aren't six numbers
+    pub fn from_objects(objects: &[PDFObject]) -> Option<Self> {
+        match objects {
+            [a, b, c, d, e, f] => Some(Matrix::new(
+                a.as_f64()?, b.as_f64()?, c.as_f64()?, d.as_f64()?, e.as_f64()?, f.as_f64()?,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Returns the matrix applying `self` and then `other`.
+    pub fn multiply(&self, other: &Matrix) -> Matrix {
+        Matrix::new(
+            self.a * other.a + self.b * other.c,
+            self.a * other.b + self.b * other.d,
+            self.c * other.a + self.d * other.c,
+            self.c * other.b + self.d * other.d,
+            self.e * other.a + self.f * other.c + other.e,
+            self.e * other.b + self.f * other.d + other.f,
+        )
+    }
+
+    /// Transforms a point.
+    pub fn transform(&self, x: f64, y: f64) -> (f64, f64) {
+        (self.a * x + self.c * y + self.e, self.b * x + self.d * y + self.f)
+    }
+
+    /// Transforms a distance vector, ignoring the translation.
+    pub fn transform_vector(&self, x: f64, y: f64) -> (f64, f64) {
+        (self.a * x + self.c * y, self.b * x + self.d * y)
+    }
+
+    /// Returns the inverse matrix, or None if the matrix is not invertible.
+    pub fn invert(&self) -> Option<Matrix> {
+        let det = self.a * self.d - self.b * self.c;
+        if det.abs() < f64::EPSILON {
+            return None;
+        }
+        Some(Matrix::new(
+            self.d / det,
+            -self.b / det,
+            -self.c / det,
+            self.a / det,
+            (self.c * self.f - self.d * self.e) / det,
+            (self.b * self.e - self.a * self.f) / det,
+        ))
+    }
+
+    /// Returns the factor by which the matrix scales lengths on average.
+    pub fn get_scale(&self) -> f64 {
+        (self.a * self.d - self.b * self.c).abs().sqrt()
+    }
+}
+
+impl Default for Matrix {
+    fn default() -> Self {
+        Matrix::identity()
+    }
+}
+
+impl Path {
+    pub(crate) fn push(&mut self, segment: PathSegment) {
+        self.segments.push(segment);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.segments.clear();
+    }
+
+    pub(crate) fn append(&mut self, other: &Path) {
+        self.segments.extend_from_slice(&other.segments);
+    }
+
+    /// Returns the path with all its points transformed by `matrix`.
+    pub fn transform(&self, matrix: &Matrix) -> Path {
+        let segments = self.segments.iter().map(|segment| match *segment {
+            PathSegment::MoveTo(x, y) => {
+                let (x, y) = matrix.transform(x, y);
+                PathSegment::MoveTo(x, y)
+            }
+            PathSegment::LineTo(x, y) => {
+                let (x, y) = matrix.transform(x, y);
+                PathSegment::LineTo(x, y)
+            }
+            PathSegment::CurveTo(x1, y1, x2, y2, x3, y3) => {
+                let (x1, y1) = matrix.transform(x1, y1);
+                let (x2, y2) = matrix.transform(x2, y2);
+                let (x3, y3) = matrix.transform(x3, y3);
+                PathSegment::CurveTo(x1, y1, x2, y2, x3, y3)
+            }
+            PathSegment::Close => PathSegment::Close,
+        });
+        Path { segments: segments.collect() }
+    }
+
+    /// Returns the segments of the path.
+    pub fn get_segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// Returns true if the path has no segments.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Returns the bounding box `[x0, y0, x1, y1]` of the points of the path,
+    /// including curve control points, or None for an empty path.
+    pub fn get_bounds(&self) -> Option<[f64; 4]> {
+        let mut points = self.segments.iter().flat_map(|segment| match *segment {
+            PathSegment::MoveTo(x, y) | PathSegment::LineTo(x, y) => vec![(x, y)],
+            PathSegment::CurveTo(x1, y1, x2, y2, x3, y3) => vec![(x1, y1), (x2, y2), (x3, y3)],
+            PathSegment::Close => vec![],
+        });
+        let (x, y) = points.next()?;
+        Some(points.fold([x, y, x, y], |[x0, y0, x1, y1], (x, y)| {
+            [x0.min(x), y0.min(y), x1.max(x), y1.max(y)]
+        }))
+    }
+}
+
+impl ClipPath {
+    pub fn new(path: Path, rule: FillRule) -> Self {
+        ClipPath { path, rule }
+    }
+
+    /// Returns the clipping path in device space.
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the rule deciding which points are inside the clipping path.
+    pub fn get_rule(&self) -> FillRule {
+        self.rule
+    }
+}
+
+impl Color {
+    pub fn new(color_space: Rc<ColorSpace>, components: Vec<f64>, pattern: Option<String>) -> Self {
+        Color { color_space, components, pattern }
+    }
+
+    /// Returns the initial colour of a colour space, set by `CS` or `cs`.
+    pub(crate) fn initial(color_space: Rc<ColorSpace>) -> Self {
+        let components = color_space.get_initial_color();
+        Color { color_space, components, pattern: None }
+    }
+
+    /// Returns the colour space.
+    pub fn get_color_space(&self) -> &ColorSpace {
+        &self.color_space
+    }
+
+    /// Returns the colour components.
+    pub fn get_components(&self) -> &[f64] {
+        &self.components
+    }
+
+    /// Returns the name of the pattern resource for the Pattern colour space.
+    pub fn get_pattern(&self) -> Option<&str> {
+        self.pattern.as_deref()
+    }
+
+    /// Converts the colour to sRGB, see [`ColorSpace::to_rgb`].
+    ///
+    /// Colours of coloured patterns have no components and are converted to black.
+    pub fn to_rgb(&self) -> [f64; 3] {
+        self.color_space.to_rgb(&self.components)
+    }
+
+    pub(crate) fn set_components(&mut self, components: Vec<f64>, pattern: Option<String>) {
+        self.components = components;
+        self.pattern = pattern;
+    }
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Color::initial(Rc::new(ColorSpace::DeviceGray))
+    }
+}
+
+impl TextState {
+    /// Returns the extra spacing added after each glyph, in unscaled text space units.
+    pub fn get_char_spacing(&self) -> f64 {
+        self.char_spacing
+    }
+    /// Returns the extra spacing added after each single-byte code 32, in unscaled text space units.
+    pub fn get_word_spacing(&self) -> f64 {
+        self.word_spacing
+    }
+    /// Returns the horizontal scaling as a factor, where 1.0 is normal width.
+    pub fn get_horizontal_scaling(&self) -> f64 {
+        self.horizontal_scaling
+    }
+    /// Returns the distance between the baselines of consecutive lines.
+    pub fn get_leading(&self) -> f64 {
+        self.leading
+    }
+    /// Returns the current font, or None before `Tf` or if the font couldn't be loaded.
+    pub fn get_font(&self) -> Option<&Rc<Font>> {
+        self.font.as_ref()
+    }
+    /// Returns the font size.
+    pub fn get_font_size(&self) -> f64 {
+        self.font_size
+    }
+    /// Returns the text rendering mode, from 0 (fill) to 7 (clip only).
+    pub fn get_render_mode(&self) -> i64 {
+        self.render_mode
+    }
+    /// Returns the distance to move the baseline up.
+    pub fn get_rise(&self) -> f64 {
+        self.rise
+    }
+
+    pub(crate) fn set_char_spacing(&mut self, char_spacing: f64) {
+        self.char_spacing = char_spacing;
+    }
+    pub(crate) fn set_word_spacing(&mut self, word_spacing: f64) {
+        self.word_spacing = word_spacing;
+    }
+    pub(crate) fn set_horizontal_scaling(&mut self, horizontal_scaling: f64) {
+        self.horizontal_scaling = horizontal_scaling;
+    }
+    pub(crate) fn set_leading(&mut self, leading: f64) {
+        self.leading = leading;
+    }
+    pub(crate) fn set_font(&mut self, font: Option<Rc<Font>>, font_size: f64) {
+        self.font = font;
+        self.font_size = font_size;
+    }
+    pub(crate) fn set_render_mode(&mut self, render_mode: i64) {
+        self.render_mode = render_mode;
+    }
+    pub(crate) fn set_rise(&mut self, rise: f64) {
+        self.rise = rise;
+    }
+}
+
+impl Default for TextState {
+    fn default() -> Self {
+        TextState {
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            horizontal_scaling: 1.0,
+            leading: 0.0,
+            font: None,
+            font_size: 0.0,
+            render_mode: 0,
+            rise: 0.0,
+        }
+    }
+}
+
+impl GraphicsState {
+    /// Creates the initial graphics state with the given transformation matrix.
+    pub fn new(ctm: Matrix) -> Self {
+        GraphicsState {
+            ctm,
+            clip: Vec::new(),
+            stroke_color: Color::default(),
+            fill_color: Color::default(),
+            text: TextState::default(),
+            line_width: 1.0,
+            line_cap: LineCap::Butt,
+            line_join: LineJoin::Miter,
+            miter_limit: 10.0,
+            dash: (Vec::new(), 0.0),
+            rendering_intent: "RelativeColorimetric".to_string(),
+            flatness: 1.0,
+            soft_mask: None,
+            stroke_alpha: 1.0,
+            fill_alpha: 1.0,
+            blend_mode: "Normal".to_string(),
+        }
+    }
+
+    /// Returns the current transformation matrix.
+    pub fn get_ctm(&self) -> &Matrix {
+        &self.ctm
+    }
+    /// Returns the paths whose intersection is the clipping region, empty if unclipped.
+    pub fn get_clip(&self) -> &[ClipPath] {
+        &self.clip
+    }
+    /// Returns the colour used for stroking.
+    pub fn get_stroke_color(&self) -> &Color {
+        &self.stroke_color
+    }
+    /// Returns the colour used for filling, including text.
+    pub fn get_fill_color(&self) -> &Color {
+        &self.fill_color
+    }
+    /// Returns the text state parameters.
+    pub fn get_text_state(&self) -> &TextState {
+        &self.text
+    }
+    /// Returns the line width in user space.
+    pub fn get_line_width(&self) -> f64 {
+        self.line_width
+    }
+    pub fn get_line_cap(&self) -> LineCap {
+        self.line_cap
+    }
+    pub fn get_line_join(&self) -> LineJoin {
+        self.line_join
+    }
+    pub fn get_miter_limit(&self) -> f64 {
+        self.miter_limit
+    }
+    /// Returns the dash array and the dash phase in user space.
+    pub fn get_dash(&self) -> (&[f64], f64) {
+        (&self.dash.0, self.dash.1)
+    }
+    pub fn get_rendering_intent(&self) -> &str {
+        &self.rendering_intent
+    }
+    pub fn get_flatness(&self) -> f64 {
+        self.flatness
+    }
+    /// Returns the soft mask dictionary, or None if there is no soft mask.
+    pub fn get_soft_mask(&self) -> Option<&PDFObject> {
+        self.soft_mask.as_ref()
+    }
+    /// Returns the constant alpha for stroking.
+    pub fn get_stroke_alpha(&self) -> f64 {
+        self.stroke_alpha
+    }
+    /// Returns the constant alpha for filling.
+    pub fn get_fill_alpha(&self) -> f64 {
+        self.fill_alpha
+    }
+    pub fn get_blend_mode(&self) -> &str {
+        &self.blend_mode
+    }
+
+    pub(crate) fn get_text_state_mut(&mut self) -> &mut TextState {
+        &mut self.text
+    }
+    pub(crate) fn get_stroke_color_mut(&mut self) -> &mut Color {
+        &mut self.stroke_color
+    }
+    pub(crate) fn get_fill_color_mut(&mut self) -> &mut Color {
+        &mut self.fill_color
+    }
+    pub(crate) fn set_ctm(&mut self, ctm: Matrix) {
+        self.ctm = ctm;
+    }
+    pub(crate) fn push_clip(&mut self, clip: ClipPath) {
+        self.clip.push(clip);
+    }
+    pub(crate) fn set_stroke_color(&mut self, color: Color) {
+        self.stroke_color = color;
+    }
+    pub(crate) fn set_fill_color(&mut self, color: Color) {
+        self.fill_color = color;
+    }
+    pub(crate) fn set_line_width(&mut self, line_width: f64) {
+        self.line_width = line_width;
+    }
+    pub(crate) fn set_line_cap(&mut self, line_cap: i64) {
+        self.line_cap = match line_cap {
+            1 => LineCap::Round,
+            2 => LineCap::ProjectingSquare,
+            _ => LineCap::Butt,
+        };
+    }
+    pub(crate) fn set_line_join(&mut self, line_join: i64) {
+        self.line_join = match line_join {
+            1 => LineJoin::Round,
+            2 => LineJoin::Bevel,
+            _ => LineJoin::Miter,
+        };
+    }
+    pub(crate) fn set_miter_limit(&mut self, miter_limit: f64) {
+        self.miter_limit = miter_limit;
+    }
+    pub(crate) fn set_dash(&mut self, array: Vec<f64>, phase: f64) {
+        self.dash = (array, phase);
+    }
+    pub(crate) fn set_rendering_intent(&mut self, rendering_intent: String) {
+        self.rendering_intent = rendering_intent;
+    }
+    pub(crate) fn set_flatness(&mut self, flatness: f64) {
+        self.flatness = flatness;
+    }
+    pub(crate) fn set_soft_mask(&mut self, soft_mask: Option<PDFObject>) {
+        self.soft_mask = soft_mask;
+    }
+    pub(crate) fn set_stroke_alpha(&mut self, alpha: f64) {
+        self.stroke_alpha = alpha;
+    }
+    pub(crate) fn set_fill_alpha(&mut self, alpha: f64) {
+        self.fill_alpha = alpha;
+    }
+    pub(crate) fn set_blend_mode(&mut self, blend_mode: String) {
+        self.blend_mode = blend_mode;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matrix() {
+        let m = Matrix::translate(10.0, 20.0).multiply(&Matrix::scale(2.0, 3.0));
+        assert_eq!(m.transform(1.0, 1.0), (22.0, 63.0));
+        let inverse = m.invert().unwrap();
+        assert_eq!(inverse.transform(22.0, 63.0), (1.0, 1.0));
+        assert_eq!(m.multiply(&inverse), Matrix::identity());
+    }
+}
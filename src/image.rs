@@ -0,0 +1,655 @@
+use crate::colorspace::ColorSpace;
+use crate::constants::{BITS_PER_COMPONENT, DECODE, HEIGHT, IMAGE_MASK, MASK, SMASK, WIDTH};
+use crate::document::PDFDocument;
+use crate::error::PDFError::ImageDecodeError;
+use crate::error::Result;
+use crate::filter::is_image_filter;
+use crate::graphics::{GraphicsState, Matrix};
+use crate::interpreter::{ContentVisitor, Image};
+use crate::objects::{PDFObject, Stream};
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use std::io::Write;
+use std::path::Path;
+
+/// The signature starting every PNG file.
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// The maximum number of pixels of a decoded image, 2^27 like a rendered page.
+const MAX_IMAGE_PIXELS: u64 = 1 << 27;
+
+/// The CRC-32 table of PNG chunks, for the polynomial `0xedb88320`.
+const CRC_TABLE: [u32; 256] = crc_table();
+
+/// The format of an exported image file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageFormat {
+    /// JPEG data of a `DCTDecode` image, exported as is.
+    Jpeg,
+    /// JPEG 2000 data of a `JPXDecode` image, exported as is.
+    Jpeg2000,
+    /// A PNG file of the decoded image, with an alpha channel if the image is masked.
+    Png,
+    /// A binary PPM file of the decoded image, masked areas being white.
+    Ppm,
+}
+
+/// An exported image file.
+#[derive(Debug)]
+pub struct ImageFile {
+    format: ImageFormat,
+    data: Vec<u8>,
+}
+
+/// An image decoded to 8-bit RGBA pixels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bitmap {
+    width: u32,
+    height: u32,
+    /// The pixels row by row from the top, four bytes each.
+    pixels: Vec<u8>,
+}
+
+/// An image painted on a page, an image XObject or an inline image.
+pub struct PageImage<'a> {
+    document: &'a PDFDocument,
+    /// The name of the XObject resource, None for inline images.
+    name: Option<String>,
+    /// The image XObject reference, None for inline images and direct objects.
+    obj_ref: Option<(u32, u16)>,
+    /// The image dictionary and data.
+    stream: Stream,
+    width: u32,
+    height: u32,
+    bits_per_component: u32,
+    /// The colour space, None for image masks and JPEG 2000 images without one.
+    color_space: Option<PDFObject>,
+    /// True for stencil masks painted with the fill colour.
+    image_mask: bool,
+    /// The transformation matrix mapping the unit square to the page.
+    matrix: Matrix,
+}
+
+/// Collects the images painted by a content stream.
+pub(crate) struct ImageCollector<'a> {
+    document: &'a PDFDocument,
+    images: Vec<PageImage<'a>>,
+}
+
+impl ImageFormat {
+    /// Returns the usual file extension of the format, without the dot.
+    pub fn get_extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Jpeg2000 => "jp2",
+            ImageFormat::Png => "png",
+            ImageFormat::Ppm => "ppm",
+        }
+    }
+}
+
+impl ImageFile {
+    /// Returns the format of the file.
+    pub fn get_format(&self) -> ImageFormat {
+        self.format
+    }
+
+    /// Returns the file data.
+    pub fn get_data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Writes the image file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file, usually with the extension of the format
+    ///
+    /// # Returns
+    ///
+    /// An error if the file can't be written
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, &self.data)?;
+        Ok(())
+    }
+}
+
+impl Bitmap {
+    /// Creates a transparent bitmap.
+    pub fn new(width: u32, height: u32) -> Self {
+        Bitmap { width, height, pixels: vec![0; width as usize * height as usize * 4] }
+    }
+
+    /// Returns the width in pixels.
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height in pixels.
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the RGBA pixels row by row from the top.
+    pub fn get_pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Returns the RGBA components of a pixel, transparent black outside the bitmap.
+    pub fn get_pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        if x >= self.width || y >= self.height {
+            return [0; 4];
+        }
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2], self.pixels[i + 3]]
+    }
+
+    pub(crate) fn get_pixels_mut(&mut self) -> &mut [u8] {
+        &mut self.pixels
+    }
+
+    pub(crate) fn set_pixel(&mut self, x: u32, y: u32, pixel: [u8; 4]) {
+        if x < self.width && y < self.height {
+            let i = (y as usize * self.width as usize + x as usize) * 4;
+            self.pixels[i..i + 4].copy_from_slice(&pixel);
+        }
+    }
+
+    /// Returns true if a pixel is not fully opaque.
+    pub fn has_alpha(&self) -> bool {
+        self.pixels.chunks_exact(4).any(|pixel| pixel[3] != 255)
+    }
+
+    /// Encodes the bitmap as a PNG file.
+    ///
+    /// # Returns
+    ///
+    /// An 8-bit RGB PNG file, RGBA if the bitmap has transparent pixels
+    pub fn to_png(&self) -> Result<Vec<u8>> {
+        let alpha = self.has_alpha();
+        let channels = if alpha { 4 } else { 3 };
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        for row in self.pixels.chunks_exact((self.width as usize * 4).max(1)) {
+            // Each scanline starts with its filter type, none
+            let mut scanline = Vec::with_capacity(self.width as usize * channels + 1);
+            scanline.push(0);
+            for pixel in row.chunks_exact(4) {
+                scanline.extend_from_slice(&pixel[..channels]);
+            }
+            encoder.write_all(&scanline)?;
+        }
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&self.width.to_be_bytes());
+        header.extend_from_slice(&self.height.to_be_bytes());
+        // Bit depth, colour type, compression, filter and interlace methods
+        header.extend_from_slice(&[8, if alpha { 6 } else { 2 }, 0, 0, 0]);
+        let mut png = PNG_SIGNATURE.to_vec();
+        write_png_chunk(&mut png, b"IHDR", &header);
+        write_png_chunk(&mut png, b"IDAT", &encoder.finish()?);
+        write_png_chunk(&mut png, b"IEND", &[]);
+        Ok(png)
+    }
+
+    /// Encodes the bitmap as a binary PPM file.
+    ///
+    /// PPM files have no alpha channel, so the bitmap is composited onto white.
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut ppm = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        ppm.reserve(self.pixels.len() / 4 * 3);
+        for pixel in self.pixels.chunks_exact(4) {
+            let alpha = pixel[3] as u32;
+            ppm.extend(pixel[..3].iter().map(|&c| ((c as u32 * alpha + 255 * (255 - alpha) + 127) / 255) as u8));
+        }
+        ppm
+    }
+}
+
+impl<'a> PageImage<'a> {
+    /// Reads the attributes of an image.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - The document used to resolve indirect references
+    /// * `name` - The name of the XObject resource, None for inline images
+    /// * `obj_ref` - The reference of the image XObject
+    /// * `stream` - The image dictionary and data
+    /// * `color_space` - The resolved colour space of the image
+    /// * `matrix` - The transformation matrix mapping the unit square to the page
+    pub(crate) fn load(
+        document: &'a PDFDocument,
+        name: Option<String>,
+        obj_ref: Option<(u32, u16)>,
+        stream: Stream,
+        color_space: Option<PDFObject>,
+        matrix: Matrix,
+    ) -> Result<PageImage<'a>> {
+        let metadata = stream.get_metadata();
+        let integer = |key: &str| -> Result<Option<i64>> {
+            match metadata.get(key) {
+                Some(value) => Ok(document.resolve(value)?.as_i64()),
+                None => Ok(None),
+            }
+        };
+        let width = integer(WIDTH)?.unwrap_or(0).max(0) as u32;
+        let height = integer(HEIGHT)?.unwrap_or(0).max(0) as u32;
+        let image_mask = match metadata.get(IMAGE_MASK) {
+            Some(value) => document.resolve(value)?.as_bool().unwrap_or(false),
+            None => false,
+        };
+        // Image masks have one bit per sample, JPEG 2000 images may leave it to the codestream
+        let bits_per_component = match image_mask {
+            true => 1,
+            false => integer(BITS_PER_COMPONENT)?.unwrap_or(8).clamp(0, u32::MAX as i64) as u32,
+        };
+        let color_space = if image_mask { None } else { color_space };
+        Ok(PageImage { document, name, obj_ref, stream, width, height, bits_per_component, color_space, image_mask, matrix })
+    }
+
+    /// Returns the name of the XObject resource, None for inline images.
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the object reference of an image XObject.
+    pub fn get_obj_ref(&self) -> Option<(u32, u16)> {
+        self.obj_ref
+    }
+
+    /// Returns true for inline images.
+    pub fn is_inline(&self) -> bool {
+        self.name.is_none()
+    }
+
+    /// Returns the image dictionary and data.
+    pub fn get_stream(&self) -> &Stream {
+        &self.stream
+    }
+
+    /// Returns the width in samples.
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height in samples.
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the number of bits per colour component, 1 for image masks.
+    pub fn get_bits_per_component(&self) -> u32 {
+        self.bits_per_component
+    }
+
+    /// Returns the colour space, None for image masks and JPEG 2000 images without one.
+    pub fn get_color_space(&self) -> Option<&PDFObject> {
+        self.color_space.as_ref()
+    }
+
+    /// Returns the colour space family, such as `DeviceRGB`, `ICCBased` or `Indexed`.
+    pub fn get_color_space_family(&self) -> Option<&str> {
+        match self.color_space.as_ref()? {
+            PDFObject::Named(name) => Some(name),
+            PDFObject::Array(values) => values.first().and_then(|family| family.as_name()).map(|name| name.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Returns the filters of the image data, in the order they are applied for decoding.
+    pub fn get_filters(&self) -> Vec<&str> {
+        self.stream.get_filters()
+    }
+
+    /// Returns true for stencil masks, painted with the fill colour.
+    pub fn is_image_mask(&self) -> bool {
+        self.image_mask
+    }
+
+    /// Returns the transformation matrix mapping the unit square to the page.
+    pub fn get_matrix(&self) -> &Matrix {
+        &self.matrix
+    }
+
+    /// Returns the bounding box of the image on the page as `[x0, y0, x1, y1]`.
+    pub fn get_bbox(&self) -> [f64; 4] {
+        let corners = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)].map(|(x, y)| self.matrix.transform(x, y));
+        corners.iter().fold([f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY], |bbox, (x, y)| {
+            [bbox[0].min(*x), bbox[1].min(*y), bbox[2].max(*x), bbox[3].max(*y)]
+        })
+    }
+
+    /// Returns the format of the encoded image data, for `DCTDecode` and `JPXDecode` images.
+    pub fn get_native_format(&self) -> Option<ImageFormat> {
+        match self.get_filters().last().copied() {
+            Some("DCTDecode" | "DCT") => Some(ImageFormat::Jpeg),
+            Some("JPXDecode") => Some(ImageFormat::Jpeg2000),
+            _ => None,
+        }
+    }
+
+    /// Decodes the image to RGBA pixels.
+    ///
+    /// The samples are mapped through the `/Decode` array and converted from the
+    /// colour space to RGB. The alpha channel comes from the `/SMask` soft mask or
+    /// the `/Mask` stencil or colour key mask. Image masks are decoded to black
+    /// where they are painted and transparent elsewhere.
+    ///
+    /// `DCTDecode` images are decompressed when the `jpeg` feature is enabled,
+    /// their colour space defaulting to the one of the JPEG components.
+    ///
+    /// # Returns
+    ///
+    /// The pixels, or an error for images encoded with another image filter such
+    /// as `JPXDecode` or `CCITTFaxDecode`, in an unsupported colour space or bit
+    /// depth, larger than 2^27 pixels or whose data is shorter than its size requires
+    pub fn decode(&self) -> Result<Bitmap> {
+        let (width, height) = (self.width, self.height);
+        if (width as u64).checked_mul(height as u64).is_none_or(|pixels| pixels > MAX_IMAGE_PIXELS) {
+            return Err(ImageDecodeError(format!("Image of {}x{} pixels too large", width, height)));
+        }
+        let (data, jpeg_components) = self.read_samples()?;
+        if self.image_mask {
+            check_data_len(&data, width, height, 1, 1)?;
+            let mut bitmap = Bitmap::new(width, height);
+            let samples = unpack_samples(&data, width, height, 1, 1);
+            // The mask is painted where the sample is 0, or 1 with a Decode array of [1 0]
+            let painted = match self.get_decode(vec![(0.0, 1.0)])?.first() {
+                Some((d0, d1)) if d0 > d1 => 1,
+                _ => 0,
+            };
+            for (i, sample) in samples.into_iter().enumerate() {
+                let alpha = if sample == painted { 255 } else { 0 };
+                bitmap.set_pixel(i as u32 % width, i as u32 / width, [0, 0, 0, alpha]);
+            }
+            return Ok(bitmap);
+        }
+        let color_space = match (&self.color_space, jpeg_components) {
+            (Some(color_space), _) => ColorSpace::load(self.document, color_space, None)?,
+            (None, Some(1)) => Some(ColorSpace::DeviceGray),
+            (None, Some(3)) => Some(ColorSpace::DeviceRGB),
+            (None, Some(4)) => Some(ColorSpace::DeviceCMYK),
+            (None, _) => None,
+        };
+        let color_space = match color_space {
+            Some(color_space) if color_space.get_components() > 0 => color_space,
+            _ => return Err(ImageDecodeError("Missing or unsupported colour space".to_string())),
+        };
+        let components = color_space.get_components();
+        if jpeg_components.is_some_and(|jpeg_components| jpeg_components != components) {
+            return Err(ImageDecodeError("The JPEG components don't match the colour space".to_string()));
+        }
+        // JPEG samples are 8-bit whatever the dictionary says
+        let bits_per_component = if jpeg_components.is_some() { 8 } else { self.bits_per_component };
+        if !matches!(bits_per_component, 1 | 2 | 4 | 8 | 16) {
+            return Err(ImageDecodeError(format!("Unsupported bits per component: {}", bits_per_component)));
+        }
+        let max = ((1u32 << bits_per_component) - 1) as f64;
+        // Indexed images hold table indexes rather than values between 0 and 1
+        let decode = match color_space {
+            ColorSpace::Indexed { .. } => self.get_decode(vec![(0.0, max)])?,
+            _ => self.get_decode(color_space.get_ranges())?,
+        };
+        check_data_len(&data, width, height, components, bits_per_component)?;
+        let mut bitmap = Bitmap::new(width, height);
+        let samples = unpack_samples(&data, width, height, components, bits_per_component);
+        let value = |i: usize, sample: u16| decode[i].0 + sample as f64 * (decode[i].1 - decode[i].0) / max;
+        if components == 1 {
+            // Colours of single component images are computed once for each sample value
+            let colors = (0..=max as u16).map(|sample| color_space.to_rgb8(&[value(0, sample)])).collect::<Vec<[u8; 3]>>();
+            for (i, sample) in samples.iter().enumerate() {
+                let [r, g, b] = colors[*sample as usize];
+                bitmap.set_pixel(i as u32 % width, i as u32 / width, [r, g, b, 255]);
+            }
+        } else {
+            let mut values = vec![0.0; components];
+            for (i, pixel) in samples.chunks_exact(components).enumerate() {
+                for (c, sample) in pixel.iter().enumerate() {
+                    values[c] = value(c, *sample);
+                }
+                let [r, g, b] = color_space.to_rgb8(&values);
+                bitmap.set_pixel(i as u32 % width, i as u32 / width, [r, g, b, 255]);
+            }
+        }
+        self.apply_mask(&mut bitmap, &samples, components)?;
+        Ok(bitmap)
+    }
+
+    /// Exports the image as a file.
+    ///
+    /// JPEG and JPEG 2000 images are exported without decoding them, other images
+    /// are decoded and encoded in `format`.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - The format of decoded images, [`ImageFormat::Png`] or [`ImageFormat::Ppm`]
+    ///
+    /// # Returns
+    ///
+    /// The image file, or an error if the image can't be decoded
+    pub fn export(&self, format: ImageFormat) -> Result<ImageFile> {
+        if let Some(native) = self.get_native_format() {
+            return Ok(ImageFile { format: native, data: self.stream.get_decoded_buf()? });
+        }
+        let data = match format {
+            ImageFormat::Png => self.decode()?.to_png()?,
+            ImageFormat::Ppm => self.decode()?.to_ppm(),
+            _ => return Err(ImageDecodeError(format!("Can't encode images as {:?}", format))),
+        };
+        Ok(ImageFile { format, data })
+    }
+
+    /// Reads the image data, decompressing it if it is encoded with `DCTDecode`.
+    ///
+    /// # Returns
+    ///
+    /// The samples packed in rows, and the number of components of JPEG images
+    fn read_samples(&self) -> Result<(Vec<u8>, Option<usize>)> {
+        let data = self.stream.get_decoded_buf()?;
+        // Decoding stopped at the first image filter
+        match self.get_filters().into_iter().find(|filter| is_image_filter(filter)) {
+            None => Ok((data, None)),
+            Some("DCTDecode" | "DCT") if !self.image_mask => {
+                let (samples, components) = decode_jpeg(&data, self.width, self.height)?;
+                Ok((samples, Some(components)))
+            }
+            Some(filter) => Err(ImageDecodeError(format!("Unsupported filter: {}", filter))),
+        }
+    }
+
+    /// Returns the `/Decode` array as ranges of each component.
+    ///
+    /// # Arguments
+    ///
+    /// * `decode` - The default ranges, used for the components without one
+    fn get_decode(&self, mut decode: Vec<(f64, f64)>) -> Result<Vec<(f64, f64)>> {
+        if let Some(object) = self.stream.get_metadata().get(DECODE)
+            && let Some(values) = self.document.resolve(object)?.as_array() {
+            for (range, pair) in decode.iter_mut().zip(values.chunks_exact(2)) {
+                if let (Some(d0), Some(d1)) = (pair[0].as_f64(), pair[1].as_f64()) {
+                    *range = (d0, d1);
+                }
+            }
+        }
+        Ok(decode)
+    }
+
+    /// Sets the alpha channel of a decoded image from its soft mask, stencil mask
+    /// or colour key mask.
+    ///
+    /// Masks of a different size than the image are scaled to it.
+    fn apply_mask(&self, bitmap: &mut Bitmap, samples: &[u16], components: usize) -> Result<()> {
+        let metadata = self.stream.get_metadata();
+        let (mask, channel) = match (metadata.get(SMASK), metadata.get(MASK)) {
+            (Some(smask), _) => (self.document.resolve(smask)?.into_owned(), 0),
+            (None, Some(mask)) => (self.document.resolve(mask)?.into_owned(), 3),
+            (None, None) => return Ok(()),
+        };
+        match mask {
+            // Soft masks are gray images whose gray level is the alpha,
+            // stencil masks are image masks whose painted area is opaque
+            PDFObject::Stream(stream) => {
+                let color_space = Some(PDFObject::Named("DeviceGray".to_string()));
+                let mask = PageImage::load(self.document, None, None, stream, color_space, self.matrix)?;
+                let mask = mask.decode()?;
+                let (width, height) = (bitmap.width as u64, bitmap.height as u64);
+                for y in 0..bitmap.height {
+                    for x in 0..bitmap.width {
+                        let mx = (x as u64 * mask.width as u64 / width) as u32;
+                        let my = (y as u64 * mask.height as u64 / height) as u32;
+                        let alpha = mask.get_pixel(mx, my)[channel];
+                        let mut pixel = bitmap.get_pixel(x, y);
+                        pixel[3] = alpha;
+                        bitmap.set_pixel(x, y, pixel);
+                    }
+                }
+            }
+            // Colour key masks hide the samples within the given range of each component
+            PDFObject::Array(values) => {
+                let ranges = values.iter().filter_map(|value| value.as_i64()).collect::<Vec<i64>>();
+                if ranges.len() < components * 2 {
+                    return Ok(());
+                }
+                for (i, pixel) in samples.chunks_exact(components).enumerate() {
+                    let masked = pixel.iter().zip(ranges.chunks_exact(2))
+                        .all(|(sample, range)| (range[0]..=range[1]).contains(&(*sample as i64)));
+                    if masked {
+                        let (x, y) = (i as u32 % bitmap.width, i as u32 / bitmap.width);
+                        let mut pixel = bitmap.get_pixel(x, y);
+                        pixel[3] = 0;
+                        bitmap.set_pixel(x, y, pixel);
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ImageCollector<'a> {
+    pub(crate) fn new(document: &'a PDFDocument) -> Self {
+        ImageCollector { document, images: Vec::new() }
+    }
+
+    /// Returns the images in the order they are painted.
+    pub(crate) fn into_images(self) -> Vec<PageImage<'a>> {
+        self.images
+    }
+}
+
+impl ContentVisitor for ImageCollector<'_> {
+    fn draw_image(&mut self, image: &Image, state: &GraphicsState) -> Result<()> {
+        let name = image.get_name().map(|name| name.to_string());
+        let stream = image.get_stream().clone();
+        let color_space = image.get_color_space().cloned();
+        let image = PageImage::load(self.document, name, image.get_obj_ref(), stream, color_space, *state.get_ctm())?;
+        self.images.push(image);
+        Ok(())
+    }
+}
+
+/// Decompresses JPEG data to 8-bit samples packed in rows.
+///
+/// YCbCr and YCCK data is converted to RGB and CMYK. The decoder doesn't
+/// allocate more than the samples of an image of the expected size.
+///
+/// # Returns
+///
+/// The samples and their number of components, 1, 3 or 4
+#[cfg(feature = "jpeg")]
+fn decode_jpeg(data: &[u8], width: u32, height: u32) -> Result<(Vec<u8>, usize)> {
+    use jpeg_decoder::{Decoder, PixelFormat};
+    let mut decoder = Decoder::new(data);
+    decoder.set_max_decoding_buffer_size((width as usize).saturating_mul(height as usize).saturating_mul(4));
+    let samples = decoder.decode().map_err(|e| ImageDecodeError(format!("Invalid JPEG data: {}", e)))?;
+    let Some(info) = decoder.info() else {
+        return Err(ImageDecodeError("Missing JPEG frame".to_string()));
+    };
+    if (info.width as u32, info.height as u32) != (width, height) {
+        return Err(ImageDecodeError(format!("JPEG size {}x{} instead of {}x{}", info.width, info.height, width, height)));
+    }
+    let components = match info.pixel_format {
+        PixelFormat::L8 => 1,
+        PixelFormat::RGB24 => 3,
+        PixelFormat::CMYK32 => 4,
+        PixelFormat::L16 => return Err(ImageDecodeError("Unsupported 16-bit JPEG".to_string())),
+    };
+    Ok((samples, components))
+}
+
+#[cfg(not(feature = "jpeg"))]
+fn decode_jpeg(_data: &[u8], _width: u32, _height: u32) -> Result<(Vec<u8>, usize)> {
+    Err(ImageDecodeError("DCTDecode images require the jpeg feature".to_string()))
+}
+
+/// Checks that image data holds every sample of the image, before allocating
+/// pixels for the size given by the image dictionary.
+fn check_data_len(data: &[u8], width: u32, height: u32, components: usize, bits: u32) -> Result<()> {
+    let len = (width as usize)
+        .checked_mul(components)
+        .and_then(|values| values.checked_mul(bits as usize))
+        .and_then(|row_bits| row_bits.div_ceil(8).checked_mul(height as usize));
+    match len {
+        Some(len) if len <= data.len() => Ok(()),
+        _ => Err(ImageDecodeError(format!("Image data of {} bytes too short for {}x{} samples", data.len(), width, height))),
+    }
+}
+
+/// Splits image data into samples.
+///
+/// Rows start on a byte boundary.
+///
+/// # Arguments
+///
+/// * `data` - The decoded image data
+/// * `width` - The width of the image in samples
+/// * `height` - The height of the image in samples
+/// * `components` - The number of colour components of each sample
+/// * `bits` - The number of bits per colour component: 1, 2, 4, 8 or 16
+fn unpack_samples(data: &[u8], width: u32, height: u32, components: usize, bits: u32) -> Vec<u16> {
+    let values_per_row = width as usize * components;
+    let row_len = (values_per_row * bits as usize).div_ceil(8);
+    let mut samples = Vec::with_capacity(values_per_row * height as usize);
+    for row in 0..height as usize {
+        let row = data.get(row * row_len..).unwrap_or(&[]);
+        for i in 0..values_per_row {
+            let sample = match bits {
+                8 => row.get(i).copied().unwrap_or(0) as u16,
+                16 => u16::from_be_bytes([row.get(i * 2).copied().unwrap_or(0), row.get(i * 2 + 1).copied().unwrap_or(0)]),
+                _ => {
+                    let bit = i * bits as usize;
+                    let byte = row.get(bit / 8).copied().unwrap_or(0) as u16;
+                    (byte >> (8 - bits as usize - bit % 8)) & ((1 << bits) - 1)
+                }
+            };
+            samples.push(sample);
+        }
+    }
+    samples
+}
+
+/// Appends a PNG chunk with its length and CRC.
+fn write_png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = kind.iter().chain(data).fold(0xffffffffu32, |crc, &b| {
+        CRC_TABLE[((crc ^ b as u32) & 0xff) as usize] ^ (crc >> 8)
+    });
+    png.extend_from_slice(&(crc ^ 0xffffffff).to_be_bytes());
+}
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xedb88320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
@@ -0,0 +1,765 @@
+use crate::constants::{
+    BBOX, COLOR_SPACE, EXT_G_STATE, FONT, MATRIX, PROPERTIES, RESOURCES, SHADING, SUBTYPE, XOBJECT,
+};
+use crate::colorspace::ColorSpace;
+use crate::content::{ContentParser, Operation, Operator};
+use crate::document::PDFDocument;
+use crate::error::Result;
+use crate::font::Font;
+use crate::graphics::{ClipPath, Color, FillRule, GraphicsState, Matrix, Path, PathSegment};
+use crate::objects::{Dictionary, PDFObject, Stream};
+use crate::page::PDFPage;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// A resource together with its object reference, None if it is a direct object.
+type Resource = (Option<(u32, u16)>, PDFObject);
+
+/// The maximum nesting of Form XObjects, guarding against cyclic forms.
+const MAX_FORM_DEPTH: usize = 32;
+
+/// Receives what a content stream draws, in device space.
+///
+/// All methods do nothing by default, so visitors only implement what they
+/// are interested in. An error returned by a visitor stops the interpreter.
+#[allow(unused_variables)]
+pub trait ContentVisitor {
+    /// Called when a path is filled.
+    fn fill_path(&mut self, path: &Path, rule: FillRule, state: &GraphicsState) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when a path is stroked. Paths which are both filled and stroked
+    /// are passed to `fill_path` first.
+    fn stroke_path(&mut self, path: &Path, state: &GraphicsState) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called for each string shown by a text-showing operator.
+    fn show_text(&mut self, glyphs: &[Glyph], state: &GraphicsState) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when an image XObject or inline image is painted.
+    ///
+    /// The image occupies the unit square of user space, which the current
+    /// transformation matrix maps to device space.
+    fn draw_image(&mut self, image: &Image, state: &GraphicsState) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when a shading is painted by `sh`, clipped to the current clipping region.
+    fn paint_shading(&mut self, shading: &PDFObject, state: &GraphicsState) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when a marked-content sequence begins.
+    fn begin_marked_content(&mut self, tag: &str, properties: Option<&Dictionary>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when a marked-content sequence ends.
+    fn end_marked_content(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called for operations with an operator not defined by the specification.
+    fn unknown_operation(&mut self, operation: &Operation) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A glyph shown by a text-showing operator.
+#[derive(Clone, Debug)]
+pub struct Glyph {
+    /// The character code.
+    code: u32,
+    /// The Unicode text of the code, if the font maps it.
+    unicode: Option<String>,
+    /// The horizontal displacement in unscaled text space units.
+    advance: f64,
+    /// The text rendering matrix, mapping the glyph's text space to device space.
+    matrix: Matrix,
+}
+
+/// An image painted by `Do` or an inline image.
+pub struct Image<'a> {
+    /// The name of the XObject resource, None for inline images.
+    name: Option<&'a str>,
+    /// The image XObject reference, None for inline images and direct objects.
+    obj_ref: Option<(u32, u16)>,
+    /// The image dictionary and data.
+    stream: &'a Stream,
+    /// The colour space of the image, with indirect references and the names of
+    /// `/ColorSpace` resources resolved. None for image masks and JPEG 2000 images
+    /// without one.
+    color_space: Option<PDFObject>,
+}
+
+/// Executes content stream operations while tracking the graphics state.
+///
+/// The interpreter reports paths, text, images and shadings to a
+/// [`ContentVisitor`] and recurses into Form XObjects with their own
+/// resources and matrix.
+pub struct ContentInterpreter<'a> {
+    document: &'a PDFDocument,
+    state: GraphicsState,
+    /// The graphics states saved by `q`.
+    stack: Vec<GraphicsState>,
+    /// The resource dictionaries of the page and of the forms being executed.
+    resources: Vec<Option<Dictionary>>,
+    /// The path being constructed, in device space.
+    path: Path,
+    /// The current point and the start of the current subpath, in device space.
+    current_point: (f64, f64),
+    start_point: (f64, f64),
+    /// The clipping rule set by `W` or `W*`, applied by the next painting operator.
+    pending_clip: Option<FillRule>,
+    text_matrix: Matrix,
+    line_matrix: Matrix,
+    /// Fonts loaded from indirect font dictionaries.
+    fonts: HashMap<(u32, u16), Rc<Font>>,
+    /// Colour spaces loaded from indirect colour space resources.
+    color_spaces: HashMap<(u32, u16), Rc<ColorSpace>>,
+    /// The Form XObjects being executed.
+    forms: Vec<Option<(u32, u16)>>,
+}
+
+impl Glyph {
+    pub(crate) fn new(code: u32, unicode: Option<String>, advance: f64, matrix: Matrix) -> Self {
+        Glyph { code, unicode, advance, matrix }
+    }
+
+    /// Returns the character code.
+    pub fn get_code(&self) -> u32 {
+        self.code
+    }
+
+    /// Returns the Unicode text of the code, or None if the font doesn't map it.
+    pub fn get_unicode(&self) -> Option<&str> {
+        self.unicode.as_deref()
+    }
+
+    /// Returns the font size in device space, the height of the text space em square.
+    pub fn get_size(&self) -> f64 {
+        let (x, y) = self.matrix.transform_vector(0.0, 1.0);
+        x.hypot(y)
+    }
+
+    /// Returns the horizontal displacement in unscaled text space units,
+    /// without character and word spacing.
+    pub fn get_advance(&self) -> f64 {
+        self.advance
+    }
+
+    /// Returns the text rendering matrix, which maps the glyph's text space
+    /// (scaled by the font size and horizontal scaling, with the rise applied)
+    /// to device space.
+    pub fn get_matrix(&self) -> &Matrix {
+        &self.matrix
+    }
+
+    /// Returns the origin of the glyph in device space.
+    pub fn get_origin(&self) -> (f64, f64) {
+        self.matrix.transform(0.0, 0.0)
+    }
+
+    /// Returns the point in device space where the glyph's advance ends.
+    pub fn get_end(&self) -> (f64, f64) {
+        self.matrix.transform(self.advance, 0.0)
+    }
+}
+
+impl<'a> Image<'a> {
+    /// Returns the name of the XObject resource, None for inline images.
+    pub fn get_name(&self) -> Option<&'a str> {
+        self.name
+    }
+
+    /// Returns the object reference of an image XObject.
+    pub fn get_obj_ref(&self) -> Option<(u32, u16)> {
+        self.obj_ref
+    }
+
+    /// Returns the image dictionary and data.
+    pub fn get_stream(&self) -> &'a Stream {
+        self.stream
+    }
+
+    /// Returns the colour space of the image, resolved from the resources if named.
+    pub fn get_color_space(&self) -> Option<&PDFObject> {
+        self.color_space.as_ref()
+    }
+
+    /// Returns true for inline images.
+    pub fn is_inline(&self) -> bool {
+        self.name.is_none()
+    }
+}
+
+impl<'a> ContentInterpreter<'a> {
+    /// Creates an interpreter.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - The document used to resolve resources
+    /// * `ctm` - The initial transformation matrix, mapping the default user space to
+    ///   device space. Use the identity matrix to report everything in user space units.
+    pub fn new(document: &'a PDFDocument, ctm: Matrix) -> Self {
+        ContentInterpreter {
+            document,
+            state: GraphicsState::new(ctm),
+            stack: Vec::new(),
+            resources: Vec::new(),
+            path: Path::default(),
+            current_point: (0.0, 0.0),
+            start_point: (0.0, 0.0),
+            pending_clip: None,
+            text_matrix: Matrix::identity(),
+            line_matrix: Matrix::identity(),
+            fonts: HashMap::new(),
+            color_spaces: HashMap::new(),
+            forms: Vec::new(),
+        }
+    }
+
+    /// Returns the current graphics state.
+    pub fn get_state(&self) -> &GraphicsState {
+        &self.state
+    }
+
+    /// Executes the content of a page with its resources.
+    ///
+    /// # Returns
+    ///
+    /// An error if the content can't be parsed, a resource can't be read, or the
+    /// visitor fails. Operations executed before the error have been reported.
+    pub fn run_page(&mut self, page: &PDFPage, visitor: &mut impl ContentVisitor) -> Result<()> {
+        let resources = page.get_resources()?;
+        self.run(page.get_content_parser()?, resources, visitor)
+    }
+
+    /// Executes content stream operations with the given resources.
+    pub fn run(&mut self, content: ContentParser, resources: Option<Dictionary>, visitor: &mut impl ContentVisitor) -> Result<()> {
+        self.resources.push(resources);
+        let result = self.execute_all(content, visitor);
+        self.resources.pop();
+        result
+    }
+
+    fn execute_all(&mut self, content: ContentParser, visitor: &mut impl ContentVisitor) -> Result<()> {
+        for operation in content {
+            self.execute(&operation?, visitor)?;
+        }
+        Ok(())
+    }
+
+    /// Executes an operation.
+    ///
+    /// Operations with missing or mistyped operands are ignored, as viewers do.
+    pub fn execute(&mut self, operation: &Operation, visitor: &mut impl ContentVisitor) -> Result<()> {
+        let operands = operation.get_operands();
+        let nums = operands.iter().filter_map(|operand| operand.as_f64()).collect::<Vec<f64>>();
+        let ctm = *self.state.get_ctm();
+        match operation.get_operator() {
+            // General graphics state
+            Operator::SetLineWidth => if let [width] = nums[..] {
+                self.state.set_line_width(width);
+            },
+            Operator::SetLineCap => if let [cap] = nums[..] {
+                self.state.set_line_cap(cap as i64);
+            },
+            Operator::SetLineJoin => if let [join] = nums[..] {
+                self.state.set_line_join(join as i64);
+            },
+            Operator::SetMiterLimit => if let [limit] = nums[..] {
+                self.state.set_miter_limit(limit);
+            },
+            Operator::SetDash => if let [PDFObject::Array(array), phase] = operands {
+                let array = array.iter().filter_map(|value| value.as_f64()).collect();
+                self.state.set_dash(array, phase.as_f64().unwrap_or(0.0));
+            },
+            Operator::SetRenderingIntent => if let [PDFObject::Named(intent)] = operands {
+                self.state.set_rendering_intent(intent.clone());
+            },
+            Operator::SetFlatness => if let [flatness] = nums[..] {
+                self.state.set_flatness(flatness);
+            },
+            Operator::SetExtGState => if let [PDFObject::Named(name)] = operands
+                && let Some((_, PDFObject::Dict(dict))) = self.get_resource(EXT_G_STATE, name)? {
+                self.set_ext_g_state(&dict)?;
+            },
+            // Special graphics state
+            Operator::SaveState => self.stack.push(self.state.clone()),
+            Operator::RestoreState => if let Some(state) = self.stack.pop() {
+                self.state = state;
+            },
+            Operator::ConcatMatrix => if let Some(matrix) = Matrix::from_objects(operands) {
+                self.state.set_ctm(matrix.multiply(&ctm));
+            },
+            // Path construction
+            Operator::MoveTo => if let [x, y] = nums[..] {
+                let point = ctm.transform(x, y);
+                self.path.push(PathSegment::MoveTo(point.0, point.1));
+                self.current_point = point;
+                self.start_point = point;
+            },
+            Operator::LineTo => if let [x, y] = nums[..] {
+                let point = ctm.transform(x, y);
+                self.path.push(PathSegment::LineTo(point.0, point.1));
+                self.current_point = point;
+            },
+            Operator::CurveTo => if let [x1, y1, x2, y2, x3, y3] = nums[..] {
+                self.curve_to(ctm.transform(x1, y1), ctm.transform(x2, y2), ctm.transform(x3, y3));
+            },
+            Operator::CurveToV => if let [x2, y2, x3, y3] = nums[..] {
+                self.curve_to(self.current_point, ctm.transform(x2, y2), ctm.transform(x3, y3));
+            },
+            Operator::CurveToY => if let [x1, y1, x3, y3] = nums[..] {
+                let end = ctm.transform(x3, y3);
+                self.curve_to(ctm.transform(x1, y1), end, end);
+            },
+            Operator::ClosePath => self.close_path(),
+            Operator::Rectangle => if let [x, y, width, height] = nums[..] {
+                let points = [(x, y), (x + width, y), (x + width, y + height), (x, y + height)];
+                for (i, (x, y)) in points.into_iter().enumerate() {
+                    let (x, y) = ctm.transform(x, y);
+                    self.path.push(if i == 0 { PathSegment::MoveTo(x, y) } else { PathSegment::LineTo(x, y) });
+                }
+                self.start_point = ctm.transform(x, y);
+                self.close_path();
+            },
+            // Path painting
+            Operator::StrokePath => self.paint(false, None, true, visitor)?,
+            Operator::CloseStrokePath => self.paint(true, None, true, visitor)?,
+            Operator::FillPath | Operator::FillPathObsolete => self.paint(false, Some(FillRule::NonZero), false, visitor)?,
+            Operator::FillPathEvenOdd => self.paint(false, Some(FillRule::EvenOdd), false, visitor)?,
+            Operator::FillStrokePath => self.paint(false, Some(FillRule::NonZero), true, visitor)?,
+            Operator::FillStrokePathEvenOdd => self.paint(false, Some(FillRule::EvenOdd), true, visitor)?,
+            Operator::CloseFillStrokePath => self.paint(true, Some(FillRule::NonZero), true, visitor)?,
+            Operator::CloseFillStrokePathEvenOdd => self.paint(true, Some(FillRule::EvenOdd), true, visitor)?,
+            Operator::EndPath => self.paint(false, None, false, visitor)?,
+            // Clipping paths
+            Operator::Clip => self.pending_clip = Some(FillRule::NonZero),
+            Operator::ClipEvenOdd => self.pending_clip = Some(FillRule::EvenOdd),
+            // Text objects
+            Operator::BeginText => {
+                self.text_matrix = Matrix::identity();
+                self.line_matrix = Matrix::identity();
+            }
+            Operator::EndText => {}
+            // Text state
+            Operator::SetCharSpacing => if let [spacing] = nums[..] {
+                self.state.get_text_state_mut().set_char_spacing(spacing);
+            },
+            Operator::SetWordSpacing => if let [spacing] = nums[..] {
+                self.state.get_text_state_mut().set_word_spacing(spacing);
+            },
+            Operator::SetHorizontalScaling => if let [scaling] = nums[..] {
+                self.state.get_text_state_mut().set_horizontal_scaling(scaling / 100.0);
+            },
+            Operator::SetLeading => if let [leading] = nums[..] {
+                self.state.get_text_state_mut().set_leading(leading);
+            },
+            Operator::SetFont => if let [PDFObject::Named(name), size] = operands {
+                let font = self.load_font(name)?;
+                self.state.get_text_state_mut().set_font(font, size.as_f64().unwrap_or(0.0));
+            },
+            Operator::SetTextRendering => if let [mode] = nums[..] {
+                self.state.get_text_state_mut().set_render_mode(mode as i64);
+            },
+            Operator::SetTextRise => if let [rise] = nums[..] {
+                self.state.get_text_state_mut().set_rise(rise);
+            },
+            // Text positioning
+            Operator::MoveText => if let [tx, ty] = nums[..] {
+                self.move_text(tx, ty);
+            },
+            Operator::MoveTextSetLeading => if let [tx, ty] = nums[..] {
+                self.state.get_text_state_mut().set_leading(-ty);
+                self.move_text(tx, ty);
+            },
+            Operator::SetTextMatrix => if let Some(matrix) = Matrix::from_objects(operands) {
+                self.text_matrix = matrix;
+                self.line_matrix = matrix;
+            },
+            Operator::NextLine => self.next_line(),
+            // Text showing
+            Operator::ShowText => if let [PDFObject::String(text)] = operands {
+                self.show_text(text.get_buf(), visitor)?;
+            },
+            Operator::ShowTextAdjusted => if let [PDFObject::Array(elements)] = operands {
+                for element in elements {
+                    match element {
+                        PDFObject::String(text) => self.show_text(text.get_buf(), visitor)?,
+                        element => if let Some(adjustment) = element.as_f64() {
+                            let text_state = self.state.get_text_state();
+                            let offset = -adjustment / 1000.0 * text_state.get_font_size();
+                            // Vertical fonts move the text down, without horizontal scaling
+                            let (tx, ty) = match text_state.get_font() {
+                                Some(font) if font.is_vertical() => (0.0, offset),
+                                _ => (offset * text_state.get_horizontal_scaling(), 0.0),
+                            };
+                            self.text_matrix = Matrix::translate(tx, ty).multiply(&self.text_matrix);
+                        },
+                    }
+                }
+            },
+            Operator::NextLineShowText => if let [PDFObject::String(text)] = operands {
+                self.next_line();
+                self.show_text(text.get_buf(), visitor)?;
+            },
+            Operator::NextLineSetSpacingShowText => if let [word_spacing, char_spacing, PDFObject::String(text)] = operands {
+                let text_state = self.state.get_text_state_mut();
+                text_state.set_word_spacing(word_spacing.as_f64().unwrap_or(0.0));
+                text_state.set_char_spacing(char_spacing.as_f64().unwrap_or(0.0));
+                self.next_line();
+                self.show_text(text.get_buf(), visitor)?;
+            },
+            // Colour
+            Operator::SetStrokeColorSpace => if let [PDFObject::Named(name)] = operands {
+                let color = self.initial_color(name)?;
+                self.state.set_stroke_color(color);
+            },
+            Operator::SetFillColorSpace => if let [PDFObject::Named(name)] = operands {
+                let color = self.initial_color(name)?;
+                self.state.set_fill_color(color);
+            },
+            Operator::SetStrokeColor | Operator::SetStrokeColorN => {
+                let pattern = operands.last().and_then(|operand| operand.as_name()).cloned();
+                self.state.get_stroke_color_mut().set_components(nums, pattern);
+            }
+            Operator::SetFillColor | Operator::SetFillColorN => {
+                let pattern = operands.last().and_then(|operand| operand.as_name()).cloned();
+                self.state.get_fill_color_mut().set_components(nums, pattern);
+            }
+            Operator::SetStrokeGray => self.state.set_stroke_color(device_color(ColorSpace::DeviceGray, nums)),
+            Operator::SetFillGray => self.state.set_fill_color(device_color(ColorSpace::DeviceGray, nums)),
+            Operator::SetStrokeRGB => self.state.set_stroke_color(device_color(ColorSpace::DeviceRGB, nums)),
+            Operator::SetFillRGB => self.state.set_fill_color(device_color(ColorSpace::DeviceRGB, nums)),
+            Operator::SetStrokeCMYK => self.state.set_stroke_color(device_color(ColorSpace::DeviceCMYK, nums)),
+            Operator::SetFillCMYK => self.state.set_fill_color(device_color(ColorSpace::DeviceCMYK, nums)),
+            // Shading patterns
+            Operator::PaintShading => if let [PDFObject::Named(name)] = operands
+                && let Some((_, shading)) = self.get_resource(SHADING, name)? {
+                visitor.paint_shading(&shading, &self.state)?;
+            },
+            // Inline images
+            Operator::BeginInlineImage => if let Some(stream) = operation.get_inline_image() {
+                let color_space = self.image_color_space(stream)?;
+                let image = Image { name: None, obj_ref: None, stream, color_space };
+                visitor.draw_image(&image, &self.state)?;
+            },
+            // XObjects
+            Operator::PaintXObject => if let [PDFObject::Named(name)] = operands {
+                self.paint_xobject(name, visitor)?;
+            },
+            // Marked content
+            Operator::BeginMarkedContent => if let [PDFObject::Named(tag)] = operands {
+                visitor.begin_marked_content(tag, None)?;
+            },
+            Operator::BeginMarkedContentWithProperties => if let [PDFObject::Named(tag), properties] = operands {
+                let properties = match properties {
+                    PDFObject::Named(name) => self.get_resource(PROPERTIES, name)?.and_then(|(_, dict)| dict.to_dict()),
+                    properties => properties.as_dict().cloned(),
+                };
+                visitor.begin_marked_content(tag, properties.as_ref())?;
+            },
+            Operator::EndMarkedContent => visitor.end_marked_content()?,
+            Operator::Unknown(_) => visitor.unknown_operation(operation)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn curve_to(&mut self, p1: (f64, f64), p2: (f64, f64), p3: (f64, f64)) {
+        self.path.push(PathSegment::CurveTo(p1.0, p1.1, p2.0, p2.1, p3.0, p3.1));
+        self.current_point = p3;
+    }
+
+    fn close_path(&mut self) {
+        self.path.push(PathSegment::Close);
+        self.current_point = self.start_point;
+    }
+
+    /// Paints the current path, then intersects it with the clipping region if
+    /// `W` or `W*` preceded the painting operator, and starts a new path.
+    fn paint(&mut self, close: bool, fill: Option<FillRule>, stroke: bool, visitor: &mut impl ContentVisitor) -> Result<()> {
+        if close {
+            self.close_path();
+        }
+        if !self.path.is_empty() {
+            if let Some(rule) = fill {
+                visitor.fill_path(&self.path, rule, &self.state)?;
+            }
+            if stroke {
+                visitor.stroke_path(&self.path, &self.state)?;
+            }
+        }
+        if let Some(rule) = self.pending_clip.take() {
+            self.state.push_clip(ClipPath::new(self.path.clone(), rule));
+        }
+        self.path.clear();
+        Ok(())
+    }
+
+    fn move_text(&mut self, tx: f64, ty: f64) {
+        self.line_matrix = Matrix::translate(tx, ty).multiply(&self.line_matrix);
+        self.text_matrix = self.line_matrix;
+    }
+
+    fn next_line(&mut self) {
+        let leading = self.state.get_text_state().get_leading();
+        self.move_text(0.0, -leading);
+    }
+
+    /// Shows a string, advancing the text matrix past each glyph.
+    fn show_text(&mut self, bytes: &[u8], visitor: &mut impl ContentVisitor) -> Result<()> {
+        let text_state = self.state.get_text_state();
+        let font = match text_state.get_font() {
+            Some(font) => font.clone(),
+            None => return Ok(()),
+        };
+        let font_size = text_state.get_font_size();
+        let scaling = text_state.get_horizontal_scaling();
+        let char_spacing = text_state.get_char_spacing();
+        let word_spacing = text_state.get_word_spacing();
+        let text_space = Matrix::new(font_size * scaling, 0.0, 0.0, font_size, 0.0, text_state.get_rise());
+        let mut glyphs = Vec::new();
+        for (code, length) in font.split_codes(bytes) {
+            let advance = font.get_advance(code);
+            // Word spacing only applies to the single-byte code 32
+            let spacing = if length == 1 && code == 32 { char_spacing + word_spacing } else { char_spacing };
+            if let Some((displacement, (vx, vy))) = font.get_vertical_metrics(code).filter(|_| font.is_vertical()) {
+                // Vertical glyphs are positioned by their vertical origin and advance downwards
+                let matrix = Matrix::translate(-vx, -vy).multiply(&text_space)
+                    .multiply(&self.text_matrix)
+                    .multiply(self.state.get_ctm());
+                glyphs.push(Glyph::new(code, font.get_unicode(code), advance, matrix));
+                let ty = displacement * font_size + spacing;
+                self.text_matrix = Matrix::translate(0.0, ty).multiply(&self.text_matrix);
+            } else {
+                let matrix = text_space.multiply(&self.text_matrix).multiply(self.state.get_ctm());
+                glyphs.push(Glyph::new(code, font.get_unicode(code), advance, matrix));
+                let tx = (advance * font_size + spacing) * scaling;
+                self.text_matrix = Matrix::translate(tx, 0.0).multiply(&self.text_matrix);
+            }
+        }
+        visitor.show_text(&glyphs, &self.state)
+    }
+
+    /// Loads a font of the current resources, caching fonts by reference.
+    ///
+    /// A font which can't be loaded is reported as None, so that text in other
+    /// fonts can still be processed.
+    fn load_font(&mut self, name: &str) -> Result<Option<Rc<Font>>> {
+        let (obj_ref, font) = match self.get_resource(FONT, name)? {
+            Some((obj_ref, PDFObject::Dict(font))) => (obj_ref, font),
+            _ => return Ok(None),
+        };
+        if let Some(font) = obj_ref.and_then(|obj_ref| self.fonts.get(&obj_ref)) {
+            return Ok(Some(font.clone()));
+        }
+        let font = match Font::load(self.document, &font) {
+            Ok(font) => Rc::new(font),
+            Err(_) => return Ok(None),
+        };
+        if let Some(obj_ref) = obj_ref {
+            self.fonts.insert(obj_ref, font.clone());
+        }
+        Ok(Some(font))
+    }
+
+    /// Returns the initial colour of a colour space set by `CS` or `cs`.
+    ///
+    /// Unknown colour spaces are replaced by `DeviceGray`.
+    fn initial_color(&mut self, name: &str) -> Result<Color> {
+        let color_space = match name {
+            "DeviceGray" => ColorSpace::DeviceGray,
+            "DeviceRGB" => ColorSpace::DeviceRGB,
+            "DeviceCMYK" => ColorSpace::DeviceCMYK,
+            "Pattern" => ColorSpace::Pattern { underlying: None },
+            _ => match self.get_resource(COLOR_SPACE, name)? {
+                Some((obj_ref, object)) => {
+                    if let Some(color_space) = obj_ref.and_then(|obj_ref| self.color_spaces.get(&obj_ref)) {
+                        return Ok(Color::initial(color_space.clone()));
+                    }
+                    let resources = self.resources.last().and_then(|resources| resources.as_ref());
+                    let color_space = Rc::new(ColorSpace::load(self.document, &object, resources)?.unwrap_or(ColorSpace::DeviceGray));
+                    if let Some(obj_ref) = obj_ref {
+                        self.color_spaces.insert(obj_ref, color_space.clone());
+                    }
+                    return Ok(Color::initial(color_space));
+                }
+                None => ColorSpace::DeviceGray,
+            },
+        };
+        Ok(Color::initial(Rc::new(color_space)))
+    }
+
+    /// Returns the colour space of an image.
+    ///
+    /// Inline images may name a colour space of the `/ColorSpace` resources
+    /// instead of a device colour space.
+    fn image_color_space(&self, stream: &Stream) -> Result<Option<PDFObject>> {
+        let color_space = match stream.get_metadata().get(COLOR_SPACE) {
+            Some(color_space) => self.document.resolve(color_space)?.into_owned(),
+            None => return Ok(None),
+        };
+        if let PDFObject::Named(name) = &color_space
+            && !matches!(name.as_str(), "DeviceGray" | "DeviceRGB" | "DeviceCMYK")
+            && let Some((_, resource)) = self.get_resource(COLOR_SPACE, name)? {
+            return Ok(Some(resource));
+        }
+        Ok(Some(color_space))
+    }
+
+    /// Applies the parameters of a graphics state parameter dictionary.
+    fn set_ext_g_state(&mut self, dict: &Dictionary) -> Result<()> {
+        for (key, value) in dict.iter() {
+            let value = self.document.resolve(value)?;
+            let number = value.as_f64();
+            match (key.as_str(), value.as_ref()) {
+                ("LW", _) => if let Some(width) = number {
+                    self.state.set_line_width(width);
+                },
+                ("LC", _) => if let Some(cap) = number {
+                    self.state.set_line_cap(cap as i64);
+                },
+                ("LJ", _) => if let Some(join) = number {
+                    self.state.set_line_join(join as i64);
+                },
+                ("ML", _) => if let Some(limit) = number {
+                    self.state.set_miter_limit(limit);
+                },
+                ("D", PDFObject::Array(dash)) => if let [PDFObject::Array(array), phase] = dash.as_slice() {
+                    let array = array.iter().filter_map(|value| value.as_f64()).collect();
+                    self.state.set_dash(array, phase.as_f64().unwrap_or(0.0));
+                },
+                ("RI", PDFObject::Named(intent)) => self.state.set_rendering_intent(intent.clone()),
+                ("FL", _) => if let Some(flatness) = number {
+                    self.state.set_flatness(flatness);
+                },
+                ("Font", PDFObject::Array(font)) => if let [font, size] = font.as_slice() {
+                    let font = match self.document.resolve(font)?.as_ref() {
+                        PDFObject::Dict(font) => Font::load(self.document, font).ok().map(Rc::new),
+                        _ => None,
+                    };
+                    self.state.get_text_state_mut().set_font(font, size.as_f64().unwrap_or(0.0));
+                },
+                ("SMask", PDFObject::Dict(_)) => self.state.set_soft_mask(Some(value.into_owned())),
+                ("SMask", _) => self.state.set_soft_mask(None),
+                ("CA", _) => if let Some(alpha) = number {
+                    self.state.set_stroke_alpha(alpha);
+                },
+                ("ca", _) => if let Some(alpha) = number {
+                    self.state.set_fill_alpha(alpha);
+                },
+                ("BM", PDFObject::Named(mode)) => self.state.set_blend_mode(mode.clone()),
+                // An array of blend modes, the first supported one is used
+                ("BM", PDFObject::Array(modes)) => if let Some(mode) = modes.first().and_then(|mode| mode.as_name()) {
+                    self.state.set_blend_mode(mode.clone());
+                },
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Paints an image XObject or executes a Form XObject.
+    fn paint_xobject(&mut self, name: &str, visitor: &mut impl ContentVisitor) -> Result<()> {
+        let (obj_ref, stream) = match self.get_resource(XOBJECT, name)? {
+            Some((obj_ref, PDFObject::Stream(stream))) => (obj_ref, stream),
+            _ => return Ok(()),
+        };
+        match stream.get_metadata().get_named_value(SUBTYPE).map(|subtype| subtype.as_str()) {
+            Some("Image") => {
+                let color_space = self.image_color_space(&stream)?;
+                let image = Image { name: Some(name), obj_ref, stream: &stream, color_space };
+                visitor.draw_image(&image, &self.state)
+            }
+            Some("Form") => self.run_form(obj_ref, &stream, visitor),
+            _ => Ok(()),
+        }
+    }
+
+    /// Executes a Form XObject in its own graphics state, with the form matrix
+    /// concatenated and the clipping region intersected with the form's bounding box.
+    fn run_form(&mut self, obj_ref: Option<(u32, u16)>, form: &Stream, visitor: &mut impl ContentVisitor) -> Result<()> {
+        if self.forms.len() >= MAX_FORM_DEPTH || (obj_ref.is_some() && self.forms.contains(&obj_ref)) {
+            return Ok(());
+        }
+        let metadata = form.get_metadata();
+        let matrix = match metadata.get(MATRIX) {
+            Some(matrix) => self.document.resolve(matrix)?.as_array().and_then(Matrix::from_objects),
+            None => None,
+        };
+        let bbox = match metadata.get(BBOX) {
+            Some(bbox) => self.document.resolve(bbox)?.as_array()
+                .map(|values| values.iter().filter_map(|value| value.as_f64()).collect::<Vec<f64>>()),
+            None => None,
+        };
+        // Forms without resources use the resources of the content they are painted from
+        let resources = match metadata.get(RESOURCES) {
+            Some(resources) => self.document.resolve(resources)?.into_owned().to_dict(),
+            None => self.resources.last().cloned().flatten(),
+        };
+        let content = ContentParser::new(form.get_decoded_buf()?);
+
+        let saved = (self.stack.len(), self.text_matrix, self.line_matrix);
+        self.stack.push(self.state.clone());
+        if let Some(matrix) = matrix {
+            self.state.set_ctm(matrix.multiply(self.state.get_ctm()));
+        }
+        if let Some([x0, y0, x1, y1]) = bbox.as_deref() {
+            let ctm = self.state.get_ctm();
+            let mut path = Path::default();
+            for (i, (x, y)) in [(*x0, *y0), (*x1, *y0), (*x1, *y1), (*x0, *y1)].into_iter().enumerate() {
+                let (x, y) = ctm.transform(x, y);
+                path.push(if i == 0 { PathSegment::MoveTo(x, y) } else { PathSegment::LineTo(x, y) });
+            }
+            path.push(PathSegment::Close);
+            self.state.push_clip(ClipPath::new(path, FillRule::NonZero));
+        }
+        self.forms.push(obj_ref);
+        let result = self.run(content, resources, visitor);
+        self.forms.pop();
+        // Restore the state saved before the form, even if the form's q and Q are unbalanced
+        self.stack.truncate(saved.0 + 1);
+        if let Some(state) = self.stack.pop() {
+            self.state = state;
+        }
+        self.text_matrix = saved.1;
+        self.line_matrix = saved.2;
+        self.path.clear();
+        result
+    }
+
+    /// Looks up a resource of the current resource dictionary.
+    ///
+    /// # Returns
+    ///
+    /// The resolved resource together with its object reference if it was
+    /// referenced indirectly, or None if there is no such resource
+    fn get_resource(&self, category: &str, name: &str) -> Result<Option<Resource>> {
+        let resources = match self.resources.last() {
+            Some(Some(resources)) => resources,
+            _ => return Ok(None),
+        };
+        let category = match resources.get(category) {
+            Some(category) => self.document.resolve(category)?,
+            None => return Ok(None),
+        };
+        let entry = match category.as_dict().and_then(|category| category.get(name)) {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        let resource = self.document.resolve(entry)?.into_owned();
+        Ok(Some((entry.as_object_ref(), resource)))
+    }
+}
+
+/// Creates a colour of a device colour space set by `G`, `g`, `RG`, `rg`, `K` or `k`.
+fn device_color(color_space: ColorSpace, components: Vec<f64>) -> Color {
+    Color::new(Rc::new(color_space), components, None)
+}
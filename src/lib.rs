@@ -8,5 +8,29 @@ pub(crate) mod parser;
 pub(crate) mod constants;
 pub(crate) mod tokenizer;
 pub(crate) mod catalog;
+pub mod page;
+pub mod content;
+pub mod graphics;
+pub mod font;
+pub mod colorspace;
+pub mod function;
+pub mod cmap;
+pub mod interpreter;
+pub mod text;
+pub mod image;
+pub(crate) mod outline;
+pub(crate) mod raster;
+pub mod render;
+pub mod svg;
+pub mod annotation;
+pub mod action;
+pub mod form;
+pub mod fill;
+pub mod xfa;
+pub mod embedded;
+pub(crate) mod writer;
+pub(crate) mod nametree;
 pub(crate) mod encoding;
+pub(crate) mod afm;
+pub(crate) mod filter;
 mod pstr;
\ No newline at end of file
@@ -0,0 +1,132 @@
+use crate::constants::{KIDS, LIMITS, NAMES};
+use crate::document::PDFDocument;
+use crate::error::Result;
+use crate::objects::PDFObject;
+use std::collections::HashSet;
+
+/// The maximum depth of the name trees walked.
+const MAX_TREE_DEPTH: usize = 32;
+
+/// Looks up a key in a name tree.
+///
+/// Kids whose `/Limits` don't cover the key are skipped.
+///
+/// # Arguments
+///
+/// * `document` - The document the tree belongs to
+/// * `root` - The root node of the tree
+/// * `key` - The bytes of the key string
+///
+/// # Returns
+///
+/// The resolved value, or None if the tree doesn't contain the key
+pub(crate) fn find_name(document: &PDFDocument, root: &PDFObject, key: &[u8]) -> Result<Option<PDFObject>> {
+    find_in_node(document, root, key, &mut HashSet::new(), 0)
+}
+
+/// Looks up a key in a node of a name tree and its kids.
+///
+/// `visited` holds the nodes already walked, so that cyclic or shared kids are
+/// walked once.
+fn find_in_node(
+    document: &PDFDocument,
+    node: &PDFObject,
+    key: &[u8],
+    visited: &mut HashSet<(u32, u16)>,
+    depth: usize,
+) -> Result<Option<PDFObject>> {
+    if let Some(obj_ref) = node.as_object_ref() && !visited.insert(obj_ref) {
+        return Ok(None);
+    }
+    let node = document.resolve(node)?;
+    if !covers(document, &node, key)? {
+        return Ok(None);
+    }
+    let Some(attrs) = node.as_dict() else {
+        return Ok(None);
+    };
+    if let Some(names) = attrs.get(NAMES) {
+        let names = document.resolve(names)?;
+        for pair in names.as_array().unwrap_or_default().chunks_exact(2) {
+            if pair[0].as_string().is_some_and(|name| name.get_buf() == key) {
+                return Ok(Some(document.resolve(&pair[1])?.into_owned()));
+            }
+        }
+    }
+    let Some(kids) = attrs.get(KIDS) else {
+        return Ok(None);
+    };
+    if depth >= MAX_TREE_DEPTH {
+        return Ok(None);
+    }
+    let kids = document.resolve(kids)?;
+    for kid in kids.as_array().unwrap_or_default() {
+        if let Some(value) = find_in_node(document, kid, key, visited, depth + 1)? {
+            return Ok(Some(value));
+        }
+    }
+    Ok(None)
+}
+
+/// Checks whether the `/Limits` of a node cover a key, true for nodes without limits.
+fn covers(document: &PDFDocument, node: &PDFObject, key: &[u8]) -> Result<bool> {
+    let Some(limits) = node.as_dict().and_then(|attrs| attrs.get(LIMITS)) else {
+        return Ok(true);
+    };
+    let limits = document.resolve(limits)?;
+    match limits.as_array().unwrap_or_default() {
+        [PDFObject::String(least), PDFObject::String(greatest)] => {
+            Ok(least.get_buf().as_slice() <= key && key <= greatest.get_buf().as_slice())
+        }
+        _ => Ok(true),
+    }
+}
+
+/// Collects the entries of a name tree, in the order of its nodes, which is
+/// the order of the keys in well-formed trees.
+///
+/// # Returns
+///
+/// The key bytes and resolved value of each entry
+pub(crate) fn collect_names(document: &PDFDocument, root: &PDFObject) -> Result<Vec<(Vec<u8>, PDFObject)>> {
+    let mut entries = Vec::new();
+    collect_in_node(document, root, &mut entries, &mut HashSet::new(), 0)?;
+    Ok(entries)
+}
+
+/// Collects the entries of a node of a name tree and its kids, walking each
+/// node of `visited` once.
+fn collect_in_node(
+    document: &PDFDocument,
+    node: &PDFObject,
+    entries: &mut Vec<(Vec<u8>, PDFObject)>,
+    visited: &mut HashSet<(u32, u16)>,
+    depth: usize,
+) -> Result<()> {
+    if let Some(obj_ref) = node.as_object_ref() && !visited.insert(obj_ref) {
+        return Ok(());
+    }
+    let node = document.resolve(node)?;
+    let Some(attrs) = node.as_dict() else {
+        return Ok(());
+    };
+    if let Some(names) = attrs.get(NAMES) {
+        let names = document.resolve(names)?;
+        for pair in names.as_array().unwrap_or_default().chunks_exact(2) {
+            if let Some(name) = pair[0].as_string() {
+                entries.push((name.get_buf().clone(), document.resolve(&pair[1])?.into_owned()));
+            }
+        }
+    }
+    let Some(kids) = attrs.get(KIDS) else {
+        return Ok(());
+    };
+    if depth >= MAX_TREE_DEPTH {
+        return Ok(());
+    }
+    let kids = document.resolve(kids)?;
+    for kid in kids.as_array().unwrap_or_default() {
+        collect_in_node(document, kid, entries, visited, depth + 1)?;
+    }
+    Ok(())
+}
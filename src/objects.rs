@@ -1,6 +1,9 @@
+use crate::constants::{DECODE_PARMS, FILTER};
+use crate::error::Result;
+use crate::filter;
 use std::collections::HashMap;
 
-#[derive(PartialEq, Clone)]
+#[derive(PartialEq, Clone, Debug)]
 pub enum PDFNumber {
     Signed(i64),
     Unsigned(u64),
@@ -19,24 +22,30 @@ pub struct XEntry {
     pub(crate) gen_num: u16,
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct Dictionary {
     entries: HashMap<String, PDFObject>,
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct Stream {
     metadata: Dictionary,
+    buf: Vec<u8>,
 }
 
-pub(crate) enum PDFStrKind {
+#[derive(Clone, Debug, PartialEq)]
+pub enum PDFStrKind {
     Literal,
     Hexadecimal,
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct PDFString {
     kind: PDFStrKind,
     buf: Vec<u8>,
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub enum PDFObject {
     /// The keywords true and false represent boolean objects with values true and false.
     Bool(bool),
@@ -175,10 +184,7 @@ pub enum PDFObject {
 impl PDFObject {
     /// Returns true if the object is a boolean.
     pub fn is_bool(&self) -> bool {
-        match self {
-            PDFObject::Bool(_) => true,
-            _ => false,
-        }
+        matches!(self, PDFObject::Bool(_))
     }
     /// Returns the boolean value of the object if it is a boolean.
     pub fn as_bool(&self) -> Option<bool> {
@@ -190,10 +196,7 @@ impl PDFObject {
 
     /// Returns true if the object is a number.
     pub fn is_number(&self) -> bool {
-        match self {
-            PDFObject::Number(_) => true,
-            _ => false,
-        }
+        matches!(self, PDFObject::Number(_))
     }
     /// Returns the number value of the object if it is a number.
     pub fn as_number(&self) -> Option<&PDFNumber> {
@@ -202,13 +205,22 @@ impl PDFObject {
             _ => None,
         }
     }
-    /// Returns true if the object is a string.
-    pub fn is_string(&self) -> bool {
+    /// Returns the value of the object as a float if it is a number.
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_number().map(|n| n.as_f64())
+    }
+    /// Returns the value of the object as an integer if it is an integer number.
+    pub fn as_i64(&self) -> Option<i64> {
         match self {
-            PDFObject::String(_) => true,
-            _ => false,
+            PDFObject::Number(PDFNumber::Unsigned(n)) => Some(*n as i64),
+            PDFObject::Number(PDFNumber::Signed(n)) => Some(*n),
+            _ => None,
         }
     }
+    /// Returns true if the object is a string.
+    pub fn is_string(&self) -> bool {
+        matches!(self, PDFObject::String(_))
+    }
 
     /// Returns the string value of the object if it is a string.
     pub fn as_string(&self) -> Option<&PDFString> {
@@ -220,10 +232,7 @@ impl PDFObject {
 
     /// Returns the string value of the object if it is a string.
     pub fn is_array(&self) -> bool {
-        match self {
-            PDFObject::Array(_) => true,
-            _ => false,
-        }
+        matches!(self, PDFObject::Array(_))
     }
     /// Returns the array of objects if it is an array.
     pub fn as_array(&self) -> Option<&[PDFObject]> {
@@ -234,10 +243,7 @@ impl PDFObject {
     }
     /// Returns true if the object is a dictionary.
     pub fn is_dict(&self) -> bool {
-        match self {
-            PDFObject::Dict(_) => true,
-            _ => false,
-        }
+        matches!(self, PDFObject::Dict(_))
     }
     /// Returns the dictionary if it is one.
     pub fn as_dict(&self) -> Option<&Dictionary> {
@@ -255,10 +261,7 @@ impl PDFObject {
     }
     /// Returns true if the object is an indirect object.
     pub fn is_object_ref(&self) -> bool {
-        match self {
-            PDFObject::ObjectRef(_, ..) => true,
-            _ => false,
-        }
+        matches!(self, PDFObject::ObjectRef(..))
     }
     /// Returns the object reference if it is one.
     pub fn as_object_ref(&self) -> Option<(u32, u16)> {
@@ -270,10 +273,7 @@ impl PDFObject {
 
     /// Returns true if the object is an indirect object.
     pub fn is_indirect_object(&self) -> bool {
-        match self {
-            PDFObject::IndirectObject(_, _, _) => true,
-            _ => false,
-        }
+        matches!(self, PDFObject::IndirectObject(_, _, _))
     }
     /// Returns the indirect object if it is one.
     pub fn as_indirect_object(&self) -> Option<(u32, u16, &PDFObject)> {
@@ -285,17 +285,11 @@ impl PDFObject {
 
     /// Returns true if the object is null.
     pub fn is_null(&self) -> bool {
-        match self {
-            PDFObject::Null => true,
-            _ => false,
-        }
+        matches!(self, PDFObject::Null)
     }
     /// Returns true if the object is a stream.
     pub fn is_stream(&self)->bool{
-        match self {
-            PDFObject::Stream(_) => true,
-            _ => false,
-        }
+        matches!(self, PDFObject::Stream(_))
     }
 
     /// Returns the stream if it is one.
@@ -307,10 +301,7 @@ impl PDFObject {
     }
     /// Returns true if the object is a name.
     pub fn is_name(&self)->bool{
-        match self {
-            PDFObject::Named(_) => true,
-            _ => false,
-        }
+        matches!(self, PDFObject::Named(_))
     }
     /// Returns the name if it is one.
     pub fn as_name(&self)->Option<&String>{
@@ -322,6 +313,17 @@ impl PDFObject {
 
 }
 
+impl PDFNumber {
+    /// Returns the number as a float.
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            PDFNumber::Signed(n) => *n as f64,
+            PDFNumber::Unsigned(n) => *n as f64,
+            PDFNumber::Real(n) => *n,
+        }
+    }
+}
+
 impl Dictionary {
     /// Creates a new dictionary with the given entries.
     pub(crate) fn new(entries: HashMap<String, PDFObject>) -> Self {
@@ -332,6 +334,16 @@ impl Dictionary {
         self.entries.get(key)
     }
 
+    /// Inserts an entry, returning the previous value of the key.
+    pub(crate) fn insert(&mut self, key: &str, value: PDFObject) -> Option<PDFObject> {
+        self.entries.insert(key.to_string(), value)
+    }
+
+    /// Returns an iterator over the entries of the dictionary.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &PDFObject)> {
+        self.entries.iter()
+    }
+
     /// Removes the entry with the given key.
     pub fn remove(&mut self,key:&str)->Option<PDFObject>{
         self.entries.remove(key)
@@ -403,8 +415,42 @@ impl XEntry {
 
 impl Stream {
     /// Creates a new stream with the given metadata.
-    pub(crate) fn new(metadata: Dictionary,buf:Vec<u8>) -> Self {
-        Stream { metadata }
+    pub(crate) fn new(metadata: Dictionary, buf: Vec<u8>) -> Self {
+        Stream { metadata, buf }
+    }
+    /// Returns the stream dictionary.
+    pub fn get_metadata(&self) -> &Dictionary {
+        &self.metadata
+    }
+    /// Returns the raw (still encoded) stream data.
+    pub fn get_buf(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Returns the names of the filters applied to the stream data, in decoding order.
+    pub fn get_filters(&self) -> Vec<&str> {
+        match self.metadata.get(FILTER) {
+            Some(PDFObject::Named(name)) => vec![name.as_str()],
+            Some(PDFObject::Array(filters)) => filters.iter()
+                .filter_map(|filter| filter.as_name())
+                .map(|name| name.as_str())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the stream data with its filters applied.
+    ///
+    /// Image filters such as `DCTDecode` are left in place, since the data they
+    /// encode is the image file itself.
+    pub fn get_decoded_buf(&self) -> Result<Vec<u8>> {
+        let filters = self.get_filters();
+        let params = match self.metadata.get(DECODE_PARMS) {
+            Some(PDFObject::Dict(params)) => vec![Some(params)],
+            Some(PDFObject::Array(params)) => params.iter().map(|param| param.as_dict()).collect(),
+            _ => Vec::new(),
+        };
+        filter::decode(&self.buf, &filters, &params)
     }
 }
 
@@ -413,11 +459,13 @@ impl PDFString {
         PDFString { kind, buf }
     }
 
-    pub(crate) fn get_buf(&self) -> &Vec<u8> {
+    /// Returns the string bytes, with hexadecimal strings already decoded.
+    pub fn get_buf(&self) -> &Vec<u8> {
         &self.buf
     }
 
-    pub(crate) fn get_kind(&self) -> &PDFStrKind {
+    /// Returns whether the string was written as a literal or hexadecimal string.
+    pub fn get_kind(&self) -> &PDFStrKind {
         &self.kind
     }
 }
\ No newline at end of file
@@ -0,0 +1,1883 @@
+use crate::document::PDFDocument;
+use crate::encoding::{glyph_name_from_u8, glyph_name_to_unicode, PreDefinedEncoding};
+use crate::font::{Font, FontProgram};
+use crate::graphics::{Matrix, Path, PathSegment};
+use crate::interpreter::Glyph;
+use crate::utils::{hex2bytes, read_u16, read_u32};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+include!("../encoding/CffStandardStrings");
+
+/// The maximum nesting of composite glyphs and accented characters.
+const MAX_COMPONENT_DEPTH: usize = 8;
+/// The maximum nesting of charstring subroutine calls.
+const MAX_SUBR_DEPTH: usize = 10;
+/// The maximum size of the charstring operand stack.
+const MAX_STACK_SIZE: usize = 513;
+/// The keys decrypting the `eexec` section and the charstrings of Type 1 fonts.
+const EEXEC_KEY: u16 = 55665;
+const CHARSTRING_KEY: u16 = 4330;
+
+/// The built-in fonts drawing the text of fonts which aren't embedded: subsets
+/// of DejaVu Sans, DejaVu Serif and DejaVu Sans Mono covering Latin, Greek,
+/// Cyrillic and common symbols.
+const SANS_FONT: &[u8] = include_bytes!("../fonts/DejaVuSans.ttf");
+const SERIF_FONT: &[u8] = include_bytes!("../fonts/DejaVuSerif.ttf");
+const MONO_FONT: &[u8] = include_bytes!("../fonts/DejaVuSansMono.ttf");
+/// The font descriptor flags choosing the built-in font replacing a font.
+const FIXED_PITCH_FLAG: u32 = 1;
+const SERIF_FLAG: u32 = 1 << 1;
+const ITALIC_FLAG: u32 = 1 << 6;
+const FORCE_BOLD_FLAG: u32 = 1 << 18;
+/// The slant of built-in glyphs replacing italic glyphs.
+const ITALIC_SLANT: f64 = 0.2;
+
+/// The glyph outlines of an embedded font program or of a built-in font.
+///
+/// Glyphs are identified by their index in the font program: the glyph ID
+/// of TrueType and CFF fonts, or the position of the charstring in the
+/// `/CharStrings` dictionary of Type 1 fonts.
+pub(crate) struct OutlineFont {
+    /// The matrix mapping glyph units to text space, where the em square is 1 unit.
+    matrix: Matrix,
+    glyphs: Glyphs,
+    /// The character maps of TrueType and OpenType fonts, by platform and encoding ID.
+    cmaps: Vec<((u16, u16), HashMap<u32, u32>)>,
+    /// The glyph index of each glyph name of Type 1 and CFF fonts.
+    names: HashMap<String, u32>,
+    /// The glyph name of each code of the built-in encoding of Type 1 and CFF fonts.
+    encoding: HashMap<u32, String>,
+    /// The glyph index of each CID of CID-keyed CFF fonts.
+    cids: Option<HashMap<u32, u32>>,
+    /// The advance widths of TrueType glyphs, in glyph units.
+    advances: Vec<u16>,
+}
+
+/// A built-in font.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum FallbackFont {
+    Sans,
+    Serif,
+    Mono,
+}
+
+/// The built-in font replacing a font, and whether its glyphs are slanted or emboldened.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct FontStyle {
+    pub(crate) family: FallbackFont,
+    pub(crate) italic: bool,
+    pub(crate) bold: bool,
+}
+
+/// Where the glyph outlines of a font come from.
+enum GlyphSource {
+    /// The embedded font program.
+    Embedded(Rc<OutlineFont>),
+    /// A built-in font, its glyphs chosen by the Unicode text of the codes.
+    Fallback { outlines: Rc<OutlineFont>, italic: bool },
+    /// Nothing, the glyphs of the font have no outline.
+    None,
+}
+
+/// Loads the glyph outlines of the fonts of a document.
+///
+/// Glyphs are drawn from the embedded TrueType, OpenType, CFF and Type 1
+/// programs. Glyphs of other fonts are drawn with built-in fonts, scaled to
+/// the widths of the original glyphs. Font programs and outlines are loaded
+/// once per font.
+pub(crate) struct GlyphCache<'a> {
+    document: &'a PDFDocument,
+    /// The glyph source of each font by address, with the font to keep the address in use.
+    fonts: HashMap<*const Font, (Rc<Font>, Rc<GlyphSource>)>,
+    /// The outline of each code of each font by address, in text space.
+    glyphs: HashMap<(*const Font, u32), Option<Rc<Path>>>,
+    fallbacks: HashMap<FallbackFont, Option<Rc<OutlineFont>>>,
+}
+
+/// The glyph programs of the supported font formats.
+enum Glyphs {
+    TrueType {
+        /// The `glyf` table.
+        glyf: Vec<u8>,
+        /// The offset of each glyph in `glyf`, followed by the end of the last glyph.
+        loca: Vec<u32>,
+    },
+    Cff(CffFont),
+    Type1 {
+        /// The decrypted charstrings, in the order of the `/CharStrings` dictionary.
+        charstrings: Vec<Vec<u8>>,
+        /// The decrypted subroutines.
+        subrs: Vec<Vec<u8>>,
+    },
+}
+
+/// The charstrings and subroutines of a CFF font.
+struct CffFont {
+    data: Vec<u8>,
+    /// The ranges of the Type 2 charstrings in `data`, by glyph index.
+    charstrings: Vec<(usize, usize)>,
+    global_subrs: Vec<(usize, usize)>,
+    /// The local subroutines of each Font DICT, a single one for name-keyed fonts.
+    local_subrs: Vec<Vec<(usize, usize)>>,
+    /// The Font DICT of each glyph of CID-keyed fonts, empty for name-keyed fonts.
+    fd_select: Vec<u8>,
+}
+
+/// Accumulates the contours of a glyph, closing each one before the next starts.
+#[derive(Default)]
+struct OutlineBuilder {
+    path: Path,
+    /// The current point.
+    x: f64,
+    y: f64,
+    /// True once a segment of the current contour was added.
+    open: bool,
+}
+
+/// The state of the Type 2 charstring interpreter.
+#[derive(Default)]
+struct Type2State {
+    builder: OutlineBuilder,
+    stack: Vec<f64>,
+    /// The number of stem hints, which decides the size of hint masks.
+    stems: usize,
+    /// True once the optional width operand of the first stack-clearing operator was handled.
+    width_done: bool,
+    /// The transient array of the `put` and `get` operators.
+    transient: Vec<f64>,
+    /// The `adx ady bchar achar` operands of an `endchar` drawing an accented character.
+    seac: Option<[f64; 4]>,
+}
+
+/// The state of the Type 1 charstring interpreter.
+#[derive(Default)]
+struct Type1State {
+    builder: OutlineBuilder,
+    stack: Vec<f64>,
+    /// The results of `callothersubr` returned by `pop`.
+    ps_stack: Vec<f64>,
+    /// The left side bearing set by `hsbw` or `sbw`.
+    sbx: f64,
+    /// The points of a flex hint collected by `rmoveto`, None outside of flex hints.
+    flex: Option<Vec<(f64, f64)>>,
+    /// The `asb adx ady bchar achar` operands of `seac`.
+    seac: Option<[f64; 5]>,
+}
+
+impl OutlineFont {
+    /// Parses an embedded font program.
+    ///
+    /// The format is detected from the data, since the font file key of the
+    /// font descriptor doesn't always match the program.
+    ///
+    /// # Returns
+    ///
+    /// The glyph outlines, or None if the program is malformed or of an unsupported format
+    pub(crate) fn parse(program: &FontProgram) -> Option<OutlineFont> {
+        OutlineFont::from_data(program.get_data())
+    }
+
+    /// Parses a TrueType, OpenType, CFF or Type 1 (PFB or PFA) font program.
+    pub(crate) fn from_data(data: &[u8]) -> Option<OutlineFont> {
+        match data.get(0..4)? {
+            [0, 1, 0, 0] | b"true" | b"OTTO" | b"ttcf" => parse_sfnt(data),
+            [0x80, 0x01, ..] | [b'%', b'!', ..] => parse_type1(data),
+            [1, _, _, _] => parse_cff(data.to_vec()),
+            _ => None,
+        }
+    }
+
+    /// Returns the matrix mapping glyph units to text space.
+    pub(crate) fn get_matrix(&self) -> &Matrix {
+        &self.matrix
+    }
+
+    /// Returns true if the font has a character map for the platform and encoding.
+    pub(crate) fn has_cmap(&self, platform: u16, encoding: u16) -> bool {
+        self.cmaps.iter().any(|(id, _)| *id == (platform, encoding))
+    }
+
+    /// Returns true if the font has any character map.
+    pub(crate) fn has_cmaps(&self) -> bool {
+        !self.cmaps.is_empty()
+    }
+
+    /// Looks a code up in the character map of a platform and encoding.
+    ///
+    /// # Returns
+    ///
+    /// The glyph index, or None if the code isn't mapped to a glyph other than `.notdef`
+    pub(crate) fn lookup_cmap(&self, platform: u16, encoding: u16, code: u32) -> Option<u32> {
+        self.cmaps.iter()
+            .find(|(id, _)| *id == (platform, encoding))
+            .and_then(|(_, cmap)| cmap.get(&code))
+            .copied()
+            .filter(|gid| *gid != 0)
+    }
+
+    /// Looks a character up in the Unicode character maps.
+    pub(crate) fn lookup_unicode(&self, c: char) -> Option<u32> {
+        let code = c as u32;
+        self.lookup_cmap(3, 10, code)
+            .or_else(|| self.lookup_cmap(3, 1, code))
+            .or_else(|| (0..=4).find_map(|encoding| self.lookup_cmap(0, encoding, code)))
+    }
+
+    /// Looks a glyph name up in the glyph names of a Type 1 or CFF font.
+    pub(crate) fn lookup_name(&self, name: &str) -> Option<u32> {
+        self.names.get(name).copied()
+    }
+
+    /// Returns the glyph name of a code in the built-in encoding of a Type 1 or CFF font.
+    pub(crate) fn get_encoding_name(&self, code: u32) -> Option<&str> {
+        self.encoding.get(&code).map(|name| name.as_str())
+    }
+
+    /// Gets the glyph index of a CID.
+    ///
+    /// CID-keyed CFF fonts map CIDs with their charset, the CIDs of other
+    /// fonts are glyph indexes.
+    pub(crate) fn lookup_cid(&self, cid: u32) -> Option<u32> {
+        match &self.cids {
+            Some(cids) => cids.get(&cid).copied(),
+            None => Some(cid),
+        }
+    }
+
+    /// Returns the advance width of a TrueType glyph in text space units, None if unknown.
+    pub(crate) fn get_advance(&self, gid: u32) -> Option<f64> {
+        let advance = *self.advances.get(gid as usize).or(self.advances.last())?;
+        Some(self.matrix.transform_vector(advance as f64, 0.0).0)
+    }
+
+    /// Builds the outline of a glyph, in glyph units.
+    ///
+    /// # Returns
+    ///
+    /// The outline, empty for glyphs without contours, or None if the glyph
+    /// doesn't exist or its program is malformed
+    pub(crate) fn get_outline(&self, gid: u32) -> Option<Path> {
+        match &self.glyphs {
+            Glyphs::TrueType { glyf, loca } => truetype_outline(glyf, loca, gid, 0),
+            Glyphs::Cff(cff) => cff.outline(gid, 0),
+            Glyphs::Type1 { charstrings, subrs } => type1_outline(self, charstrings, subrs, gid, 0),
+        }
+    }
+}
+
+impl FontStyle {
+    /// Chooses the built-in font replacing a font from its name and descriptor flags.
+    pub(crate) fn of(font: &Font) -> Self {
+        let name = font.get_standard_font().or(font.get_base_font()).unwrap_or("");
+        let flags = font.get_descriptor().map_or(0, |descriptor| descriptor.get_flags());
+        let has = |words: &[&str]| words.iter().any(|word| name.contains(word));
+        let family = if flags & FIXED_PITCH_FLAG != 0 || has(&["Courier", "Mono", "Consolas"]) {
+            FallbackFont::Mono
+        } else if flags & SERIF_FLAG != 0 || has(&["Times", "Serif", "Roman", "Georgia", "Garamond", "Cambria", "Book"]) {
+            FallbackFont::Serif
+        } else {
+            FallbackFont::Sans
+        };
+        FontStyle {
+            family,
+            italic: flags & ITALIC_FLAG != 0 || has(&["Italic", "Oblique"]),
+            bold: flags & FORCE_BOLD_FLAG != 0 || has(&["Bold", "Black", "Heavy"]),
+        }
+    }
+}
+
+impl<'a> GlyphCache<'a> {
+    pub(crate) fn new(document: &'a PDFDocument) -> Self {
+        GlyphCache { document, fonts: HashMap::new(), glyphs: HashMap::new(), fallbacks: HashMap::new() }
+    }
+
+    /// Returns whether the glyphs of a font are drawn with a built-in bold font, which only
+    /// has regular glyphs to be emboldened.
+    pub(crate) fn is_fallback_bold(&mut self, font: &Rc<Font>) -> bool {
+        matches!(self.get_glyph_source(font).as_ref(), GlyphSource::Fallback { .. }) && FontStyle::of(font).bold
+    }
+
+    /// Returns where the glyphs of a font come from, loading the embedded font program once.
+    fn get_glyph_source(&mut self, font: &Rc<Font>) -> Rc<GlyphSource> {
+        if let Some((_, source)) = self.fonts.get(&Rc::as_ptr(font)) {
+            return source.clone();
+        }
+        let source = Rc::new(self.load_glyph_source(font));
+        self.fonts.insert(Rc::as_ptr(font), (font.clone(), source.clone()));
+        source
+    }
+
+    fn load_glyph_source(&mut self, font: &Font) -> GlyphSource {
+        if font.get_subtype() != "Type3"
+            && let Some(descriptor) = font.get_descriptor()
+            && let Ok(Some(program)) = descriptor.get_font_program(self.document)
+            && let Some(outlines) = OutlineFont::parse(&program) {
+            return GlyphSource::Embedded(Rc::new(outlines));
+        }
+        let style = FontStyle::of(font);
+        let outlines = self.fallbacks.entry(style.family).or_insert_with(|| {
+            let data = match style.family {
+                FallbackFont::Sans => SANS_FONT,
+                FallbackFont::Serif => SERIF_FONT,
+                FallbackFont::Mono => MONO_FONT,
+            };
+            OutlineFont::from_data(data).map(Rc::new)
+        });
+        match outlines {
+            Some(outlines) => GlyphSource::Fallback { outlines: outlines.clone(), italic: style.italic },
+            None => GlyphSource::None,
+        }
+    }
+
+    /// Returns the outline of a glyph in its text space, where the em square is 1 unit.
+    ///
+    /// # Returns
+    ///
+    /// The outline, or None if the font has no glyph for the code
+    pub(crate) fn get_outline(&mut self, font: &Rc<Font>, glyph: &Glyph) -> Option<Rc<Path>> {
+        let key = (Rc::as_ptr(font), glyph.get_code());
+        if let Some(outline) = self.glyphs.get(&key) {
+            return outline.clone();
+        }
+        let outline = match self.get_glyph_source(font).as_ref() {
+            GlyphSource::Embedded(outlines) => embedded_glyph(font, outlines, glyph)
+                .and_then(|gid| outlines.get_outline(gid))
+                .map(|path| path.transform(outlines.get_matrix())),
+            GlyphSource::Fallback { outlines, italic } => {
+                let gid = glyph.get_unicode().and_then(|text| text.chars().next()).and_then(|c| outlines.lookup_unicode(c));
+                gid.and_then(|gid| {
+                    // The glyph is stretched to the width of the original one
+                    let scale = match (outlines.get_advance(gid), glyph.get_advance()) {
+                        (Some(width), advance) if width > 0.0 && advance > 0.0 => (advance / width).clamp(0.5, 2.0),
+                        _ => 1.0,
+                    };
+                    let slant = if *italic { ITALIC_SLANT } else { 0.0 };
+                    let matrix = outlines.get_matrix()
+                        .multiply(&Matrix::scale(scale, 1.0))
+                        .multiply(&Matrix::new(1.0, 0.0, slant, 1.0, 0.0, 0.0));
+                    Some(outlines.get_outline(gid)?.transform(&matrix))
+                })
+            }
+            GlyphSource::None => None,
+        };
+        let outline = outline.map(Rc::new);
+        self.glyphs.insert(key, outline.clone());
+        outline
+    }
+}
+
+impl OutlineBuilder {
+    fn move_to(&mut self, x: f64, y: f64) {
+        self.close();
+        self.x = x;
+        self.y = y;
+    }
+
+    fn line_to(&mut self, x: f64, y: f64) {
+        self.open();
+        self.path.push(PathSegment::LineTo(x, y));
+        self.x = x;
+        self.y = y;
+    }
+
+    fn curve_to(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, x: f64, y: f64) {
+        self.open();
+        self.path.push(PathSegment::CurveTo(x1, y1, x2, y2, x, y));
+        self.x = x;
+        self.y = y;
+    }
+
+    /// Moves relative to the current point.
+    fn r_move_to(&mut self, dx: f64, dy: f64) {
+        self.move_to(self.x + dx, self.y + dy);
+    }
+
+    fn r_line_to(&mut self, dx: f64, dy: f64) {
+        self.line_to(self.x + dx, self.y + dy);
+    }
+
+    fn r_curve_to(&mut self, dx1: f64, dy1: f64, dx2: f64, dy2: f64, dx3: f64, dy3: f64) {
+        let (x1, y1) = (self.x + dx1, self.y + dy1);
+        let (x2, y2) = (x1 + dx2, y1 + dy2);
+        self.curve_to(x1, y1, x2, y2, x2 + dx3, y2 + dy3);
+    }
+
+    /// Starts the contour at the current point if it has no segment yet.
+    fn open(&mut self) {
+        if !self.open {
+            self.path.push(PathSegment::MoveTo(self.x, self.y));
+            self.open = true;
+        }
+    }
+
+    fn close(&mut self) {
+        if self.open {
+            self.path.push(PathSegment::Close);
+            self.open = false;
+        }
+    }
+
+    fn finish(mut self) -> Path {
+        self.close();
+        self.path
+    }
+}
+
+/// Gets the glyph index of a code in an embedded font program.
+///
+/// Composite fonts map CIDs with `/CIDToGIDMap` for TrueType programs and with
+/// the charset of CID-keyed CFF programs. Simple fonts look up the glyph name of
+/// the code in Type 1 and CFF programs. TrueType programs are looked up in the
+/// Unicode, Mac Roman and symbol character maps, as the encoding and symbolic
+/// flag of the font suggest, and programs without character map use codes as
+/// glyph indexes.
+fn embedded_glyph(font: &Font, outlines: &OutlineFont, glyph: &Glyph) -> Option<u32> {
+    let code = glyph.get_code();
+    if let Some(cid) = font.get_cid(code) {
+        return match font.get_cid_font() {
+            Some(cid_font) if cid_font.get_subtype() == "CIDFontType2" => Some(cid_font.get_gid(cid)),
+            _ => outlines.lookup_cid(cid),
+        };
+    }
+    let name = font.get_glyph_name(code).or_else(|| outlines.get_encoding_name(code));
+    if let Some(gid) = name.and_then(|name| outlines.lookup_name(name)) {
+        return Some(gid);
+    }
+    if !outlines.has_cmaps() {
+        return match outlines.get_encoding_name(code).and_then(|name| outlines.lookup_name(name)) {
+            Some(gid) => Some(gid),
+            // Bare TrueType programs of subset fonts may use codes as glyph indexes
+            None => Some(code).filter(|_| font.get_subtype() == "TrueType"),
+        };
+    }
+    if let Some(name) = font.get_glyph_name(code) {
+        let unicode = glyph_name_to_unicode(name).and_then(|text| text.chars().next());
+        if let Some(gid) = unicode.and_then(|c| outlines.lookup_unicode(c)) {
+            return Some(gid);
+        }
+        let mac_code = (0..=255u8).find(|code| glyph_name_from_u8(*code, &PreDefinedEncoding::MacRoman) == Some(name));
+        if let Some(gid) = mac_code.and_then(|code| outlines.lookup_cmap(1, 0, code as u32)) {
+            return Some(gid);
+        }
+    }
+    // Symbol character maps place single-byte codes at 0xf000 and above
+    if outlines.has_cmap(3, 0) {
+        let gid = [0, 0xf000, 0xf100, 0xf200].iter().find_map(|base| outlines.lookup_cmap(3, 0, base + code));
+        if gid.is_some() {
+            return gid;
+        }
+    }
+    outlines.lookup_cmap(1, 0, code)
+        .or_else(|| glyph.get_unicode().and_then(|text| text.chars().next()).and_then(|c| outlines.lookup_unicode(c)))
+        .or_else(|| outlines.lookup_cmap(3, 1, code))
+}
+
+/// Returns the offset and length of each table of an sfnt font, the first font of a collection.
+fn read_table_directory(data: &[u8]) -> Option<HashMap<[u8; 4], (usize, usize)>> {
+    let start = match data.get(0..4)? {
+        b"ttcf" => read_u32(data, 12)? as usize,
+        _ => 0,
+    };
+    let count = read_u16(data, start + 4)? as usize;
+    let mut tables = HashMap::new();
+    for i in 0..count {
+        let record = start + 12 + i * 16;
+        let tag: [u8; 4] = data.get(record..record + 4)?.try_into().ok()?;
+        let offset = read_u32(data, record + 8)? as usize;
+        let length = read_u32(data, record + 12)? as usize;
+        // Lengths past the end of the data are truncated, as some embedded fonts are
+        let length = length.min(data.len().saturating_sub(offset));
+        tables.insert(tag, (offset, length));
+    }
+    Some(tables)
+}
+
+/// Parses a TrueType or OpenType font, with TrueType or CFF outlines.
+fn parse_sfnt(data: &[u8]) -> Option<OutlineFont> {
+    let tables = read_table_directory(data)?;
+    let table = |tag: &[u8; 4]| tables.get(tag).and_then(|(offset, length)| data.get(*offset..offset + length));
+    let cmaps = table(b"cmap").map(read_cmaps).unwrap_or_default();
+    if let Some(cff) = table(b"CFF ") {
+        let mut font = parse_cff(cff.to_vec())?;
+        font.cmaps = cmaps;
+        return Some(font);
+    }
+    let head = table(b"head")?;
+    let units_per_em = match read_u16(head, 18)? {
+        units @ 16..=16384 => units as f64,
+        _ => 1000.0,
+    };
+    let long_offsets = read_u16(head, 50)? != 0;
+    let glyph_count = table(b"maxp").and_then(|maxp| read_u16(maxp, 4)).map(|count| count as usize);
+    let loca_data = table(b"loca")?;
+    let glyf = table(b"glyf")?.to_vec();
+    let entries = match long_offsets {
+        true => loca_data.len() / 4,
+        false => loca_data.len() / 2,
+    };
+    let entries = glyph_count.map_or(entries, |count| entries.min(count + 1));
+    let loca = (0..entries)
+        .map(|i| match long_offsets {
+            true => read_u32(loca_data, i * 4),
+            false => read_u16(loca_data, i * 2).map(|offset| offset as u32 * 2),
+        })
+        .collect::<Option<Vec<u32>>>()?;
+    let metrics = table(b"hhea").and_then(|hhea| read_u16(hhea, 34)).unwrap_or(0) as usize;
+    let advances = match table(b"hmtx") {
+        Some(hmtx) => (0..metrics).map_while(|i| read_u16(hmtx, i * 4)).collect(),
+        None => Vec::new(),
+    };
+    Some(OutlineFont {
+        matrix: Matrix::scale(1.0 / units_per_em, 1.0 / units_per_em),
+        glyphs: Glyphs::TrueType { glyf, loca },
+        cmaps,
+        names: HashMap::new(),
+        encoding: HashMap::new(),
+        cids: None,
+        advances,
+    })
+}
+
+/// Reads the character maps of the `cmap` table, in formats 0, 4, 6 and 12.
+fn read_cmaps(cmap: &[u8]) -> Vec<((u16, u16), HashMap<u32, u32>)> {
+    let count = read_u16(cmap, 2).unwrap_or(0) as usize;
+    let mut cmaps = Vec::new();
+    for i in 0..count {
+        let record = 4 + i * 8;
+        let (Some(platform), Some(encoding), Some(offset)) =
+            (read_u16(cmap, record), read_u16(cmap, record + 2), read_u32(cmap, record + 4)) else {
+            break;
+        };
+        if let Some(map) = cmap.get(offset as usize..).and_then(read_cmap_subtable) {
+            cmaps.push(((platform, encoding), map));
+        }
+    }
+    cmaps
+}
+
+fn read_cmap_subtable(data: &[u8]) -> Option<HashMap<u32, u32>> {
+    let mut map = HashMap::new();
+    match read_u16(data, 0)? {
+        0 => {
+            for (code, gid) in data.get(6..262)?.iter().enumerate() {
+                map.insert(code as u32, *gid as u32);
+            }
+        }
+        4 => {
+            let segments = read_u16(data, 6)? as usize / 2;
+            let ends = 14;
+            let starts = ends + segments * 2 + 2;
+            let deltas = starts + segments * 2;
+            let range_offsets = deltas + segments * 2;
+            for segment in 0..segments {
+                let end = read_u16(data, ends + segment * 2)? as u32;
+                let start = read_u16(data, starts + segment * 2)? as u32;
+                let delta = read_u16(data, deltas + segment * 2)? as u32;
+                let range_offset = read_u16(data, range_offsets + segment * 2)? as usize;
+                for code in start..=end.min(0xfffe) {
+                    let gid = match range_offset {
+                        0 => (code + delta) & 0xffff,
+                        _ => {
+                            let address = range_offsets + segment * 2 + range_offset + (code - start) as usize * 2;
+                            match read_u16(data, address) {
+                                Some(0) | None => 0,
+                                Some(gid) => (gid as u32 + delta) & 0xffff,
+                            }
+                        }
+                    };
+                    map.insert(code, gid);
+                }
+            }
+        }
+        6 => {
+            let first = read_u16(data, 6)? as u32;
+            let count = read_u16(data, 8)? as u32;
+            for i in 0..count {
+                map.insert(first + i, read_u16(data, 10 + i as usize * 2)? as u32);
+            }
+        }
+        12 => {
+            let groups = read_u32(data, 12)? as usize;
+            for group in 0..groups {
+                let record = 16 + group * 12;
+                let start = read_u32(data, record)?;
+                let end = read_u32(data, record + 4)?.min(start.saturating_add(0xffff));
+                let gid = read_u32(data, record + 8)?;
+                // Groups whose glyph IDs overflow are broken
+                if gid.checked_add(end.saturating_sub(start)).is_none() {
+                    continue;
+                }
+                for code in start..=end {
+                    map.insert(code, gid + (code - start));
+                }
+            }
+        }
+        _ => return None,
+    }
+    Some(map)
+}
+
+/// Builds the outline of a TrueType glyph, converting quadratic curves to cubic ones.
+fn truetype_outline(glyf: &[u8], loca: &[u32], gid: u32, depth: usize) -> Option<Path> {
+    let start = *loca.get(gid as usize)? as usize;
+    let end = *loca.get(gid as usize + 1)? as usize;
+    if end <= start {
+        return Some(Path::default());
+    }
+    let data = glyf.get(start..end)?;
+    let contours = read_u16(data, 0)? as i16;
+    if contours < 0 {
+        return composite_outline(glyf, loca, data, depth);
+    }
+    let contours = contours as usize;
+    let end_points = (0..contours).map(|i| read_u16(data, 10 + i * 2).map(|end| end as usize)).collect::<Option<Vec<usize>>>()?;
+    let points = end_points.last().map_or(0, |last| last + 1);
+    let instructions = read_u16(data, 10 + contours * 2)? as usize;
+    let mut offset = 12 + contours * 2 + instructions;
+    let mut flags = Vec::with_capacity(points);
+    while flags.len() < points {
+        let flag = *data.get(offset)?;
+        offset += 1;
+        flags.push(flag);
+        // Bit 3 repeats the flag
+        if flag & 0x08 != 0 {
+            let repeat = *data.get(offset)?;
+            offset += 1;
+            flags.extend(std::iter::repeat_n(flag, repeat as usize));
+        }
+    }
+    flags.truncate(points);
+    let mut read_coordinates = |short: u8, same: u8| -> Option<Vec<f64>> {
+        let mut value = 0i32;
+        let mut values = Vec::with_capacity(points);
+        for flag in &flags {
+            if flag & short != 0 {
+                let delta = *data.get(offset)? as i32;
+                offset += 1;
+                value += if flag & same != 0 { delta } else { -delta };
+            } else if flag & same == 0 {
+                value += read_u16(data, offset)? as i16 as i32;
+                offset += 2;
+            }
+            values.push(value as f64);
+        }
+        Some(values)
+    };
+    let xs = read_coordinates(0x02, 0x10)?;
+    let ys = read_coordinates(0x04, 0x20)?;
+    let mut path = Path::default();
+    let mut first = 0;
+    for end in end_points {
+        if end < first || end >= points {
+            return None;
+        }
+        let contour = (first..=end).map(|i| (xs[i], ys[i], flags[i] & 0x01 != 0)).collect::<Vec<_>>();
+        push_quadratic_contour(&mut path, &contour);
+        first = end + 1;
+    }
+    Some(path)
+}
+
+/// Adds a contour of on-curve and off-curve points, with implied on-curve
+/// points between consecutive off-curve points.
+fn push_quadratic_contour(path: &mut Path, points: &[(f64, f64, bool)]) {
+    let Some(&(x0, y0, on0)) = points.first() else {
+        return;
+    };
+    let &(xn, yn, onn) = points.last().unwrap();
+    // The contour starts at an on-curve point, or between the first and last off-curve points
+    let (start, rest) = match (on0, onn) {
+        (true, _) => ((x0, y0), &points[1..]),
+        (false, true) => ((xn, yn), &points[..points.len() - 1]),
+        (false, false) => (((x0 + xn) / 2.0, (y0 + yn) / 2.0), points),
+    };
+    path.push(PathSegment::MoveTo(start.0, start.1));
+    let mut current = start;
+    let mut control: Option<(f64, f64)> = None;
+    let quad_to = |path: &mut Path, current: &mut (f64, f64), (cx, cy): (f64, f64), (x, y): (f64, f64)| {
+        let (x0, y0) = *current;
+        path.push(PathSegment::CurveTo(
+            x0 + 2.0 / 3.0 * (cx - x0), y0 + 2.0 / 3.0 * (cy - y0),
+            x + 2.0 / 3.0 * (cx - x), y + 2.0 / 3.0 * (cy - y),
+            x, y,
+        ));
+        *current = (x, y);
+    };
+    for &(x, y, on) in rest.iter().chain(std::iter::once(&(start.0, start.1, true))) {
+        match (on, control) {
+            (true, None) => {
+                path.push(PathSegment::LineTo(x, y));
+                current = (x, y);
+            }
+            (true, Some(c)) => {
+                quad_to(path, &mut current, c, (x, y));
+                control = None;
+            }
+            (false, None) => control = Some((x, y)),
+            (false, Some(c)) => {
+                quad_to(path, &mut current, c, ((c.0 + x) / 2.0, (c.1 + y) / 2.0));
+                control = Some((x, y));
+            }
+        }
+    }
+    path.push(PathSegment::Close);
+}
+
+/// Builds the outline of a composite TrueType glyph from its transformed components.
+fn composite_outline(glyf: &[u8], loca: &[u32], data: &[u8], depth: usize) -> Option<Path> {
+    if depth >= MAX_COMPONENT_DEPTH {
+        return None;
+    }
+    let mut path = Path::default();
+    let mut offset = 10;
+    loop {
+        let flags = read_u16(data, offset)?;
+        let gid = read_u16(data, offset + 2)? as u32;
+        offset += 4;
+        // Bit 0: word arguments, bit 1: the arguments are offsets rather than point numbers
+        let (dx, dy) = if flags & 0x0001 != 0 {
+            let args = (read_u16(data, offset)? as i16 as f64, read_u16(data, offset + 2)? as i16 as f64);
+            offset += 4;
+            args
+        } else {
+            let args = (*data.get(offset)? as i8 as f64, *data.get(offset + 1)? as i8 as f64);
+            offset += 2;
+            args
+        };
+        let (dx, dy) = if flags & 0x0002 != 0 { (dx, dy) } else { (0.0, 0.0) };
+        let f2dot14 = |offset: usize| read_u16(data, offset).map(|value| value as i16 as f64 / 16384.0);
+        let (a, b, c, d) = if flags & 0x0008 != 0 {
+            let scale = f2dot14(offset)?;
+            offset += 2;
+            (scale, 0.0, 0.0, scale)
+        } else if flags & 0x0040 != 0 {
+            let scales = (f2dot14(offset)?, 0.0, 0.0, f2dot14(offset + 2)?);
+            offset += 4;
+            scales
+        } else if flags & 0x0080 != 0 {
+            let scales = (f2dot14(offset)?, f2dot14(offset + 2)?, f2dot14(offset + 4)?, f2dot14(offset + 6)?);
+            offset += 8;
+            scales
+        } else {
+            (1.0, 0.0, 0.0, 1.0)
+        };
+        if let Some(component) = truetype_outline(glyf, loca, gid, depth + 1) {
+            path.append(&component.transform(&Matrix::new(a, b, c, d, dx, dy)));
+        }
+        // Bit 5: more components follow
+        if flags & 0x0020 == 0 {
+            return Some(path);
+        }
+    }
+}
+
+/// Reads the offset ranges of the objects of a CFF INDEX.
+///
+/// # Returns
+///
+/// The ranges and the offset following the INDEX
+fn read_index(data: &[u8], offset: usize) -> Option<(Vec<(usize, usize)>, usize)> {
+    let count = read_u16(data, offset)? as usize;
+    if count == 0 {
+        return Some((Vec::new(), offset + 2));
+    }
+    let size = *data.get(offset + 2)? as usize;
+    if !(1..=4).contains(&size) {
+        return None;
+    }
+    let read_offset = |i: usize| -> Option<usize> {
+        let start = offset + 3 + i * size;
+        Some(data.get(start..start + size)?.iter().fold(0, |value, b| value << 8 | *b as usize))
+    };
+    let base = offset + 3 + (count + 1) * size - 1;
+    let mut ranges = Vec::with_capacity(count);
+    for i in 0..count {
+        let (start, end) = (base + read_offset(i)?, base + read_offset(i + 1)?);
+        if start > end || end > data.len() {
+            return None;
+        }
+        ranges.push((start, end));
+    }
+    Some((ranges, base + read_offset(count)?))
+}
+
+/// Reads a CFF DICT into its operands by operator, two-byte operators being `1200 + b1`.
+fn read_dict(data: &[u8]) -> HashMap<u16, Vec<f64>> {
+    let mut dict = HashMap::new();
+    let mut operands = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        let b0 = data[i];
+        match b0 {
+            0..=21 => {
+                let operator = match b0 {
+                    12 => {
+                        i += 1;
+                        1200 + *data.get(i).unwrap_or(&0) as u16
+                    }
+                    _ => b0 as u16,
+                };
+                dict.insert(operator, std::mem::take(&mut operands));
+                i += 1;
+            }
+            28 | 29 => {
+                let size = if b0 == 28 { 2 } else { 4 };
+                let Some(bytes) = data.get(i + 1..i + 1 + size) else {
+                    break;
+                };
+                let value = match size {
+                    2 => i16::from_be_bytes([bytes[0], bytes[1]]) as f64,
+                    _ => i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+                };
+                operands.push(value);
+                i += 1 + size;
+            }
+            30 => {
+                // A real number of nibbles, ending with 0xf
+                let mut text = String::new();
+                i += 1;
+                'real: while let Some(b) = data.get(i) {
+                    i += 1;
+                    for nibble in [b >> 4, b & 0x0f] {
+                        match nibble {
+                            0..=9 => text.push((b'0' + nibble) as char),
+                            0xa => text.push('.'),
+                            0xb => text.push('E'),
+                            0xc => text.push_str("E-"),
+                            0xe => text.push('-'),
+                            0xf => break 'real,
+                            _ => {}
+                        }
+                    }
+                }
+                operands.push(text.parse().unwrap_or(0.0));
+            }
+            32..=246 => {
+                operands.push(b0 as f64 - 139.0);
+                i += 1;
+            }
+            247..=254 => {
+                let b1 = *data.get(i + 1).unwrap_or(&0) as f64;
+                let value = match b0 {
+                    247..=250 => (b0 as f64 - 247.0) * 256.0 + b1 + 108.0,
+                    _ => -(b0 as f64 - 251.0) * 256.0 - b1 - 108.0,
+                };
+                operands.push(value);
+                i += 2;
+            }
+            _ => i += 1,
+        }
+    }
+    dict
+}
+
+/// Parses a bare CFF font program, name-keyed or CID-keyed.
+fn parse_cff(data: Vec<u8>) -> Option<OutlineFont> {
+    let header_size = *data.get(2)? as usize;
+    let (_, offset) = read_index(&data, header_size)?;
+    let (top_dicts, offset) = read_index(&data, offset)?;
+    let (strings, offset) = read_index(&data, offset)?;
+    let (global_subrs, _) = read_index(&data, offset)?;
+    let &(start, end) = top_dicts.first()?;
+    let top = read_dict(&data[start..end]);
+    let integer = |dict: &HashMap<u16, Vec<f64>>, operator: u16| dict.get(&operator).and_then(|operands| operands.first()).map(|value| *value as usize);
+    let (charstrings, _) = read_index(&data, integer(&top, 17)?)?;
+    let string = |sid: usize| -> Option<String> {
+        match sid {
+            0..=390 => Some(CFF_STANDARD_STRINGS[sid].to_string()),
+            _ => strings.get(sid - 391).map(|(start, end)| String::from_utf8_lossy(&data[*start..*end]).into_owned()),
+        }
+    };
+    // Local subroutines are given relative to their Private DICT
+    let read_private = |dict: &HashMap<u16, Vec<f64>>| -> Vec<(usize, usize)> {
+        let Some([size, offset]) = dict.get(&18).and_then(|operands| <[f64; 2]>::try_from(operands.as_slice()).ok()) else {
+            return Vec::new();
+        };
+        let (size, offset) = (size as usize, offset as usize);
+        let Some(private) = data.get(offset..offset + size) else {
+            return Vec::new();
+        };
+        match integer(&read_dict(private), 19) {
+            Some(subrs) => read_index(&data, offset + subrs).map(|(subrs, _)| subrs).unwrap_or_default(),
+            None => Vec::new(),
+        }
+    };
+    let cid_keyed = top.contains_key(&1230);
+    let (local_subrs, fd_select) = if cid_keyed {
+        let (fd_array, _) = read_index(&data, integer(&top, 1236)?)?;
+        let local_subrs = fd_array.iter().map(|(start, end)| read_private(&read_dict(&data[*start..*end]))).collect();
+        let fd_select = integer(&top, 1237).and_then(|offset| read_fd_select(&data, offset, charstrings.len())).unwrap_or_default();
+        (local_subrs, fd_select)
+    } else {
+        (vec![read_private(&top)], Vec::new())
+    };
+    let charset = read_charset(&data, integer(&top, 15).unwrap_or(0), charstrings.len());
+    let (names, cids) = match cid_keyed {
+        true => (HashMap::new(), Some(charset.iter().enumerate().map(|(gid, cid)| (*cid as u32, gid as u32)).collect())),
+        false => {
+            let names = charset.iter().enumerate()
+                .filter_map(|(gid, sid)| Some((string(*sid as usize)?, gid as u32)))
+                .collect();
+            (names, None)
+        }
+    };
+    let mut encoding = HashMap::new();
+    if !cid_keyed {
+        match integer(&top, 16).unwrap_or(0) {
+            0 => for code in 0..=255u8 {
+                if let Some(name) = glyph_name_from_u8(code, &PreDefinedEncoding::Standard).filter(|name| *name != ".notdef") {
+                    encoding.insert(code as u32, name.to_string());
+                }
+            },
+            // The Expert encoding isn't supported
+            1 => {}
+            offset => {
+                let (codes, supplements) = read_encoding(&data, offset);
+                for (code, gid) in codes {
+                    if let Some(name) = charset.get(gid as usize).and_then(|sid| string(*sid as usize)) {
+                        encoding.insert(code, name);
+                    }
+                }
+                for (code, sid) in supplements {
+                    if let Some(name) = string(sid as usize) {
+                        encoding.insert(code, name);
+                    }
+                }
+            }
+        }
+    }
+    let matrix = match top.get(&1207).map(|operands| operands.as_slice()) {
+        Some(&[a, b, c, d, e, f]) if a * d - b * c != 0.0 => Matrix::new(a, b, c, d, e, f),
+        _ => Matrix::scale(0.001, 0.001),
+    };
+    let cff = CffFont { data, charstrings, global_subrs, local_subrs, fd_select };
+    Some(OutlineFont {
+        matrix,
+        glyphs: Glyphs::Cff(cff),
+        cmaps: Vec::new(),
+        names,
+        encoding,
+        cids,
+        advances: Vec::new(),
+    })
+}
+
+/// Reads the charset of a CFF font, the SID or CID of each glyph.
+///
+/// The predefined ISOAdobe charset maps glyphs to the first SIDs, the Expert
+/// charsets aren't supported.
+fn read_charset(data: &[u8], offset: usize, glyphs: usize) -> Vec<u16> {
+    let mut charset = vec![0u16];
+    if offset <= 2 {
+        if offset == 0 {
+            charset.extend((1..glyphs.min(229)).map(|sid| sid as u16));
+        }
+        return charset;
+    }
+    let format = data.get(offset).copied();
+    let mut position = offset + 1;
+    while charset.len() < glyphs {
+        let Some(first) = read_u16(data, position) else {
+            break;
+        };
+        let count = match format {
+            Some(0) => {
+                position += 2;
+                0
+            }
+            Some(1) => {
+                let Some(count) = data.get(position + 2) else {
+                    break;
+                };
+                position += 3;
+                *count as usize
+            }
+            Some(2) => {
+                let Some(count) = read_u16(data, position + 2) else {
+                    break;
+                };
+                position += 4;
+                count as usize
+            }
+            _ => break,
+        };
+        charset.extend((0..=count).map(|i| first.wrapping_add(i as u16)));
+    }
+    charset.truncate(glyphs);
+    charset
+}
+
+/// The codes of a custom CFF encoding with their glyph index, and the
+/// supplementary codes with the SID of their glyph name.
+type CustomEncoding = (Vec<(u32, u32)>, Vec<(u32, u16)>);
+
+/// Reads a custom CFF encoding.
+fn read_encoding(data: &[u8], offset: usize) -> CustomEncoding {
+    let (mut codes, mut supplements) = (Vec::new(), Vec::new());
+    let Some(&format) = data.get(offset) else {
+        return (codes, supplements);
+    };
+    let count = *data.get(offset + 1).unwrap_or(&0) as usize;
+    let mut position = offset + 2;
+    match format & 0x7f {
+        0 => {
+            for gid in 1..=count {
+                if let Some(code) = data.get(position) {
+                    codes.push((*code as u32, gid as u32));
+                }
+                position += 1;
+            }
+        }
+        1 => {
+            let mut gid = 1;
+            for _ in 0..count {
+                let (Some(first), Some(left)) = (data.get(position), data.get(position + 1)) else {
+                    break;
+                };
+                for code in *first as u32..=*first as u32 + *left as u32 {
+                    codes.push((code, gid));
+                    gid += 1;
+                }
+                position += 2;
+            }
+        }
+        _ => return (codes, supplements),
+    }
+    // Bit 7 of the format announces more codes for glyphs which already have one
+    if format & 0x80 != 0 {
+        let count = *data.get(position).unwrap_or(&0) as usize;
+        for i in 0..count {
+            let record = position + 1 + i * 3;
+            if let (Some(code), Some(sid)) = (data.get(record), read_u16(data, record + 1)) {
+                supplements.push((*code as u32, sid));
+            }
+        }
+    }
+    (codes, supplements)
+}
+
+/// Reads the Font DICT index of each glyph of a CID-keyed CFF font.
+fn read_fd_select(data: &[u8], offset: usize, glyphs: usize) -> Option<Vec<u8>> {
+    match data.get(offset)? {
+        0 => Some(data.get(offset + 1..offset + 1 + glyphs)?.to_vec()),
+        3 => {
+            let ranges = read_u16(data, offset + 1)? as usize;
+            let mut select = vec![0; glyphs];
+            for i in 0..ranges {
+                let record = offset + 3 + i * 3;
+                let first = read_u16(data, record)? as usize;
+                let fd = *data.get(record + 2)?;
+                let end = read_u16(data, record + 3)? as usize;
+                for entry in select.iter_mut().take(end.min(glyphs)).skip(first) {
+                    *entry = fd;
+                }
+            }
+            Some(select)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the bias added to subroutine numbers, which depends on the number of subroutines.
+fn subr_bias(count: usize) -> i64 {
+    match count {
+        0..1240 => 107,
+        1240..33900 => 1131,
+        _ => 32768,
+    }
+}
+
+impl CffFont {
+    fn outline(&self, gid: u32, depth: usize) -> Option<Path> {
+        let &(start, end) = self.charstrings.get(gid as usize)?;
+        let fd = self.fd_select.get(gid as usize).copied().unwrap_or(0) as usize;
+        let mut state = Type2State::default();
+        self.execute(&self.data[start..end], fd, &mut state, 0)?;
+        let mut path = state.builder.finish();
+        // endchar with four operands draws an accented character from two standard encoding codes
+        if let Some([adx, ady, base, accent]) = state.seac {
+            if depth >= MAX_COMPONENT_DEPTH {
+                return None;
+            }
+            let mut composite = self.standard_glyph(base as u8).and_then(|gid| self.outline(gid, depth + 1))?;
+            if let Some(accent) = self.standard_glyph(accent as u8).and_then(|gid| self.outline(gid, depth + 1)) {
+                composite.append(&accent.transform(&Matrix::translate(adx, ady)));
+            }
+            path = composite;
+        }
+        Some(path)
+    }
+
+    /// Returns the glyph of a code of the standard encoding, looked up by the SID of its name.
+    fn standard_glyph(&self, code: u8) -> Option<u32> {
+        let name = glyph_name_from_u8(code, &PreDefinedEncoding::Standard)?;
+        let sid = CFF_STANDARD_STRINGS.iter().position(|standard| *standard == name)?;
+        // Fonts with accented characters have a name-keyed charset; the predefined one is the identity
+        Some(sid as u32).filter(|gid| (*gid as usize) < self.charstrings.len())
+    }
+
+    /// Executes a Type 2 charstring.
+    ///
+    /// # Returns
+    ///
+    /// True if the charstring ended with `endchar`, False if it returned, or
+    /// None if it is malformed
+    fn execute(&self, code: &[u8], fd: usize, state: &mut Type2State, depth: usize) -> Option<bool> {
+        if depth > MAX_SUBR_DEPTH {
+            return None;
+        }
+        let mut i = 0;
+        while i < code.len() {
+            let b0 = code[i];
+            i += 1;
+            match b0 {
+                28 => {
+                    state.stack.push(read_u16(code, i)? as i16 as f64);
+                    i += 2;
+                }
+                32..=246 => state.stack.push(b0 as f64 - 139.0),
+                247..=250 => {
+                    state.stack.push((b0 as f64 - 247.0) * 256.0 + *code.get(i)? as f64 + 108.0);
+                    i += 1;
+                }
+                251..=254 => {
+                    state.stack.push(-(b0 as f64 - 251.0) * 256.0 - *code.get(i)? as f64 - 108.0);
+                    i += 1;
+                }
+                255 => {
+                    state.stack.push(read_u32(code, i)? as i32 as f64 / 65536.0);
+                    i += 4;
+                }
+                // hstem, vstem, hstemhm, vstemhm
+                1 | 3 | 18 | 23 => {
+                    state.take_width(state.stack.len() % 2 == 1);
+                    state.stems += state.stack.len() / 2;
+                    state.stack.clear();
+                }
+                // hintmask, cntrmask, with implicit vstem operands
+                19 | 20 => {
+                    state.take_width(state.stack.len() % 2 == 1);
+                    state.stems += state.stack.len() / 2;
+                    state.stack.clear();
+                    i += state.stems.div_ceil(8);
+                }
+                // rmoveto
+                21 => {
+                    state.take_width(state.stack.len() > 2);
+                    let [dx, dy] = state.last::<2>()?;
+                    state.builder.r_move_to(dx, dy);
+                    state.stack.clear();
+                }
+                // hmoveto
+                22 => {
+                    state.take_width(state.stack.len() > 1);
+                    let [dx] = state.last::<1>()?;
+                    state.builder.r_move_to(dx, 0.0);
+                    state.stack.clear();
+                }
+                // vmoveto
+                4 => {
+                    state.take_width(state.stack.len() > 1);
+                    let [dy] = state.last::<1>()?;
+                    state.builder.r_move_to(0.0, dy);
+                    state.stack.clear();
+                }
+                // rlineto
+                5 => {
+                    for pair in state.stack.chunks_exact(2) {
+                        state.builder.r_line_to(pair[0], pair[1]);
+                    }
+                    state.stack.clear();
+                }
+                // hlineto, vlineto: alternating horizontal and vertical lines
+                6 | 7 => {
+                    let mut horizontal = b0 == 6;
+                    for d in &state.stack {
+                        match horizontal {
+                            true => state.builder.r_line_to(*d, 0.0),
+                            false => state.builder.r_line_to(0.0, *d),
+                        }
+                        horizontal = !horizontal;
+                    }
+                    state.stack.clear();
+                }
+                // rrcurveto
+                8 => {
+                    for c in state.stack.chunks_exact(6) {
+                        state.builder.r_curve_to(c[0], c[1], c[2], c[3], c[4], c[5]);
+                    }
+                    state.stack.clear();
+                }
+                // rcurveline
+                24 => {
+                    let curves = state.stack.len().saturating_sub(2) / 6;
+                    for c in state.stack.chunks_exact(6).take(curves) {
+                        state.builder.r_curve_to(c[0], c[1], c[2], c[3], c[4], c[5]);
+                    }
+                    if let [dx, dy] = state.stack[curves * 6..] {
+                        state.builder.r_line_to(dx, dy);
+                    }
+                    state.stack.clear();
+                }
+                // rlinecurve
+                25 => {
+                    let lines = state.stack.len().saturating_sub(6) / 2;
+                    for pair in state.stack.chunks_exact(2).take(lines) {
+                        state.builder.r_line_to(pair[0], pair[1]);
+                    }
+                    if let [a, b, c, d, e, f] = state.stack[lines * 2..] {
+                        state.builder.r_curve_to(a, b, c, d, e, f);
+                    }
+                    state.stack.clear();
+                }
+                // vvcurveto, hhcurveto, with an optional first offset across the direction
+                26 | 27 => {
+                    let stack = std::mem::take(&mut state.stack);
+                    let (mut across, curves) = match stack.len() % 4 {
+                        1 => (stack[0], &stack[1..]),
+                        _ => (0.0, &stack[..]),
+                    };
+                    for c in curves.chunks_exact(4) {
+                        match b0 {
+                            26 => state.builder.r_curve_to(across, c[0], c[1], c[2], 0.0, c[3]),
+                            _ => state.builder.r_curve_to(c[0], across, c[1], c[2], c[3], 0.0),
+                        }
+                        across = 0.0;
+                    }
+                }
+                // vhcurveto, hvcurveto: curves alternately starting vertically and horizontally
+                30 | 31 => {
+                    let stack = std::mem::take(&mut state.stack);
+                    let mut vertical = b0 == 30;
+                    let mut rest = &stack[..];
+                    while rest.len() >= 4 {
+                        // The last curve may end with an offset across its end direction
+                        let last = if rest.len() == 5 { rest[4] } else { 0.0 };
+                        match vertical {
+                            true => state.builder.r_curve_to(0.0, rest[0], rest[1], rest[2], rest[3], last),
+                            false => state.builder.r_curve_to(rest[0], 0.0, rest[1], rest[2], last, rest[3]),
+                        }
+                        rest = &rest[4..];
+                        vertical = !vertical;
+                    }
+                }
+                // callsubr, callgsubr
+                10 | 29 => {
+                    let subrs = match b0 {
+                        10 => self.local_subrs.get(fd)?,
+                        _ => &self.global_subrs,
+                    };
+                    let index = state.stack.pop()? as i64 + subr_bias(subrs.len());
+                    let &(start, end) = subrs.get(usize::try_from(index).ok()?)?;
+                    if self.execute(&self.data[start..end], fd, state, depth + 1)? {
+                        return Some(true);
+                    }
+                }
+                // return
+                11 => return Some(false),
+                // endchar
+                14 => {
+                    state.take_width(matches!(state.stack.len(), 1 | 5));
+                    if let Ok(seac) = <[f64; 4]>::try_from(&state.stack[state.stack.len().saturating_sub(4)..]) {
+                        state.seac = Some(seac);
+                    }
+                    state.stack.clear();
+                    return Some(true);
+                }
+                12 => {
+                    let b1 = *code.get(i)?;
+                    i += 1;
+                    state.escape(b1)?;
+                }
+                _ => state.stack.clear(),
+            }
+            if state.stack.len() > MAX_STACK_SIZE {
+                return None;
+            }
+        }
+        Some(false)
+    }
+}
+
+impl Type2State {
+    /// Drops the width operand of the first stack-clearing operator, if it has one.
+    fn take_width(&mut self, has_width: bool) {
+        if !self.width_done {
+            self.width_done = true;
+            if has_width && !self.stack.is_empty() {
+                self.stack.remove(0);
+            }
+        }
+    }
+
+    /// Returns the last `N` operands.
+    fn last<const N: usize>(&self) -> Option<[f64; N]> {
+        self.stack.get(self.stack.len().checked_sub(N)?..)?.try_into().ok()
+    }
+
+    /// Executes a two-byte operator: flex curves and arithmetic operators.
+    fn escape(&mut self, operator: u8) -> Option<()> {
+        let stack = &mut self.stack;
+        let builder = &mut self.builder;
+        match operator {
+            // flex
+            35 => {
+                if let [a, b, c, d, e, f, g, h, i, j, k, l, ..] = stack[..] {
+                    builder.r_curve_to(a, b, c, d, e, f);
+                    builder.r_curve_to(g, h, i, j, k, l);
+                }
+            }
+            // hflex
+            34 => {
+                if let [dx1, dx2, dy2, dx3, dx4, dx5, dx6, ..] = stack[..] {
+                    let y = builder.y;
+                    builder.r_curve_to(dx1, 0.0, dx2, dy2, dx3, 0.0);
+                    builder.r_curve_to(dx4, 0.0, dx5, y - builder.y, dx6, 0.0);
+                }
+            }
+            // hflex1
+            36 => {
+                if let [dx1, dy1, dx2, dy2, dx3, dx4, dx5, dy5, dx6, ..] = stack[..] {
+                    let y = builder.y;
+                    builder.r_curve_to(dx1, dy1, dx2, dy2, dx3, 0.0);
+                    let dy6 = y - (builder.y + dy5);
+                    builder.r_curve_to(dx4, 0.0, dx5, dy5, dx6, dy6);
+                }
+            }
+            // flex1, whose last point moves along the dominant direction
+            37 => {
+                if let [dx1, dy1, dx2, dy2, dx3, dy3, dx4, dy4, dx5, dy5, d6, ..] = stack[..] {
+                    let dx = dx1 + dx2 + dx3 + dx4 + dx5;
+                    let dy = dy1 + dy2 + dy3 + dy4 + dy5;
+                    let (dx6, dy6) = if dx.abs() > dy.abs() { (d6, -dy) } else { (-dx, d6) };
+                    builder.r_curve_to(dx1, dy1, dx2, dy2, dx3, dy3);
+                    builder.r_curve_to(dx4, dy4, dx5, dy5, dx6, dy6);
+                }
+            }
+            _ => {
+                let unary = |stack: &mut Vec<f64>, f: fn(f64) -> f64| {
+                    if let Some(value) = stack.last_mut() {
+                        *value = f(*value);
+                    }
+                };
+                let binary = |stack: &mut Vec<f64>, f: fn(f64, f64) -> f64| {
+                    if let (Some(b), Some(a)) = (stack.pop(), stack.pop()) {
+                        stack.push(f(a, b));
+                    }
+                };
+                match operator {
+                    3 => binary(stack, |a, b| (a != 0.0 && b != 0.0) as u8 as f64),
+                    4 => binary(stack, |a, b| (a != 0.0 || b != 0.0) as u8 as f64),
+                    5 => unary(stack, |a| (a == 0.0) as u8 as f64),
+                    9 => unary(stack, f64::abs),
+                    10 => binary(stack, |a, b| a + b),
+                    11 => binary(stack, |a, b| a - b),
+                    12 => binary(stack, |a, b| if b != 0.0 { a / b } else { 0.0 }),
+                    14 => unary(stack, |a| -a),
+                    15 => binary(stack, |a, b| (a == b) as u8 as f64),
+                    18 => {
+                        stack.pop();
+                    }
+                    20 => {
+                        if let (Some(index), Some(value)) = (stack.pop(), stack.pop()) {
+                            let index = index.max(0.0) as usize;
+                            if index < 32 {
+                                if self.transient.len() <= index {
+                                    self.transient.resize(index + 1, 0.0);
+                                }
+                                self.transient[index] = value;
+                            }
+                        }
+                    }
+                    21 => {
+                        let index = stack.pop()?.max(0.0) as usize;
+                        stack.push(self.transient.get(index).copied().unwrap_or(0.0));
+                    }
+                    22 => {
+                        let [s1, s2, v1, v2] = self.last::<4>()?;
+                        let stack = &mut self.stack;
+                        stack.truncate(stack.len() - 4);
+                        stack.push(if v1 <= v2 { s1 } else { s2 });
+                    }
+                    23 => stack.push(0.5),
+                    24 => binary(stack, |a, b| a * b),
+                    26 => unary(stack, |a| a.max(0.0).sqrt()),
+                    27 => stack.push(*stack.last()?),
+                    28 => {
+                        let length = stack.len();
+                        if length >= 2 {
+                            stack.swap(length - 1, length - 2);
+                        }
+                    }
+                    29 => {
+                        let index = stack.pop()?.max(0.0) as usize;
+                        let value = *stack.get(stack.len().checked_sub(index + 1)?)?;
+                        stack.push(value);
+                    }
+                    30 => {
+                        let shift = stack.pop()? as i64;
+                        let count = stack.pop()?.max(0.0) as usize;
+                        let start = stack.len().checked_sub(count)?;
+                        if count > 0 {
+                            stack[start..].rotate_right(shift.rem_euclid(count as i64) as usize);
+                        }
+                    }
+                    // Hint operators such as dotsection
+                    _ => stack.clear(),
+                }
+                return Some(());
+            }
+        }
+        self.stack.clear();
+        Some(())
+    }
+}
+
+/// Parses a Type 1 font program, as PFB segments or as a PFA file.
+fn parse_type1(data: &[u8]) -> Option<OutlineFont> {
+    let (clear, encrypted) = match data.starts_with(&[0x80, 0x01]) {
+        true => split_pfb(data)?,
+        false => {
+            let eexec = data.windows(5).position(|window| window == b"eexec")?;
+            let start = eexec + 5 + data[eexec + 5..].iter().take_while(|b| b.is_ascii_whitespace()).count();
+            (data[..eexec].to_vec(), data[start..].to_vec())
+        }
+    };
+    // The encrypted section may be hexadecimal
+    let encrypted = match encrypted.iter().take(4).all(|b| b.is_ascii_hexdigit()) {
+        true => hex2bytes(&encrypted.iter().copied().filter(|b| b.is_ascii_hexdigit()).collect::<Vec<u8>>()),
+        false => encrypted,
+    };
+    let private = decrypt(&encrypted, EEXEC_KEY, 4);
+    let mut scanner = Scanner::new(&private);
+    let len_iv = match scanner.find("/lenIV") {
+        true => scanner.integer().unwrap_or(4),
+        false => 4,
+    };
+    let decrypt_charstring = |data: &[u8]| match len_iv {
+        0.. => decrypt(data, CHARSTRING_KEY, len_iv as usize),
+        _ => data.to_vec(),
+    };
+    let mut scanner = Scanner::new(&private);
+    let mut subrs = Vec::new();
+    if scanner.find("/Subrs") {
+        let count = scanner.integer().unwrap_or(0).max(0) as usize;
+        subrs.resize(count.min(u16::MAX as usize), Vec::new());
+        let mut read = 0;
+        while read < count {
+            match scanner.token() {
+                Some(b"dup") => {
+                    let (Some(index), Some(data)) = (scanner.integer(), scanner.binary()) else {
+                        break;
+                    };
+                    if let Some(subr) = usize::try_from(index).ok().and_then(|index| subrs.get_mut(index)) {
+                        *subr = decrypt_charstring(data);
+                    }
+                    read += 1;
+                }
+                Some(b"/CharStrings") | None => break,
+                _ => {}
+            }
+        }
+    }
+    let mut scanner = Scanner::new(&private);
+    let mut charstrings = Vec::new();
+    let mut names = HashMap::new();
+    if scanner.find("/CharStrings") {
+        while let Some(token) = scanner.token() {
+            if token == b"end" && !charstrings.is_empty() {
+                break;
+            }
+            if let Some(name) = token.strip_prefix(b"/") {
+                let Some(data) = scanner.binary() else {
+                    break;
+                };
+                names.insert(String::from_utf8_lossy(name).into_owned(), charstrings.len() as u32);
+                charstrings.push(decrypt_charstring(data));
+            }
+        }
+    }
+    if charstrings.is_empty() {
+        return None;
+    }
+    let matrix = read_type1_font_matrix(&clear).unwrap_or(Matrix::scale(0.001, 0.001));
+    Some(OutlineFont {
+        matrix,
+        glyphs: Glyphs::Type1 { charstrings, subrs },
+        cmaps: Vec::new(),
+        names,
+        encoding: read_type1_encoding(&clear),
+        cids: None,
+        advances: Vec::new(),
+    })
+}
+
+/// Splits PFB segments into the clear text and the encrypted binary section.
+fn split_pfb(data: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    let (mut clear, mut encrypted) = (Vec::new(), Vec::new());
+    let mut offset = 0;
+    while let [0x80, kind @ (1 | 2), ..] = data.get(offset..)? {
+        let length = u32::from_le_bytes(data.get(offset + 2..offset + 6)?.try_into().ok()?) as usize;
+        let segment = data.get(offset + 6..(offset + 6 + length).min(data.len()))?;
+        match (kind, encrypted.is_empty()) {
+            (1, true) => clear.extend_from_slice(segment),
+            (2, _) => encrypted.extend_from_slice(segment),
+            _ => {}
+        }
+        offset += 6 + length;
+    }
+    Some((clear, encrypted))
+}
+
+/// Decrypts `eexec` data or a charstring, dropping the leading random bytes.
+fn decrypt(data: &[u8], key: u16, skip: usize) -> Vec<u8> {
+    let mut r = key;
+    let plain = data.iter().map(|&cipher| {
+        let plain = cipher ^ (r >> 8) as u8;
+        r = (cipher as u16).wrapping_add(r).wrapping_mul(52845).wrapping_add(22719);
+        plain
+    });
+    plain.skip(skip).collect()
+}
+
+fn read_type1_font_matrix(clear: &[u8]) -> Option<Matrix> {
+    let mut scanner = Scanner::new(clear);
+    if !scanner.find("/FontMatrix") {
+        return None;
+    }
+    let mut values = Vec::new();
+    while values.len() < 6 {
+        match scanner.token()? {
+            b"[" | b"{" => {}
+            token => values.push(std::str::from_utf8(token).ok()?.parse::<f64>().ok()?),
+        }
+    }
+    let [a, b, c, d, e, f] = values[..] else {
+        return None;
+    };
+    Some(Matrix::new(a, b, c, d, e, f)).filter(|_| a * d - b * c != 0.0)
+}
+
+/// Reads the built-in encoding of a Type 1 font, `StandardEncoding` or `dup code /name put` entries.
+fn read_type1_encoding(clear: &[u8]) -> HashMap<u32, String> {
+    let mut encoding = HashMap::new();
+    let mut scanner = Scanner::new(clear);
+    if !scanner.find("/Encoding") {
+        return encoding;
+    }
+    if scanner.token() == Some(b"StandardEncoding") {
+        for code in 0..=255u8 {
+            if let Some(name) = glyph_name_from_u8(code, &PreDefinedEncoding::Standard).filter(|name| *name != ".notdef") {
+                encoding.insert(code as u32, name.to_string());
+            }
+        }
+        return encoding;
+    }
+    while let Some(token) = scanner.token() {
+        match token {
+            b"dup" => {
+                let code = scanner.integer();
+                let name = scanner.token().and_then(|name| name.strip_prefix(b"/"));
+                if let (Some(code @ 0..=255), Some(name)) = (code, name) {
+                    encoding.insert(code as u32, String::from_utf8_lossy(name).into_owned());
+                }
+            }
+            b"readonly" | b"def" => break,
+            _ => {}
+        }
+    }
+    encoding
+}
+
+/// Splits the clear text and the decrypted private section of Type 1 fonts into tokens.
+struct Scanner<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Scanner { data, position: 0 }
+    }
+
+    /// Moves past the next occurrence of a token, returning false if there is none.
+    fn find(&mut self, token: &str) -> bool {
+        while let Some(next) = self.token() {
+            if next == token.as_bytes() {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Returns the next token, names keeping their slash, and brackets and braces being tokens of their own.
+    fn token(&mut self) -> Option<&'a [u8]> {
+        let data = self.data;
+        while let Some(b) = data.get(self.position) {
+            match b {
+                b'%' => while data.get(self.position).is_some_and(|b| *b != b'\n' && *b != b'\r') {
+                    self.position += 1;
+                },
+                _ if b.is_ascii_whitespace() || *b == 0 => self.position += 1,
+                _ => break,
+            }
+        }
+        let start = self.position;
+        let first = *data.get(start)?;
+        self.position += 1;
+        if matches!(first, b'[' | b']' | b'{' | b'}') {
+            return Some(&data[start..self.position]);
+        }
+        while data.get(self.position).is_some_and(|b| !b.is_ascii_whitespace() && !b"[]{}/%".contains(b)) {
+            self.position += 1;
+        }
+        Some(&data[start..self.position])
+    }
+
+    fn integer(&mut self) -> Option<i64> {
+        std::str::from_utf8(self.token()?).ok()?.parse().ok()
+    }
+
+    /// Reads `length RD <binary>`, where the binary data follows the `RD` (or `-|`) token and a single space.
+    fn binary(&mut self) -> Option<&'a [u8]> {
+        let length = usize::try_from(self.integer()?).ok()?;
+        self.token()?;
+        let start = self.position + 1;
+        let data = self.data.get(start..start + length)?;
+        self.position = start + length;
+        Some(data)
+    }
+}
+
+/// Builds the outline of a Type 1 glyph.
+fn type1_outline(font: &OutlineFont, charstrings: &[Vec<u8>], subrs: &[Vec<u8>], gid: u32, depth: usize) -> Option<Path> {
+    let mut state = Type1State::default();
+    execute_type1(charstrings.get(gid as usize)?, subrs, &mut state, 0)?;
+    let sbx = state.sbx;
+    let mut path = state.builder.finish();
+    // seac places an accent relative to the base character, both of the standard encoding
+    if let Some([asb, adx, ady, base, accent]) = state.seac {
+        if depth >= MAX_COMPONENT_DEPTH {
+            return None;
+        }
+        let glyph = |code: f64| {
+            let name = glyph_name_from_u8(code as u8, &PreDefinedEncoding::Standard)?;
+            type1_outline(font, charstrings, subrs, font.lookup_name(name)?, depth + 1)
+        };
+        path = glyph(base)?;
+        if let Some(accent) = glyph(accent) {
+            path.append(&accent.transform(&Matrix::translate(adx + sbx - asb, ady)));
+        }
+    }
+    Some(path)
+}
+
+/// Executes a Type 1 charstring.
+///
+/// # Returns
+///
+/// True if the charstring ended with `endchar` or `seac`, False if it returned,
+/// or None if it is malformed
+fn execute_type1(code: &[u8], subrs: &[Vec<u8>], state: &mut Type1State, depth: usize) -> Option<bool> {
+    if depth > MAX_SUBR_DEPTH {
+        return None;
+    }
+    let mut i = 0;
+    while i < code.len() {
+        let b0 = code[i];
+        i += 1;
+        let stack = &mut state.stack;
+        let builder = &mut state.builder;
+        match b0 {
+            32..=246 => stack.push(b0 as f64 - 139.0),
+            247..=250 => {
+                stack.push((b0 as f64 - 247.0) * 256.0 + *code.get(i)? as f64 + 108.0);
+                i += 1;
+            }
+            251..=254 => {
+                stack.push(-(b0 as f64 - 251.0) * 256.0 - *code.get(i)? as f64 - 108.0);
+                i += 1;
+            }
+            255 => {
+                stack.push(read_u32(code, i)? as i32 as f64);
+                i += 4;
+            }
+            // hsbw: the side bearing is the first current point
+            13 => {
+                if let [sbx, _] = stack[..] {
+                    state.sbx = sbx;
+                    builder.x = sbx;
+                    builder.y = 0.0;
+                }
+                stack.clear();
+            }
+            // closepath
+            9 => {
+                builder.close();
+                stack.clear();
+            }
+            5 => {
+                if let [dx, dy] = stack[..] {
+                    builder.r_line_to(dx, dy);
+                }
+                stack.clear();
+            }
+            6 => {
+                if let [dx] = stack[..] {
+                    builder.r_line_to(dx, 0.0);
+                }
+                stack.clear();
+            }
+            7 => {
+                if let [dy] = stack[..] {
+                    builder.r_line_to(0.0, dy);
+                }
+                stack.clear();
+            }
+            8 => {
+                if let [a, b, c, d, e, f] = stack[..] {
+                    builder.r_curve_to(a, b, c, d, e, f);
+                }
+                stack.clear();
+            }
+            // vhcurveto
+            30 => {
+                if let [dy1, dx2, dy2, dx3] = stack[..] {
+                    builder.r_curve_to(0.0, dy1, dx2, dy2, dx3, 0.0);
+                }
+                stack.clear();
+            }
+            // hvcurveto
+            31 => {
+                if let [dx1, dx2, dy2, dy3] = stack[..] {
+                    builder.r_curve_to(dx1, 0.0, dx2, dy2, 0.0, dy3);
+                }
+                stack.clear();
+            }
+            // rmoveto, hmoveto, vmoveto, which only collect points inside flex hints
+            21 | 22 | 4 => {
+                let (dx, dy) = match (b0, &stack[..]) {
+                    (21, &[dx, dy]) => (dx, dy),
+                    (22, &[dx]) => (dx, 0.0),
+                    (4, &[dy]) => (0.0, dy),
+                    _ => (0.0, 0.0),
+                };
+                match &mut state.flex {
+                    Some(points) => {
+                        builder.x += dx;
+                        builder.y += dy;
+                        points.push((builder.x, builder.y));
+                    }
+                    None => builder.r_move_to(dx, dy),
+                }
+                stack.clear();
+            }
+            // callsubr
+            10 => {
+                let index = stack.pop()? as usize;
+                if execute_type1(subrs.get(index)?, subrs, state, depth + 1)? {
+                    return Some(true);
+                }
+            }
+            // return
+            11 => return Some(false),
+            // endchar
+            14 => return Some(true),
+            // hstem, vstem
+            1 | 3 => stack.clear(),
+            12 => {
+                let b1 = *code.get(i)?;
+                i += 1;
+                match b1 {
+                    // sbw
+                    7 => {
+                        if let [sbx, sby, _, _] = stack[..] {
+                            state.sbx = sbx;
+                            builder.x = sbx;
+                            builder.y = sby;
+                        }
+                        stack.clear();
+                    }
+                    // seac
+                    6 => {
+                        state.seac = <[f64; 5]>::try_from(&stack[..]).ok();
+                        return Some(true);
+                    }
+                    // div
+                    12 => {
+                        let (b, a) = (stack.pop()?, stack.pop()?);
+                        stack.push(if b != 0.0 { a / b } else { 0.0 });
+                    }
+                    // callothersubr
+                    16 => {
+                        let subr = stack.pop()? as i64;
+                        let count = (stack.pop()?.max(0.0) as usize).min(stack.len());
+                        let args = stack.split_off(stack.len() - count);
+                        match (subr, state.flex.take()) {
+                            // The end of a flex hint, drawing two curves through the collected points
+                            (0, Some(points)) => {
+                                if let [_, (x1, y1), (x2, y2), (x3, y3), (x4, y4), (x5, y5), (x6, y6), ..] = points[..] {
+                                    builder.curve_to(x1, y1, x2, y2, x3, y3);
+                                    builder.curve_to(x4, y4, x5, y5, x6, y6);
+                                }
+                                state.ps_stack = vec![builder.y, builder.x];
+                            }
+                            (1, _) => {
+                                state.flex = Some(Vec::new());
+                                state.ps_stack.clear();
+                            }
+                            (2, flex) => {
+                                state.flex = flex;
+                                state.ps_stack.clear();
+                            }
+                            // Hint replacement returns the subroutine number
+                            (3, flex) => {
+                                state.flex = flex;
+                                state.ps_stack = vec![3.0];
+                            }
+                            (_, flex) => {
+                                state.flex = flex;
+                                state.ps_stack = args.into_iter().collect();
+                            }
+                        }
+                    }
+                    // pop
+                    17 => stack.push(state.ps_stack.pop().unwrap_or(0.0)),
+                    // setcurrentpoint
+                    33 => {
+                        if let [x, y] = stack[..] {
+                            builder.x = x;
+                            builder.y = y;
+                        }
+                        stack.clear();
+                    }
+                    // dotsection, vstem3, hstem3
+                    _ => stack.clear(),
+                }
+            }
+            _ => stack.clear(),
+        }
+        if state.stack.len() > MAX_STACK_SIZE {
+            return None;
+        }
+    }
+    Some(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cmap_format12() {
+        let mut data = vec![0, 12, 0, 0, 0, 0, 0, 40, 0, 0, 0, 0, 0, 0, 0, 2];
+        // Codes 0x41 to 0x43 map to glyphs 5 to 7, the second group overflows
+        for (start, end, gid) in [(0x41u32, 0x43u32, 5u32), (0x61, 0x62, u32::MAX)] {
+            data.extend(start.to_be_bytes().into_iter().chain(end.to_be_bytes()).chain(gid.to_be_bytes()));
+        }
+        let map = read_cmap_subtable(&data).unwrap();
+        assert_eq!(map.len(), 3);
+        assert_eq!((map[&0x41], map[&0x43]), (5, 7));
+    }
+}
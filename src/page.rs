@@ -0,0 +1,383 @@
+use crate::action::{read_additional_actions, read_links, Action, Link};
+use crate::annotation::{read_annotations, Annotation};
+use crate::constants::{CONTENTS, CROP_BOX, MEDIA_BOX, RESOURCES, ROTATE};
+use crate::content::{ContentParser, Operation};
+use crate::document::PDFDocument;
+use crate::embedded::{read_file_attachments, EmbeddedFile};
+use crate::error::{PDFError, Result};
+use crate::graphics::Matrix;
+use crate::image::{Bitmap, ImageCollector, PageImage};
+use crate::interpreter::{ContentInterpreter, ContentVisitor};
+use crate::objects::{Dictionary, PDFObject};
+use crate::render::{Renderer, SkippedImage, MAX_RENDER_PIXELS};
+use crate::svg::SvgWriter;
+use crate::text::{TextExtractor, TextGlyph, TextLine, TextWord};
+
+/// The media box of pages which don't define one, US Letter.
+const US_LETTER: [f64; 4] = [0.0, 0.0, 612.0, 792.0];
+
+/// A page of a PDF document.
+///
+/// Inheritable attributes (`Resources`, `MediaBox`, `CropBox` and `Rotate`) are
+/// resolved from the page tree when the page is created, the other objects the
+/// page refers to are loaded from the document on demand.
+pub struct PDFPage<'a> {
+    document: &'a PDFDocument,
+    /// The index of the page in document order.
+    index: usize,
+    /// The object number and generation number of the page object.
+    obj_ref: (u32, u16),
+    /// The page attributes, including the inherited ones.
+    attrs: Dictionary,
+}
+
+impl<'a> PDFPage<'a> {
+    pub(crate) fn new(document: &'a PDFDocument, index: usize, obj_ref: (u32, u16), attrs: Dictionary) -> Self {
+        PDFPage { document, index, obj_ref, attrs }
+    }
+
+    /// Returns the document the page belongs to.
+    pub fn get_document(&self) -> &'a PDFDocument {
+        self.document
+    }
+
+    /// Returns the index of the page in document order, starting from 0.
+    pub fn get_index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the object number and generation number of the page object.
+    pub fn get_obj_ref(&self) -> (u32, u16) {
+        self.obj_ref
+    }
+
+    /// Returns the page attributes, including the inherited ones.
+    pub fn get_attrs(&self) -> &Dictionary {
+        &self.attrs
+    }
+
+    /// Gets the media box, the boundaries of the physical medium of the page.
+    ///
+    /// # Returns
+    ///
+    /// The rectangle as `[llx, lly, urx, ury]`, or None if the page doesn't define one
+    pub fn get_media_box(&self) -> Result<Option<[f64; 4]>> {
+        self.get_rectangle(MEDIA_BOX)
+    }
+
+    /// Gets the crop box, the region the page is displayed or printed in.
+    ///
+    /// # Returns
+    ///
+    /// The rectangle as `[llx, lly, urx, ury]`, defaulting to the media box
+    pub fn get_crop_box(&self) -> Result<Option<[f64; 4]>> {
+        match self.get_rectangle(CROP_BOX)? {
+            Some(rectangle) => Ok(Some(rectangle)),
+            None => self.get_media_box(),
+        }
+    }
+
+    /// Gets the clockwise rotation of the page when displayed.
+    ///
+    /// # Returns
+    ///
+    /// The rotation in degrees, normalized to 0, 90, 180 or 270
+    pub fn get_rotate(&self) -> Result<i64> {
+        let rotate = match self.attrs.get(ROTATE) {
+            Some(object) => self.document.resolve(object)?.as_i64().unwrap_or(0),
+            None => 0,
+        };
+        Ok(rotate.div_euclid(90).rem_euclid(4) * 90)
+    }
+
+    /// Gets the matrix mapping the default user space to page coordinates.
+    ///
+    /// Page coordinates are the coordinates of the page as displayed: the page
+    /// is rotated by `/Rotate`, and the origin is the lower-left corner of the
+    /// rotated crop box, with y pointing up.
+    ///
+    /// # Returns
+    ///
+    /// The matrix, assuming a US Letter page if the page has no media box
+    pub fn get_page_matrix(&self) -> Result<Matrix> {
+        let [x0, y0, x1, y1] = self.get_crop_box()?.unwrap_or(US_LETTER);
+        let matrix = match self.get_rotate()? {
+            90 => Matrix::new(0.0, -1.0, 1.0, 0.0, -y0, x1),
+            180 => Matrix::new(-1.0, 0.0, 0.0, -1.0, x1, y1),
+            270 => Matrix::new(0.0, 1.0, -1.0, 0.0, y1, -x0),
+            _ => Matrix::translate(-x0, -y0),
+        };
+        Ok(matrix)
+    }
+
+    /// Gets the resource dictionary of the page.
+    ///
+    /// # Returns
+    ///
+    /// The resources, or None if the page doesn't have any
+    pub fn get_resources(&self) -> Result<Option<Dictionary>> {
+        match self.attrs.get(RESOURCES) {
+            Some(object) => Ok(self.document.resolve(object)?.into_owned().to_dict()),
+            None => Ok(None),
+        }
+    }
+
+    /// Gets the decoded content of the page.
+    ///
+    /// When `/Contents` is an array, the streams are concatenated as one
+    /// stream. The divisions fall between lexical tokens, so an operator may
+    /// take its operands from the previous stream; the line break inserted
+    /// between the streams keeps adjacent tokens apart.
+    ///
+    /// # Returns
+    ///
+    /// The decoded content, empty if the page has no content
+    pub fn get_contents(&self) -> Result<Vec<u8>> {
+        let contents = match self.attrs.get(CONTENTS) {
+            Some(object) => self.document.resolve(object)?,
+            None => return Ok(Vec::new()),
+        };
+        let streams = match contents.as_ref() {
+            PDFObject::Array(streams) => streams.as_slice(),
+            object => std::slice::from_ref(object),
+        };
+        let mut data = Vec::new();
+        for stream in streams {
+            if let PDFObject::Stream(stream) = self.document.resolve(stream)?.as_ref() {
+                if !data.is_empty() {
+                    data.push(b'\n');
+                }
+                data.extend(stream.get_decoded_buf()?);
+            }
+        }
+        Ok(data)
+    }
+
+    /// Creates a parser over the content of the page.
+    pub fn get_content_parser(&self) -> Result<ContentParser> {
+        Ok(ContentParser::new(self.get_contents()?))
+    }
+
+    /// Parses the content of the page into operations.
+    ///
+    /// # Returns
+    ///
+    /// The operations in the order they appear, or the first error raised while parsing
+    pub fn get_operations(&self) -> Result<Vec<Operation>> {
+        self.get_content_parser()?.collect()
+    }
+
+    /// Executes the content of the page, reporting what it draws to `visitor`.
+    ///
+    /// Coordinates are reported in the default user space of the page.
+    ///
+    /// # Arguments
+    ///
+    /// * `visitor` - The visitor receiving paths, text, images and shadings
+    ///
+    /// # Returns
+    ///
+    /// An error if the content can't be interpreted or the visitor fails
+    pub fn interpret(&self, visitor: &mut impl ContentVisitor) -> Result<()> {
+        ContentInterpreter::new(self.document, Matrix::identity()).run_page(self, visitor)
+    }
+
+    /// Renders the page to an RGBA bitmap.
+    ///
+    /// The bitmap shows the crop box rotated by `/Rotate` on a white
+    /// background. Paths are anti-aliased, text is drawn with the embedded font
+    /// programs or built-in replacements, and images are resampled to the
+    /// resolution. Shadings, patterns, soft masks, blend modes and annotations
+    /// aren't drawn. Images which can't be decoded, such as JPX, JBIG2 and
+    /// CCITT images, are drawn as gray boxes; [`PDFPage::render_with_skipped`]
+    /// lists them.
+    ///
+    /// # Arguments
+    ///
+    /// * `dpi` - The resolution in pixels per inch, 72 giving one pixel per point
+    ///
+    /// # Returns
+    ///
+    /// The bitmap, or an error if the resolution is invalid or the bitmap would be too large
+    pub fn render(&self, dpi: f64) -> Result<Bitmap> {
+        Ok(self.render_with_skipped(dpi)?.0)
+    }
+
+    /// Renders the page to an RGBA bitmap like [`PDFPage::render`], also
+    /// returning the images which couldn't be decoded.
+    ///
+    /// # Arguments
+    ///
+    /// * `dpi` - The resolution in pixels per inch, 72 giving one pixel per point
+    ///
+    /// # Returns
+    ///
+    /// The bitmap and the skipped images, or an error if the resolution is
+    /// invalid or the bitmap would be too large
+    pub fn render_with_skipped(&self, dpi: f64) -> Result<(Bitmap, Vec<SkippedImage>)> {
+        if !dpi.is_finite() || dpi <= 0.0 {
+            return Err(PDFError::RenderError(format!("Invalid resolution {}", dpi)));
+        }
+        let scale = dpi / 72.0;
+        let (width, height) = self.get_display_size()?;
+        let (width, height) = ((width * scale).ceil(), (height * scale).ceil());
+        if width < 1.0 || height < 1.0 || width * height > MAX_RENDER_PIXELS as f64 {
+            return Err(PDFError::RenderError(format!("Invalid bitmap size {}x{}", width, height)));
+        }
+        // Page coordinates have y pointing up, pixel rows go down
+        let ctm = self.get_page_matrix()?.multiply(&Matrix::new(scale, 0.0, 0.0, -scale, 0.0, height));
+        let mut renderer = Renderer::new(self.document, width as u32, height as u32);
+        ContentInterpreter::new(self.document, ctm).run_page(self, &mut renderer)?;
+        Ok(renderer.finish())
+    }
+
+    /// Converts the page to an SVG document.
+    ///
+    /// The SVG user space is the crop box rotated by `/Rotate`, one unit per
+    /// point. Paths and clipping paths keep their vector shapes, images are
+    /// embedded as PNG or JPEG data URIs, and text is written as `<text>`
+    /// elements with the position of each glyph, or as glyph outlines when the
+    /// font doesn't map the glyphs to Unicode. Shadings, patterns, soft masks,
+    /// blend modes and annotations aren't written.
+    ///
+    /// # Returns
+    ///
+    /// The SVG document, or an error if the content can't be interpreted
+    pub fn to_svg(&self) -> Result<String> {
+        let (width, height) = self.get_display_size()?;
+        let ctm = self.get_page_matrix()?.multiply(&Matrix::new(1.0, 0.0, 0.0, -1.0, 0.0, height));
+        let mut writer = SvgWriter::new(self.document, width, height);
+        ContentInterpreter::new(self.document, ctm).run_page(self, &mut writer)?;
+        Ok(writer.finish())
+    }
+
+    /// Returns the width and height of the crop box rotated by `/Rotate`.
+    fn get_display_size(&self) -> Result<(f64, f64)> {
+        let [x0, y0, x1, y1] = self.get_crop_box()?.unwrap_or(US_LETTER);
+        let (width, height) = ((x1 - x0).abs(), (y1 - y0).abs());
+        match self.get_rotate()? {
+            90 | 270 => Ok((height, width)),
+            _ => Ok((width, height)),
+        }
+    }
+
+    /// Gets the annotations of the page, in the order of `/Annots`.
+    ///
+    /// # Returns
+    ///
+    /// The annotations, empty if the page has none
+    pub fn get_annotations(&self) -> Result<Vec<Annotation>> {
+        read_annotations(self.document, &self.attrs)
+    }
+
+    /// Gets the link annotations of the page with their targets.
+    ///
+    /// # Returns
+    ///
+    /// The links, in the order of `/Annots`
+    pub fn get_links(&self) -> Result<Vec<Link>> {
+        read_links(self.document, &self.attrs)
+    }
+
+    /// Gets the additional actions of the page, `/AA`: `O` performed when the
+    /// page is opened and `C` when it is closed.
+    ///
+    /// # Returns
+    ///
+    /// The actions keyed by their trigger, sorted by trigger
+    pub fn get_additional_actions(&self) -> Result<Vec<(String, Action)>> {
+        read_additional_actions(self.document, &self.attrs)
+    }
+
+    /// Gets the files attached to the page by file attachment annotations.
+    ///
+    /// # Returns
+    ///
+    /// The files, in the order of `/Annots`
+    pub fn get_file_attachments(&self) -> Result<Vec<EmbeddedFile>> {
+        read_file_attachments(self.document, &self.attrs)
+    }
+
+    /// Extracts the text of the page.
+    ///
+    /// Character codes are mapped to Unicode with the `/ToUnicode` CMaps and
+    /// encodings of the fonts. Spaces and line breaks are inserted from the glyph
+    /// positions, and the text is ordered for reading, column by column.
+    ///
+    /// # Returns
+    ///
+    /// The text, with lines separated by a line break and blocks of text by an empty line
+    pub fn extract_text(&self) -> Result<String> {
+        Ok(self.run_text_extractor()?.get_text())
+    }
+
+    /// Extracts the glyphs of the page with their position, in page coordinates.
+    ///
+    /// # Returns
+    ///
+    /// The glyphs with a Unicode mapping, in content order
+    pub fn extract_glyphs(&self) -> Result<Vec<TextGlyph>> {
+        Ok(self.run_text_extractor()?.into_glyphs())
+    }
+
+    /// Extracts the lines of text of the page, each split into words.
+    ///
+    /// # Returns
+    ///
+    /// The lines in reading order, positioned in page coordinates
+    pub fn extract_lines(&self) -> Result<Vec<TextLine>> {
+        Ok(self.run_text_extractor()?.get_lines())
+    }
+
+    /// Extracts the words of the page.
+    ///
+    /// # Returns
+    ///
+    /// The words in reading order, positioned in page coordinates
+    pub fn extract_words(&self) -> Result<Vec<TextWord>> {
+        let lines = self.run_text_extractor()?.get_lines();
+        Ok(lines.into_iter().flat_map(|line| line.into_words()).collect())
+    }
+
+    /// Returns the images painted on the page, image XObjects and inline images,
+    /// including those painted by Form XObjects.
+    ///
+    /// An image painted several times is returned once for each time.
+    ///
+    /// # Returns
+    ///
+    /// The images in the order they are painted, placed in the default user space of the page
+    pub fn get_images(&self) -> Result<Vec<PageImage<'a>>> {
+        let mut collector = ImageCollector::new(self.document);
+        self.interpret(&mut collector)?;
+        Ok(collector.into_images())
+    }
+
+    /// Collects the text of the page in page coordinates.
+    fn run_text_extractor(&self) -> Result<TextExtractor> {
+        let mut extractor = TextExtractor::new();
+        ContentInterpreter::new(self.document, self.get_page_matrix()?).run_page(self, &mut extractor)?;
+        Ok(extractor)
+    }
+
+    /// Reads a rectangle attribute, normalizing it so that the lower-left corner comes first.
+    fn get_rectangle(&self, key: &str) -> Result<Option<[f64; 4]>> {
+        let object = match self.attrs.get(key) {
+            Some(object) => self.document.resolve(object)?,
+            None => return Ok(None),
+        };
+        let values = match object.as_array() {
+            Some(values) if values.len() == 4 => values,
+            _ => return Ok(None),
+        };
+        let mut rectangle = [0f64; 4];
+        for (i, value) in values.iter().enumerate() {
+            match self.document.resolve(value)?.as_f64() {
+                Some(value) => rectangle[i] = value,
+                None => return Ok(None),
+            }
+        }
+        let [x0, y0, x1, y1] = rectangle;
+        Ok(Some([x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)]))
+    }
+}
@@ -1,215 +1,212 @@
 use crate::constants::pdf_key::{END_OBJ, END_STREAM, OBJ, R, STREAM};
 use crate::constants::*;
-use crate::error::{PDFError, Result};
+use crate::error::Result;
 use crate::objects::{Dictionary, PDFNumber, PDFObject, PDFStrKind, PDFString, Stream, XEntry};
-use crate::tokenizer::Token::{Delimiter, Id, Key, Number};
-use crate::tokenizer::{Token, Tokenizer};
+use crate::tokenizer::Token::{Delimiter, Eof, HexStr, Id, Key, LiteralStr, Name, Number};
+use crate::tokenizer::Tokenizer;
 use std::collections::HashMap;
 use crate::error::PDFError::{EOFError, PDFParseError, PDFParseError0};
-use crate::utils::hex2bytes;
+use crate::utils::xrefs_search;
 
-pub(crate) fn parse_with_offset(tokenizer: &mut Tokenizer, offset: u64) -> Result<PDFObject> {
+pub(crate) fn parse_with_offset(tokenizer: &mut Tokenizer, offset: u64, xrefs: &[XEntry]) -> Result<PDFObject> {
     tokenizer.seek(offset)?;
-    parse(tokenizer)
+    parse(tokenizer, xrefs)
 }
 
-pub(crate) fn parse(mut tokenizer: &mut Tokenizer) -> Result<PDFObject>
+/// Parses the next object from the tokenizer.
+///
+/// `xrefs` is used to resolve indirect references which must be known while
+/// parsing, such as a stream's `/Length`.
+pub(crate) fn parse(tokenizer: &mut Tokenizer, xrefs: &[XEntry]) -> Result<PDFObject>
 {
     let token = tokenizer.next_token()?;
-    let object = parser0(&mut tokenizer, token)?;
-    Ok(object)
-}
-
-fn parser0(tokenizer: &mut Tokenizer, token: Token) -> Result<PDFObject> {
     match token {
-        Delimiter(delimiter) => match delimiter.as_str() {
+        Delimiter(delimiter) => match delimiter {
             "<<" => {
-                let dict = parse_dict(tokenizer)?;
+                let dict = parse_dict(tokenizer, xrefs)?;
                 // If the next token is stream, then it is a stream
-                if tokenizer.check_next_token0(false, |token| token.key_was(STREAM))? {
-                    return parse_stream(tokenizer, dict);
+                if tokenizer.next_token_if(|token| token.key_was(STREAM))? {
+                    return parse_stream(tokenizer, dict, xrefs);
                 }
                 Ok(PDFObject::Dict(dict))
             }
-            "[" => parse_array(tokenizer),
-            "/" => parse_named(tokenizer),
-            "<" | "(" => parse_string(tokenizer, delimiter == "("),
+            "[" => parse_array(tokenizer, xrefs),
             _ => Err(PDFParseError0(format!("Delimiter '{}' not implemented", delimiter))),
         },
-        Key(key) => match key.as_str() {
+        Name(name) => Ok(PDFObject::Named(name.into_owned())),
+        LiteralStr(buf) => Ok(PDFObject::String(PDFString::new(PDFStrKind::Literal, buf.into_owned()))),
+        HexStr(buf) => Ok(PDFObject::String(PDFString::new(PDFStrKind::Hexadecimal, buf))),
+        Key(key) => match key {
             pdf_key::NULL => Ok(PDFObject::Null),
             pdf_key::TURE => Ok(PDFObject::Bool(true)),
             pdf_key::FALSE => Ok(PDFObject::Bool(false)),
-            pdf_key::TRAILER => {
-                let token = tokenizer.next_token()?;
-                parser0(tokenizer, token)
-            }
-            &_ => Err(PDFParseError0(format!("Key '{}' not implemented", key))),
+            pdf_key::TRAILER => parse(tokenizer, xrefs),
+            _ => Err(PDFParseError0(format!("Key '{}' not implemented", key))),
         }
         Number(number) => match number {
             PDFNumber::Unsigned(value) => {
-                let is_num = tokenizer.check_next_token(|token| token.is_u64())?;
-                if !is_num {
-                    return Ok(PDFObject::Number(number));
-                }
-                let is_obj = tokenizer.check_next_token(|token| token.key_was(R) || token.key_was(OBJ))?;
+                // `obj_num gen_num R` or `obj_num gen_num obj`
+                let is_obj = tokenizer.peek_token(0)?.is_u64() && {
+                    let token = tokenizer.peek_token(1)?;
+                    token.key_was(R) || token.key_was(OBJ)
+                };
                 if is_obj {
-                    return parse_obj(tokenizer, Some(value as u32));
+                    return parse_obj(tokenizer, value as u32, xrefs);
                 }
                 Ok(PDFObject::Number(number))
             }
             _ => Ok(PDFObject::Number(number))
         },
-        Token::Eof => Err(EOFError),
-        _ => Err(PDFParseError0(format!("Illegal token:{}", token.to_string())))
+        Eof => Err(EOFError),
+        token => Err(PDFParseError0(format!("Illegal token:{}", token.to_text())))
     }
 }
 
+/// Parses a cross-reference section made of one or more subsections.
 pub(crate) fn parse_text_xref(tokenizer: &mut Tokenizer) -> Result<Vec<XEntry>> {
-    let obj_num = tokenizer.next_token()?.as_u32()?;
-    let length = tokenizer.next_token()?.as_u32()?;
     let mut entries = Vec::<XEntry>::new();
-    for i in 0..length {
-        let value = tokenizer.next_token()?.as_u64()?;
-        let gen_num = tokenizer.next_token()?.as_u16()?;
-        let state = tokenizer.next_token()?.to_string();
-        let using = match state.as_str() {
-            "n" => true,
-            "f" => false,
-            _ => return Err(PDFParseError0(format!("Except a token with 'f' or 'n' but it is '{}'", state)))
-        };
-        let obj_num = obj_num + i;
-        let entry = XEntry::new(
-            obj_num,
-            gen_num,
-            value,
-            using,
-        );
-        entries.push(entry);
+    // Each subsection starts with its first object number and entry count
+    while tokenizer.peek_token(0)?.is_u64() {
+        let obj_num = tokenizer.next_token()?.as_u32()?;
+        let length = tokenizer.next_token()?.as_u32()?;
+        for i in 0..length {
+            let value = tokenizer.next_token()?.as_u64()?;
+            let gen_num = tokenizer.next_token()?.as_u16()?;
+            let using = match tokenizer.next_token()? {
+                Id("n") => true,
+                Id("f") => false,
+                token => return Err(PDFParseError0(format!("Except a token with 'f' or 'n' but it is '{}'", token.to_text())))
+            };
+            let obj_num = obj_num + i;
+            let entry = XEntry::new(
+                obj_num,
+                gen_num,
+                value,
+                using,
+            );
+            entries.push(entry);
+        }
     }
     Ok(entries)
 }
 
-fn parse_obj(tokenizer: &mut Tokenizer, option: Option<u32>) -> Result<PDFObject> {
-    let obj_num = match option {
-        Some(num) => num,
-        None => tokenizer.next_token()?.as_u32()?
-    };
-    let obj_gen_token = tokenizer.next_token()?.except(|token| token.is_u64())?;
-    let type_token = tokenizer.next_token()?.except(|token| token.key_was(R) || token.key_was(OBJ))?;
-    let gen_num = obj_gen_token.as_u16()?;
-    if let Key(ref key) = type_token {
-        let object = match key.as_str() {
-            OBJ => {
-                let token = tokenizer.next_token()?;
-                let value = parser0(tokenizer, token)?;
-                // Except a token with 'endobj'
-                tokenizer.next_token()?.except(|token| token.key_was(END_OBJ))?;
-                return Ok(PDFObject::IndirectObject(obj_num, gen_num, Box::new(value)));
-            }
-            _ => {
-                PDFObject::ObjectRef(obj_num, gen_num)
-            }
-        };
-        return Ok(object);
+fn parse_obj(tokenizer: &mut Tokenizer, obj_num: u32, xrefs: &[XEntry]) -> Result<PDFObject> {
+    let gen_num = tokenizer.next_token()?.as_u16()?;
+    let token = tokenizer.next_token()?;
+    if token.key_was(OBJ) {
+        let value = parse(tokenizer, xrefs)?;
+        // Except a token with 'endobj'
+        tokenizer.next_token()?.except(|token| token.key_was(END_OBJ))?;
+        return Ok(PDFObject::IndirectObject(obj_num, gen_num, Box::new(value)));
+    }
+    if token.key_was(R) {
+        return Ok(PDFObject::ObjectRef(obj_num, gen_num));
     }
     Err(PDFParseError("Except a token with R or obj"))
 }
-fn parse_dict(mut tokenizer: &mut Tokenizer) -> Result<Dictionary> {
+
+fn parse_dict(tokenizer: &mut Tokenizer, xrefs: &[XEntry]) -> Result<Dictionary> {
     let mut entries = HashMap::<String, PDFObject>::new();
     loop {
-        let token = tokenizer.next_token()?;
-        if let Delimiter(ref delimiter) = token {
-            if delimiter == ">>" {
-                break;
-            }
-        }
-        let object = parser0(&mut tokenizer, token)?;
-        if let PDFObject::Named(named) = object {
-            let token = tokenizer.next_token()?;
-            let value = parser0(&mut tokenizer, token)?;
-            entries.insert(named, value);
-        } else {
-            return Err(PDFParseError("Except a named token."));
-        }
+        let named = match tokenizer.next_token()? {
+            Delimiter(">>") => break,
+            Name(name) => name.into_owned(),
+            _ => return Err(PDFParseError("Except a named token.")),
+        };
+        let value = parse(tokenizer, xrefs)?;
+        entries.insert(named, value);
     }
     Ok(Dictionary::new(entries))
 }
 
-fn parse_named(tokenizer: &mut Tokenizer) -> Result<PDFObject> {
-    let token = tokenizer.next_token()?;
-    if let Id(name) = token {
-        return Ok(PDFObject::Named(name));
-    }
-    Err(PDFParseError("Except a identifier token."))
-}
-
-fn parse_array(tokenizer: &mut Tokenizer) -> Result<PDFObject> {
+fn parse_array(tokenizer: &mut Tokenizer, xrefs: &[XEntry]) -> Result<PDFObject> {
     let mut elements = Vec::<PDFObject>::new();
     loop {
-        let token = tokenizer.next_token()?;
-        if let Delimiter(ref delimiter) = token {
-            if delimiter == "]" {
-                return Ok(PDFObject::Array(elements));
-            }
+        if tokenizer.next_token_if(|token| token.delimiter_was("]"))? {
+            return Ok(PDFObject::Array(elements));
         }
-        let object = parser0(tokenizer, token)?;
+        let object = parse(tokenizer, xrefs)?;
         elements.push(object);
     }
 }
 
-fn parse_string(tokenizer: &mut Tokenizer, literal_str: bool) -> Result<PDFObject> {
-    let end_chr = if literal_str { ')' } else { '>' };
-    let mut is_escape = false;
-    let result = tokenizer.loop_util(&[], |chr| {
-        let m = if chr == '\\' {
-            is_escape = !is_escape;
-            false
-        } else {
-            let mut end = chr == end_chr;
-            if is_escape {
-                end = false;
-            }
-            is_escape = false;
-            end
-        };
-        Ok(m)
-    });
-    match result {
-        Ok(range) => {
-            let buf = tokenizer.drain_from_buf(range);
-            let buf = if literal_str {
-                buf
-            } else {
-                hex2bytes(&buf)
-            };
-            // Remove '>' or ')'
-            tokenizer.remove_buf_len(1);
-            let kind = if literal_str { PDFStrKind::Literal } else { PDFStrKind::Hexadecimal };
-            let value = PDFString::new(kind, buf);
-            Ok(PDFObject::String(value))
-        }
-        Err(_e) => Err(PDFParseError("String did not close properly")),
-    }
-}
-
 /// A stream has a `/Length` entry that specifies the number of bytes of data
 /// between the `stream` and `endstream` keywords. This length does not include
 /// the `stream` or `endstream` keywords themselves, nor the required
 /// end-of-line marker (CRLF or LF) immediately following `stream`.
-pub(crate) fn parse_stream(tokenizer: &mut Tokenizer, metadata: Dictionary) -> Result<PDFObject> {
-    if let Some(PDFObject::Number(PDFNumber::Unsigned(length))) = metadata.get(LENGTH) {
-        // Skip CRLF
-        tokenizer.skip_crlf()?;
-        let length = *length as usize;
+///
+/// `/Length` may be an indirect reference, which is resolved through `xrefs`.
+/// When the length is missing, can't be resolved or doesn't land on the
+/// `endstream` keyword, the data is recovered by scanning for `endstream`.
+pub(crate) fn parse_stream(tokenizer: &mut Tokenizer, metadata: Dictionary, xrefs: &[XEntry]) -> Result<PDFObject> {
+    tokenizer.skip_eol()?;
+    let start = tokenizer.position();
+    let length = stream_length(tokenizer, &metadata, xrefs);
+    // Resolving an indirect length moves the tokenizer elsewhere
+    if tokenizer.position() != start {
+        tokenizer.seek(start)?;
+    }
+    // A length past the end of the data is wrong, it isn't allocated
+    let remaining = tokenizer.size()?.saturating_sub(start);
+    if let Some(length) = length.filter(|length| *length as u64 <= remaining) {
         let buf = tokenizer.read_bytes(length)?;
-        if buf.len() != length {
-            return Err(PDFParseError0(format!("Require Stream length is {} but it is {}", length, buf.len())));
+        // A lexing error means the length is wrong as well
+        let end = matches!(tokenizer.next_token_if(|token| token.key_was(END_STREAM)), Ok(true));
+        if buf.len() == length && end {
+            let stream = Stream::new(metadata, buf);
+            return Ok(PDFObject::Stream(stream));
+        }
+        tokenizer.seek(start)?;
+    }
+    let buf = scan_stream_data(tokenizer)?;
+    // Except next token is `endstream`
+    tokenizer.next_token()?.except(|token| token.key_was(END_STREAM))?;
+    let stream = Stream::new(metadata, buf);
+    Ok(PDFObject::Stream(stream))
+}
+
+/// Returns the value of the stream's `/Length` entry.
+///
+/// An indirect length is resolved by reading only the `obj_num gen_num obj`
+/// header and the number following it, never a nested stream, so that a
+/// length referring to a stream can't recurse. `None` is returned when the
+/// length is absent or can't be resolved for any reason, which leaves the
+/// tokenizer somewhere else.
+fn stream_length(tokenizer: &mut Tokenizer, metadata: &Dictionary, xrefs: &[XEntry]) -> Option<usize> {
+    match metadata.get(LENGTH) {
+        Some(PDFObject::Number(PDFNumber::Unsigned(length))) => usize::try_from(*length).ok(),
+        Some(PDFObject::ObjectRef(obj_num, gen_num)) => {
+            let entry = xrefs_search(xrefs, (*obj_num, *gen_num)).ok().filter(|entry| entry.is_using())?;
+            read_indirect_length(tokenizer, entry.get_value(), (*obj_num, *gen_num)).ok().flatten()
         }
-        let stream = Stream::new(metadata, buf);
-        // Except next token is `endstream`
-        tokenizer.next_token()?.except(|token| token.key_was(END_STREAM))?;
-        return Ok(PDFObject::Stream(stream));
+        _ => None,
+    }
+}
+
+/// Reads an indirect object at `offset` which must be an unsigned number.
+fn read_indirect_length(tokenizer: &mut Tokenizer, offset: u64, obj_ref: (u32, u16)) -> Result<Option<usize>> {
+    tokenizer.seek(offset)?;
+    if tokenizer.next_token()?.as_u32()? != obj_ref.0 || tokenizer.next_token()?.as_u16()? != obj_ref.1 {
+        return Ok(None);
+    }
+    if !tokenizer.next_token()?.key_was(OBJ) {
+        return Ok(None);
     }
-    Err(PDFParseError("Stream length is not found"))
-}
\ No newline at end of file
+    match tokenizer.next_token()? {
+        Number(PDFNumber::Unsigned(length)) => Ok(usize::try_from(length).ok()),
+        _ => Ok(None),
+    }
+}
+
+/// Reads stream data up to the `endstream` keyword.
+///
+/// The end-of-line marker preceding `endstream` is not part of the data.
+fn scan_stream_data(tokenizer: &mut Tokenizer) -> Result<Vec<u8>> {
+    let mut buf = tokenizer.read_until(END_STREAM.as_bytes())?;
+    if buf.ends_with(b"\r\n") {
+        buf.truncate(buf.len() - 2);
+    } else if buf.ends_with(b"\n") || buf.ends_with(b"\r") {
+        buf.truncate(buf.len() - 1);
+    }
+    Ok(buf)
+}
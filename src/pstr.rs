@@ -1,5 +1,5 @@
 use crate::encoding::{PreDefinedEncoding, mapper_chr_from_u8};
-use crate::objects::PDFString;
+use crate::objects::{PDFStrKind, PDFString};
 
 #[macro_export] macro_rules! convert_glyph_from_dict {
     ($dict:ident,$key:ident,$encoding:expr) => {
@@ -14,10 +14,40 @@ pub(crate) fn convert_glyph_text(str: &PDFString, encoding: &PreDefinedEncoding)
     let buf = str.get_buf();
     let mut chr_buf = Vec::<char>::new();
     for b in buf {
-        let t = mapper_chr_from_u8(*b - 1, encoding);
+        // The tables start at code 1, NUL maps to nothing
+        let t = mapper_chr_from_u8(b.wrapping_sub(1), encoding);
         if let Some(chr) = t {
             chr_buf.push(chr);
         }
     }
     chr_buf.iter().collect()
 }
+
+/// Decodes a text string, UTF-16BE or UTF-8 when it starts with a byte order
+/// mark and PDFDocEncoding otherwise.
+///
+/// The escape sequences marking the language of UTF-16BE text are removed.
+pub(crate) fn decode_text_string(str: &PDFString) -> String {
+    let buf = str.get_buf();
+    if let Some(data) = buf.strip_prefix(b"\xfe\xff") {
+        let units = data.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]]));
+        let text = char::decode_utf16(units).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)).collect::<String>();
+        // A language code is enclosed in two ESC characters
+        return text.split('\u{1b}').step_by(2).collect();
+    }
+    if let Some(data) = buf.strip_prefix(b"\xef\xbb\xbf") {
+        return String::from_utf8_lossy(data).into_owned();
+    }
+    convert_glyph_text(str, &PreDefinedEncoding::PDFDoc)
+}
+
+/// Encodes a text string, as is when it is printable ASCII and in UTF-16BE
+/// with a byte order mark otherwise.
+pub(crate) fn encode_text_string(text: &str) -> PDFString {
+    if text.bytes().all(|b| (0x20..0x7f).contains(&b) || b == b'\n' || b == b'\r' || b == b'\t') {
+        return PDFString::new(PDFStrKind::Literal, text.as_bytes().to_vec());
+    }
+    let mut buf = vec![0xfe, 0xff];
+    buf.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+    PDFString::new(PDFStrKind::Hexadecimal, buf)
+}
@@ -0,0 +1,485 @@
+use crate::graphics::{FillRule, LineCap, LineJoin, Matrix, Path, PathSegment};
+use std::f64::consts::PI;
+
+/// The number of sample rows per pixel row, the vertical anti-aliasing levels.
+const SUBSAMPLES: usize = 8;
+/// The maximum distance between a curve and its flattened polyline, in device pixels.
+const TOLERANCE: f64 = 0.2;
+/// The maximum number of lines a curve is flattened into.
+const MAX_CURVE_LINES: usize = 256;
+/// The maximum number of dashes and gaps of a path, denser dash patterns are stroked solid.
+const MAX_DASHES: f64 = 100_000.0;
+
+/// A polyline in device or user space, with whether it is closed.
+type Polyline = (Vec<(f64, f64)>, bool);
+
+/// The coverage of a rectangle of device pixels by a shape, between 0 and 1.
+#[derive(Clone, Debug)]
+pub(crate) struct Mask {
+    /// The left and top pixel of the rectangle.
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    /// The coverage of the pixels of the rectangle, row by row.
+    coverage: Vec<f32>,
+}
+
+/// The parameters of a stroke, in user space.
+pub(crate) struct StrokeStyle<'a> {
+    pub(crate) width: f64,
+    pub(crate) cap: LineCap,
+    pub(crate) join: LineJoin,
+    pub(crate) miter_limit: f64,
+    pub(crate) dash: &'a [f64],
+    pub(crate) phase: f64,
+}
+
+/// A line of a flattened path, oriented downwards.
+struct Edge {
+    y0: f64,
+    y1: f64,
+    /// The x coordinate at `y0`.
+    x0: f64,
+    /// The change of x for each unit of y.
+    slope: f64,
+    /// 1 if the line goes down, -1 if it goes up.
+    winding: i32,
+}
+
+impl Mask {
+    /// Creates a mask covering no pixel.
+    pub(crate) fn empty() -> Self {
+        Mask { x: 0, y: 0, width: 0, height: 0, coverage: Vec::new() }
+    }
+
+    /// Returns the rectangle `[x0, y0, x1, y1]` of pixels the mask may cover.
+    pub(crate) fn get_bounds(&self) -> [usize; 4] {
+        [self.x, self.y, self.x + self.width, self.y + self.height]
+    }
+
+    /// Returns the coverage of a pixel, 0 outside the rectangle of the mask.
+    pub(crate) fn get(&self, x: usize, y: usize) -> f32 {
+        match (x.checked_sub(self.x), y.checked_sub(self.y)) {
+            (Some(dx), Some(dy)) if dx < self.width && dy < self.height => self.coverage[dy * self.width + dx],
+            _ => 0.0,
+        }
+    }
+
+    /// Returns the intersection of two masks.
+    pub(crate) fn intersect(&self, other: &Mask) -> Mask {
+        let [x0, y0, x1, y1] = self.get_bounds();
+        let [ox0, oy0, ox1, oy1] = other.get_bounds();
+        let (x0, y0, x1, y1) = (x0.max(ox0), y0.max(oy0), x1.min(ox1), y1.min(oy1));
+        if x0 >= x1 || y0 >= y1 {
+            return Mask::empty();
+        }
+        let mut coverage = Vec::with_capacity((x1 - x0) * (y1 - y0));
+        for y in y0..y1 {
+            coverage.extend((x0..x1).map(|x| self.get(x, y) * other.get(x, y)));
+        }
+        Mask { x: x0, y: y0, width: x1 - x0, height: y1 - y0, coverage }
+    }
+}
+
+/// Computes the pixel coverage of a filled path.
+///
+/// Each pixel row is sampled by [`SUBSAMPLES`] rows, along which the spans
+/// inside the path are covered exactly, so that edges are anti-aliased both
+/// horizontally and vertically.
+///
+/// # Arguments
+///
+/// * `path` - The path in device space, its subpaths being implicitly closed
+/// * `rule` - The rule deciding which points are inside the path
+/// * `width` - The width of the device in pixels, the mask being clipped to the device
+/// * `height` - The height of the device in pixels
+pub(crate) fn fill(path: &Path, rule: FillRule, width: usize, height: usize) -> Mask {
+    let mut edges = Vec::new();
+    for (points, _) in flatten(path, TOLERANCE) {
+        let closing = (points[points.len() - 1], points[0]);
+        for ((x0, y0), (x1, y1)) in points.windows(2).map(|pair| (pair[0], pair[1])).chain(std::iter::once(closing)) {
+            if y0 == y1 || !(x0.is_finite() && y0.is_finite() && x1.is_finite() && y1.is_finite()) {
+                continue;
+            }
+            let edge = match y0 < y1 {
+                true => Edge { y0, y1, x0, slope: (x1 - x0) / (y1 - y0), winding: 1 },
+                false => Edge { y0: y1, y1: y0, x0: x1, slope: (x0 - x1) / (y0 - y1), winding: -1 },
+            };
+            edges.push(edge);
+        }
+    }
+    let min = |a: f64, b: f64| a.min(b);
+    let max = |a: f64, b: f64| a.max(b);
+    let bounds = edges.iter().fold(None, |bounds: Option<[f64; 4]>, edge| {
+        let x1 = edge.x0 + edge.slope * (edge.y1 - edge.y0);
+        let [bx0, by0, bx1, by1] = bounds.unwrap_or([f64::MAX, f64::MAX, f64::MIN, f64::MIN]);
+        Some([min(bx0, min(edge.x0, x1)), min(by0, edge.y0), max(bx1, max(edge.x0, x1)), max(by1, edge.y1)])
+    });
+    let Some([bx0, by0, bx1, by1]) = bounds else {
+        return Mask::empty();
+    };
+    let x0 = bx0.floor().clamp(0.0, width as f64) as usize;
+    let x1 = bx1.ceil().clamp(0.0, width as f64) as usize;
+    let y0 = by0.floor().clamp(0.0, height as f64) as usize;
+    let y1 = by1.ceil().clamp(0.0, height as f64) as usize;
+    if x0 >= x1 || y0 >= y1 {
+        return Mask::empty();
+    }
+    let mask_width = x1 - x0;
+    edges.sort_by(|a, b| a.y0.total_cmp(&b.y0));
+    let mut coverage = vec![0f32; mask_width * (y1 - y0)];
+    // The coverage of partially covered pixels, and the changes of the coverage of fully covered runs
+    let mut partial = vec![0f32; mask_width + 1];
+    let mut runs = vec![0f32; mask_width + 1];
+    let mut active: Vec<usize> = Vec::new();
+    let mut next = 0;
+    let mut crossings: Vec<(f64, i32)> = Vec::new();
+    let weight = 1.0 / SUBSAMPLES as f32;
+    for y in y0..y1 {
+        partial.fill(0.0);
+        runs.fill(0.0);
+        for sample in 0..SUBSAMPLES {
+            let sy = y as f64 + (sample as f64 + 0.5) / SUBSAMPLES as f64;
+            while next < edges.len() && edges[next].y0 <= sy {
+                active.push(next);
+                next += 1;
+            }
+            active.retain(|index| edges[*index].y1 > sy);
+            crossings.clear();
+            crossings.extend(active.iter()
+                .map(|index| &edges[*index])
+                .filter(|edge| edge.y0 <= sy)
+                .map(|edge| (edge.x0 + (sy - edge.y0) * edge.slope - x0 as f64, edge.winding)));
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let mut winding = 0;
+            let mut start = 0.0;
+            for (x, direction) in &crossings {
+                let was_inside = inside(winding, rule);
+                winding += direction;
+                match (was_inside, inside(winding, rule)) {
+                    (false, true) => start = *x,
+                    (true, false) => add_span(&mut partial, &mut runs, start, *x, weight),
+                    _ => {}
+                }
+            }
+        }
+        let row = &mut coverage[(y - y0) * mask_width..(y - y0 + 1) * mask_width];
+        let mut run = 0.0;
+        for (x, value) in row.iter_mut().enumerate() {
+            run += runs[x];
+            *value = (partial[x] + run).min(1.0);
+        }
+    }
+    Mask { x: x0, y: y0, width: mask_width, height: y1 - y0, coverage }
+}
+
+fn inside(winding: i32, rule: FillRule) -> bool {
+    match rule {
+        FillRule::NonZero => winding != 0,
+        FillRule::EvenOdd => winding % 2 != 0,
+    }
+}
+
+/// Adds the coverage of a span of a sample row, relative to the left of the mask.
+fn add_span(partial: &mut [f32], runs: &mut [f32], start: f64, end: f64, weight: f32) {
+    let width = (partial.len() - 1) as f64;
+    let (start, end) = (start.clamp(0.0, width), end.clamp(0.0, width));
+    if end <= start {
+        return;
+    }
+    let (first, last) = (start.floor() as usize, end.floor() as usize);
+    if first == last {
+        partial[first] += (end - start) as f32 * weight;
+        return;
+    }
+    partial[first] += (first as f64 + 1.0 - start) as f32 * weight;
+    runs[first + 1] += weight;
+    runs[last] -= weight;
+    partial[last] += (end - last as f64) as f32 * weight;
+}
+
+/// Flattens the curves of a path into polylines.
+///
+/// Subpaths without any segment after their `MoveTo` are dropped, since they
+/// are neither filled nor stroked.
+///
+/// # Arguments
+///
+/// * `path` - The path
+/// * `tolerance` - The maximum distance between the curves and the polylines
+fn flatten(path: &Path, tolerance: f64) -> Vec<Polyline> {
+    let mut polylines: Vec<Polyline> = Vec::new();
+    let mut current: Vec<(f64, f64)> = Vec::new();
+    let mut start = (0.0, 0.0);
+    let mut finish = |current: &mut Vec<(f64, f64)>, closed: bool| {
+        if current.len() > 1 {
+            polylines.push((std::mem::take(current), closed));
+        }
+        current.clear();
+    };
+    for segment in path.get_segments() {
+        match *segment {
+            PathSegment::MoveTo(x, y) => {
+                finish(&mut current, false);
+                start = (x, y);
+                current.push(start);
+            }
+            PathSegment::LineTo(x, y) => {
+                if current.is_empty() {
+                    current.push(start);
+                }
+                current.push((x, y));
+            }
+            PathSegment::CurveTo(x1, y1, x2, y2, x3, y3) => {
+                if current.is_empty() {
+                    current.push(start);
+                }
+                let (x0, y0) = current[current.len() - 1];
+                // The second differences bound the distance between the curve and its chords
+                let dd = ((x0 - 2.0 * x1 + x2).hypot(y0 - 2.0 * y1 + y2)).max((x1 - 2.0 * x2 + x3).hypot(y1 - 2.0 * y2 + y3));
+                let lines = ((0.75 * dd / tolerance).sqrt().ceil() as usize).clamp(1, MAX_CURVE_LINES);
+                for i in 1..=lines {
+                    let t = i as f64 / lines as f64;
+                    let u = 1.0 - t;
+                    let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+                    current.push((a * x0 + b * x1 + c * x2 + d * x3, a * y0 + b * y1 + c * y2 + d * y3));
+                }
+            }
+            PathSegment::Close => {
+                finish(&mut current, true);
+                current.push(start);
+            }
+        }
+    }
+    finish(&mut current, false);
+    polylines
+}
+
+/// Computes the outline of a stroked path.
+///
+/// The path is stroked in user space, where the line width and the dash
+/// pattern are defined, and the outline is transformed back to device space.
+/// Lines thinner than a device pixel are widened to one pixel.
+///
+/// # Arguments
+///
+/// * `path` - The path in device space
+/// * `ctm` - The matrix mapping user space to device space
+/// * `style` - The line width, caps, joins and dash pattern
+///
+/// # Returns
+///
+/// The outline in device space, to be filled with the nonzero winding rule
+pub(crate) fn stroke(path: &Path, ctm: &Matrix, style: &StrokeStyle) -> Path {
+    let mut outline = Path::default();
+    let Some(inverse) = ctm.invert() else {
+        return outline;
+    };
+    let scale = ctm.get_scale();
+    let half_width = style.width.max(1.0 / scale) / 2.0;
+    let tolerance = TOLERANCE / scale;
+    // Degenerate subpaths such as `x y m x y l` become single points, drawn as dots by round and square caps
+    let mut polylines = flatten(&path.transform(&inverse), tolerance);
+    let dash_total: f64 = style.dash.iter().sum();
+    // Dash patterns too dense to draw each dash are stroked solid
+    let length: f64 = polylines.iter().map(|(points, closed)| polyline_length(points, *closed)).sum();
+    let dashes = length / dash_total * style.dash.len() as f64;
+    if !style.dash.is_empty() && dash_total > 0.0 && style.dash.iter().all(|length| *length >= 0.0) && dashes <= MAX_DASHES {
+        polylines = polylines.into_iter()
+            .flat_map(|(points, closed)| {
+                let mut points = points;
+                if closed {
+                    points.push(points[0]);
+                }
+                dash_polyline(&points, style.dash, style.phase)
+            })
+            .collect();
+    }
+    for (points, closed) in polylines {
+        let mut points = points;
+        points.dedup();
+        if closed && points.len() > 1 && points[0] == points[points.len() - 1] {
+            points.pop();
+        }
+        stroke_polyline(&mut outline, &points, closed, half_width, tolerance, style);
+    }
+    outline.transform(ctm)
+}
+
+/// Computes the length of a polyline, with its closing line if it is closed.
+fn polyline_length(points: &[(f64, f64)], closed: bool) -> f64 {
+    let length = |(x0, y0): (f64, f64), (x1, y1): (f64, f64)| (x1 - x0).hypot(y1 - y0);
+    let open: f64 = points.windows(2).map(|pair| length(pair[0], pair[1])).sum();
+    match (closed, points.first(), points.last()) {
+        (true, Some(first), Some(last)) => open + length(*last, *first),
+        _ => open,
+    }
+}
+
+/// Splits a polyline into the polylines of its dashes.
+fn dash_polyline(points: &[(f64, f64)], dash: &[f64], phase: f64) -> Vec<Polyline> {
+    let mut dashes = Vec::new();
+    // Odd dash arrays repeat twice so that dashes and gaps alternate
+    let period = dash.iter().sum::<f64>() * if dash.len() % 2 == 1 { 2.0 } else { 1.0 };
+    let mut index = 0;
+    let mut on = true;
+    let mut remaining = dash[0];
+    let mut skip = phase.rem_euclid(period);
+    while skip > 0.0 {
+        if skip < remaining {
+            remaining -= skip;
+            break;
+        }
+        skip -= remaining;
+        index = (index + 1) % dash.len();
+        remaining = dash[index];
+        on = !on;
+    }
+    let mut current: Vec<(f64, f64)> = Vec::new();
+    for pair in points.windows(2) {
+        let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+        let length = (x1 - x0).hypot(y1 - y0);
+        let mut position = 0.0;
+        let point = |position: f64| match length {
+            0.0 => (x0, y0),
+            _ => (x0 + (x1 - x0) * position / length, y0 + (y1 - y0) * position / length),
+        };
+        loop {
+            if on && current.is_empty() {
+                current.push(point(position));
+            }
+            let step = remaining.min(length - position);
+            position += step;
+            remaining -= step;
+            if on {
+                current.push(point(position));
+            }
+            if remaining > 1e-9 {
+                break;
+            }
+            if on {
+                dashes.push((std::mem::take(&mut current), false));
+            }
+            index = (index + 1) % dash.len();
+            remaining = dash[index];
+            on = !on;
+        }
+    }
+    if current.len() > 1 {
+        dashes.push((current, false));
+    }
+    dashes
+}
+
+/// Adds the outline of a stroked polyline as positively oriented polygons:
+/// a quadrilateral for each line, the joins and the caps.
+fn stroke_polyline(outline: &mut Path, points: &[(f64, f64)], closed: bool, half_width: f64, tolerance: f64, style: &StrokeStyle) {
+    if points.len() == 1 {
+        let (x, y) = points[0];
+        match style.cap {
+            LineCap::Round => push_circle(outline, x, y, half_width, tolerance),
+            LineCap::ProjectingSquare => push_polygon(outline, &[
+                (x - half_width, y - half_width), (x + half_width, y - half_width),
+                (x + half_width, y + half_width), (x - half_width, y + half_width),
+            ]),
+            LineCap::Butt => {}
+        }
+        return;
+    }
+    let count = points.len();
+    let lines = if closed { count } else { count - 1 };
+    for i in 0..lines {
+        let (p0, p1) = (points[i], points[(i + 1) % count]);
+        let (nx, ny) = normal(p0, p1, half_width);
+        push_polygon(outline, &[(p0.0 + nx, p0.1 + ny), (p1.0 + nx, p1.1 + ny), (p1.0 - nx, p1.1 - ny), (p0.0 - nx, p0.1 - ny)]);
+    }
+    let joins = if closed { 0..count } else { 1..count - 1 };
+    for i in joins {
+        let previous = points[(i + count - 1) % count];
+        push_join(outline, previous, points[i], points[(i + 1) % count], half_width, tolerance, style);
+    }
+    if !closed {
+        push_cap(outline, points[1], points[0], half_width, tolerance, style.cap);
+        push_cap(outline, points[count - 2], points[count - 1], half_width, tolerance, style.cap);
+    }
+}
+
+/// Returns the normal of a line, to its left, with the length of half the line width.
+fn normal((x0, y0): (f64, f64), (x1, y1): (f64, f64), half_width: f64) -> (f64, f64) {
+    let length = (x1 - x0).hypot(y1 - y0);
+    (-(y1 - y0) / length * half_width, (x1 - x0) / length * half_width)
+}
+
+fn push_join(outline: &mut Path, p0: (f64, f64), p1: (f64, f64), p2: (f64, f64), half_width: f64, tolerance: f64, style: &StrokeStyle) {
+    let (n0, n1) = (normal(p0, p1, half_width), normal(p1, p2, half_width));
+    let (d0, d1) = ((p1.0 - p0.0, p1.1 - p0.1), (p2.0 - p1.0, p2.1 - p1.1));
+    let cross = d0.0 * d1.1 - d0.1 * d1.0;
+    let cos = (n0.0 * n1.0 + n0.1 * n1.1) / (half_width * half_width);
+    if cross.abs() < 1e-12 && cos > 0.0 {
+        return;
+    }
+    // The outer side of the corner is to the right of a left turn
+    let side = if cross > 0.0 { -1.0 } else { 1.0 };
+    let a = (p1.0 + side * n0.0, p1.1 + side * n0.1);
+    let b = (p1.0 + side * n1.0, p1.1 + side * n1.1);
+    match style.join {
+        LineJoin::Round => push_circle(outline, p1.0, p1.1, half_width, tolerance),
+        // The miter length relative to the line width is 1 / sin(angle / 2)
+        LineJoin::Miter if cos > -1.0 && 1.0 / ((1.0 + cos) / 2.0).sqrt() <= style.miter_limit => {
+            let tip = (p1.0 + side * (n0.0 + n1.0) / (1.0 + cos), p1.1 + side * (n0.1 + n1.1) / (1.0 + cos));
+            push_polygon(outline, &[p1, a, tip, b]);
+        }
+        _ => push_polygon(outline, &[p1, a, b]),
+    }
+}
+
+/// Adds the cap at the end `p1` of a line coming from `p0`.
+fn push_cap(outline: &mut Path, p0: (f64, f64), p1: (f64, f64), half_width: f64, tolerance: f64, cap: LineCap) {
+    match cap {
+        LineCap::Butt => {}
+        LineCap::Round => push_circle(outline, p1.0, p1.1, half_width, tolerance),
+        LineCap::ProjectingSquare => {
+            let (nx, ny) = normal(p0, p1, half_width);
+            // The direction of the line, with the length of half the line width
+            let (dx, dy) = (ny, -nx);
+            push_polygon(outline, &[
+                (p1.0 + nx, p1.1 + ny), (p1.0 + nx + dx, p1.1 + ny + dy),
+                (p1.0 - nx + dx, p1.1 - ny + dy), (p1.0 - nx, p1.1 - ny),
+            ]);
+        }
+    }
+}
+
+fn push_circle(outline: &mut Path, x: f64, y: f64, radius: f64, tolerance: f64) {
+    let steps = match radius > tolerance {
+        true => (PI / (1.0 - tolerance / radius).acos()).ceil().clamp(8.0, 256.0) as usize,
+        false => 8,
+    };
+    let points = (0..steps)
+        .map(|i| {
+            let angle = 2.0 * PI * i as f64 / steps as f64;
+            (x + radius * angle.cos(), y + radius * angle.sin())
+        })
+        .collect::<Vec<_>>();
+    push_polygon(outline, &points);
+}
+
+/// Adds a polygon, reversed if needed so that all polygons of an outline turn the same way.
+fn push_polygon(outline: &mut Path, points: &[(f64, f64)]) {
+    let area: f64 = (0..points.len())
+        .map(|i| {
+            let ((x0, y0), (x1, y1)) = (points[i], points[(i + 1) % points.len()]);
+            x0 * y1 - x1 * y0
+        })
+        .sum();
+    let mut ordered = points.to_vec();
+    if area < 0.0 {
+        ordered.reverse();
+    }
+    outline.push(PathSegment::MoveTo(ordered[0].0, ordered[0].1));
+    for (x, y) in &ordered[1..] {
+        outline.push(PathSegment::LineTo(*x, *y));
+    }
+    outline.push(PathSegment::Close);
+}
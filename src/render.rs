@@ -0,0 +1,364 @@
+use crate::colorspace::ColorSpace;
+use crate::document::PDFDocument;
+use crate::error::Result;
+use crate::graphics::{ClipPath, Color, FillRule, GraphicsState, LineCap, LineJoin, Matrix, Path};
+use crate::image::{Bitmap, PageImage};
+use crate::interpreter::{ContentVisitor, Glyph, Image};
+use crate::outline::GlyphCache;
+use crate::raster::{fill, stroke, Mask, StrokeStyle};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// The largest bitmap a page is rendered to, in pixels.
+pub(crate) const MAX_RENDER_PIXELS: u64 = 1 << 27;
+
+/// The width of the stroke emboldening built-in glyphs replacing bold glyphs, relative to the font size.
+const BOLD_STROKE_WIDTH: f64 = 0.03;
+/// The maximum number of samples along each axis averaged into a pixel of a downscaled image.
+const MAX_IMAGE_SAMPLES: usize = 4;
+/// The colour of the box drawn in place of an image which can't be decoded.
+const PLACEHOLDER_COLOR: [u8; 4] = [192, 192, 192, 255];
+
+/// An image decoded to pixels, with whether it is a stencil mask painted with the fill colour.
+type DecodedImage = (Bitmap, bool);
+
+/// Draws what a content stream paints into a bitmap.
+///
+/// The device space of the interpreter must be the pixel grid of the bitmap,
+/// with y pointing down. Paths are filled and stroked with anti-aliasing and
+/// clipped by the clipping region, images are resampled, and text is drawn
+/// from the glyph outlines of embedded TrueType, OpenType, CFF and Type 1
+/// fonts. Text in other fonts is drawn with built-in fonts, scaled to the
+/// widths of the original glyphs.
+///
+/// Shadings, patterns, soft masks and blend modes aren't supported. Images
+/// which can't be decoded, such as `JPXDecode`, `JBIG2Decode` and
+/// `CCITTFaxDecode` images, are drawn as gray boxes and listed by
+/// [`Renderer::get_skipped_images`].
+pub struct Renderer<'a> {
+    document: &'a PDFDocument,
+    bitmap: Bitmap,
+    /// The clipping paths of the last painting operation and the coverage of
+    /// their intersection, None when nothing is clipped.
+    clip: (Vec<ClipPath>, Option<Mask>),
+    glyphs: GlyphCache<'a>,
+    /// Image XObjects decoded by reference, placeholders for images which can't be decoded.
+    images: HashMap<(u32, u16), Rc<DecodedImage>>,
+    skipped: Vec<SkippedImage>,
+}
+
+/// An image the renderer couldn't decode, drawn as a gray box instead.
+#[derive(Clone, Debug)]
+pub struct SkippedImage {
+    /// The name of the XObject resource, None for inline images.
+    name: Option<String>,
+    obj_ref: Option<(u32, u16)>,
+    /// Why the image couldn't be decoded.
+    reason: String,
+}
+
+impl SkippedImage {
+    /// Returns the name of the image XObject resource, None for inline images.
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the image XObject reference, None for inline images and direct objects.
+    pub fn get_obj_ref(&self) -> Option<(u32, u16)> {
+        self.obj_ref
+    }
+
+    /// Returns why the image couldn't be decoded, such as an unsupported filter.
+    pub fn get_reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+impl<'a> Renderer<'a> {
+    /// Creates a renderer drawing into a white bitmap.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - The document the content belongs to
+    /// * `width` - The width of the bitmap in pixels
+    /// * `height` - The height of the bitmap in pixels
+    pub fn new(document: &'a PDFDocument, width: u32, height: u32) -> Self {
+        let mut bitmap = Bitmap::new(width, height);
+        bitmap.get_pixels_mut().fill(255);
+        Renderer {
+            document,
+            bitmap,
+            clip: (Vec::new(), None),
+            glyphs: GlyphCache::new(document),
+            images: HashMap::new(),
+            skipped: Vec::new(),
+        }
+    }
+
+    /// Returns the images which couldn't be decoded, once for each image
+    /// XObject, in the order they are painted.
+    pub fn get_skipped_images(&self) -> &[SkippedImage] {
+        &self.skipped
+    }
+
+    /// Returns the bitmap drawn so far.
+    pub fn get_bitmap(&self) -> &Bitmap {
+        &self.bitmap
+    }
+
+    /// Returns the bitmap.
+    pub fn into_bitmap(self) -> Bitmap {
+        self.bitmap
+    }
+
+    /// Returns the bitmap and the images which couldn't be decoded.
+    pub fn finish(self) -> (Bitmap, Vec<SkippedImage>) {
+        (self.bitmap, self.skipped)
+    }
+
+    fn size(&self) -> (usize, usize) {
+        (self.bitmap.get_width() as usize, self.bitmap.get_height() as usize)
+    }
+
+    /// Recomputes the clipping mask when the clipping paths have changed since the last painting operation.
+    fn update_clip(&mut self, clip: &[ClipPath]) {
+        if self.clip.0.as_slice() == clip {
+            return;
+        }
+        let (width, height) = self.size();
+        let mask = clip.iter().fold(None, |mask: Option<Mask>, clip| {
+            let clip_mask = fill(clip.get_path(), clip.get_rule(), width, height);
+            Some(match mask {
+                Some(mask) => mask.intersect(&clip_mask),
+                None => clip_mask,
+            })
+        });
+        self.clip = (clip.to_vec(), mask);
+    }
+
+    /// Paints a colour through a coverage mask and the clipping region.
+    fn paint(&mut self, mask: &Mask, rgb: [f64; 3], alpha: f64, clip: &[ClipPath]) {
+        self.update_clip(clip);
+        let clip = self.clip.1.as_ref();
+        let width = self.bitmap.get_width() as usize;
+        let pixels = self.bitmap.get_pixels_mut();
+        let [x0, y0, x1, y1] = mask.get_bounds();
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let coverage = mask.get(x, y) as f64 * clip.map_or(1.0, |clip| clip.get(x, y) as f64) * alpha;
+                if coverage > 0.0 {
+                    blend(&mut pixels[(y * width + x) * 4..], rgb, coverage);
+                }
+            }
+        }
+    }
+
+    /// Fills a path in device space with the nonzero winding rule.
+    fn fill_outline(&mut self, path: &Path, color: &Color, alpha: f64, state: &GraphicsState) {
+        if let Some(rgb) = paint_color(color) {
+            let (width, height) = self.size();
+            let mask = fill(path, FillRule::NonZero, width, height);
+            self.paint(&mask, rgb, alpha, state.get_clip());
+        }
+    }
+
+    /// Decodes an image, or records it as skipped and returns a gray placeholder
+    /// if it can't be decoded.
+    fn decode_image(&mut self, image: &Image, state: &GraphicsState) -> Rc<DecodedImage> {
+        let stream = image.get_stream().clone();
+        let color_space = image.get_color_space().cloned();
+        let decoded = PageImage::load(self.document, None, image.get_obj_ref(), stream, color_space, *state.get_ctm())
+            .and_then(|decoded| Ok((decoded.decode()?, decoded.is_image_mask())));
+        match decoded {
+            Ok(decoded) => Rc::new(decoded),
+            Err(e) => {
+                let name = image.get_name().map(|name| name.to_string());
+                self.skipped.push(SkippedImage { name, obj_ref: image.get_obj_ref(), reason: e.to_string() });
+                let mut placeholder = Bitmap::new(1, 1);
+                placeholder.set_pixel(0, 0, PLACEHOLDER_COLOR);
+                Rc::new((placeholder, false))
+            }
+        }
+    }
+
+    /// Draws an image mapped from the unit square to device space by `ctm`.
+    ///
+    /// Each pixel averages samples of the image, up to [`MAX_IMAGE_SAMPLES`]
+    /// along each axis for downscaled images, which also anti-aliases the
+    /// edges of the image.
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - The decoded image
+    /// * `stencil` - The colour of a stencil mask, None for images with their own colours
+    /// * `ctm` - The current transformation matrix
+    /// * `alpha` - The constant opacity
+    /// * `clip` - The clipping paths
+    fn draw_bitmap(&mut self, image: &Bitmap, stencil: Option<[f64; 3]>, ctm: &Matrix, alpha: f64, clip: &[ClipPath]) {
+        let (image_width, image_height) = (image.get_width(), image.get_height());
+        let Some(inverse) = ctm.invert() else {
+            return;
+        };
+        if image_width == 0 || image_height == 0 {
+            return;
+        }
+        let (width, height) = self.size();
+        let corners = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)].map(|(x, y)| ctm.transform(x, y));
+        let bound = |values: [f64; 4], limit: usize| {
+            let min = values.iter().fold(f64::MAX, |min, value| min.min(*value)).floor().clamp(0.0, limit as f64);
+            let max = values.iter().fold(f64::MIN, |max, value| max.max(*value)).ceil().clamp(0.0, limit as f64);
+            (min as usize, max as usize)
+        };
+        let (x0, x1) = bound(corners.map(|corner| corner.0), width);
+        let (y0, y1) = bound(corners.map(|corner| corner.1), height);
+        // The number of image pixels covering a device pixel decides how many samples are averaged
+        let area = (ctm.a * ctm.d - ctm.b * ctm.c).abs();
+        let density = (image_width as f64 * image_height as f64 / area).sqrt();
+        let samples = (density.ceil() as usize).clamp(1, MAX_IMAGE_SAMPLES);
+        self.update_clip(clip);
+        let clip = self.clip.1.as_ref();
+        let pixels = self.bitmap.get_pixels_mut();
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let clip_coverage = clip.map_or(1.0, |clip| clip.get(x, y) as f64);
+                if clip_coverage <= 0.0 {
+                    continue;
+                }
+                let (mut sum, mut coverage) = ([0.0; 3], 0.0);
+                for sy in 0..samples {
+                    for sx in 0..samples {
+                        let (u, v) = inverse.transform(
+                            x as f64 + (sx as f64 + 0.5) / samples as f64,
+                            y as f64 + (sy as f64 + 0.5) / samples as f64,
+                        );
+                        if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+                            continue;
+                        }
+                        // The first row of the image is at the top of the unit square
+                        let column = ((u * image_width as f64) as u32).min(image_width - 1);
+                        let row = (((1.0 - v) * image_height as f64) as u32).min(image_height - 1);
+                        let [r, g, b, a] = image.get_pixel(column, row);
+                        let a = a as f64 / 255.0;
+                        sum[0] += r as f64 / 255.0 * a;
+                        sum[1] += g as f64 / 255.0 * a;
+                        sum[2] += b as f64 / 255.0 * a;
+                        coverage += a;
+                    }
+                }
+                if coverage <= 0.0 {
+                    continue;
+                }
+                let rgb = stencil.unwrap_or(sum.map(|component| component / coverage));
+                let coverage = coverage / (samples * samples) as f64 * clip_coverage * alpha;
+                blend(&mut pixels[(y * width + x) * 4..], rgb, coverage);
+            }
+        }
+    }
+}
+
+impl ContentVisitor for Renderer<'_> {
+    fn fill_path(&mut self, path: &Path, rule: FillRule, state: &GraphicsState) -> Result<()> {
+        if let Some(rgb) = paint_color(state.get_fill_color()) {
+            let (width, height) = self.size();
+            let mask = fill(path, rule, width, height);
+            self.paint(&mask, rgb, state.get_fill_alpha(), state.get_clip());
+        }
+        Ok(())
+    }
+
+    fn stroke_path(&mut self, path: &Path, state: &GraphicsState) -> Result<()> {
+        let outline = stroke(path, state.get_ctm(), &stroke_style(state));
+        self.fill_outline(&outline, state.get_stroke_color(), state.get_stroke_alpha(), state);
+        Ok(())
+    }
+
+    fn show_text(&mut self, glyphs: &[Glyph], state: &GraphicsState) -> Result<()> {
+        let text_state = state.get_text_state();
+        let Some(font) = text_state.get_font() else {
+            return Ok(());
+        };
+        // Modes 4 to 7 also add the text to the clipping path, which isn't supported
+        let mode = text_state.get_render_mode();
+        let (filled, stroked) = (matches!(mode, 0 | 2 | 4 | 6), matches!(mode, 1 | 2 | 5 | 6));
+        if !filled && !stroked {
+            return Ok(());
+        }
+        let mut path = Path::default();
+        for glyph in glyphs {
+            if let Some(outline) = self.glyphs.get_outline(font, glyph) {
+                path.append(&outline.transform(glyph.get_matrix()));
+            }
+        }
+        if path.is_empty() {
+            return Ok(());
+        }
+        if filled {
+            self.fill_outline(&path, state.get_fill_color(), state.get_fill_alpha(), state);
+            // Built-in glyphs replacing bold glyphs are emboldened by a stroke
+            if self.glyphs.is_fallback_bold(font) {
+                let width = BOLD_STROKE_WIDTH * glyphs[0].get_matrix().get_scale();
+                let style = StrokeStyle { width, cap: LineCap::Butt, join: LineJoin::Round, miter_limit: 10.0, dash: &[], phase: 0.0 };
+                let outline = stroke(&path, &Matrix::identity(), &style);
+                self.fill_outline(&outline, state.get_fill_color(), state.get_fill_alpha(), state);
+            }
+        }
+        if stroked {
+            let outline = stroke(&path, state.get_ctm(), &stroke_style(state));
+            self.fill_outline(&outline, state.get_stroke_color(), state.get_stroke_alpha(), state);
+        }
+        Ok(())
+    }
+
+    fn draw_image(&mut self, image: &Image, state: &GraphicsState) -> Result<()> {
+        let decoded = match image.get_obj_ref() {
+            Some(obj_ref) => match self.images.get(&obj_ref) {
+                Some(decoded) => decoded.clone(),
+                None => {
+                    let decoded = self.decode_image(image, state);
+                    self.images.insert(obj_ref, decoded.clone());
+                    decoded
+                }
+            },
+            None => self.decode_image(image, state),
+        };
+        let (bitmap, image_mask) = decoded.as_ref();
+        let stencil = match image_mask {
+            true => match paint_color(state.get_fill_color()) {
+                Some(rgb) => Some(rgb),
+                None => return Ok(()),
+            },
+            false => None,
+        };
+        self.draw_bitmap(bitmap, stencil, state.get_ctm(), state.get_fill_alpha(), state.get_clip());
+        Ok(())
+    }
+}
+
+/// Returns the sRGB colour painted by a colour, None for shading and coloured tiling patterns.
+fn paint_color(color: &Color) -> Option<[f64; 3]> {
+    match color.get_color_space() {
+        ColorSpace::Pattern { underlying: None } => None,
+        _ => Some(color.to_rgb()),
+    }
+}
+
+fn stroke_style(state: &GraphicsState) -> StrokeStyle<'_> {
+    let (dash, phase) = state.get_dash();
+    StrokeStyle {
+        width: state.get_line_width(),
+        cap: state.get_line_cap(),
+        join: state.get_line_join(),
+        miter_limit: state.get_miter_limit(),
+        dash,
+        phase,
+    }
+}
+
+/// Blends a colour over an opaque pixel.
+fn blend(pixel: &mut [u8], rgb: [f64; 3], alpha: f64) {
+    let alpha = alpha.min(1.0);
+    for (component, value) in pixel.iter_mut().zip(rgb) {
+        let current = *component as f64;
+        *component = (current + (value.clamp(0.0, 1.0) * 255.0 - current) * alpha).round() as u8;
+    }
+}
@@ -12,6 +12,12 @@ pub trait Sequence {
     fn read_line_str(&mut self) -> Result<String>;
     fn seek(&mut self, pos: u64) -> Result<u64>;
     fn size(&self) -> Result<u64>;
+    /// Returns the whole data if the sequence keeps it in memory.
+    ///
+    /// The tokenizer scans such data in place instead of copying it into its own buffer.
+    fn as_slice(&self) -> Option<&[u8]> {
+        None
+    }
 }
 
 pub struct FileSequence {
@@ -19,61 +25,130 @@ pub struct FileSequence {
     buf: Vec<u8>,
 }
 
+/// A sequence over PDF data held in memory.
+///
+/// Any owner of a byte slice can back the sequence, such as `Vec<u8>`,
+/// `&'static [u8]` or `bytes::Bytes`.
+pub struct MemorySequence<T> {
+    data: T,
+    /// The offset of the next byte to read.
+    pos: usize,
+}
+
+/// A sequence over a memory-mapped file.
+///
+/// Pages of the file are loaded by the operating system as they are touched, so
+/// objects can be loaded at random without reading or buffering whole regions
+/// of the file.
+#[cfg(feature = "mmap")]
+pub type MmapSequence = MemorySequence<memmap2::Mmap>;
+
+/// A sequence adapter for any reader implementing `Read + Seek`.
+///
+/// The size of the data is determined once when the sequence is created.
+pub struct ReadSeekSequence<R> {
+    reader: R,
+    buf: Vec<u8>,
+    size: u64,
+}
+
 impl FileSequence {
     pub fn new(file: File) -> Self {
         let buf = Vec::new();
         Self { file, buf }
     }
+}
 
-    fn split_line_data(&mut self, index: usize) -> Vec<u8> {
-        let buf = &mut self.buf;
-        let line = buf.drain(0..index).collect::<Vec<u8>>();
-        buf.len();
-        let crlf_num = count_leading_line_endings(buf);
-        if crlf_num != 0 {
-            buf.drain(0..crlf_num as usize);
+impl<T: AsRef<[u8]>> MemorySequence<T> {
+    pub fn new(data: T) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    /// Consumes the sequence, returning the underlying data.
+    pub fn into_inner(self) -> T {
+        self.data
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl MemorySequence<memmap2::Mmap> {
+    /// Maps the file at `path` into memory.
+    ///
+    /// The file must not be truncated or modified while the sequence is alive,
+    /// otherwise reading from the mapping may fail or observe changing data.
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the mapping is only read, and callers are told not to modify the file
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(Self::new(mmap))
+    }
+}
+
+impl<R: Read + Seek> ReadSeekSequence<R> {
+    /// Creates a sequence over `reader`, starting from the beginning of the data.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let size = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(0))?;
+        let buf = Vec::new();
+        Ok(Self { reader, buf, size })
+    }
+
+    /// Consumes the sequence, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// Reads into `buf`, draining the bytes left over by a previous line read first.
+fn buffered_read(reader: &mut impl Read, line_buf: &mut Vec<u8>, buf: &mut [u8]) -> Result<usize> {
+    if !line_buf.is_empty() {
+        let len = line_buf.len();
+        let n = min(len, buf.len());
+        buf[0..n].copy_from_slice(&line_buf[0..n]);
+        line_buf.drain(0..n);
+        return Ok(n);
+    }
+    let n = reader.read(buf)?;
+    Ok(n)
+}
+
+/// Reads a line from `reader`, keeping the bytes read past the line end in `line_buf`.
+///
+/// Line endings before and after the line are skipped.
+fn buffered_read_line(reader: &mut impl Read, line_buf: &mut Vec<u8>) -> Result<Vec<u8>> {
+    let mut bytes = [0u8; 1024];
+    let mut tmp = 0;
+    loop {
+        let len = line_buf.len();
+        if let Some(i) = line_buf[tmp..].iter().position(|b| line_ending(*b)) {
+            let line = line_buf.drain(0..tmp + i).collect::<Vec<u8>>();
+            let crlf_num = count_leading_line_endings(line_buf);
+            if crlf_num != 0 {
+                line_buf.drain(0..crlf_num as usize);
+            }
+            return Ok(line);
         }
-        line
+        tmp = len;
+        let n = reader.read(&mut bytes)?;
+        if n == 0 {
+            return Err(PDFError::EOFError);
+        }
+        let offset = if len == 0 {
+            count_leading_line_endings(&bytes[0..n])
+        } else {
+            0u64
+        } as usize;
+        line_buf.extend_from_slice(&bytes[offset..n]);
     }
 }
 
 impl Sequence for FileSequence {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
-        if !self.buf.is_empty() {
-            let len = self.buf.len();
-            let n = min(len, buf.len());
-            buf[0..n].copy_from_slice(&self.buf[0..n]);
-            self.buf.drain(0..n);
-            return Ok(n);
-        }
-        let n = self.file.read(buf)?;
-        Ok(n)
+        buffered_read(&mut self.file, &mut self.buf, buf)
     }
 
     fn read_line(&mut self) -> Result<Vec<u8>> {
-        let buf = &mut self.buf;
-        let mut bytes = [0u8; 1024];
-        let mut tmp = 0;
-        loop {
-            let len = buf.len();
-            for i in tmp..len {
-                if line_ending(buf[i]) {
-                    let line_data = self.split_line_data(i);
-                    return Ok(line_data);
-                }
-            }
-            tmp = len;
-            let n = self.file.read(&mut bytes)?;
-            if n == 0 {
-                return Err(PDFError::EOFError);
-            }
-            let offset = if len == 0 {
-                count_leading_line_endings(&bytes)
-            }else {
-                0u64
-            } as usize;
-            buf.extend_from_slice(&bytes[offset..n]);
-        }
+        buffered_read_line(&mut self.file, &mut self.buf)
     }
 
     fn read_line_str(&mut self) -> Result<String> {
@@ -98,3 +173,76 @@ impl Sequence for FileSequence {
         Ok(n)
     }
 }
+
+impl<T: AsRef<[u8]>> Sequence for MemorySequence<T> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let data = &self.data.as_ref()[self.pos..];
+        let n = min(data.len(), buf.len());
+        buf[0..n].copy_from_slice(&data[0..n]);
+        self.pos += n;
+        Ok(n)
+    }
+
+    fn read_line(&mut self) -> Result<Vec<u8>> {
+        let data = self.data.as_ref();
+        let start = self.pos + count_leading_line_endings(&data[self.pos..]) as usize;
+        let end = match data[start..].iter().position(|b| line_ending(*b)) {
+            Some(i) => start + i,
+            None => return Err(PDFError::EOFError),
+        };
+        self.pos = end + count_leading_line_endings(&data[end..]) as usize;
+        Ok(data[start..end].to_vec())
+    }
+
+    fn read_line_str(&mut self) -> Result<String> {
+        let buf = self.read_line()?;
+        let text = String::from_utf8(buf)?;
+        Ok(text)
+    }
+
+    fn seek(&mut self, pos: u64) -> Result<u64> {
+        if self.size()? < pos {
+            return Err(PDFError::SeekExceedError);
+        }
+        self.pos = pos as usize;
+        Ok(pos)
+    }
+
+    fn size(&self) -> Result<u64> {
+        Ok(self.data.as_ref().len() as u64)
+    }
+
+    fn as_slice(&self) -> Option<&[u8]> {
+        Some(self.data.as_ref())
+    }
+}
+
+impl<R: Read + Seek> Sequence for ReadSeekSequence<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        buffered_read(&mut self.reader, &mut self.buf, buf)
+    }
+
+    fn read_line(&mut self) -> Result<Vec<u8>> {
+        buffered_read_line(&mut self.reader, &mut self.buf)
+    }
+
+    fn read_line_str(&mut self) -> Result<String> {
+        let buf = self.read_line()?;
+        let text = String::from_utf8(buf)?;
+        Ok(text)
+    }
+
+    fn seek(&mut self, pos: u64) -> Result<u64> {
+        if self.size < pos {
+            return Err(PDFError::SeekExceedError);
+        }
+        let n = self.reader.seek(SeekFrom::Start(pos))?;
+        // Due to seek, the buffer is no longer valid
+        self.buf.clear();
+        Ok(n)
+    }
+
+    fn size(&self) -> Result<u64> {
+        Ok(self.size)
+    }
+}
@@ -0,0 +1,552 @@
+use crate::colorspace::ColorSpace;
+use crate::document::PDFDocument;
+use crate::error::Result;
+use crate::font::Font;
+use crate::graphics::{ClipPath, Color, FillRule, GraphicsState, LineCap, LineJoin, Matrix, Path, PathSegment};
+use crate::image::{Bitmap, ImageFormat, PageImage};
+use crate::interpreter::{ContentVisitor, Glyph, Image};
+use crate::outline::{FallbackFont, FontStyle, GlyphCache};
+use crate::text::SPACE_GAP;
+use crate::utils::base64_encode;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Writes what a content stream paints as an SVG document.
+///
+/// The device space of the interpreter must be the SVG user space, with y
+/// pointing down. Paths are written in device space, strokes in user space
+/// with the current transformation matrix so that line widths and dashes keep
+/// their shape. Clipping paths become `<clipPath>` elements, images are
+/// embedded as PNG or JPEG data URIs, and text is written as `<text>` elements
+/// positioned glyph by glyph. Glyphs without Unicode text are written as paths
+/// of their outlines instead.
+///
+/// Shadings, patterns, soft masks and blend modes aren't supported, nor are
+/// images which can't be decoded, except JPEG images which are embedded as is.
+pub struct SvgWriter<'a> {
+    document: &'a PDFDocument,
+    width: f64,
+    height: f64,
+    /// The elements written so far.
+    body: String,
+    /// The clipping paths with the ID of their `<clipPath>` element.
+    clips: Vec<(Vec<ClipPath>, String)>,
+    /// The ID of the clipping path of the open group, None when no group is open.
+    group: Option<String>,
+    /// The `<text>` element glyphs are added to, written before anything else is painted.
+    text: Option<PendingText>,
+    glyphs: GlyphCache<'a>,
+    /// The ID of the `<image>` element of each image XObject, by reference and
+    /// colour of stencil masks, None for images which can't be embedded.
+    images: HashMap<ImageKey, Option<String>>,
+    next_id: usize,
+}
+
+/// An image XObject reference with the colour of stencil masks.
+type ImageKey = ((u32, u16), Option<[u8; 3]>);
+
+/// A `<text>` element being written.
+struct PendingText {
+    /// The text rendering matrix of its first glyph.
+    matrix: Matrix,
+    /// The font size in device space.
+    size: f64,
+    /// The font and paint attributes.
+    attributes: String,
+    clip: Vec<ClipPath>,
+    text: String,
+    /// The position of each character, in the coordinates of the element.
+    positions: Vec<(f64, f64)>,
+    /// Where the advance of the last glyph ends, in the text space of the first glyph.
+    end: Option<(f64, f64)>,
+}
+
+impl<'a> SvgWriter<'a> {
+    /// Creates a writer for an SVG document of the given size.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - The document the content belongs to
+    /// * `width` - The width of the SVG user space
+    /// * `height` - The height of the SVG user space
+    pub fn new(document: &'a PDFDocument, width: f64, height: f64) -> Self {
+        SvgWriter {
+            document,
+            width,
+            height,
+            body: String::new(),
+            clips: Vec::new(),
+            group: None,
+            text: None,
+            glyphs: GlyphCache::new(document),
+            images: HashMap::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Returns the SVG document.
+    pub fn finish(mut self) -> String {
+        self.flush_text();
+        self.set_group(None);
+        let (width, height) = (number(self.width), number(self.height));
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" version=\"1.1\" \
+             width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">\n{}</svg>\n",
+            self.body
+        )
+    }
+
+    fn new_id(&mut self, prefix: &str) -> String {
+        let id = format!("{}{}", prefix, self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    /// Returns the ID of the `<clipPath>` element of clipping paths, writing it and
+    /// the elements of the clipping paths it intersects if they don't exist yet.
+    fn get_clip_id(&mut self, clip: &[ClipPath]) -> Option<String> {
+        let (last, outer) = clip.split_last()?;
+        if let Some((_, id)) = self.clips.iter().rev().find(|(paths, _)| paths.as_slice() == clip) {
+            return Some(id.clone());
+        }
+        let outer = self.get_clip_id(outer);
+        let id = self.new_id("clip");
+        let rule = match last.get_rule() {
+            FillRule::NonZero => "nonzero",
+            FillRule::EvenOdd => "evenodd",
+        };
+        let element = format!(
+            "<defs><clipPath id=\"{id}\"{}><path d=\"{}\" clip-rule=\"{rule}\"/></clipPath></defs>\n",
+            outer.map(|outer| format!(" clip-path=\"url(#{outer})\"")).unwrap_or_default(),
+            path_data(last.get_path()),
+        );
+        self.write(&element);
+        self.clips.push((clip.to_vec(), id.clone()));
+        Some(id)
+    }
+
+    /// Opens a group clipped by a clipping path, closing the previous group.
+    fn set_group(&mut self, clip_id: Option<String>) {
+        if self.group == clip_id {
+            return;
+        }
+        if self.group.is_some() {
+            self.body.push_str("</g>\n");
+        }
+        if let Some(id) = &clip_id {
+            let _ = writeln!(self.body, "<g clip-path=\"url(#{id})\">");
+        }
+        self.group = clip_id;
+    }
+
+    /// Writes definitions outside the clipping group, which doesn't apply to them.
+    fn write(&mut self, definition: &str) {
+        self.flush_text();
+        self.set_group(None);
+        self.body.push_str(definition);
+    }
+
+    /// Writes a painting element inside the group of the clipping paths.
+    fn paint(&mut self, element: &str, clip: &[ClipPath]) {
+        self.flush_text();
+        let clip_id = self.get_clip_id(clip);
+        self.set_group(clip_id);
+        self.body.push_str(element);
+        self.body.push('\n');
+    }
+
+    /// Fills a path in device space.
+    fn fill(&mut self, path: &Path, rule: FillRule, state: &GraphicsState) {
+        let Some(fill) = paint_color(state.get_fill_color()) else {
+            return;
+        };
+        let rule = match rule {
+            FillRule::NonZero => "",
+            FillRule::EvenOdd => " fill-rule=\"evenodd\"",
+        };
+        let element = format!("<path d=\"{}\" fill=\"{fill}\"{rule}{}/>", path_data(path), opacity("fill-opacity", state.get_fill_alpha()));
+        self.paint(&element, state.get_clip());
+    }
+
+    /// Strokes a path in device space, written in user space with the current transformation matrix.
+    fn stroke(&mut self, path: &Path, state: &GraphicsState) {
+        let Some(stroke) = paint_color(state.get_stroke_color()) else {
+            return;
+        };
+        let ctm = state.get_ctm();
+        let Some(inverse) = ctm.invert() else {
+            return;
+        };
+        let element = format!(
+            "<path d=\"{}\" transform=\"{}\" fill=\"none\" stroke=\"{stroke}\"{}{}/>",
+            path_data(&path.transform(&inverse)),
+            matrix(ctm),
+            stroke_attributes(state, 1.0),
+            opacity("stroke-opacity", state.get_stroke_alpha()),
+        );
+        self.paint(&element, state.get_clip());
+    }
+
+    /// Adds glyphs with Unicode text to the pending `<text>` element, starting
+    /// a new one unless the glyphs share its font, paint and text orientation.
+    fn write_text(&mut self, font: &Font, glyphs: &[&Glyph], state: &GraphicsState) {
+        let Some(first) = glyphs.first() else {
+            return;
+        };
+        // The text element is scaled to the font size, so that browsers don't draw tiny glyphs
+        let size = first.get_size();
+        if size <= 0.0 || first.get_matrix().invert().is_none() {
+            return;
+        }
+        let style = FontStyle::of(font);
+        let mut attributes = format!(" font-family=\"{}\" font-size=\"{}\"", font_family(font, style.family), number(size));
+        if style.bold {
+            attributes.push_str(" font-weight=\"bold\"");
+        }
+        if style.italic {
+            attributes.push_str(" font-style=\"italic\"");
+        }
+        let (filled, stroked) = text_paint(state);
+        match paint_color(state.get_fill_color()).filter(|_| filled) {
+            Some(color) => {
+                let _ = write!(attributes, " fill=\"{color}\"{}", opacity("fill-opacity", state.get_fill_alpha()));
+            }
+            None => attributes.push_str(" fill=\"none\""),
+        }
+        if let Some(color) = paint_color(state.get_stroke_color()).filter(|_| stroked) {
+            // The line width in user space is scaled to the text space of the element
+            let scale = state.get_ctm().get_scale() * size / first.get_matrix().get_scale();
+            let _ = write!(
+                attributes,
+                " stroke=\"{color}\"{}{}",
+                stroke_attributes(state, scale),
+                opacity("stroke-opacity", state.get_stroke_alpha()),
+            );
+        }
+        let linear = |matrix: &Matrix| [matrix.a, matrix.b, matrix.c, matrix.d];
+        let continued = self.text.as_ref().is_some_and(|text| {
+            text.attributes == attributes
+                && text.clip.as_slice() == state.get_clip()
+                && linear(&text.matrix).iter().zip(linear(first.get_matrix())).all(|(a, b)| (a - b).abs() < 1e-9)
+        });
+        if !continued {
+            self.flush_text();
+            self.text = Some(PendingText {
+                matrix: *first.get_matrix(),
+                size,
+                attributes,
+                clip: state.get_clip().to_vec(),
+                text: String::new(),
+                positions: Vec::new(),
+                end: None,
+            });
+        }
+        let Some(pending) = self.text.as_mut() else {
+            return;
+        };
+        let Some(inverse) = pending.matrix.invert() else {
+            return;
+        };
+        for glyph in glyphs {
+            let unicode = glyph.get_unicode().unwrap_or_default();
+            let (ox, oy) = glyph.get_origin();
+            let (ex, ey) = glyph.get_end();
+            let ((x, y), (end, _)) = (inverse.transform(ox, oy), inverse.transform(ex, ey));
+            // Words positioned apart or on another line without a space glyph are
+            // separated by a space, so that the text can be copied
+            if let Some((last_x, last_y)) = pending.end
+                && (x - last_x > SPACE_GAP || (y - last_y).abs() > SPACE_GAP)
+                && !pending.text.ends_with(char::is_whitespace)
+                && !unicode.starts_with(char::is_whitespace) {
+                pending.positions.push((last_x * pending.size, -last_y * pending.size));
+                pending.text.push(' ');
+            }
+            pending.end = Some((end, y));
+            // Characters of ligatures share the advance of the glyph
+            let count = unicode.chars().count();
+            for (i, c) in unicode.chars().enumerate() {
+                pending.positions.push(((x + (end - x) * i as f64 / count as f64) * pending.size, -y * pending.size));
+                push_escaped(&mut pending.text, c);
+            }
+        }
+    }
+
+    /// Writes the pending `<text>` element.
+    fn flush_text(&mut self) {
+        let Some(text) = self.text.take() else {
+            return;
+        };
+        let xs = text.positions.iter().map(|(x, _)| number(*x)).collect::<Vec<_>>();
+        let mut ys = text.positions.iter().map(|(_, y)| number(*y)).collect::<Vec<_>>();
+        if ys.iter().all(|y| *y == ys[0]) {
+            ys.truncate(1);
+        }
+        let transform = Matrix::new(1.0 / text.size, 0.0, 0.0, -1.0 / text.size, 0.0, 0.0).multiply(&text.matrix);
+        let element = format!(
+            "<text transform=\"{}\" x=\"{}\" y=\"{}\"{} xml:space=\"preserve\">{}</text>",
+            matrix(&transform),
+            xs.join(" "),
+            ys.join(" "),
+            text.attributes,
+            text.text,
+        );
+        self.paint(&element, &text.clip);
+    }
+
+    /// Returns the data URI of an image, decoding images other than JPEG images to PNG.
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - The image
+    /// * `stencil` - The colour of a stencil mask, None for images with their own colours
+    ///
+    /// # Returns
+    ///
+    /// The data URI, or None if the image can't be decoded
+    fn image_data(image: &PageImage, stencil: Option<[u8; 3]>) -> Option<String> {
+        if image.get_native_format() == Some(ImageFormat::Jpeg) {
+            let file = image.export(ImageFormat::Jpeg).ok()?;
+            return Some(format!("data:image/jpeg;base64,{}", base64_encode(file.get_data())));
+        }
+        let mut bitmap = image.decode().ok()?;
+        if let Some(rgb) = stencil {
+            colorize(&mut bitmap, rgb);
+        }
+        Some(format!("data:image/png;base64,{}", base64_encode(&bitmap.to_png().ok()?)))
+    }
+}
+
+impl ContentVisitor for SvgWriter<'_> {
+    fn fill_path(&mut self, path: &Path, rule: FillRule, state: &GraphicsState) -> Result<()> {
+        self.fill(path, rule, state);
+        Ok(())
+    }
+
+    fn stroke_path(&mut self, path: &Path, state: &GraphicsState) -> Result<()> {
+        self.stroke(path, state);
+        Ok(())
+    }
+
+    fn show_text(&mut self, glyphs: &[Glyph], state: &GraphicsState) -> Result<()> {
+        let Some(font) = state.get_text_state().get_font() else {
+            return Ok(());
+        };
+        // Text is written as long as it has Unicode text, even when invisible, so that it can be selected
+        let (text, outlined): (Vec<&Glyph>, Vec<&Glyph>) = glyphs.iter()
+            .partition(|glyph| glyph.get_unicode().is_some_and(|text| !text.is_empty() && text.chars().all(is_xml_char)));
+        self.write_text(font, &text, state);
+        let mut path = Path::default();
+        for glyph in outlined {
+            if let Some(outline) = self.glyphs.get_outline(font, glyph) {
+                path.append(&outline.transform(glyph.get_matrix()));
+            }
+        }
+        if path.is_empty() {
+            return Ok(());
+        }
+        let (filled, stroked) = text_paint(state);
+        if filled {
+            self.fill(&path, FillRule::NonZero, state);
+        }
+        if stroked {
+            self.stroke(&path, state);
+        }
+        Ok(())
+    }
+
+    fn draw_image(&mut self, image: &Image, state: &GraphicsState) -> Result<()> {
+        let stream = image.get_stream().clone();
+        let color_space = image.get_color_space().cloned();
+        let Ok(page_image) = PageImage::load(self.document, None, image.get_obj_ref(), stream, color_space, *state.get_ctm()) else {
+            return Ok(());
+        };
+        let stencil = match page_image.is_image_mask() {
+            true => match paint_color(state.get_fill_color()) {
+                Some(_) => Some(state.get_fill_color().get_color_space().to_rgb8(state.get_fill_color().get_components())),
+                None => return Ok(()),
+            },
+            false => None,
+        };
+        let transform = image_matrix(state.get_ctm());
+        let opacity = opacity("opacity", state.get_fill_alpha());
+        let Some(obj_ref) = image.get_obj_ref() else {
+            if let Some(data) = Self::image_data(&page_image, stencil) {
+                let element = format!(
+                    "<image width=\"1\" height=\"1\" preserveAspectRatio=\"none\" transform=\"{transform}\"{opacity} xlink:href=\"{data}\"/>"
+                );
+                self.paint(&element, state.get_clip());
+            }
+            return Ok(());
+        };
+        // Image XObjects are written once and used wherever they are drawn
+        let key = (obj_ref, stencil);
+        let id = match self.images.get(&key) {
+            Some(id) => id.clone(),
+            None => {
+                let id = Self::image_data(&page_image, stencil).map(|data| {
+                    let id = self.new_id("image");
+                    self.write(&format!(
+                        "<defs><image id=\"{id}\" width=\"1\" height=\"1\" preserveAspectRatio=\"none\" xlink:href=\"{data}\"/></defs>\n"
+                    ));
+                    id
+                });
+                self.images.insert(key, id.clone());
+                id
+            }
+        };
+        if let Some(id) = id {
+            self.paint(&format!("<use xlink:href=\"#{id}\" transform=\"{transform}\"{opacity}/>"), state.get_clip());
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether the text rendering mode fills and strokes glyphs.
+fn text_paint(state: &GraphicsState) -> (bool, bool) {
+    let mode = state.get_text_state().get_render_mode();
+    (matches!(mode, 0 | 2 | 4 | 6), matches!(mode, 1 | 2 | 5 | 6))
+}
+
+/// Returns the SVG colour of a colour, None for shading and coloured tiling patterns.
+fn paint_color(color: &Color) -> Option<String> {
+    match color.get_color_space() {
+        ColorSpace::Pattern { underlying: None } => None,
+        space => Some(hex_color(space.to_rgb8(color.get_components()))),
+    }
+}
+
+fn hex_color([r, g, b]: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Returns an opacity attribute, empty for opaque painting.
+fn opacity(name: &str, alpha: f64) -> String {
+    if alpha >= 1.0 {
+        String::new()
+    } else {
+        format!(" {name}=\"{}\"", number(alpha.max(0.0)))
+    }
+}
+
+/// Returns the stroke attributes of the graphics state, other than the colour.
+///
+/// # Arguments
+///
+/// * `state` - The graphics state
+/// * `scale` - The scale of user space lengths in the coordinates of the element
+fn stroke_attributes(state: &GraphicsState, scale: f64) -> String {
+    // Zero-width lines are the thinnest line the device can draw
+    let width = state.get_line_width() * scale;
+    let mut attributes = match width > 0.0 {
+        true => format!(" stroke-width=\"{}\"", number(width)),
+        false => " stroke-width=\"1\" vector-effect=\"non-scaling-stroke\"".to_string(),
+    };
+    match state.get_line_cap() {
+        LineCap::Butt => {}
+        LineCap::Round => attributes.push_str(" stroke-linecap=\"round\""),
+        LineCap::ProjectingSquare => attributes.push_str(" stroke-linecap=\"square\""),
+    }
+    match state.get_line_join() {
+        LineJoin::Miter => {
+            let _ = write!(attributes, " stroke-miterlimit=\"{}\"", number(state.get_miter_limit().max(1.0)));
+        }
+        LineJoin::Round => attributes.push_str(" stroke-linejoin=\"round\""),
+        LineJoin::Bevel => attributes.push_str(" stroke-linejoin=\"bevel\""),
+    }
+    let (dash, phase) = state.get_dash();
+    if dash.iter().any(|length| *length > 0.0) && dash.iter().all(|length| *length >= 0.0) {
+        let lengths = dash.iter().map(|length| number(length * scale)).collect::<Vec<_>>();
+        let _ = write!(attributes, " stroke-dasharray=\"{}\"", lengths.join(" "));
+        if phase != 0.0 {
+            let _ = write!(attributes, " stroke-dashoffset=\"{}\"", number(phase * scale));
+        }
+    }
+    attributes
+}
+
+/// Returns the `font-family` of the `<text>` elements of a font: the family
+/// name from the font name, then a generic family matching the built-in font
+/// replacing the font.
+fn font_family(font: &Font, fallback: FallbackFont) -> String {
+    let generic = match fallback {
+        FallbackFont::Sans => "sans-serif",
+        FallbackFont::Serif => "serif",
+        FallbackFont::Mono => "monospace",
+    };
+    let name = font.get_standard_font().or(font.get_base_font()).unwrap_or("");
+    // Subset fonts are prefixed with six uppercase letters and a plus sign
+    let name = match name.split_once('+') {
+        Some((prefix, name)) if prefix.len() == 6 && prefix.bytes().all(|b| b.is_ascii_uppercase()) => name,
+        _ => name,
+    };
+    let family = name.split([',', '-']).next().unwrap_or("");
+    if family.is_empty() || !family.chars().all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '_') {
+        return generic.to_string();
+    }
+    format!("'{family}', {generic}")
+}
+
+/// Returns whether a character may appear in an XML document.
+fn is_xml_char(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\r' | '\u{20}'..='\u{d7ff}' | '\u{e000}'..='\u{fffd}' | '\u{10000}'..)
+}
+
+fn push_escaped(text: &mut String, c: char) {
+    match c {
+        '<' => text.push_str("&lt;"),
+        '>' => text.push_str("&gt;"),
+        '&' => text.push_str("&amp;"),
+        '"' => text.push_str("&quot;"),
+        c => text.push(c),
+    }
+}
+
+/// Paints the opaque pixels of a decoded stencil mask with a colour.
+fn colorize(bitmap: &mut Bitmap, [r, g, b]: [u8; 3]) {
+    for pixel in bitmap.get_pixels_mut().chunks_exact_mut(4) {
+        pixel[..3].copy_from_slice(&[r, g, b]);
+    }
+}
+
+/// Returns the SVG path data of a path.
+fn path_data(path: &Path) -> String {
+    let mut data = String::new();
+    for segment in path.get_segments() {
+        if !data.is_empty() {
+            data.push(' ');
+        }
+        let _ = match *segment {
+            PathSegment::MoveTo(x, y) => write!(data, "M{} {}", number(x), number(y)),
+            PathSegment::LineTo(x, y) => write!(data, "L{} {}", number(x), number(y)),
+            PathSegment::CurveTo(x1, y1, x2, y2, x3, y3) => write!(
+                data,
+                "C{} {} {} {} {} {}",
+                number(x1), number(y1), number(x2), number(y2), number(x3), number(y3)
+            ),
+            PathSegment::Close => write!(data, "Z"),
+        };
+    }
+    data
+}
+
+fn matrix(matrix: &Matrix) -> String {
+    let values = [matrix.a, matrix.b, matrix.c, matrix.d, matrix.e, matrix.f];
+    format!("matrix({})", values.map(number).join(" "))
+}
+
+/// Returns the transform of an `<image>` element of the unit square, whose
+/// first row is at the top of the unit square in PDF and at y = 0 in SVG.
+fn image_matrix(ctm: &Matrix) -> String {
+    matrix(&Matrix::new(1.0, 0.0, 0.0, -1.0, 0.0, 1.0).multiply(ctm))
+}
+
+/// Formats a number with at most four decimals.
+fn number(value: f64) -> String {
+    let value = (value * 10000.0).round() / 10000.0;
+    if value == 0.0 || !value.is_finite() {
+        return "0".to_string();
+    }
+    let text = format!("{:.4}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
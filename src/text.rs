@@ -0,0 +1,491 @@
+use crate::error::Result;
+use crate::graphics::{Color, GraphicsState};
+use crate::interpreter::{ContentVisitor, Glyph};
+
+/// The gap between two glyphs, relative to the font size, above which a space is inserted.
+pub(crate) const SPACE_GAP: f64 = 0.15;
+/// The gap between two glyphs on the same baseline, relative to the font size,
+/// above which they belong to different lines, as in a table or separate columns.
+const COLUMN_GAP: f64 = 3.0;
+/// The baseline shift, relative to the font size, below which glyphs are on the same line.
+const BASELINE_TOLERANCE: f64 = 0.5;
+/// The distance between baselines, relative to the font size, up to which
+/// consecutive lines belong to the same block.
+const LINE_SPACING: f64 = 2.0;
+/// The distance between baselines, relative to the font size, up to which a
+/// paragraph continues a column.
+const PARAGRAPH_SPACING: f64 = 4.0;
+
+/// Collects the text shown by a content stream and lays it out.
+///
+/// Glyphs are grouped into words and lines by their position and into blocks
+/// by their proximity, and blocks are ordered for reading by recursively
+/// cutting the page along horizontal and vertical whitespace, so that columns
+/// are read one after the other.
+#[derive(Default)]
+pub struct TextExtractor {
+    glyphs: Vec<TextGlyph>,
+}
+
+/// A glyph with its Unicode text and position.
+#[derive(Clone, Debug)]
+pub struct TextGlyph {
+    text: String,
+    code: u32,
+    /// The PostScript name of the font.
+    font_name: Option<String>,
+    /// The font size, scaled to device space.
+    font_size: f64,
+    fill_color: Color,
+    /// The glyph box as `[x0, y0, x1, y1]`, from the descent to the ascent of the font.
+    bbox: [f64; 4],
+    /// The origin of the glyph and the end of its advance, on the baseline.
+    origin: (f64, f64),
+    end: (f64, f64),
+}
+
+/// A word, a run of glyphs not separated by whitespace.
+#[derive(Clone, Debug)]
+pub struct TextWord {
+    text: String,
+    bbox: [f64; 4],
+    glyphs: Vec<TextGlyph>,
+}
+
+/// A line of words sharing a baseline.
+#[derive(Clone, Debug)]
+pub struct TextLine {
+    text: String,
+    bbox: [f64; 4],
+    words: Vec<TextWord>,
+    /// The baseline of the first glyph.
+    baseline: f64,
+    /// The largest font size of the line.
+    font_size: f64,
+}
+
+/// Glyphs on the same baseline, before they are split into words.
+struct LineBuilder {
+    glyphs: Vec<TextGlyph>,
+    x0: f64,
+    x1: f64,
+    y: f64,
+    size: f64,
+}
+
+/// Consecutive lines of text.
+struct TextBlock {
+    lines: Vec<TextLine>,
+    x0: f64,
+    x1: f64,
+    top: f64,
+    bottom: f64,
+}
+
+impl ContentVisitor for TextExtractor {
+    fn show_text(&mut self, glyphs: &[Glyph], state: &GraphicsState) -> Result<()> {
+        let font = state.get_text_state().get_font();
+        let font_name = font.and_then(|font| font.get_base_font()).map(|name| name.to_string());
+        let (ascent, descent) = font.map_or((0.8, -0.2), |font| (font.get_ascent(), font.get_descent()));
+        for glyph in glyphs {
+            let text = match glyph.get_unicode() {
+                Some(text) if !text.is_empty() => text.to_string(),
+                _ => continue,
+            };
+            let advance = glyph.get_advance();
+            let matrix = glyph.get_matrix();
+            let corners = [(0.0, descent), (advance, descent), (advance, ascent), (0.0, ascent)]
+                .map(|(x, y)| matrix.transform(x, y));
+            let bbox = corners.iter().fold([f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY], |bbox, (x, y)| {
+                [bbox[0].min(*x), bbox[1].min(*y), bbox[2].max(*x), bbox[3].max(*y)]
+            });
+            self.glyphs.push(TextGlyph {
+                text,
+                code: glyph.get_code(),
+                font_name: font_name.clone(),
+                font_size: glyph.get_size(),
+                fill_color: state.get_fill_color().clone(),
+                bbox,
+                origin: glyph.get_origin(),
+                end: glyph.get_end(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl TextExtractor {
+    /// Creates an extractor without text.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the collected glyphs, in content order.
+    pub fn get_glyphs(&self) -> &[TextGlyph] {
+        &self.glyphs
+    }
+
+    /// Returns the collected glyphs, in content order.
+    pub fn into_glyphs(self) -> Vec<TextGlyph> {
+        self.glyphs
+    }
+
+    /// Lays out the collected glyphs into lines.
+    ///
+    /// # Returns
+    ///
+    /// The lines in reading order
+    pub fn get_lines(&self) -> Vec<TextLine> {
+        let blocks = build_blocks(build_lines(&self.glyphs));
+        order_blocks(blocks).into_iter().flat_map(|block| block.lines).collect()
+    }
+
+    /// Lays out the collected text.
+    ///
+    /// # Returns
+    ///
+    /// The text in reading order, with lines separated by a line break and
+    /// blocks by an empty line
+    pub fn get_text(&self) -> String {
+        let blocks = build_blocks(build_lines(&self.glyphs));
+        order_blocks(blocks)
+            .iter()
+            .map(|block| block.lines.iter().map(|line| line.text.as_str()).collect::<Vec<&str>>().join("\n"))
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    }
+}
+
+impl TextGlyph {
+    /// Returns the Unicode text of the glyph, which may be several characters for ligatures.
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the character code.
+    pub fn get_code(&self) -> u32 {
+        self.code
+    }
+
+    /// Returns the PostScript name of the font.
+    pub fn get_font_name(&self) -> Option<&str> {
+        self.font_name.as_deref()
+    }
+
+    /// Returns the font size, scaled by the text and transformation matrices.
+    pub fn get_font_size(&self) -> f64 {
+        self.font_size
+    }
+
+    /// Returns the fill colour the glyph is painted with.
+    pub fn get_fill_color(&self) -> &Color {
+        &self.fill_color
+    }
+
+    /// Returns the glyph box as `[x0, y0, x1, y1]`, spanning the advance of the
+    /// glyph from the descent to the ascent of the font.
+    pub fn get_bbox(&self) -> [f64; 4] {
+        self.bbox
+    }
+
+    /// Returns the origin of the glyph on the baseline.
+    pub fn get_origin(&self) -> (f64, f64) {
+        self.origin
+    }
+
+    fn is_whitespace(&self) -> bool {
+        self.text.chars().all(char::is_whitespace)
+    }
+
+    /// Returns the horizontal extent of the advance.
+    fn get_extent(&self) -> (f64, f64) {
+        (self.origin.0.min(self.end.0), self.origin.0.max(self.end.0))
+    }
+}
+
+impl TextWord {
+    fn new(glyphs: Vec<TextGlyph>) -> Self {
+        let text = glyphs.iter().map(|glyph| glyph.text.as_str()).collect();
+        let bbox = union(glyphs.iter().map(|glyph| glyph.bbox));
+        TextWord { text, bbox, glyphs }
+    }
+
+    /// Returns the text of the word.
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the union of the glyph boxes as `[x0, y0, x1, y1]`.
+    pub fn get_bbox(&self) -> [f64; 4] {
+        self.bbox
+    }
+
+    /// Returns the glyphs of the word.
+    pub fn get_glyphs(&self) -> &[TextGlyph] {
+        &self.glyphs
+    }
+}
+
+impl TextLine {
+    /// Returns the text of the line, with words separated by a single space.
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the union of the word boxes as `[x0, y0, x1, y1]`.
+    pub fn get_bbox(&self) -> [f64; 4] {
+        self.bbox
+    }
+
+    /// Returns the words of the line, from left to right.
+    pub fn get_words(&self) -> &[TextWord] {
+        &self.words
+    }
+
+    /// Returns the words of the line, from left to right.
+    pub fn into_words(self) -> Vec<TextWord> {
+        self.words
+    }
+}
+
+impl LineBuilder {
+    fn new(glyphs: Vec<TextGlyph>) -> Self {
+        let (x0, x1) = glyphs.iter().map(|glyph| glyph.get_extent())
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(x0, x1), extent| (x0.min(extent.0), x1.max(extent.1)));
+        let y = glyphs[0].origin.1;
+        let size = glyphs.iter().map(|glyph| glyph.font_size).fold(0.0, f64::max);
+        LineBuilder { glyphs, x0, x1, y, size }
+    }
+
+    /// Returns true if `x0`, at baseline `y`, continues the line.
+    fn is_continued_by(&self, x0: f64, y: f64, size: f64) -> bool {
+        let size = self.size.max(size);
+        (y - self.y).abs() <= BASELINE_TOLERANCE * size
+            && x0 >= self.x1 - BASELINE_TOLERANCE * size
+            && x0 - self.x1 <= COLUMN_GAP * size
+    }
+
+    fn append(&mut self, other: LineBuilder) {
+        self.x1 = self.x1.max(other.x1);
+        self.size = self.size.max(other.size);
+        self.glyphs.extend(other.glyphs);
+    }
+
+    /// Splits the line into words at whitespace glyphs and at gaps wider than a space.
+    ///
+    /// # Returns
+    ///
+    /// The line, or None if it has no other glyphs than whitespace
+    fn build(self) -> Option<TextLine> {
+        let mut words = Vec::new();
+        let mut word: Vec<TextGlyph> = Vec::new();
+        let mut previous: Option<(f64, f64)> = None;
+        for glyph in self.glyphs {
+            let (x0, x1) = glyph.get_extent();
+            let gap = previous.is_some_and(|(end, size)| x0 - end > SPACE_GAP * size.max(glyph.font_size));
+            if (gap || glyph.is_whitespace()) && !word.is_empty() {
+                words.push(TextWord::new(std::mem::take(&mut word)));
+            }
+            previous = Some((x1, glyph.font_size));
+            if !glyph.is_whitespace() {
+                word.push(glyph);
+            }
+        }
+        if !word.is_empty() {
+            words.push(TextWord::new(word));
+        }
+        if words.is_empty() {
+            return None;
+        }
+        let text = words.iter().map(|word| word.text.as_str()).collect::<Vec<&str>>().join(" ");
+        let bbox = union(words.iter().map(|word| word.bbox));
+        Some(TextLine { text, bbox, words, baseline: self.y, font_size: self.size })
+    }
+}
+
+impl TextBlock {
+    fn new(line: TextLine) -> Self {
+        TextBlock {
+            x0: line.bbox[0],
+            x1: line.bbox[2],
+            top: line.baseline + line.font_size,
+            bottom: line.baseline,
+            lines: vec![line],
+        }
+    }
+
+    /// Returns true if `line` is the next line of the block.
+    fn is_continued_by(&self, line: &TextLine) -> bool {
+        let last = &self.lines[self.lines.len() - 1];
+        let spacing = last.baseline - line.baseline;
+        spacing > 0.0
+            && spacing <= LINE_SPACING * last.font_size.max(line.font_size)
+            && line.bbox[0] < self.x1
+            && line.bbox[2] > self.x0
+    }
+
+    fn push(&mut self, line: TextLine) {
+        self.x0 = self.x0.min(line.bbox[0]);
+        self.x1 = self.x1.max(line.bbox[2]);
+        self.bottom = self.bottom.min(line.baseline);
+        self.lines.push(line);
+    }
+}
+
+/// Groups glyphs into lines.
+///
+/// Glyphs are taken in content order, and a line is continued by the next glyph
+/// on the same baseline unless it is too far apart. Fragments of a line shown
+/// separately are then joined.
+fn build_lines(glyphs: &[TextGlyph]) -> Vec<TextLine> {
+    let mut fragments: Vec<LineBuilder> = Vec::new();
+    for glyph in glyphs {
+        let (x0, _) = glyph.get_extent();
+        match fragments.last_mut() {
+            Some(line) if line.is_continued_by(x0, glyph.origin.1, glyph.font_size) => {
+                line.append(LineBuilder::new(vec![glyph.clone()]));
+            }
+            _ => fragments.push(LineBuilder::new(vec![glyph.clone()])),
+        }
+    }
+    let mut joined: Vec<LineBuilder> = Vec::new();
+    for fragment in fragments {
+        match joined.iter_mut().find(|line| line.is_continued_by(fragment.x0, fragment.y, fragment.size)) {
+            Some(line) => line.append(fragment),
+            None => joined.push(fragment),
+        }
+    }
+    joined.into_iter().filter_map(LineBuilder::build).collect()
+}
+
+/// Returns the union of boxes.
+fn union(boxes: impl Iterator<Item = [f64; 4]>) -> [f64; 4] {
+    boxes.fold([f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY], |a, b| {
+        [a[0].min(b[0]), a[1].min(b[1]), a[2].max(b[2]), a[3].max(b[3])]
+    })
+}
+
+/// Groups lines into blocks of consecutive, horizontally overlapping lines.
+fn build_blocks(mut lines: Vec<TextLine>) -> Vec<TextBlock> {
+    lines.sort_by(|a, b| b.baseline.total_cmp(&a.baseline).then(a.bbox[0].total_cmp(&b.bbox[0])));
+    let mut blocks: Vec<TextBlock> = Vec::new();
+    for line in lines {
+        match blocks.iter_mut().find(|block| block.is_continued_by(&line)) {
+            Some(block) => block.push(line),
+            None => blocks.push(TextBlock::new(line)),
+        }
+    }
+    blocks
+}
+
+/// Orders blocks for reading by recursive XY-cut.
+///
+/// The blocks are first cut into horizontal bands along the whitespace running
+/// across the whole region. Consecutive bands split by the same vertical gutter,
+/// or lying on either side of it, form columns, which are read one after the other.
+fn order_blocks(mut blocks: Vec<TextBlock>) -> Vec<TextBlock> {
+    if blocks.len() <= 1 {
+        return blocks;
+    }
+    let mut bands = horizontal_bands(blocks);
+    if bands.len() == 1 {
+        blocks = bands.remove(0);
+        return match vertical_gutter(&blocks) {
+            Some(gutter) => split_columns(blocks, gutter),
+            None => {
+                blocks.sort_by(|a, b| b.top.total_cmp(&a.top).then(a.x0.total_cmp(&b.x0)));
+                blocks
+            }
+        };
+    }
+    let mut ordered = Vec::new();
+    let mut group: Vec<TextBlock> = Vec::new();
+    let mut group_gutter: Option<(f64, f64)> = None;
+    let mut group_bands = 0;
+    for band in bands {
+        let gutter = vertical_gutter(&band);
+        // A band continues the columns if it is split by the same gutter, or if it
+        // lies on one side of it, like a paragraph ending lower than its neighbour
+        let shared = match (group_gutter, gutter) {
+            (Some((a0, a1)), Some((b0, b1))) if a0.max(b0) < a1.min(b1) => Some((a0.max(b0), a1.min(b1))),
+            (Some((a0, a1)), None) if continues_columns(&group, &band, (a0, a1)) => Some((a0, a1)),
+            _ => None,
+        };
+        if shared.is_some() {
+            group.extend(band);
+            group_gutter = shared;
+            group_bands += 1;
+            continue;
+        }
+        ordered.extend(flush_group(std::mem::take(&mut group), group_gutter, group_bands));
+        group = band;
+        group_gutter = gutter;
+        group_bands = 1;
+    }
+    ordered.extend(flush_group(group, group_gutter, group_bands));
+    ordered
+}
+
+/// Returns true if a band without gutter lies on either side of the gutter of
+/// the columns above it, close enough to continue them.
+fn continues_columns(columns: &[TextBlock], band: &[TextBlock], gutter: (f64, f64)) -> bool {
+    let bottom = columns.iter().map(|block| block.bottom).fold(f64::INFINITY, f64::min);
+    band.iter().all(|block| {
+        let first = &block.lines[0];
+        (block.x1 <= gutter.0 || block.x0 >= gutter.1) && bottom - first.baseline <= PARAGRAPH_SPACING * first.font_size
+    })
+}
+
+/// Orders a group of bands, splitting it at its gutter if it spans several bands.
+fn flush_group(group: Vec<TextBlock>, gutter: Option<(f64, f64)>, bands: usize) -> Vec<TextBlock> {
+    match gutter {
+        Some(gutter) if bands > 1 => split_columns(group, gutter),
+        _ => order_blocks(group),
+    }
+}
+
+/// Splits blocks at a vertical gutter and orders the left side before the right side.
+fn split_columns(blocks: Vec<TextBlock>, gutter: (f64, f64)) -> Vec<TextBlock> {
+    let (left, right): (Vec<TextBlock>, Vec<TextBlock>) = blocks.into_iter().partition(|block| block.x1 <= gutter.0);
+    let mut ordered = order_blocks(left);
+    ordered.extend(order_blocks(right));
+    ordered
+}
+
+/// Cuts blocks into bands, from top to bottom, along horizontal whitespace.
+fn horizontal_bands(mut blocks: Vec<TextBlock>) -> Vec<Vec<TextBlock>> {
+    blocks.sort_by(|a, b| b.top.total_cmp(&a.top));
+    let mut bands: Vec<Vec<TextBlock>> = Vec::new();
+    let mut bottom = f64::INFINITY;
+    for block in blocks {
+        match bands.last_mut() {
+            Some(band) if block.top > bottom => {
+                bottom = bottom.min(block.bottom);
+                band.push(block);
+            }
+            _ => {
+                bottom = block.bottom;
+                bands.push(vec![block]);
+            }
+        }
+    }
+    bands
+}
+
+/// Finds the leftmost vertical whitespace running across all blocks.
+///
+/// # Returns
+///
+/// The horizontal extent of the gutter, or None if the blocks can't be cut vertically
+fn vertical_gutter(blocks: &[TextBlock]) -> Option<(f64, f64)> {
+    let mut extents = blocks.iter().map(|block| (block.x0, block.x1)).collect::<Vec<(f64, f64)>>();
+    extents.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut right = f64::NEG_INFINITY;
+    for (x0, x1) in extents {
+        if right > f64::NEG_INFINITY && x0 > right {
+            return Some((right, x0));
+        }
+        right = right.max(x1);
+    }
+    None
+}
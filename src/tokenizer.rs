@@ -1,73 +1,71 @@
-use crate::constants::is_key;
+use crate::constants::as_key;
 use crate::error::PDFError;
-use crate::error::PDFError::{PDFParseError0};
+use crate::error::PDFError::{EOFError, PDFParseError, PDFParseError0};
 use crate::error::Result;
 use crate::objects::PDFNumber;
 use crate::sequence::Sequence;
-use crate::tokenizer::Token::{Bool, Delimiter, Eof, Id, Key, Number};
-use crate::utils::{hexdump, line_ending};
+use crate::tokenizer::Token::{Delimiter, Eof, HexStr, Id, Key, LiteralStr, Name, Number};
+use crate::utils::{hex2bytes, is_delimiter, is_whitespace, line_ending};
+use std::borrow::Cow;
+use std::collections::VecDeque;
 use std::ops::Range;
 
-/// Common end characters
-const COMMON_END_CHARS: [char; 11] = [
-    '<',
-    '>',
-    '(',
-    ')',
-    '\r',
-    '\n',
-    '\\',
-    ' ',
-    '/',
-    '[',
-    ']',
-];
+/// The size of each read from a streamed sequence.
+const READ_SIZE: usize = 4096;
 
+/// Splits PDF data into tokens.
+///
+/// Tokens borrow the bytes they were lexed from, so identifiers, names and
+/// strings without escapes are never copied. When the sequence keeps its data
+/// in memory (see [`Sequence::as_slice`]), tokens borrow that data in place.
+/// Otherwise the data is read into `buf` on demand.
+///
+/// Peeked tokens are kept as spans in `lookahead` and lexed again when they are
+/// consumed, which keeps the tokenizer free of self-references.
 pub(crate) struct Tokenizer {
+    /// Bytes read from a streamed sequence, unused for in-memory sequences.
     buf: Vec<u8>,
-    token_buf: Vec<Token>,
+    /// Index of the next unread byte in `buf`.
+    cursor: usize,
+    /// Absolute offset of the next unread byte.
+    offset: u64,
+    /// Absolute spans of the tokens peeked but not yet consumed.
+    lookahead: VecDeque<Range<u64>>,
     sequence: Box<dyn Sequence>,
 }
 
-#[derive(PartialEq, Clone)]
-pub(crate) enum Token {
-    Id(String),
-    Bool(bool),
-    Key(String),
+#[derive(PartialEq, Clone, Debug)]
+pub(crate) enum Token<'a> {
+    /// A regular token that is neither a keyword nor a number, e.g. a content stream operator.
+    Id(&'a str),
+    Key(&'static str),
+    /// A name with its `#xx` escapes decoded, without the leading slash.
+    Name(Cow<'a, str>),
+    /// A literal string with its escapes decoded.
+    LiteralStr(Cow<'a, [u8]>),
+    /// A hexadecimal string decoded to bytes.
+    HexStr(Vec<u8>),
     Number(PDFNumber),
-    Delimiter(String),
+    Delimiter(&'static str),
     Eof,
 }
 
-impl Token {
-    pub(crate) fn is_numer(&self) -> bool {
-        if let Number(_) = self {
-            return true;
-        }
-        false
-    }
-
+impl Token<'_> {
     pub(crate) fn is_u64(&self) -> bool {
-        if let Number(PDFNumber::Unsigned(_)) = self {
-            true
-        } else {
-            false
-        }
-    }
-
-    pub(crate) fn is_id(&self) -> bool {
-        if let Id(_) = self { true } else { false }
+        matches!(self, Number(PDFNumber::Unsigned(_)))
     }
 
-    pub(crate) fn to_string(&self) -> String {
+    pub(crate) fn to_text(&self) -> String {
         match self {
-            Id(id) => id.clone(),
-            Key(key) => key.clone(),
-            Delimiter(delimiter) => delimiter.clone(),
+            Id(id) => id.to_string(),
+            Key(key) => key.to_string(),
+            Name(name) => format!("/{}", name),
+            LiteralStr(buf) => format!("({})", String::from_utf8_lossy(buf)),
+            HexStr(buf) => format!("<{}>", buf.iter().map(|b| format!("{:02x}", b)).collect::<String>()),
+            Delimiter(delimiter) => delimiter.to_string(),
             Number(PDFNumber::Unsigned(num)) => num.to_string(),
             Number(PDFNumber::Signed(num)) => num.to_string(),
             Number(PDFNumber::Real(num)) => num.to_string(),
-            Bool(bool) => bool.to_string(),
             Eof => "_eof".to_string(),
         }
     }
@@ -76,21 +74,21 @@ impl Token {
         if let Number(PDFNumber::Unsigned(num)) = self {
             return Ok(*num);
         }
-        Err(PDFParseError0(format!("Token can't convert to u64:'{}'",self.to_string())))
+        Err(PDFParseError0(format!("Token can't convert to u64:'{}'",self.to_text())))
     }
 
     pub(crate) fn as_u32(&self) -> Result<u32> {
         if let Number(PDFNumber::Unsigned(num)) = self {
             return Ok(*num as u32);
         }
-        Err(PDFParseError0(format!("Token can't convert to u32:'{}'", self.to_string())))
+        Err(PDFParseError0(format!("Token can't convert to u32:'{}'", self.to_text())))
     }
-    
+
     pub(crate) fn as_u16(&self) -> Result<u16> {
         if let Number(PDFNumber::Unsigned(num)) = self {
             return Ok(*num as u16);
         }
-        Err(PDFParseError0(format!("Token can't convert to u16:'{}'", self.to_string())))
+        Err(PDFParseError0(format!("Token can't convert to u16:'{}'", self.to_text())))
     }
 
     pub(crate) fn except<F>(self, func: F) -> Result<Self>
@@ -106,20 +104,15 @@ impl Token {
 
     pub(crate) fn key_was(&self, str: &str) -> bool {
         if let Key(key) = self {
-            return key == str;
+            return *key == str;
         }
         false
     }
-    
-    /// Returns true if the token is a delimiter.
-    pub(crate) fn is_delimiter(&self) -> bool {
-        if let Delimiter(_) = self { true } else { false }
-    }
 
-    /// Returns true if the token is a delimiter and the delimiter is the specified string.
+    /// Returns true if the token is the given delimiter.
     pub(crate) fn delimiter_was(&self, str: &str) -> bool {
         if let Delimiter(delimiter) = self {
-            return delimiter == str;
+            return *delimiter == str;
         }
         false
     }
@@ -130,245 +123,526 @@ impl Tokenizer {
         Self {
             sequence: Box::new(sequence),
             buf: Vec::new(),
-            token_buf: Vec::new(),
+            cursor: 0,
+            offset: 0,
+            lookahead: VecDeque::new(),
         }
     }
 
-    pub(crate) fn check_next_token<F>(&mut self, func: F) -> Result<bool>
-    where
-        F: FnMut(&Token) -> bool,
-    {
-        self.check_next_token0(true, func)
+    /// Returns the next token, consuming it.
+    pub(crate) fn next_token(&mut self) -> Result<Token<'_>> {
+        let span = match self.lookahead.pop_front() {
+            Some(span) => span,
+            None => match self.scan(self.offset)? {
+                Some(span) => span,
+                None => return Ok(Eof),
+            },
+        };
+        self.consume((span.end - self.offset) as usize);
+        self.token_of(span)
     }
 
-    pub(crate) fn check_next_token0<F>(&mut self, cache: bool,mut func: F) -> Result<bool>
+    /// Returns the `n`-th token after the current position without consuming it.
+    pub(crate) fn peek_token(&mut self, n: usize) -> Result<Token<'_>> {
+        while self.lookahead.len() <= n {
+            let pos = self.lookahead.back().map_or(self.offset, |span| span.end);
+            match self.scan(pos)? {
+                Some(span) => self.lookahead.push_back(span),
+                None => return Ok(Eof),
+            }
+        }
+        let span = self.lookahead[n].clone();
+        self.token_of(span)
+    }
+
+    /// Consumes the next token if it satisfies `func`.
+    ///
+    /// Returns whether the token was consumed.
+    pub(crate) fn next_token_if<F>(&mut self, func: F) -> Result<bool>
     where
-        F: FnMut(&Token) -> bool,
+        F: FnOnce(&Token) -> bool,
     {
-        let token = if let Some(chr) = self.next_chr()? {
-            self.chr2token(chr)?
-        } else {
-            Eof
-        };
-        let m = func(&token);
-        if !m || cache {
-            self.token_buf.push(token);
+        let m = func(&self.peek_token(0)?);
+        if m {
+            self.next_token()?;
         }
         Ok(m)
     }
 
-    pub(crate) fn next_token(&mut self) -> Result<Token> {
-        let token_buf = &mut self.token_buf;
-        if !token_buf.is_empty() {
-            return Ok(token_buf.remove(0));
+    /// Returns the unread bytes currently available without reading the sequence.
+    ///
+    /// For in-memory sequences this is the rest of the data, borrowed in place.
+    fn window(&self) -> &[u8] {
+        match self.sequence.as_slice() {
+            Some(data) => &data[self.offset as usize..],
+            None => &self.buf[self.cursor..],
+        }
+    }
+
+    /// Returns the available bytes from the absolute position `pos` on.
+    fn window_from(&self, pos: u64) -> &[u8] {
+        &self.window()[(pos - self.offset) as usize..]
+    }
+
+    /// Reads more data into the window.
+    ///
+    /// Returns false if no more data is available.
+    fn fill(&mut self) -> Result<bool> {
+        if self.sequence.as_slice().is_some() {
+            return Ok(false);
         }
-        let option = self.next_chr()?;
-        if option.is_none() {
-            return Ok(Eof);
+        // Drop the consumed bytes before growing the buffer, peeked tokens are
+        // never consumed so their spans stay in the window
+        if self.cursor > 0 {
+            self.buf.drain(0..self.cursor);
+            self.cursor = 0;
         }
-        self.chr2token(option.unwrap())
+        let len = self.buf.len();
+        self.buf.resize(len + READ_SIZE, 0);
+        let n = self.sequence.read(&mut self.buf[len..])?;
+        self.buf.truncate(len + n);
+        Ok(n > 0)
     }
 
-    fn chr2token(&mut self, chr: char) -> Result<Token> {
-        let token = match chr {
-            '<' => match self.next_chr_was('<') {
-                true => Delimiter(String::from("<<")),
-                false => Delimiter(String::from('<')),
-            },
-            '>' => match self.next_chr_was('>') {
-                true => Delimiter(String::from(">>")),
-                false => Delimiter(String::from(">")),
-            },
-            '/' | '(' | ')' | '[' | ']' => Delimiter(chr.into()),
-            '+' | '-' | '.' => self.num_deco(chr)?,
-            chr => {
-                // If the character is a digit, then we need to read the number
-                if chr.is_digit(10) {
-                    self.num_deco(chr)?
-                }
-                // Identifier
-                else {
-                    let range = self.loop_util(&COMMON_END_CHARS, |_c| Ok(false))?;
-                    let mut buf = self.buf.drain(range).collect::<Vec<u8>>();
-                    buf.insert(0, chr as u8);
-                    let text = String::from_utf8(buf)?;
-                    if is_key(text.as_str()) {
-                        return Ok(Key(text));
-                    }
-                    Id(text)
-                }
+    /// Makes sure the byte at the absolute position `pos` is in the window.
+    ///
+    /// Returns false if the data ends before `pos`.
+    fn ensure(&mut self, pos: u64) -> Result<bool> {
+        while pos >= self.offset + self.window().len() as u64 {
+            if !self.fill()? {
+                return Ok(false);
             }
-        };
-        Ok(token)
+        }
+        Ok(true)
     }
 
-    fn num_deco(&mut self, chr: char) -> Result<Token> {
-        let mut is_real = chr == '.';
-        let range = self.loop_util(&COMMON_END_CHARS, |c| {
-            let is_dot = c == '.';
-            // If the character is a dot, then we need to check if it is a valid real number
-            if is_dot {
-                if is_real {
-                    return Err(PDFError::PDFParseError("Multiple dot was found in real number."));
-                }
-                is_real = true;
-            } else {
-                let is_digit = c.is_digit(10);
-                if !is_digit {
-                    return Err(PDFError::PDFParseError0(format!("Invalid number character: {:0x}", c as u8)));
-                }
+    /// Marks the next `len` bytes of the window as read.
+    fn consume(&mut self, len: usize) {
+        self.offset += len as u64;
+        if self.sequence.as_slice().is_none() {
+            self.cursor += len;
+        }
+    }
+
+    /// Finds the span of the first token at or after the absolute position `pos`,
+    /// skipping whitespace and comments.
+    ///
+    /// Returns None at the end of the data.
+    fn scan(&mut self, mut pos: u64) -> Result<Option<Range<u64>>> {
+        loop {
+            if !self.ensure(pos)? {
+                return Ok(None);
             }
-            return Ok(false);
-        })?;
-        let mut bytes = self.buf.drain(range).collect::<Vec<u8>>();
-        bytes.insert(0, chr as u8);
-        let text = String::from_utf8(bytes)?;
-        let value = if is_real {
-            PDFNumber::Real(text.parse::<f64>()?)
-        } else {
-            let signed = chr == '-';
-            if signed {
-                PDFNumber::Signed(text.parse::<i64>()?)
-            } else {
-                PDFNumber::Unsigned(text.parse::<u64>()?)
+            let window = self.window_from(pos);
+            let n = window.iter().take_while(|b| is_whitespace(**b)).count();
+            pos += n as u64;
+            if n == window.len() {
+                continue;
+            }
+            if window[n] != b'%' {
+                break;
             }
+            // A comment runs to the end of the line
+            pos = self.scan_while(pos, |b| !line_ending(b))?;
+        }
+        let start = pos;
+        let first = self.window_from(pos)[0];
+        let end = match first {
+            b'(' => self.scan_literal_str(pos)?,
+            b'<' if self.next_byte_at(pos)? == Some(b'<') => pos + 2,
+            b'<' => {
+                let end = self.scan_while(pos + 1, |b| b != b'>')?;
+                if !self.ensure(end)? {
+                    return Err(PDFParseError("String did not close properly"));
+                }
+                end + 1
+            }
+            b'>' if self.next_byte_at(pos)? == Some(b'>') => pos + 2,
+            b'/' => self.scan_while(pos + 1, |b| !is_whitespace(b) && !is_delimiter(b))?,
+            b if is_delimiter(b) => pos + 1,
+            _ => self.scan_while(pos, |b| !is_whitespace(b) && !is_delimiter(b))?,
         };
-        Ok(Number(value))
+        Ok(Some(start..end))
+    }
+
+    /// Returns the byte following `pos`, reading more data if needed.
+    fn next_byte_at(&mut self, pos: u64) -> Result<Option<u8>> {
+        if !self.ensure(pos + 1)? {
+            return Ok(None);
+        }
+        Ok(Some(self.window_from(pos + 1)[0]))
     }
 
-    pub(crate) fn loop_util<F>(&mut self, end_chars: &[char], mut func: F) -> Result<Range<usize>>
+    /// Advances from `pos` while `func` holds, returning the position of the
+    /// first byte failing it or the end of the data.
+    fn scan_while<F>(&mut self, mut pos: u64, func: F) -> Result<u64>
     where
-        F: FnMut(char) -> Result<bool>,
+        F: Fn(u8) -> bool,
     {
-        let mut index = 0usize;
-        let buf = &mut self.buf;
-        'ext: loop {
-            // If index is equal to buffer length, then we need to read more data
-            if index == buf.len() {
-                let mut bytes = [0u8; 1024];
-                let n = self.sequence.read(&mut bytes)?;
-                if n == 0 {
-                    return Err(PDFError::EOFError);
-                }
-                buf.extend_from_slice(&bytes[0..n]);
+        while self.ensure(pos)? {
+            let window = self.window_from(pos);
+            match window.iter().position(|b| !func(*b)) {
+                Some(i) => return Ok(pos + i as u64),
+                None => pos += window.len() as u64,
             }
-            let len = buf.len();
-            for i in index..len {
-                let chr = char::from(buf[i]);
-                if end_chars.contains(&chr) || func(chr)? {
-                    index = i;
-                    break 'ext;
+        }
+        Ok(pos)
+    }
+
+    /// Finds the end of the literal string starting at `pos`, balancing nested parentheses.
+    fn scan_literal_str(&mut self, mut pos: u64) -> Result<u64> {
+        let mut depth = 0usize;
+        let mut is_escape = false;
+        while self.ensure(pos)? {
+            let window = self.window_from(pos);
+            for (i, b) in window.iter().enumerate() {
+                if is_escape {
+                    is_escape = false;
+                    continue;
+                }
+                match b {
+                    b'\\' => is_escape = true,
+                    b'(' => depth += 1,
+                    b')' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Ok(pos + i as u64 + 1);
+                        }
+                    }
+                    _ => {}
                 }
             }
-            index = len;
+            pos += window.len() as u64;
         }
-        Ok(0..index)
+        Err(PDFParseError("String did not close properly"))
     }
 
-    fn next_chr(&mut self) -> Result<Option<char>> {
-        let option = match self.next_chr0(|_| true)? {
-            None => None,
-            Some((_, chr)) => Some(chr),
+    /// Lexes the token within `span`, borrowing its bytes.
+    ///
+    /// The span may lie just before the window, since consumed bytes are only
+    /// dropped from `buf` by the next fill.
+    fn token_of(&self, span: Range<u64>) -> Result<Token<'_>> {
+        let bytes = match self.sequence.as_slice() {
+            Some(data) => &data[span.start as usize..span.end as usize],
+            None => {
+                let start = (self.cursor as u64 + span.start - self.offset) as usize;
+                &self.buf[start..start + (span.end - span.start) as usize]
+            }
         };
-        Ok(option)
+        let token = match bytes[0] {
+            b'(' => LiteralStr(decode_literal_str(&bytes[1..bytes.len() - 1])),
+            b'<' if bytes.len() == 2 && bytes[1] == b'<' => Delimiter("<<"),
+            b'<' => HexStr(decode_hex_str(&bytes[1..bytes.len() - 1])?),
+            b'>' if bytes.len() == 2 => Delimiter(">>"),
+            b'>' => Delimiter(">"),
+            b')' => Delimiter(")"),
+            b'[' => Delimiter("["),
+            b']' => Delimiter("]"),
+            b'{' => Delimiter("{"),
+            b'}' => Delimiter("}"),
+            b'/' => Name(decode_name(&bytes[1..])),
+            b'0'..=b'9' | b'+' | b'-' | b'.' => Number(decode_number(bytes)?),
+            _ => {
+                let text = std::str::from_utf8(bytes)
+                    .map_err(|_| PDFParseError("Token is not a valid utf8 text."))?;
+                match as_key(text) {
+                    Some(key) => Key(key),
+                    None => Id(text),
+                }
+            }
+        };
+        Ok(token)
     }
 
-    fn next_chr_was(&mut self, chr: char) -> bool {
-        match self.next_chr0(|c| c == chr) {
-            Ok(Some((equal, _))) => equal,
-            _ => false,
+    pub(crate) fn seek(&mut self, offset: u64) -> Result<u64> {
+        let n = self.sequence.seek(offset)?;
+        self.lookahead.clear();
+        self.buf.clear();
+        self.cursor = 0;
+        self.offset = n;
+        Ok(n)
+    }
+
+    /// Returns the absolute offset of the next unread byte.
+    ///
+    /// Peeked tokens are not consumed, so they are not counted in the position.
+    pub(crate) fn position(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the size of the sequence in bytes.
+    pub(crate) fn size(&self) -> Result<u64> {
+        self.sequence.size()
+    }
+
+    /// Returns the whole data, or None if the sequence doesn't keep its data in memory.
+    pub(crate) fn get_data(&self) -> Option<&[u8]> {
+        self.sequence.as_slice()
+    }
+
+    /// Returns the unread data, or None if the sequence doesn't keep its data in memory.
+    pub(crate) fn get_unread(&self) -> Option<&[u8]> {
+        self.sequence.as_slice().map(|data| &data[self.offset as usize..])
+    }
+
+    /// Reads `len` bytes, fewer if the end of the data comes first.
+    pub(crate) fn read_bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        // The length may come from the document, don't allocate past the end
+        let remaining = self.size()?.saturating_sub(self.position());
+        let len = len.min(usize::try_from(remaining).unwrap_or(usize::MAX));
+        self.lookahead.clear();
+        let window = self.window();
+        let available = window.len().min(len);
+        let mut buf = Vec::<u8>::with_capacity(len);
+        buf.extend_from_slice(&window[0..available]);
+        self.consume(available);
+        if available < len && self.sequence.as_slice().is_none() {
+            // The window is drained, read the rest straight from the sequence
+            let mut filled = available;
+            buf.resize(len, 0);
+            // A single read may return fewer bytes than requested
+            while filled < len {
+                let n = self.sequence.read(&mut buf[filled..])?;
+                if n == 0 {
+                    break;
+                }
+                filled += n;
+            }
+            buf.truncate(filled);
+            self.buf.clear();
+            self.cursor = 0;
+            self.offset += (filled - available) as u64;
         }
+        Ok(buf)
     }
 
-    /// Read next byte
-    fn next_chr0<F>(&mut self, func: F) -> Result<Option<(bool, char)>>
-    where
-        F: Fn(char) -> bool,
-    {
-        let buf = &mut self.buf;
-        let mut bytes = [0u8; 1024];
-        if buf.is_empty() {
-            let n = self.sequence.read(&mut bytes)?;
-            if n == 0 {
-                return Ok(None);
+    /// Reads bytes until `pattern` is encountered.
+    ///
+    /// The returned bytes don't contain the pattern, which stays unread so
+    /// that it can be read as the next token.
+    pub(crate) fn read_until(&mut self, pattern: &[u8]) -> Result<Vec<u8>> {
+        self.lookahead.clear();
+        let mut from = 0usize;
+        loop {
+            let window = self.window();
+            if let Some(index) = window[from..].windows(pattern.len()).position(|w| w == pattern) {
+                let buf = window[0..from + index].to_vec();
+                self.consume(buf.len());
+                return Ok(buf);
             }
-            buf.extend_from_slice(&bytes[0..n]);
-        }
-        let len = buf.len();
-        let mut skip_cunt = 0;
-        for i in 0..len {
-            let b = buf[i];
-            if line_ending(b) || b == b' ' {
-                skip_cunt += 1;
-            } else {
-                break;
+            // The pattern may straddle the window boundary
+            from = window.len().saturating_sub(pattern.len() - 1);
+            if !self.fill()? {
+                return Err(EOFError);
             }
         }
-        if skip_cunt > 0 {
-            buf.drain(0..skip_cunt);
+    }
+
+    /// Skip the end-of-line marker following the `stream` keyword.
+    ///
+    /// The marker is either CRLF or a single LF; a lone CR is tolerated as well.
+    /// Only one marker is skipped, since the stream data may itself start with
+    /// line ending bytes.
+    ///
+    /// Return the number of bytes skipped
+    pub(crate) fn skip_eol(&mut self) -> Result<usize> {
+        self.lookahead.clear();
+        let mut count = 0usize;
+        if self.next_byte_was(b'\r')? {
+            count += 1;
+        }
+        if self.next_byte_was(b'\n')? {
+            count += 1;
         }
-        // If buffer is empty, then we need to read more data
-        if buf.is_empty() {
-            return self.next_chr0(func);
+        Ok(count)
+    }
+
+    /// Consume the next byte if it equals `b`, without skipping whitespace.
+    fn next_byte_was(&mut self, b: u8) -> Result<bool> {
+        if !self.ensure(self.offset)? {
+            return Ok(false);
         }
-        let b = buf[0];
-        let chr = char::from(b);
-        let equal = func(chr);
+        let equal = self.window()[0] == b;
         if equal {
-            buf.remove(0);
+            self.consume(1);
         }
-        Ok(Some((equal, chr)))
+        Ok(equal)
     }
+}
 
-    pub(crate) fn seek(&mut self, offset: u64) -> Result<u64> {
-        let n = self.sequence.seek(offset)?;
-        self.token_buf.clear();
-        self.buf.clear();
-        Ok(n)
+/// Decodes the escape sequences of a literal string.
+///
+/// End-of-line markers are normalized to a single LF as required by the
+/// specification, and a backslash before an end-of-line marker continues the
+/// string on the next line.
+fn decode_literal_str(bytes: &[u8]) -> Cow<'_, [u8]> {
+    if !bytes.iter().any(|b| *b == b'\\' || *b == b'\r') {
+        return Cow::Borrowed(bytes);
+    }
+    let mut buf = Vec::with_capacity(bytes.len());
+    let mut i = 0usize;
+    while i < bytes.len() {
+        let b = bytes[i];
+        i += 1;
+        if b == b'\r' {
+            if bytes.get(i) == Some(&b'\n') {
+                i += 1;
+            }
+            buf.push(b'\n');
+            continue;
+        }
+        if b != b'\\' || i == bytes.len() {
+            buf.push(b);
+            continue;
+        }
+        let escaped = bytes[i];
+        i += 1;
+        match escaped {
+            b'n' => buf.push(b'\n'),
+            b'r' => buf.push(b'\r'),
+            b't' => buf.push(b'\t'),
+            b'b' => buf.push(0x08),
+            b'f' => buf.push(0x0C),
+            b'0'..=b'7' => {
+                // Up to three octal digits, high-order overflow is ignored
+                let mut value = (escaped - b'0') as u32;
+                let mut digits = 1;
+                while digits < 3 && i < bytes.len() && (b'0'..=b'7').contains(&bytes[i]) {
+                    value = value * 8 + (bytes[i] - b'0') as u32;
+                    digits += 1;
+                    i += 1;
+                }
+                buf.push(value as u8);
+            }
+            b'\r' => {
+                if bytes.get(i) == Some(&b'\n') {
+                    i += 1;
+                }
+            }
+            b'\n' => {}
+            // `\(`, `\)`, `\\` and unknown escapes yield the escaped byte
+            _ => buf.push(escaped),
+        }
     }
+    Cow::Owned(buf)
+}
 
-    pub(crate) fn read_bytes(&mut self, len: usize) -> Result<Vec<u8>> {
-        let buf_len = self.buf.len();
-        let buf = if buf_len >= len {
-            self.buf.drain(0..len).collect::<Vec<u8>>()
+/// Decodes a hexadecimal string, ignoring whitespace between the digits.
+fn decode_hex_str(bytes: &[u8]) -> Result<Vec<u8>> {
+    let digits = bytes.iter()
+        .copied()
+        .filter(|b| !is_whitespace(*b))
+        .collect::<Vec<u8>>();
+    if let Some(b) = digits.iter().find(|b| !b.is_ascii_hexdigit()) {
+        return Err(PDFParseError0(format!("Invalid hexadecimal string character: {:02x}", b)));
+    }
+    Ok(hex2bytes(&digits))
+}
+
+/// Decodes the `#xx` escapes of a name.
+///
+/// Names are byte sequences, those which aren't valid UTF-8 are converted lossily.
+fn decode_name(bytes: &[u8]) -> Cow<'_, str> {
+    if !bytes.contains(&b'#') {
+        return String::from_utf8_lossy(bytes);
+    }
+    let mut buf = Vec::with_capacity(bytes.len());
+    let mut i = 0usize;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b == b'#' && i + 2 < bytes.len()
+            && bytes[i + 1].is_ascii_hexdigit() && bytes[i + 2].is_ascii_hexdigit() {
+            buf.extend(hex2bytes(&bytes[i + 1..i + 3]));
+            i += 3;
         } else {
-            let diff = len - buf_len;
-            let mut bytes = vec![0u8; diff];
-            let n = self.sequence.read(&mut bytes)?;
-            let mut buf = Vec::<u8>::new();
-            buf.extend_from_slice(&self.buf);
-            buf.extend_from_slice(&bytes[0..n]);
-            // Should clear buffer
-            self.buf.clear();
-            buf
-        };
-        #[cfg(debug_assertions)]
-        {
-            hexdump(&buf);
+            buf.push(b);
+            i += 1;
         }
-        // Clear token buffer
-        self.token_buf.clear();
-        Ok(buf)
     }
+    Cow::Owned(String::from_utf8_lossy(&buf).into_owned())
+}
 
-    pub(crate) fn drain_from_buf(&mut self, range: Range<usize>) -> Vec<u8> {
-        self.buf.drain(range).collect()
+/// Decodes an integer or real number.
+fn decode_number(bytes: &[u8]) -> Result<PDFNumber> {
+    let text = std::str::from_utf8(bytes)
+        .map_err(|_| PDFParseError("Number is not a valid utf8 text."))?;
+    let body = text.strip_prefix(['+', '-']).unwrap_or(text);
+    if body.is_empty() || !body.bytes().all(|b| b.is_ascii_digit() || b == b'.') {
+        return Err(PDFParseError0(format!("Invalid number: {}", text)));
     }
-
-    pub(crate) fn remove_buf_len(&mut self, len: usize) {
-        self.buf.drain(0..len);
+    if body.bytes().filter(|b| *b == b'.').count() > 1 {
+        return Err(PDFParseError("Multiple dot was found in real number."));
     }
+    let value = if body.contains('.') {
+        // A lone dot is a valid PDF number but not a valid Rust float
+        PDFNumber::Real(if body == "." { 0.0 } else { text.parse::<f64>()? })
+    } else if text.starts_with('-') {
+        match text.parse::<i64>() {
+            Ok(value) => PDFNumber::Signed(value),
+            Err(_) => PDFNumber::Real(text.parse::<f64>()?),
+        }
+    } else {
+        match body.parse::<u64>() {
+            Ok(value) => PDFNumber::Unsigned(value),
+            Err(_) => PDFNumber::Real(body.parse::<f64>()?),
+        }
+    };
+    Ok(value)
+}
 
-    /// Skip CRLF
-    ///
-    /// Return the number of bytes skipped
-    pub(crate) fn skip_crlf(&mut self) -> Result<usize> {
-        let range = self.loop_util(&[], |chr| Ok(chr != '\r' && chr != '\n'))?;
-        let mut count = 0usize;
-        if range.start < range.end {
-            count = range.end - range.start;
-            self.remove_buf_len(range.end);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence::MemorySequence;
+
+    /// Collects all tokens of `data` as text.
+    fn tokens(data: &'static [u8]) -> Result<Vec<String>> {
+        let mut tokenizer = Tokenizer::new(MemorySequence::new(data));
+        let mut tokens = Vec::new();
+        loop {
+            let token = tokenizer.next_token()?;
+            if token == Eof {
+                return Ok(tokens);
+            }
+            tokens.push(token.to_text());
         }
-        Ok(count)
+    }
+
+    /// Tests lexing of the basic token kinds, including tokens at the end of
+    /// the data and comments.
+    #[test]
+    fn test_tokens() -> Result<()> {
+        let data = b"<</R[1 0 R]/A#20B -.5 +3 % comment\r\n(a(b)\\051\\n)<4 1 4>>>true ET";
+        assert_eq!(tokens(data)?, [
+            "<<", "/R", "[", "1", "0", "R", "]", "/A B", "-0.5", "3",
+            "(a(b))\n)", "<4140>", ">>", "true", "ET",
+        ]);
+        Ok(())
+    }
+
+    /// Tests lexing of PostScript calculator functions, whose procedures are
+    /// delimited by braces which may touch the tokens around them.
+    #[test]
+    fn test_calculator_tokens() -> Result<()> {
+        let data = b"{2 copy gt{exch}if pop 1.5{-1}{true}ifelse}";
+        assert_eq!(tokens(data)?, [
+            "{", "2", "copy", "gt", "{", "exch", "}", "if", "pop",
+            "1.5", "{", "-1", "}", "{", "true", "}", "ifelse", "}",
+        ]);
+        Ok(())
+    }
+
+    /// Tests that peeked tokens are returned again by `next_token`.
+    #[test]
+    fn test_peek_token() -> Result<()> {
+        let mut tokenizer = Tokenizer::new(MemorySequence::new(b"12 0 obj".as_slice()));
+        assert!(tokenizer.peek_token(1)?.is_u64());
+        assert!(tokenizer.peek_token(2)?.key_was("obj"));
+        assert_eq!(tokenizer.next_token()?.as_u64()?, 12);
+        assert!(tokenizer.next_token_if(|token| token.is_u64())?);
+        assert!(!tokenizer.next_token_if(|token| token.is_u64())?);
+        assert!(tokenizer.next_token()?.key_was("obj"));
+        assert_eq!(tokenizer.next_token()?, Eof);
+        Ok(())
     }
 }
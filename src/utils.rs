@@ -93,11 +93,9 @@ hex_convert!(
 ///
 /// The parsed u64 value
 pub(crate) fn literal_to_u64(bytes: &[u8]) -> u64 {
-    let len = bytes.len();
     let mut value: u64 = 0;
-    for i in 0..len {
-        let b = bytes[i] - 48;
-        value = (value * 10) + b as u64;
+    for b in bytes {
+        value = (value * 10) + (b - b'0') as u64;
     }
     value
 }
@@ -115,14 +113,7 @@ pub(crate) fn literal_to_u64(bytes: &[u8]) -> u64 {
 ///
 /// The count of leading line ending characters
 pub(crate) fn count_leading_line_endings(bytes: &[u8]) -> u64 {
-    let mut count = 0u64;
-    for i in 0..bytes.len() {
-        if !line_ending(bytes[i]) {
-            break;
-        }
-        count += 1;
-    }
-    count
+    bytes.iter().take_while(|b| line_ending(**b)).count() as u64
 }
 
 /// Checks if a byte represents a line ending character.
@@ -142,6 +133,22 @@ pub(crate) fn line_ending(b: u8) -> bool {
     b == b'\r' || b == b'\n'
 }
 
+/// Checks if a byte is a PDF whitespace character.
+///
+/// The whitespace characters are NUL, TAB, LF, FF, CR and SPACE.
+#[inline]
+pub(crate) fn is_whitespace(b: u8) -> bool {
+    matches!(b, b'\0' | b'\t' | b'\n' | 0x0C | b'\r' | b' ')
+}
+
+/// Checks if a byte is a PDF delimiter character.
+///
+/// Delimiters end regular tokens such as names, numbers and keywords.
+#[inline]
+pub(crate) fn is_delimiter(b: u8) -> bool {
+    matches!(b, b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%')
+}
+
 /// Converts a hexadecimal string representation to a vector of bytes.
 ///
 /// Takes a byte slice containing hexadecimal characters and converts pairs
@@ -173,6 +180,33 @@ pub(crate) fn hex2bytes(bytes: &[u8])-> Vec<u8>{
     buf
 }
 
+/// Reads a big-endian `u16`, None past the end of the data.
+pub(crate) fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+/// Reads a big-endian `u32`, None past the end of the data.
+pub(crate) fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// Encodes bytes in base64 with padding.
+pub(crate) fn base64_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, byte)| bits | (*byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
 /// Utility function to dump a byte slice in hexadecimal and output it to stdout.
 ///
 /// This function formats a byte slice as a hexadecimal dump similar to tools like xxd or hexdump,
@@ -181,9 +215,10 @@ pub(crate) fn hex2bytes(bytes: &[u8])-> Vec<u8>{
 /// # Arguments
 ///
 /// * `bytes` - A slice of bytes to dump in hexadecimal format
+#[allow(dead_code)]
 pub(crate) fn hexdump(bytes: &[u8]) {
     let len = bytes.len();
-    let groups = len / 16 + if len % 16 == 0 { 0 } else { 1 };
+    let groups = len.div_ceil(16);
     let mut hex = Vec::<String>::new();
     let mut ascii = [' '; 16];
     for group in 0..groups {
@@ -223,7 +258,7 @@ pub(crate) fn hexdump(bytes: &[u8]) {
 ///
 /// * `Ok(&XEntry)` - A reference to the matching XRef entry if found
 /// * `Err(Error)` - An error if no matching entry is found, with a message indicating
-///                  the object number and generation number that could not be found
+///   the object number and generation number that could not be found
 ///
 /// # Errors
 ///
@@ -231,7 +266,7 @@ pub(crate) fn hexdump(bytes: &[u8]) {
 pub(crate) fn xrefs_search(xrefs: &[XEntry], obj_ref: (u32, u16)) -> Result<&XEntry> {
     xrefs.iter()
         .find(|x| x.obj_num == obj_ref.0 && x.gen_num == obj_ref.1)
-        .ok_or_else(|| XrefEntryNotFound(obj_ref.0, obj_ref.1))
+        .ok_or(XrefEntryNotFound(obj_ref.0, obj_ref.1))
 }
 
 #[cfg(test)]
@@ -251,4 +286,14 @@ mod tests {
         // Test if the last byte is not a hex digit
         assert_eq!(hex2bytes(hex), [0x01, 0x2F, 0x3D, 0x40])
     }
+
+    /// Tests base64 encoding with and without padding.
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64_encode(&[0xfb, 0xff]), "+/8=");
+    }
 }
\ No newline at end of file
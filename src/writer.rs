@@ -0,0 +1,215 @@
+use crate::constants::{ENCRYPT, ID, INFO, LENGTH, PREV, ROOT, SIZE, XREF_STM};
+use crate::document::PDFDocument;
+use crate::error::PDFError::FormError;
+use crate::error::Result;
+use crate::objects::{Dictionary, PDFNumber, PDFObject, PDFStrKind, PDFString, Stream};
+use crate::utils::{is_delimiter, is_whitespace};
+use std::collections::{BTreeMap, HashMap};
+
+/// An incremental update of a document: the objects it replaces and adds,
+/// written after the original bytes with a cross-reference section of their own.
+///
+/// The section is a classic cross-reference table, like the sections of the
+/// documents it can update: documents using cross-reference streams can't be
+/// opened, hybrid-reference files and encrypted documents are refused.
+pub(crate) struct IncrementalUpdate<'a> {
+    document: &'a PDFDocument,
+    objects: BTreeMap<(u32, u16), PDFObject>,
+    /// The object number given to the next added object.
+    next_obj_num: u32,
+}
+
+impl<'a> IncrementalUpdate<'a> {
+    /// Creates an update of a document.
+    ///
+    /// # Returns
+    ///
+    /// The update, or a FormError if the document is encrypted, as the objects
+    /// would be written unencrypted, or has a cross-reference stream, `/XRefStm`,
+    /// whose compressed objects can't be read
+    pub(crate) fn new(document: &'a PDFDocument) -> Result<Self> {
+        let trailer = document.get_trailer();
+        if trailer.get(ENCRYPT).is_some() {
+            return Err(FormError("encrypted documents can't be updated".to_string()));
+        }
+        if trailer.get(XREF_STM).is_some() {
+            return Err(FormError("documents with cross-reference streams can't be updated".to_string()));
+        }
+        let size = trailer.get_u64_num(SIZE).unwrap_or(0) as u32;
+        let last = document.get_xref_slice().iter().map(|entry| entry.get_obj_num()).max().unwrap_or(0);
+        Ok(IncrementalUpdate { document, objects: BTreeMap::new(), next_obj_num: size.max(last + 1) })
+    }
+
+    /// Gets an object, as replaced by the update.
+    ///
+    /// # Returns
+    ///
+    /// The object, or None if it doesn't exist
+    pub(crate) fn get(&self, obj_ref: (u32, u16)) -> Result<Option<PDFObject>> {
+        match self.objects.get(&obj_ref) {
+            Some(object) => Ok(Some(object.clone())),
+            None => self.document.get_object(obj_ref),
+        }
+    }
+
+    /// Resolves an indirect reference to the object it refers to, as replaced by the update.
+    pub(crate) fn resolve(&self, object: &PDFObject) -> Result<PDFObject> {
+        match object.as_object_ref() {
+            Some(obj_ref) if self.objects.contains_key(&obj_ref) => Ok(self.objects[&obj_ref].clone()),
+            _ => Ok(self.document.resolve(object)?.into_owned()),
+        }
+    }
+
+    /// Replaces an object.
+    pub(crate) fn set(&mut self, obj_ref: (u32, u16), object: PDFObject) {
+        self.objects.insert(obj_ref, object);
+    }
+
+    /// Adds an object.
+    ///
+    /// # Returns
+    ///
+    /// The reference of the new object
+    pub(crate) fn add(&mut self, object: PDFObject) -> (u32, u16) {
+        let obj_ref = (self.next_obj_num, 0);
+        self.next_obj_num += 1;
+        self.objects.insert(obj_ref, object);
+        obj_ref
+    }
+
+    /// Writes the original document followed by the update.
+    pub(crate) fn into_bytes(self) -> Result<Vec<u8>> {
+        let mut out = self.document.read_bytes()?;
+        if !out.ends_with(b"\n") && !out.ends_with(b"\r") {
+            out.push(b'\n');
+        }
+        let mut offsets = Vec::with_capacity(self.objects.len());
+        for (obj_ref, object) in &self.objects {
+            offsets.push((*obj_ref, out.len()));
+            out.extend_from_slice(format!("{} {} obj\n", obj_ref.0, obj_ref.1).as_bytes());
+            write_object(&mut out, object);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+        let xref = out.len();
+        out.extend_from_slice(b"xref\n");
+        // Consecutive object numbers share a subsection
+        let mut start = 0;
+        while start < offsets.len() {
+            let mut end = start + 1;
+            while end < offsets.len() && offsets[end].0.0 == offsets[end - 1].0.0 + 1 {
+                end += 1;
+            }
+            out.extend_from_slice(format!("{} {}\n", offsets[start].0.0, end - start).as_bytes());
+            for ((_, gen_num), offset) in &offsets[start..end] {
+                out.extend_from_slice(format!("{:010} {:05} n \n", offset, gen_num).as_bytes());
+            }
+            start = end;
+        }
+        let trailer = self.document.get_trailer();
+        let mut entries = HashMap::new();
+        entries.insert(SIZE.to_string(), PDFObject::Number(PDFNumber::Unsigned(self.next_obj_num as u64)));
+        entries.insert(PREV.to_string(), PDFObject::Number(PDFNumber::Unsigned(self.document.get_xref_offset())));
+        for key in [ROOT, INFO, ID] {
+            if let Some(value) = trailer.get(key) {
+                entries.insert(key.to_string(), value.clone());
+            }
+        }
+        out.extend_from_slice(b"trailer\n");
+        write_object(&mut out, &PDFObject::Dict(Dictionary::new(entries)));
+        out.extend_from_slice(format!("\nstartxref\n{}\n%%EOF\n", xref).as_bytes());
+        Ok(out)
+    }
+}
+
+/// Writes an object in PDF syntax. Dictionary keys are sorted so that the output is stable.
+pub(crate) fn write_object(out: &mut Vec<u8>, object: &PDFObject) {
+    match object {
+        PDFObject::Bool(value) => out.extend_from_slice(if *value { b"true" } else { b"false" }),
+        PDFObject::Number(PDFNumber::Signed(value)) => out.extend_from_slice(value.to_string().as_bytes()),
+        PDFObject::Number(PDFNumber::Unsigned(value)) => out.extend_from_slice(value.to_string().as_bytes()),
+        PDFObject::Number(PDFNumber::Real(value)) => out.extend_from_slice(format_number(*value).as_bytes()),
+        PDFObject::Named(name) => write_name(out, name),
+        PDFObject::String(text) => write_string(out, text),
+        PDFObject::Array(values) => {
+            out.push(b'[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(b' ');
+                }
+                write_object(out, value);
+            }
+            out.push(b']');
+        }
+        PDFObject::Dict(dict) => write_dict(out, dict),
+        PDFObject::Stream(stream) => write_stream(out, stream),
+        PDFObject::ObjectRef(obj_num, gen_num) | PDFObject::IndirectObject(obj_num, gen_num, _) => {
+            out.extend_from_slice(format!("{} {} R", obj_num, gen_num).as_bytes())
+        }
+        PDFObject::Null => out.extend_from_slice(b"null"),
+    }
+}
+
+fn write_dict(out: &mut Vec<u8>, dict: &Dictionary) {
+    let mut entries = dict.iter().collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    out.extend_from_slice(b"<<");
+    for (key, value) in entries {
+        write_name(out, key);
+        out.push(b' ');
+        write_object(out, value);
+    }
+    out.extend_from_slice(b">>");
+}
+
+/// Writes a stream with its raw data, the `/Length` set to the length of the data.
+fn write_stream(out: &mut Vec<u8>, stream: &Stream) {
+    let mut metadata = stream.get_metadata().clone();
+    metadata.insert(LENGTH, PDFObject::Number(PDFNumber::Unsigned(stream.get_buf().len() as u64)));
+    write_dict(out, &metadata);
+    out.extend_from_slice(b"\nstream\n");
+    out.extend_from_slice(stream.get_buf());
+    out.extend_from_slice(b"\nendstream");
+}
+
+/// Writes a name, escaping delimiters, whitespace and bytes outside printable ASCII as `#xx`.
+fn write_name(out: &mut Vec<u8>, name: &str) {
+    out.push(b'/');
+    for b in name.bytes() {
+        if b == b'#' || !(0x21..0x7f).contains(&b) || is_delimiter(b) || is_whitespace(b) {
+            out.extend_from_slice(format!("#{:02X}", b).as_bytes());
+        } else {
+            out.push(b);
+        }
+    }
+}
+
+fn write_string(out: &mut Vec<u8>, text: &PDFString) {
+    if let PDFStrKind::Hexadecimal = text.get_kind() {
+        out.push(b'<');
+        for b in text.get_buf() {
+            out.extend_from_slice(format!("{:02X}", b).as_bytes());
+        }
+        out.push(b'>');
+        return;
+    }
+    out.push(b'(');
+    for b in text.get_buf() {
+        match b {
+            b'(' | b')' | b'\\' => out.extend_from_slice(&[b'\\', *b]),
+            b'\r' => out.extend_from_slice(b"\\r"),
+            b'\n' => out.extend_from_slice(b"\\n"),
+            _ => out.push(*b),
+        }
+    }
+    out.push(b')');
+}
+
+/// Formats a real number without an exponent, with at most 4 decimals.
+pub(crate) fn format_number(value: f64) -> String {
+    let text = format!("{:.4}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" | "" => "0".to_string(),
+        text => text.to_string(),
+    }
+}
@@ -0,0 +1,234 @@
+use crate::constants::{ACRO_FORM, NEEDS_RENDERING, XFA};
+use crate::document::PDFDocument;
+use crate::error::Result;
+use crate::objects::PDFObject;
+use crate::pstr::decode_text_string;
+use std::ops::Range;
+
+/// The XML Forms Architecture (XFA) description of a form, `/AcroForm /XFA`.
+///
+/// The XDP document is split in packets, such as `config`, `template` and
+/// `datasets`, either by the PDF in an array of named streams or by the
+/// top-level elements of a single stream.
+#[derive(Clone, Debug)]
+pub struct Xfa {
+    /// The XDP document, the packets concatenated in order.
+    xml: Vec<u8>,
+    /// The name and range in `xml` of each packet.
+    packets: Vec<(String, Range<usize>)>,
+    dynamic: bool,
+}
+
+impl Xfa {
+    /// Returns the XDP document, the packets concatenated in order.
+    pub fn get_xml(&self) -> &[u8] {
+        &self.xml
+    }
+
+    /// Returns the name and XML of each packet, in order.
+    ///
+    /// Forms written as an array also have `preamble` and `postamble` packets
+    /// holding the tags of the `xdp:xdp` root element.
+    pub fn get_packets(&self) -> Vec<(&str, &[u8])> {
+        self.packets.iter().map(|(name, range)| (name.as_str(), &self.xml[range.clone()])).collect()
+    }
+
+    /// Gets the XML of a packet by its name, without namespace prefix.
+    ///
+    /// # Returns
+    ///
+    /// The first packet with the name, or None if the form has none
+    pub fn get_packet(&self, name: &str) -> Option<&[u8]> {
+        self.packets.iter().find(|(packet, _)| packet == name).map(|(_, range)| &self.xml[range.clone()])
+    }
+
+    /// Returns the `datasets` packet holding the data of the form, with the
+    /// values entered in its fields.
+    pub fn get_datasets(&self) -> Option<String> {
+        self.get_packet("datasets").map(|xml| String::from_utf8_lossy(xml).into_owned())
+    }
+
+    /// Returns whether the form is dynamic: its layout is computed by the XFA
+    /// processor from the template, the PDF pages are only a placeholder. The
+    /// pages of static forms are rendered in the PDF, with AcroForm fields.
+    pub fn is_dynamic(&self) -> bool {
+        self.dynamic
+    }
+}
+
+/// Reads the XFA description of the interactive form of a document.
+///
+/// The form is dynamic if the catalog requires rendering it, `/NeedsRendering`,
+/// or if its `config` packet requires dynamic rendering.
+///
+/// # Returns
+///
+/// The XFA description, or None if the document doesn't have an XFA form
+pub(crate) fn read_xfa(document: &PDFDocument) -> Result<Option<Xfa>> {
+    let Some(form) = document.get_catalog().get(ACRO_FORM) else {
+        return Ok(None);
+    };
+    let form = document.resolve(form)?;
+    let Some(xfa) = form.as_dict().and_then(|form| form.get(XFA)) else {
+        return Ok(None);
+    };
+    let mut xml = Vec::new();
+    let mut packets = Vec::new();
+    match document.resolve(xfa)?.as_ref() {
+        PDFObject::Array(entries) => {
+            for entry in entries.chunks_exact(2) {
+                let name = document.resolve(&entry[0])?.as_string().map(decode_text_string).unwrap_or_default();
+                let PDFObject::Stream(stream) = document.resolve(&entry[1])?.into_owned() else {
+                    continue;
+                };
+                let start = xml.len();
+                xml.extend(stream.get_decoded_buf()?);
+                packets.push((name, start..xml.len()));
+            }
+        }
+        PDFObject::Stream(stream) => {
+            xml = stream.get_decoded_buf()?;
+            packets = split_packets(&xml);
+        }
+        _ => return Ok(None),
+    }
+    let needs_rendering = match document.get_catalog().get(NEEDS_RENDERING) {
+        Some(object) => document.resolve(object)?.as_bool().unwrap_or(false),
+        None => false,
+    };
+    let mut xfa = Xfa { xml, packets, dynamic: needs_rendering };
+    if !xfa.dynamic {
+        xfa.dynamic = xfa.get_packet("config").and_then(|config| find_element_text(config, b"dynamicRender")) == Some(b"required");
+    }
+    Ok(Some(xfa))
+}
+
+/// Splits an XDP document in packets, the children of its root element.
+fn split_packets(xml: &[u8]) -> Vec<(String, Range<usize>)> {
+    let mut packets = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let mut name = String::new();
+    let mut i = 0;
+    while i < xml.len() {
+        if xml[i] != b'<' {
+            i += 1;
+            continue;
+        }
+        let Some(tag) = read_tag(xml, i) else {
+            break;
+        };
+        match tag.kind {
+            TagKind::Start if depth == 1 => {
+                (start, name) = (i, tag.local_name(xml));
+                depth += 1;
+            }
+            TagKind::Empty if depth == 1 => packets.push((tag.local_name(xml), i..tag.end)),
+            TagKind::Start => depth += 1,
+            TagKind::End => {
+                depth -= 1;
+                if depth == 1 {
+                    packets.push((std::mem::take(&mut name), start..tag.end));
+                } else if depth == 0 {
+                    break;
+                }
+            }
+            _ => {}
+        }
+        i = tag.end;
+    }
+    packets
+}
+
+/// Finds the text of the first element with a local name.
+fn find_element_text<'a>(xml: &'a [u8], element: &[u8]) -> Option<&'a [u8]> {
+    let mut i = 0;
+    while i < xml.len() {
+        if xml[i] != b'<' {
+            i += 1;
+            continue;
+        }
+        let tag = read_tag(xml, i)?;
+        if tag.kind == TagKind::Start && tag.local_name(xml).as_bytes() == element {
+            let end = xml[tag.end..].iter().position(|b| *b == b'<')?;
+            return Some(xml[tag.end..tag.end + end].trim_ascii());
+        }
+        i = tag.end;
+    }
+    None
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TagKind {
+    Start,
+    End,
+    /// An element without content, `<name/>`.
+    Empty,
+    /// A comment, processing instruction, CDATA section or declaration.
+    Other,
+}
+
+struct Tag {
+    kind: TagKind,
+    /// The range of the element name, prefix included.
+    name: Range<usize>,
+    /// The position following the tag.
+    end: usize,
+}
+
+impl Tag {
+    /// Gets the name of the element without its namespace prefix.
+    fn local_name(&self, xml: &[u8]) -> String {
+        let name = &xml[self.name.clone()];
+        let local = match name.iter().position(|b| *b == b':') {
+            Some(colon) => &name[colon + 1..],
+            None => name,
+        };
+        String::from_utf8_lossy(local).into_owned()
+    }
+}
+
+/// Reads the markup starting with `<` at a position.
+///
+/// # Returns
+///
+/// The tag, or None if it isn't closed
+fn read_tag(xml: &[u8], start: usize) -> Option<Tag> {
+    let rest = &xml[start..];
+    for (open, close) in [(&b"<!--"[..], &b"-->"[..]), (b"<![CDATA[", b"]]>"), (b"<?", b"?>")] {
+        if rest.starts_with(open) {
+            let end = find(rest, close)? + close.len();
+            return Some(Tag { kind: TagKind::Other, name: start..start, end: start + end });
+        }
+    }
+    let (kind, name_start) = match rest.get(1) {
+        Some(b'/') => (TagKind::End, 2),
+        Some(b'!') => (TagKind::Other, 2),
+        _ => (TagKind::Start, 1),
+    };
+    // Attribute values may contain '>'
+    let mut quote = None;
+    let mut end = None;
+    for (i, b) in rest.iter().enumerate().skip(name_start) {
+        match (quote, *b) {
+            (Some(q), b) if b == q => quote = None,
+            (None, b'"' | b'\'') => quote = Some(*b),
+            (None, b'>') => {
+                end = Some(i + 1);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let end = end?;
+    let name_len = rest[name_start..end]
+        .iter()
+        .position(|b| b.is_ascii_whitespace() || *b == b'/' || *b == b'>')
+        .unwrap_or(0);
+    let kind = if kind == TagKind::Start && rest[..end].ends_with(b"/>") { TagKind::Empty } else { kind };
+    Some(Tag { kind, name: start + name_start..start + name_start + name_len, end: start + end })
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
@@ -0,0 +1,100 @@
+use pdf_rs::action::{ActionKind, DestinationView};
+use pdf_rs::error::Result;
+mod common;
+
+#[test]
+fn test_links() -> Result<()> {
+    for document in common::open_all("document/links.pdf")? {
+        let links = document.get_page(0).unwrap().get_links()?;
+        // The text annotation isn't a link
+        assert_eq!(links.len(), 9);
+        assert_eq!(links[0].get_obj_ref(), Some((10, 0)));
+        assert_eq!(links[0].get_rect(), [0.0, 0.0, 10.0, 10.0]);
+
+        // A URI followed by a JavaScript stream and a named action
+        let action = links[0].get_action().unwrap();
+        assert_eq!(action.get_kind(), &ActionKind::Uri { uri: "https://example.com/a?b=1".to_string() });
+        let chain = action.get_chain();
+        assert_eq!(chain.len(), 3);
+        assert_eq!(chain[1].get_kind(), &ActionKind::JavaScript { script: "app.alert('hi');".to_string() });
+        assert_eq!(chain[2].get_kind(), &ActionKind::Named { name: "NextPage".to_string() });
+
+        // An explicit destination on the last page, nested in the page tree
+        let ActionKind::GoTo { destination } = links[1].get_action().unwrap().get_kind() else {
+            panic!("expected a GoTo action");
+        };
+        assert_eq!(destination.get_page(), Some(2));
+        assert_eq!(destination.get_name(), None);
+        assert_eq!(destination.get_view(), Some(&DestinationView::Xyz { left: None, top: None, zoom: None }));
+
+        // A named destination from the name tree, and one from the catalog dictionary
+        let ActionKind::GoTo { destination } = links[2].get_action().unwrap().get_kind() else {
+            panic!("expected a GoTo action");
+        };
+        assert_eq!(destination.get_name(), Some("chapter2"));
+        assert_eq!(destination.get_page(), Some(1));
+        assert_eq!(destination.get_view(), Some(&DestinationView::Xyz { left: Some(10.0), top: Some(190.0), zoom: Some(2.0) }));
+        let ActionKind::GoTo { destination } = links[3].get_action().unwrap().get_kind() else {
+            panic!("expected a GoTo action");
+        };
+        assert_eq!((destination.get_name(), destination.get_page()), (Some("old"), Some(1)));
+        assert_eq!(destination.get_view(), Some(&DestinationView::Fit));
+
+        // A remote destination keeps the page number of the other file
+        let ActionKind::GoToR { file, destination, new_window } = links[4].get_action().unwrap().get_kind() else {
+            panic!("expected a GoToR action");
+        };
+        assert_eq!(file.as_deref(), Some("ö.pdf"));
+        assert_eq!(destination.as_ref().unwrap().get_page(), Some(2));
+        assert_eq!(destination.as_ref().unwrap().get_view(), Some(&DestinationView::FitB));
+        assert_eq!(*new_window, Some(true));
+        assert_eq!(links[5].get_action().unwrap().get_kind(), &ActionKind::Launch { file: Some("notepad.exe".to_string()), new_window: None });
+
+        // An undefined named destination is reported without a page
+        let ActionKind::GoTo { destination } = links[6].get_action().unwrap().get_kind() else {
+            panic!("expected a GoTo action");
+        };
+        assert_eq!((destination.get_name(), destination.get_page(), destination.get_view()), (Some("missing"), None, None));
+
+        // A cyclic chain ends at the repeated action
+        let action = links[7].get_action().unwrap();
+        assert_eq!(action.get_kind(), &ActionKind::Named { name: "FirstPage".to_string() });
+        assert!(action.get_next().is_empty());
+        assert!(links[8].get_action().is_none());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_document_actions() -> Result<()> {
+    let document = common::open_all("document/links.pdf")?.remove(0);
+    assert_eq!(document.get_page_index((5, 0)), Some(2));
+    assert_eq!(document.get_page_index((6, 0)), None);
+
+    // The open action is a destination
+    let action = document.get_open_action()?.unwrap();
+    let ActionKind::GoTo { destination } = action.get_kind() else {
+        panic!("expected a GoTo action");
+    };
+    assert_eq!(destination.get_page(), Some(2));
+    assert_eq!(destination.get_view(), Some(&DestinationView::FitH { top: Some(700.0) }));
+
+    let destination = document.get_named_destination("appendix")?.unwrap();
+    assert_eq!(destination.get_page(), Some(2));
+    assert_eq!(destination.get_view(), Some(&DestinationView::FitR { left: 10.0, bottom: 20.0, right: 110.0, top: 120.0 }));
+    assert!(document.get_named_destination("nothing")?.is_none());
+
+    let actions = document.get_page(0).unwrap().get_additional_actions()?;
+    assert_eq!(actions.len(), 2);
+    assert_eq!(actions[0].0, "C");
+    let ActionKind::GoTo { destination } = actions[0].1.get_kind() else {
+        panic!("expected a GoTo action");
+    };
+    // A zoom of 0 keeps the current zoom
+    assert_eq!((destination.get_name(), destination.get_page()), (Some("index"), Some(0)));
+    assert_eq!(destination.get_view(), Some(&DestinationView::Xyz { left: None, top: Some(150.0), zoom: None }));
+    assert_eq!(actions[1].0, "O");
+    assert_eq!(actions[1].1.get_kind(), &ActionKind::JavaScript { script: "app.alert(1)".to_string() });
+    assert!(document.get_page(1).unwrap().get_additional_actions()?.is_empty());
+    Ok(())
+}
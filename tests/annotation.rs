@@ -0,0 +1,80 @@
+use pdf_rs::annotation::AnnotationKind;
+use pdf_rs::colorspace::ColorSpace;
+use pdf_rs::error::Result;
+mod common;
+
+#[test]
+fn test_markup_annotations() -> Result<()> {
+    for document in common::open_all("document/annotations.pdf")? {
+        let annotations = document.get_page(0).unwrap().get_annotations()?;
+        // The trailing entry isn't an annotation dictionary and is skipped
+        assert_eq!(annotations.len(), 12);
+        let note = &annotations[0];
+        assert_eq!(note.get_obj_ref(), Some((10, 0)));
+        assert_eq!(note.get_subtype(), "Text");
+        assert_eq!(note.get_kind(), &AnnotationKind::Text { open: true, icon: Some("Comment".to_string()) });
+        assert_eq!(note.get_rect(), [30.0, 130.0, 50.0, 150.0]);
+        assert_eq!(note.get_contents(), Some("Café — check"));
+        assert_eq!(note.get_author(), Some("Reviewer"));
+        assert_eq!(note.get_subject(), Some("Note"));
+        assert_eq!(note.get_name(), Some("note-1"));
+        assert_eq!(note.get_mod_date(), Some("D:20240102030405Z"));
+        assert_eq!(note.get_flags(), 4);
+        assert_eq!(note.get_popup(), Some((11, 0)));
+        let color = note.get_color().unwrap();
+        assert_eq!(color.get_color_space(), &ColorSpace::DeviceRGB);
+        assert_eq!(color.get_components(), [1.0, 1.0, 0.0]);
+
+        let popup = &annotations[1];
+        assert_eq!(popup.get_kind(), &AnnotationKind::Popup { parent: Some((10, 0)), open: false });
+        assert_eq!(popup.get_flags(), 28);
+
+        let reply = &annotations[2];
+        assert_eq!(reply.get_in_reply_to(), Some((10, 0)));
+        assert_eq!(reply.get_author(), Some("李四"));
+        assert_eq!(reply.get_contents(), Some("Agreed"));
+
+        let highlight = &annotations[3];
+        assert_eq!(highlight.get_kind(), &AnnotationKind::Highlight {
+            quad_points: vec![
+                [(10.0, 170.0), (90.0, 170.0), (90.0, 180.0), (10.0, 180.0)],
+                [(10.0, 160.0), (50.0, 160.0), (50.0, 170.0), (10.0, 170.0)],
+            ],
+        });
+        assert_eq!(highlight.get_color().unwrap().get_color_space(), &ColorSpace::DeviceGray);
+        assert!(matches!(annotations[4].get_kind(), AnnotationKind::Underline { quad_points } if quad_points.len() == 1));
+        // Incomplete quadrilaterals are dropped
+        assert_eq!(annotations[5].get_kind(), &AnnotationKind::StrikeOut { quad_points: vec![] });
+        assert_eq!(annotations[6].get_kind(), &AnnotationKind::Squiggly { quad_points: vec![] });
+        assert_eq!(annotations[7].get_kind(), &AnnotationKind::Ink {
+            ink_list: vec![vec![(0.0, 0.0), (10.0, 10.0), (20.0, 0.0)], vec![(30.0, 30.0), (40.0, 40.0)]],
+        });
+        assert_eq!(annotations[7].get_color().unwrap().get_color_space(), &ColorSpace::DeviceCMYK);
+        assert_eq!(annotations[8].get_kind(), &AnnotationKind::FreeText { default_appearance: Some("/Helv 12 Tf 0 g".to_string()) });
+        assert_eq!(annotations[8].get_contents(), Some("Typed"));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_other_annotations() -> Result<()> {
+    let document = common::open_all("document/annotations.pdf")?.remove(0);
+    let annotations = document.get_page(0).unwrap().get_annotations()?;
+    // The /T of links and widgets isn't an author
+    let link = &annotations[9];
+    assert!(matches!(link.get_kind(), AnnotationKind::Link { quad_points } if quad_points.len() == 1));
+    assert_eq!(link.get_author(), None);
+    let widget = &annotations[10];
+    assert_eq!(widget.get_kind(), &AnnotationKind::Widget);
+    assert_eq!(widget.get_author(), None);
+    // Direct annotations have no reference, and an empty colour array is transparent
+    let square = &annotations[11];
+    assert_eq!(square.get_obj_ref(), None);
+    assert!(square.get_color().is_none());
+    let AnnotationKind::Square { interior_color: Some(color) } = square.get_kind() else {
+        panic!("expected a square with an interior colour");
+    };
+    assert_eq!(color.to_rgb(), [1.0, 0.0, 0.0]);
+    assert!(document.get_page(1).unwrap().get_annotations()?.is_empty());
+    Ok(())
+}
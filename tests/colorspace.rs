@@ -0,0 +1,51 @@
+use pdf_rs::error::Result;
+use pdf_rs::graphics::{FillRule, GraphicsState, Path};
+use pdf_rs::interpreter::ContentVisitor;
+mod common;
+
+/// Collects the colour space and sRGB colour of each filled path.
+#[derive(Default)]
+struct ColorCollector {
+    colors: Vec<(&'static str, [u8; 3])>,
+}
+
+impl ContentVisitor for ColorCollector {
+    fn fill_path(&mut self, _path: &Path, _rule: FillRule, state: &GraphicsState) -> Result<()> {
+        let color = state.get_fill_color();
+        let rgb = color.get_color_space().to_rgb8(color.get_components());
+        self.colors.push((color.get_color_space().get_family(), rgb));
+        Ok(())
+    }
+}
+
+#[test]
+fn test_color_spaces() -> Result<()> {
+    for document in common::open_all("document/colorspaces.pdf")? {
+        let mut collector = ColorCollector::default();
+        document.get_page(0).unwrap().interpret(&mut collector)?;
+        assert_eq!(collector.colors, [
+            ("CalGray", [128, 128, 128]),
+            // CIE colours are adapted from their white point to D65
+            ("Lab", [255, 255, 255]),
+            ("Lab", [255, 0, 0]),
+            // The sRGB matrix/TRC profile maps colours to themselves
+            ("ICCBased", [255, 0, 0]),
+            ("ICCBased", [153, 153, 153]),
+            ("ICCBased", [128, 128, 128]),
+            // Without a usable profile, the alternate colour space converts the colour
+            ("ICCBased", [255, 0, 0]),
+            // Separations are initially full tints, converted by the tint transform
+            ("Separation", [255, 0, 0]),
+            ("Separation", [255, 128, 128]),
+            ("DeviceN", [204, 102, 102]),
+            // Tints are shown as gray when the tint transform isn't supported
+            ("DeviceN", [102, 102, 102]),
+            // Table entries are scaled to the component ranges of the base colour space
+            ("Indexed", [255, 2, 1]),
+            ("Pattern", [0, 0, 255]),
+            // Unknown colour spaces are replaced by DeviceGray
+            ("DeviceGray", [0, 0, 0]),
+        ]);
+    }
+    Ok(())
+}
@@ -0,0 +1,28 @@
+#![allow(dead_code)]
+
+use pdf_rs::document::PDFDocument;
+use pdf_rs::error::Result;
+use pdf_rs::sequence::ReadSeekSequence;
+use std::fs::File;
+use std::io::{BufReader, Cursor};
+use std::path::PathBuf;
+
+/// Opens the document at `path` through every sequence backend.
+///
+/// Tests iterate over the returned documents so that each backend is held to
+/// the same behaviour.
+pub fn open_all(path: &str) -> Result<Vec<PDFDocument>> {
+    let bytes = std::fs::read(path)?;
+    let static_bytes: &'static [u8] = Box::leak(bytes.clone().into_boxed_slice());
+    #[allow(unused_mut)]
+    let mut documents = vec![
+        PDFDocument::open(PathBuf::from(path))?,
+        PDFDocument::from_bytes(bytes.clone())?,
+        PDFDocument::from_bytes(static_bytes)?,
+        PDFDocument::new(ReadSeekSequence::new(Cursor::new(bytes))?)?,
+        PDFDocument::new(ReadSeekSequence::new(BufReader::new(File::open(path)?))?)?,
+    ];
+    #[cfg(feature = "mmap")]
+    documents.push(PDFDocument::open_mmap(PathBuf::from(path))?);
+    Ok(documents)
+}
@@ -0,0 +1,55 @@
+use pdf_rs::content::Operator;
+use pdf_rs::error::Result;
+use pdf_rs::objects::PDFObject;
+mod common;
+
+#[test]
+fn test_page_contents() -> Result<()> {
+    for document in common::open_all("document/content.pdf")? {
+        assert_eq!(document.get_page_num(), 2);
+        let page = document.get_page(0).unwrap();
+        // Attributes inherited from the page tree
+        assert_eq!(page.get_media_box()?, Some([0.0, 0.0, 612.0, 792.0]));
+        assert_eq!(page.get_rotate()?, 270);
+        let resources = page.get_resources()?.unwrap();
+        assert!(resources.get("Font").is_some_and(|font| font.is_dict()));
+        // Two streams, the second one with two filters
+        assert_eq!(page.get_contents()?, b"q 1 0 0 1 72 720 cm\nBT /F1 12 Tf\n(Hello) Tj ET\n0.5 g foo Q");
+        let operations = page.get_operations()?;
+        let operators = operations.iter().map(|op| op.get_operator().clone()).collect::<Vec<Operator>>();
+        assert_eq!(operators, [
+            Operator::SaveState,
+            Operator::ConcatMatrix,
+            Operator::BeginText,
+            Operator::SetFont,
+            Operator::ShowText,
+            Operator::EndText,
+            Operator::SetFillGray,
+            Operator::Unknown("foo".to_string()),
+            Operator::RestoreState,
+        ]);
+        assert!(matches!(operations[4].get_operands(), [PDFObject::String(_)]));
+
+        let page = document.get_page(1).unwrap();
+        assert_eq!(page.get_obj_ref(), (4, 0));
+        // The page overrides the inherited media box
+        assert_eq!(page.get_media_box()?, Some([0.0, 0.0, 200.0, 300.0]));
+        assert!(page.get_operations()?.is_empty());
+        assert!(document.get_page(2).is_none());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_reference_operations() -> Result<()> {
+    let document = pdf_rs::document::PDFDocument::open("document/pdfreference1.0.pdf".into())?;
+    let mut count = 0;
+    for page in document.get_pages() {
+        for operation in page.get_operations()? {
+            assert!(!operation.is_unknown(), "Unknown operator on page {}", page.get_index());
+            count += 1;
+        }
+    }
+    assert!(count > 0);
+    Ok(())
+}
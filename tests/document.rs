@@ -1,15 +1,18 @@
-use std::path::PathBuf;
 use pdf_rs::document::PDFDocument;
 use pdf_rs::error::Result;
+use pdf_rs::objects::PDFObject;
+mod common;
+
 #[test]
 fn document() -> Result<()> {
-    let mut document = PDFDocument::open(PathBuf::from("document/pdfreference1.0.pdf"))?;
-    let xrefs = document.get_xref_slice();
-    assert!(!xrefs.is_empty());
-    assert_eq!(document.get_page_num(), 230);
-    match document.read_object(0)?{
-        Some(obj) => assert!(obj.is_indirect_object()),
-        _ => assert!(false),
+    for document in common::open_all("document/pdfreference1.0.pdf")? {
+        let xrefs = document.get_xref_slice();
+        assert!(!xrefs.is_empty());
+        assert_eq!(document.get_page_num(), 230);
+        match document.read_object(0)? {
+            Some(obj) => assert!(obj.is_indirect_object()),
+            _ => panic!("Object 0 not found"),
+        }
     }
     Ok(())
 }
@@ -17,19 +20,51 @@ fn document() -> Result<()> {
 
 #[test]
 fn test_stream_read()->Result<()>{
-    let mut document = PDFDocument::open(PathBuf::from("document/pdfreference1.0.pdf"))?;
-    match document.find_xref_index(|entry|entry.get_obj_num() == 1354){
-        Some(index) => {
-            let object = document.read_object(index)?.unwrap();
-            match object.as_indirect_object() {
-                Some((obj_num,gen_num,obj)) => {
-                    assert!(obj.is_stream());
-                    assert!(obj_num == 1354 && gen_num == 0)
+    for document in common::open_all("document/pdfreference1.0.pdf")? {
+        match document.find_xref_index(|entry|entry.get_obj_num() == 1354){
+            Some(index) => {
+                let object = document.read_object(index)?.unwrap();
+                match object.as_indirect_object() {
+                    Some((obj_num,gen_num,obj)) => {
+                        assert!(obj.is_stream());
+                        assert!(obj_num == 1354 && gen_num == 0)
+                    }
+                    _ => panic!("Object 1354 is not an indirect object"),
                 }
-                _ => assert!(false),
             }
+            _ => panic!("Object 1354 not found"),
         }
-        _ => assert!(false),
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Reads the stream of the given object from the document.
+fn read_stream_buf(document: &PDFDocument, obj_num: u32) -> Result<Vec<u8>> {
+    let index = document.find_xref_index(|entry| entry.get_obj_num() == obj_num).unwrap();
+    let object = document.read_object(index)?.unwrap();
+    match object.as_indirect_object() {
+        Some((_, _, PDFObject::Stream(stream))) => Ok(stream.get_buf().to_vec()),
+        _ => panic!("Object {} is not a stream", obj_num),
+    }
+}
+
+#[test]
+fn test_stream_length() -> Result<()> {
+    let content = b"BT\n/F1 12 Tf\n72 712 Td (A stream with an indirect Length) Tj\nET";
+    for document in common::open_all("document/stream-length.pdf")? {
+        // Indirect length
+        assert_eq!(read_stream_buf(&document, 4)?, content);
+        // Wrong length
+        assert_eq!(read_stream_buf(&document, 6)?, b"\nendstrea\r\nm data");
+        // Missing length
+        assert_eq!(read_stream_buf(&document, 7)?, b"no length");
+        // Length references a missing object
+        assert_eq!(read_stream_buf(&document, 8)?, content);
+        // Lengths referring to a stream, which mustn't be parsed recursively
+        assert_eq!(read_stream_buf(&document, 10)?, b"self length");
+        assert_eq!(read_stream_buf(&document, 11)?, b"stream length");
+        // Length past the end of the file
+        assert_eq!(read_stream_buf(&document, 12)?, b"huge length");
+    }
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use pdf_rs::error::Result;
+mod common;
+
+#[test]
+fn test_embedded_files() -> Result<()> {
+    for document in common::open_all("document/attachments.pdf")? {
+        // The external file reference doesn't embed a file
+        let files = document.get_embedded_files()?;
+        let keys = files.iter().map(|file| file.get_key().unwrap()).collect::<Vec<_>>();
+        assert_eq!(keys, ["factur-x.xml", "notes.txt"]);
+
+        let invoice = &files[0];
+        assert_eq!(invoice.get_file_name(), Some("factur-x.xml"));
+        assert_eq!(invoice.get_description(), Some("Factur-X invoice"));
+        assert_eq!(invoice.get_mime_type(), Some("text/xml"));
+        assert_eq!(invoice.get_relationship(), Some("Data"));
+        assert_eq!(invoice.get_size(), Some(236));
+        assert_eq!(invoice.get_creation_date(), Some("D:20261001120000+02'00'"));
+        assert_eq!(invoice.get_mod_date(), Some("D:20261002120000+02'00'"));
+        let checksum = invoice.get_checksum().unwrap().iter().map(|b| format!("{:02x}", b)).collect::<String>();
+        assert_eq!(checksum, "9b170591885ca3a582dbd9a8d6ac8d2e");
+        assert!(invoice.get_annotation().is_none());
+        // The file is compressed in the document
+        let bytes = invoice.get_bytes()?;
+        assert_eq!(bytes.len(), 236);
+        assert!(invoice.get_stored_size() < 236);
+        assert!(String::from_utf8_lossy(&bytes).contains("<ram:ID>INV-42</ram:ID>"));
+
+        let notes = &files[1];
+        assert_eq!(notes.get_file_name(), Some("notes.txt"));
+        assert_eq!(notes.get_mime_type(), None);
+        assert_eq!(notes.get_size(), None);
+        assert_eq!(notes.get_relationship(), None);
+        assert_eq!(notes.get_bytes()?, b"Remember to pay.\n");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_file_attachments() -> Result<()> {
+    let document = common::open_all("document/attachments.pdf")?.remove(0);
+    let files = document.get_page(0).unwrap().get_file_attachments()?;
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0].get_annotation(), Some((40, 0)));
+    assert!(files[0].get_key().is_none());
+    // The Unicode file name is preferred
+    assert_eq!(files[0].get_file_name(), Some("résumé.txt"));
+    assert_eq!(files[0].get_mime_type(), Some("text/plain"));
+    assert_eq!(String::from_utf8(files[0].get_bytes()?).unwrap(), "Zoë's résumé\n");
+    assert_eq!(files[1].get_annotation(), Some((42, 0)));
+    assert_eq!(files[1].get_file_name(), Some("notes.txt"));
+
+    // Documents without attachments
+    let document = common::open_all("document/forms.pdf")?.remove(0);
+    assert!(document.get_embedded_files()?.is_empty());
+    assert!(document.get_page(0).unwrap().get_file_attachments()?.is_empty());
+    Ok(())
+}
@@ -0,0 +1,146 @@
+use pdf_rs::document::PDFDocument;
+use pdf_rs::error::{PDFError, Result};
+use pdf_rs::fill::FormFiller;
+use pdf_rs::form::{FieldValue, FormField};
+use pdf_rs::objects::PDFObject;
+mod common;
+
+/// Reads the content of the normal appearance of a widget.
+fn get_appearance(document: &PDFDocument, field: &FormField) -> Result<String> {
+    let widget = document.get_object(field.get_widgets()[0].get_obj_ref().unwrap())?.unwrap();
+    let appearances = widget.as_dict().unwrap().get("AP").unwrap().as_dict().unwrap().clone();
+    let normal = document.resolve(appearances.get("N").unwrap())?;
+    let PDFObject::Stream(stream) = normal.as_ref() else {
+        panic!("expected an appearance stream");
+    };
+    Ok(String::from_utf8_lossy(&stream.get_decoded_buf()?).into_owned())
+}
+
+/// Appends a cross-reference section whose trailer has extra entries.
+fn append_trailer(path: &str, entries: &str) -> Result<PDFDocument> {
+    let mut bytes = std::fs::read(path)?;
+    let text = String::from_utf8_lossy(&bytes).into_owned();
+    let start = text.rfind("startxref").unwrap() + "startxref".len();
+    let prev = text[start..].split_whitespace().next().unwrap().to_string();
+    let root = PDFDocument::from_bytes(bytes.clone())?.get_trailer().get("Root").unwrap().as_object_ref().unwrap();
+    let xref = bytes.len();
+    bytes.extend_from_slice(b"xref\n0 1\n0000000000 65535 f \n");
+    let trailer = format!("trailer\n<< /Size 1 /Root {} {} R /Prev {} {} >>\nstartxref\n{}\n%%EOF\n", root.0, root.1, prev, entries, xref);
+    bytes.extend_from_slice(trailer.as_bytes());
+    PDFDocument::from_bytes(bytes)
+}
+
+#[test]
+fn test_fill_fields() -> Result<()> {
+    for document in common::open_all("document/forms.pdf")? {
+        let mut filler = FormFiller::new(&document)?;
+        filler.set_text("name", "Alice (A.)")?;
+        filler.set_text("address.zip", "75")?;
+        filler.set_checked("agree", false)?;
+        filler.set_radio("color", "Green")?;
+        filler.set_choice("country", &["DE"])?;
+        filler.set_choice("langs", &["fr", "en"])?;
+        let filled = PDFDocument::from_bytes(filler.into_bytes()?)?;
+        let form = filled.get_form()?.unwrap();
+
+        let name = form.get_field("name").unwrap();
+        assert_eq!(name.get_value(), Some(&FieldValue::Text("Alice (A.)".to_string())));
+        // The appearance uses the font, size and colour of /DA
+        let appearance = get_appearance(&filled, name)?;
+        assert!(appearance.starts_with("/Tx BMC"));
+        assert!(appearance.contains("/Helv 12 Tf\n0 0 1 rg"));
+        assert!(appearance.contains("1 0 0 1 2 "));
+        assert!(appearance.contains("(Alice \\(A.\\)) Tj"));
+
+        // Right aligned text ends at the padding
+        let zip = form.get_field("address.zip").unwrap();
+        assert_eq!(zip.get_value(), Some(&FieldValue::Text("75".to_string())));
+        let appearance = get_appearance(&filled, zip)?;
+        assert!(appearance.contains("/Helv "));
+        assert!(!appearance.contains("1 0 0 1 2 "));
+
+        let agree = form.get_field("agree").unwrap();
+        assert_eq!(agree.get_value(), Some(&FieldValue::Name("Off".to_string())));
+        assert_eq!(agree.get_widgets()[0].get_appearance_state(), Some("Off"));
+
+        let color = form.get_field("color").unwrap();
+        assert_eq!(color.get_value(), Some(&FieldValue::Name("Green".to_string())));
+        let states = color.get_widgets().iter().map(|widget| widget.get_appearance_state().unwrap()).collect::<Vec<_>>();
+        assert_eq!(states, ["Off", "Off", "Green"]);
+
+        // The combo box shows the text of the option
+        let country = form.get_field("country").unwrap();
+        assert_eq!(country.get_value(), Some(&FieldValue::Text("DE".to_string())));
+        assert!(get_appearance(&filled, country)?.contains("(Germany) Tj"));
+
+        let langs = form.get_field("langs").unwrap();
+        assert_eq!(langs.get_value(), Some(&FieldValue::Array(vec!["fr".to_string(), "en".to_string()])));
+        let indices = langs.get_attrs().get("I").unwrap().as_array().unwrap().iter().map(|index| index.as_i64().unwrap()).collect::<Vec<_>>();
+        assert_eq!(indices, [0, 1]);
+        let appearance = get_appearance(&filled, langs)?;
+        assert_eq!(appearance.matches(" re f\n").count(), 2);
+        assert!(appearance.contains("(de) Tj"));
+
+        // Fields left alone keep their values
+        assert_eq!(form.get_field("address.city").unwrap().get_value(), Some(&FieldValue::Text("Paris".to_string())));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_fill_errors() -> Result<()> {
+    let document = common::open_all("document/forms.pdf")?.remove(0);
+    let mut filler = FormFiller::new(&document)?;
+    assert!(matches!(filler.set_text("missing", "x"), Err(PDFError::FormError(_))));
+    assert!(matches!(filler.set_text("agree", "x"), Err(PDFError::FormError(_))));
+    assert!(matches!(filler.set_radio("color", "Purple"), Err(PDFError::FormError(_))));
+    assert!(matches!(filler.set_choice("country", &["FR", "DE"]), Err(PDFError::FormError(_))));
+    assert!(matches!(filler.set_choice("langs", &["it"]), Err(PDFError::FormError(_))));
+
+    // The text is truncated to the maximum length
+    filler.set_text("name", "abcdefghijklmnopqrstuvwxyz")?;
+    let filled = PDFDocument::from_bytes(filler.into_bytes()?)?;
+    let name = filled.get_form()?.unwrap().get_field("name").unwrap().clone();
+    assert_eq!(name.get_value(), Some(&FieldValue::Text("abcdefghijklmnopqrst".to_string())));
+
+    let document = common::open_all("document/links.pdf")?.remove(0);
+    assert!(matches!(FormFiller::new(&document), Err(PDFError::FormError(_))));
+
+    // The update would be written unencrypted
+    let document = append_trailer("document/forms.pdf", "/Encrypt << /Filter /Standard /V 2 /R 3 >>")?;
+    assert!(document.get_form()?.is_some());
+    assert!(matches!(FormFiller::new(&document), Err(PDFError::FormError(_))));
+    // The objects of the cross-reference stream of a hybrid-reference file can't be read
+    let document = append_trailer("document/forms.pdf", "/XRefStm 0")?;
+    assert!(matches!(FormFiller::new(&document), Err(PDFError::FormError(_))));
+    Ok(())
+}
+
+#[test]
+fn test_flatten() -> Result<()> {
+    let document = common::open_all("document/forms.pdf")?.remove(0);
+    let mut filler = FormFiller::new(&document)?;
+    filler.set_text("name", "Alice")?;
+    filler.set_flatten(true);
+    let flattened = PDFDocument::from_bytes(filler.into_bytes()?)?;
+
+    let form = flattened.get_form()?.unwrap();
+    assert!(form.get_fields().is_empty());
+    assert!(!form.is_need_appearances());
+    // Every widget of the first page is removed, the page draws their appearances
+    let page = flattened.get_page(0).unwrap();
+    assert!(page.get_annotations()?.is_empty());
+    let contents = page.get_attrs().get("Contents").unwrap().as_array().unwrap();
+    assert_eq!(contents.len(), 2);
+    let PDFObject::Stream(stream) = flattened.resolve(&contents[1])?.into_owned() else {
+        panic!("expected a content stream");
+    };
+    let content = String::from_utf8_lossy(&stream.get_decoded_buf()?).into_owned();
+    assert!(content.starts_with("Q\n"));
+    assert!(content.contains("q 1 0 0 1 10 250 cm /Fm"));
+    let resources = flattened.resolve(page.get_attrs().get("Resources").unwrap())?.into_owned();
+    let xobjects = resources.as_dict().unwrap().get("XObject").unwrap().as_dict().unwrap();
+    assert_eq!(xobjects.iter().count(), content.matches(" Do Q").count());
+    assert_eq!(flattened.get_page(1).unwrap().get_annotations()?.len(), 0);
+    Ok(())
+}
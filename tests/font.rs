@@ -0,0 +1,195 @@
+#[cfg(feature = "cjk")]
+use pdf_rs::cmap::CMap;
+use pdf_rs::error::Result;
+use pdf_rs::font::Font;
+use pdf_rs::graphics::GraphicsState;
+use pdf_rs::interpreter::{ContentVisitor, Glyph};
+use std::rc::Rc;
+mod common;
+
+/// Collects the fonts text is shown with.
+#[derive(Default)]
+struct FontCollector {
+    fonts: Vec<Rc<Font>>,
+}
+
+impl ContentVisitor for FontCollector {
+    fn show_text(&mut self, _glyphs: &[Glyph], state: &GraphicsState) -> Result<()> {
+        if let Some(font) = state.get_text_state().get_font() {
+            self.fonts.push(font.clone());
+        }
+        Ok(())
+    }
+}
+
+/// Rounds a box to three decimals, to compare widths computed from glyph space units.
+fn round(bbox: [f64; 4]) -> [f64; 4] {
+    bbox.map(|value| (value * 1000.0).round() / 1000.0)
+}
+
+#[test]
+fn test_simple_fonts() -> Result<()> {
+    for document in common::open_all("document/fonts.pdf")? {
+        let glyphs = document.get_page(0).unwrap().extract_glyphs()?;
+        let glyphs = glyphs.iter()
+            .map(|glyph| (glyph.get_text(), glyph.get_font_name().unwrap_or_default(), round(glyph.get_bbox())))
+            .collect::<Vec<(&str, &str, [f64; 4])>>();
+        assert_eq!(glyphs, [
+            // Standard 14 fonts without /Widths use their AFM widths and ascent and descent
+            ("H", "Helvetica", [10.0, 97.93, 17.22, 107.18]),
+            ("i", "Helvetica", [17.22, 97.93, 19.44, 107.18]),
+            // Symbolic standard fonts use their built-in encoding
+            ("α", "Symbol", [10.0, 77.07, 16.31, 90.1]),
+            ("✁", "ZapfDingbats", [10.0, 58.57, 19.74, 68.2]),
+            // Alternative names of standard fonts
+            ("A", "Arial,Bold", [10.0, 37.93, 17.22, 47.18]),
+            // Type3 glyph names are mapped with the Adobe Glyph List, ligatures and
+            // suffixes included, and widths and the font box are scaled by the font matrix
+            ("fi", "", [10.0, 20.0, 15.0, 30.0]),
+            ("A", "", [15.0, 20.0, 21.0, 30.0]),
+            // Base encoding and metrics from the font descriptor
+            ("ä", "ABCDEF+Minion", [100.0, 97.5, 104.8, 109.0]),
+        ]);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_composite_fonts() -> Result<()> {
+    for document in common::open_all("document/cid.pdf")? {
+        let page = document.get_page(0).unwrap();
+        let glyphs = page.extract_glyphs()?;
+        let glyphs = glyphs.iter()
+            .map(|glyph| (glyph.get_text(), glyph.get_code(), round(glyph.get_bbox())))
+            .collect::<Vec<(&str, u32, [f64; 4])>>();
+        assert_eq!(glyphs, [
+            // Identity-H with widths from both forms of /W
+            ("中", 1, [10.0, 98.59, 16.0, 108.59]),
+            ("文", 2, [16.0, 98.59, 23.0, 108.59]),
+            ("日", 10, [23.0, 98.59, 27.0, 108.59]),
+            ("本", 11, [27.0, 98.59, 31.0, 108.59]),
+            // An embedded CMap splitting one and two byte codes
+            ("A", 0x41, [10.0, 58.0, 15.0, 68.0]),
+            ("ア", 0x8141, [15.0, 58.0, 23.0, 68.0]),
+            ("B", 0x42, [23.0, 58.0, 29.0, 68.0]),
+            // Identity-V glyphs are positioned by their vertical origin from /W2 and /DW2,
+            // TJ adjustments move them down
+            ("縦", 5, [145.0, 89.8, 155.0, 99.8]),
+            ("書", 6, [145.0, 76.0, 155.0, 86.0]),
+        ]);
+
+        let mut collector = FontCollector::default();
+        page.interpret(&mut collector)?;
+        // TJ shows each string of the vertical text separately
+        let [identity, embedded, vertical, _] = collector.fonts.as_slice() else {
+            panic!("expected four strings");
+        };
+        let cid_font = identity.get_cid_font().unwrap();
+        assert_eq!(cid_font.get_subtype(), "CIDFontType2");
+        assert_eq!((cid_font.get_registry(), cid_font.get_ordering(), cid_font.get_supplement()), (Some("Adobe"), Some("Identity"), 0));
+        assert_eq!((cid_font.get_width(0), cid_font.get_width(15), cid_font.get_width(21)), (500.0, 400.0, 500.0));
+        assert_eq!((cid_font.get_gid(1), cid_font.get_gid(10), cid_font.get_gid(11), cid_font.get_gid(99)), (7, 30, 31, 0));
+        assert!(!identity.is_vertical());
+
+        let cmap = embedded.get_cmap().unwrap();
+        assert_eq!(cmap.get_name(), Some("Test-RKSJ-H"));
+        assert_eq!(cmap.split_codes(&[0x20, 0x81, 0x40, 0x81]), [(0x20, 1), (0x8140, 2), (0x81, 1)]);
+        assert_eq!((embedded.get_cid(0x20), embedded.get_cid(0x8140), embedded.get_cid(0x9000)), (Some(1), Some(633), Some(0)));
+        assert_eq!(embedded.get_cid_font().unwrap().get_default_width(), 1000.0);
+
+        assert!(vertical.is_vertical());
+        assert_eq!(vertical.get_vertical_metrics(5), Some((-1.2, (0.5, 0.9))));
+        assert_eq!(vertical.get_vertical_metrics(6), Some((-1.0, (0.5, 0.88))));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_font_inventory() -> Result<()> {
+    for document in common::open_all("document/fontfiles.pdf")? {
+        let fonts = document.get_fonts()?;
+        let inventory = fonts.iter()
+            .map(|font| (
+                font.get_name().unwrap_or_default(),
+                font.get_subtype(),
+                font.get_encoding(),
+                font.is_embedded(),
+                font.is_subset(),
+                font.get_pages(),
+            ))
+            .collect::<Vec<_>>();
+        assert_eq!(inventory, [
+            ("ABCDEF+Garamond", "Type1", Some("WinAnsiEncoding"), true, true, &[0, 1][..]),
+            ("Verdana", "TrueType", Some("Custom"), true, false, &[0][..]),
+            ("Helvetica", "Type1", None, false, false, &[0, 1][..]),
+            // Fonts of forms, including a form painting itself, belong to the pages painting them
+            ("GHIJKL+SourceHanSans", "Type0", Some("Identity-H"), true, true, &[0, 1][..]),
+            ("Minion", "Type1", None, true, false, &[1][..]),
+            ("", "Type3", Some("Custom"), true, false, &[1][..]),
+        ]);
+
+        let programs = fonts.iter()
+            .map(|font| font.get_font_program())
+            .collect::<Result<Vec<_>>>()?;
+        let formats = programs.iter()
+            .map(|program| program.as_ref().map(|program| program.get_format().get_extension()))
+            .collect::<Vec<_>>();
+        assert_eq!(formats, [Some("pfb"), Some("ttf"), None, Some("cff"), Some("otf"), None]);
+
+        // Type 1 programs are split into PFB segments
+        let pfb = programs[0].as_ref().unwrap().get_data();
+        assert_eq!(&pfb[..2], [0x80, 0x01]);
+        let clear = u32::from_le_bytes(pfb[2..6].try_into().unwrap()) as usize;
+        assert!(pfb[6..6 + clear].ends_with(b"currentfile eexec\n"));
+        assert_eq!(&pfb[6 + clear..8 + clear], [0x80, 0x02]);
+        assert_eq!(u32::from_le_bytes(pfb[8 + clear..12 + clear].try_into().unwrap()), 512);
+        assert_eq!(&pfb[pfb.len() - 2..], [0x80, 0x03]);
+
+        let cff = programs[3].as_ref().unwrap();
+        let path = std::env::temp_dir().join(format!("pdf-rs-font-inventory.{}", cff.get_format().get_extension()));
+        cff.save(&path)?;
+        assert_eq!(std::fs::read(&path)?, b"\x01\x00\x04\x02cff");
+        std::fs::remove_file(&path)?;
+    }
+    Ok(())
+}
+
+/// Composite fonts using predefined CMaps without `/ToUnicode` map their CIDs
+/// with the CID-to-Unicode CMap of their character collection.
+#[cfg(feature = "cjk")]
+#[test]
+fn test_predefined_cmaps() -> Result<()> {
+    for document in common::open_all("document/cjk.pdf")? {
+        let page = document.get_page(0).unwrap();
+        let glyphs = page.extract_glyphs()?;
+        let glyphs = glyphs.iter()
+            .map(|glyph| (glyph.get_text(), glyph.get_code(), round(glyph.get_bbox())))
+            .collect::<Vec<(&str, u32, [f64; 4])>>();
+        assert_eq!(glyphs, [
+            // UniGB-UCS2-H
+            ("中", 0x4E2D, [10.0, 98.0, 20.0, 108.0]),
+            ("文", 0x6587, [20.0, 98.0, 30.0, 108.0]),
+            // 90ms-RKSJ-H mixes one and two byte codes
+            ("A", 0x41, [10.0, 78.0, 20.0, 88.0]),
+            ("日", 0x93FA, [20.0, 78.0, 30.0, 88.0]),
+            ("本", 0x967B, [30.0, 78.0, 40.0, 88.0]),
+            // KSCms-UHC-H
+            ("한", 0xC7D1, [10.0, 58.0, 20.0, 68.0]),
+            ("국", 0xB1B9, [20.0, 58.0, 30.0, 68.0]),
+            // UniCNS-UCS2-V is vertical and based on UniCNS-UCS2-H through usecmap
+            ("中", 0x4E2D, [145.0, 89.2, 155.0, 99.2]),
+            ("文", 0x6587, [145.0, 79.2, 155.0, 89.2]),
+        ]);
+    }
+
+    let cmap = CMap::predefined("90ms-RKSJ-H").unwrap();
+    assert_eq!(cmap.split_codes(b"A\x93\xFA"), [(0x41, 1), (0x93FA, 2)]);
+    assert_eq!((cmap.get_cid(0x41), cmap.get_cid(0x93FA)), (Some(264), Some(3284)));
+    let cmap = CMap::predefined("UniJIS-UCS2-HW-V").unwrap();
+    assert!(cmap.is_vertical());
+    // Half-width Roman from the CMap itself, other codes from UniJIS-UCS2-H
+    assert_eq!((cmap.get_cid(0x5C), cmap.get_cid(0x65E5)), (Some(8719), Some(3284)));
+    let cmap = CMap::predefined("Adobe-Japan1-UCS2").unwrap();
+    assert_eq!(cmap.get_unicode(3284).as_deref(), Some("日"));
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use pdf_rs::error::Result;
+use pdf_rs::form::{FieldType, FieldValue};
+mod common;
+
+#[test]
+fn test_form_fields() -> Result<()> {
+    for document in common::open_all("document/forms.pdf")? {
+        let form = document.get_form()?.unwrap();
+        assert!(form.is_need_appearances());
+        assert_eq!(form.get_sig_flags(), 3);
+        assert_eq!(form.get_default_appearance(), Some("/Helv 0 Tf 0 g"));
+        let names = form.get_fields().iter().map(|field| field.get_name()).collect::<Vec<_>>();
+        // The field listing itself as a kid is skipped
+        assert_eq!(names, ["name", "address.city", "address.zip", "agree", "color", "submit", "country", "langs", "sig"]);
+
+        let name = form.get_field("name").unwrap();
+        assert_eq!(name.get_obj_ref(), Some((10, 0)));
+        assert_eq!(name.get_field_type(), FieldType::Text);
+        assert_eq!(name.get_alternate_name(), Some("Full name"));
+        assert!(name.is_required() && !name.is_read_only());
+        assert_eq!(name.get_value(), Some(&FieldValue::Text("Zoë".to_string())));
+        assert_eq!(name.get_default_value(), Some(&FieldValue::Text("none".to_string())));
+        assert_eq!(name.get_max_len(), Some(20));
+        // The field is merged with its widget
+        assert_eq!(name.get_widgets().len(), 1);
+        assert_eq!(name.get_widgets()[0].get_rect(), [10.0, 250.0, 110.0, 270.0]);
+        assert_eq!(name.get_widgets()[0].get_page(), Some(0));
+
+        // Kids inherit the field type from their parent
+        let zip = form.get_field("address.zip").unwrap();
+        assert_eq!(zip.get_partial_name(), Some("zip"));
+        assert_eq!(zip.get_field_type(), FieldType::Text);
+        assert!(zip.is_read_only());
+        assert_eq!(zip.get_value(), None);
+        assert_eq!(form.get_field("address.city").unwrap().get_value(), Some(&FieldValue::Text("Paris".to_string())));
+        assert!(form.get_field("address").is_none());
+
+        let agree = form.get_field("agree").unwrap();
+        assert_eq!(agree.get_field_type(), FieldType::CheckBox);
+        assert_eq!(agree.get_value(), Some(&FieldValue::Name("Yes".to_string())));
+        assert_eq!(agree.get_widgets()[0].get_appearance_state(), Some("Yes"));
+        assert_eq!(agree.get_widgets()[0].get_on_state(), Some("Yes"));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_button_and_choice_fields() -> Result<()> {
+    let document = common::open_all("document/forms.pdf")?.remove(0);
+    let form = document.get_form()?.unwrap();
+
+    // Radio button widgets are the kids of the field, found on their pages
+    let color = form.get_field("color").unwrap();
+    assert_eq!(color.get_field_type(), FieldType::RadioButton);
+    assert_eq!(color.get_flags(), 49152);
+    assert_eq!(color.get_value(), Some(&FieldValue::Name("Blue".to_string())));
+    assert_eq!(color.get_default_value(), Some(&FieldValue::Name("Red".to_string())));
+    let widgets = color.get_widgets();
+    assert_eq!(widgets.iter().map(|widget| widget.get_page()).collect::<Vec<_>>(), [Some(0), Some(1), Some(1)]);
+    assert_eq!(widgets.iter().map(|widget| widget.get_on_state().unwrap()).collect::<Vec<_>>(), ["Red", "Blue", "Green"]);
+    assert_eq!(widgets[1].get_obj_ref(), Some((17, 0)));
+    assert_eq!(form.get_field("submit").unwrap().get_field_type(), FieldType::PushButton);
+
+    let country = form.get_field("country").unwrap();
+    assert_eq!(country.get_field_type(), FieldType::ComboBox);
+    assert_eq!(country.get_options(), [("FR".to_string(), "France".to_string()), ("DE".to_string(), "Germany".to_string())]);
+    assert_eq!(country.get_value(), Some(&FieldValue::Text("FR".to_string())));
+    let langs = form.get_field("langs").unwrap();
+    assert_eq!(langs.get_field_type(), FieldType::ListBox);
+    assert_eq!(langs.get_options().len(), 3);
+    assert_eq!(langs.get_value(), Some(&FieldValue::Array(vec!["en".to_string(), "de".to_string()])));
+
+    let sig = form.get_field("sig").unwrap();
+    assert_eq!(sig.get_field_type(), FieldType::Signature);
+    let Some(FieldValue::Other(value)) = sig.get_value() else {
+        panic!("expected a signature dictionary");
+    };
+    assert_eq!(value.as_dict().unwrap().get_named_value("Filter").map(String::as_str), Some("Adobe.PPKLite"));
+
+    // Documents without a form
+    let document = common::open_all("document/links.pdf")?.remove(0);
+    assert!(document.get_form()?.is_none());
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use pdf_rs::document::PDFDocument;
+use pdf_rs::error::Result;
+use pdf_rs::function::Function;
+use pdf_rs::objects::PDFObject;
+mod common;
+
+/// Loads the function of object `num`.
+fn load(document: &PDFDocument, num: u32) -> Result<Function> {
+    Ok(Function::load(document, &PDFObject::ObjectRef(num, 0))?.expect("function"))
+}
+
+/// Evaluates a function, rounding the outputs to six decimals.
+fn eval(function: &Function, input: &[f64]) -> Vec<f64> {
+    function.eval(input).iter().map(|value| (value * 1e6).round() / 1e6).collect()
+}
+
+#[test]
+fn test_sampled_functions() -> Result<()> {
+    for document in common::open_all("document/functions.pdf")? {
+        let function = load(&document, 10)?;
+        assert_eq!((function.get_input_count(), function.get_output_count()), (1, 1));
+        assert_eq!(eval(&function, &[0.25]), [0.5]);
+        assert_eq!(eval(&function, &[0.5]), [1.0]);
+        // Inputs are clipped to the domain
+        assert_eq!(eval(&function, &[2.0]), [0.0]);
+        // Bilinear interpolation of 4-bit samples
+        let function = load(&document, 11)?;
+        assert_eq!(eval(&function, &[1.0, 0.0]), [1.0, 0.0, 0.0]);
+        assert_eq!(eval(&function, &[0.0, 1.0]), [0.0, 1.0, 0.0]);
+        assert_eq!(eval(&function, &[0.5, 0.5]), [0.25, 0.25, 0.25]);
+        assert_eq!(eval(&function, &[1.0, 0.5]), [0.5, 0.0, 0.5]);
+        // 12-bit samples with the encoding reversed and decoded to [10, 20]
+        let function = load(&document, 12)?;
+        assert_eq!(eval(&function, &[0.0]), [20.0]);
+        assert_eq!(eval(&function, &[0.75]), [12.5]);
+        // Tables overflowing or larger than their data and too many inputs aren't loaded
+        for num in [21, 22, 23] {
+            assert!(Function::load(&document, &PDFObject::ObjectRef(num, 0))?.is_none());
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_exponential_and_stitching_functions() -> Result<()> {
+    let document = common::open_all("document/functions.pdf")?.remove(0);
+    let function = load(&document, 13)?;
+    assert_eq!((function.get_input_count(), function.get_output_count()), (1, 2));
+    assert_eq!(eval(&function, &[0.5]), [0.25, 0.5]);
+    let function = load(&document, 14)?;
+    assert_eq!(eval(&function, &[0.25]), [0.5]);
+    // Bounds belong to the next subdomain
+    assert_eq!(eval(&function, &[0.5]), [1.0]);
+    assert_eq!(eval(&function, &[0.75]), [0.5]);
+    assert_eq!(eval(&function, &[1.0]), [0.0]);
+    Ok(())
+}
+
+#[test]
+fn test_calculator_functions() -> Result<()> {
+    let document = common::open_all("document/functions.pdf")?.remove(0);
+    // The minimum of two values
+    let function = load(&document, 16)?;
+    assert_eq!(eval(&function, &[0.3, 0.7]), [0.3]);
+    assert_eq!(eval(&function, &[0.9, 0.2]), [0.2]);
+    // Trigonometry in degrees and ifelse
+    let function = load(&document, 17)?;
+    assert_eq!(eval(&function, &[0.25]), [1.0, 1.0]);
+    assert_eq!(eval(&function, &[0.75]), [0.0, 0.0]);
+    // Integer and stack operators
+    let function = load(&document, 18)?;
+    assert_eq!(eval(&function, &[0.5]), [4.0, 1.0, 16.0]);
+    // Programs failing at runtime output 0, programs with unknown operators aren't loaded
+    assert_eq!(eval(&load(&document, 19)?, &[0.5]), [0.0]);
+    assert!(Function::load(&document, &PDFObject::ObjectRef(20, 0))?.is_none());
+    Ok(())
+}
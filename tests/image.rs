@@ -0,0 +1,133 @@
+use flate2::read::ZlibDecoder;
+use pdf_rs::error::{PDFError, Result};
+use pdf_rs::graphics::Matrix;
+use pdf_rs::image::{Bitmap, ImageFormat};
+use std::io::Read;
+mod common;
+
+/// Returns the pixels of a bitmap, one RGBA array each.
+fn pixels(bitmap: &Bitmap) -> Vec<[u8; 4]> {
+    bitmap.get_pixels().chunks_exact(4).map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]]).collect()
+}
+
+#[test]
+fn test_page_images() -> Result<()> {
+    for document in common::open_all("document/images.pdf")? {
+        let images = document.get_page(0).unwrap().get_images()?;
+        let attributes = images.iter()
+            .map(|image| (
+                image.get_name(),
+                image.get_width(),
+                image.get_height(),
+                image.get_bits_per_component(),
+                image.get_color_space_family(),
+                image.get_filters(),
+                image.is_image_mask(),
+            ))
+            .collect::<Vec<_>>();
+        assert_eq!(attributes, [
+            (Some("Im1"), 2, 2, 8, Some("DeviceRGB"), vec!["FlateDecode"], false),
+            (Some("Im2"), 1, 1, 8, Some("DeviceRGB"), vec!["DCTDecode"], false),
+            (Some("Im3"), 1, 1, 8, None, vec!["JPXDecode"], false),
+            (Some("Im4"), 4, 1, 2, Some("Indexed"), vec![], false),
+            (Some("Im5"), 8, 1, 1, Some("DeviceGray"), vec![], false),
+            (Some("Im6"), 3, 1, 1, None, vec![], true),
+            (Some("Im7"), 1, 1, 16, Some("DeviceCMYK"), vec![], false),
+            (Some("Im8"), 2, 1, 8, Some("DeviceRGB"), vec![], false),
+            // The inline image of the form names a colour space of the form resources
+            (None, 2, 1, 8, Some("ICCBased"), vec!["ASCIIHexDecode"], false),
+            (Some("Im9"), 2, 1, 12, Some("DeviceGray"), vec![], false),
+        ]);
+        assert_eq!(images[0].get_obj_ref(), Some((10, 0)));
+        assert_eq!(images[0].get_matrix(), &Matrix::new(20.0, 0.0, 0.0, 10.0, 100.0, 200.0));
+        assert_eq!(images[0].get_bbox(), [100.0, 200.0, 120.0, 210.0]);
+        // The inline image is placed by its own matrix and the form matrix
+        assert!(images[8].is_inline());
+        assert_eq!(images[8].get_matrix(), &Matrix::new(5.0, 0.0, 0.0, 5.0, 50.0, 50.0));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_decode_images() -> Result<()> {
+    let document = common::open_all("document/images.pdf")?.remove(0);
+    let images = document.get_page(0).unwrap().get_images()?;
+    // The soft mask provides the alpha channel
+    let bitmap = images[0].decode()?;
+    assert_eq!((bitmap.get_width(), bitmap.get_height()), (2, 2));
+    assert_eq!(pixels(&bitmap), [[255, 0, 0, 255], [0, 255, 0, 128], [0, 0, 255, 0], [255, 255, 255, 255]]);
+    // Truncated JPEG data can't be decoded
+    assert!(images[1].decode().is_err());
+    // Indexed colours with a colour key mask hiding index 1, index 3 is clamped to hival
+    assert_eq!(pixels(&images[3].decode()?), [[255, 0, 0, 255], [0, 255, 0, 0], [0, 0, 255, 255], [0, 0, 255, 255]]);
+    // The Decode array inverts the samples
+    let gray = pixels(&images[4].decode()?).iter().map(|pixel| pixel[0]).collect::<Vec<u8>>();
+    assert_eq!(gray, [0, 0, 0, 0, 255, 255, 255, 255]);
+    // Image masks are black where painted
+    assert_eq!(pixels(&images[5].decode()?), [[0, 0, 0, 255], [0, 0, 0, 0], [0, 0, 0, 255]]);
+    assert_eq!(pixels(&images[6].decode()?), [[255, 0, 0, 255]]);
+    // The stencil mask hides the second sample
+    assert_eq!(pixels(&images[7].decode()?), [[0, 0, 255, 255], [0, 0, 255, 0]]);
+    assert_eq!(pixels(&images[8].decode()?), [[64, 64, 64, 255], [192, 192, 192, 255]]);
+    // Only 1, 2, 4, 8 and 16 bit samples can be unpacked
+    assert!(matches!(images[9].decode(), Err(PDFError::ImageDecodeError(_))));
+    assert!(images[9].export(ImageFormat::Png).is_err());
+    Ok(())
+}
+
+#[cfg(feature = "jpeg")]
+#[test]
+fn test_decode_jpeg() -> Result<()> {
+    for document in common::open_all("document/jpeg.pdf")? {
+        let images = document.get_page(0).unwrap().get_images()?;
+        let bitmap = images[0].decode()?;
+        assert_eq!((bitmap.get_width(), bitmap.get_height()), (16, 16));
+        let corners = [(0, 0), (15, 0), (0, 15), (15, 15)].map(|(x, y)| bitmap.get_pixel(x, y));
+        assert_eq!(corners, [[255, 0, 0, 255], [0, 0, 255, 255], [0, 255, 0, 255], [255, 255, 255, 255]]);
+        // The colour space of a gray JPEG comes from its components
+        let gray = images[1].decode()?;
+        assert_eq!([gray.get_pixel(0, 0), gray.get_pixel(15, 7)], [[0, 0, 0, 255], [128, 128, 128, 255]]);
+        assert!(images[2].decode().is_err());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_export_images() -> Result<()> {
+    let document = common::open_all("document/images.pdf")?.remove(0);
+    let images = document.get_page(0).unwrap().get_images()?;
+    // JPEG and JPEG 2000 data are exported as is whatever the requested format
+    let jpeg = images[1].export(ImageFormat::Png)?;
+    assert_eq!(jpeg.get_format().get_extension(), "jpg");
+    assert!(jpeg.get_data().starts_with(&[0xff, 0xd8]) && jpeg.get_data().ends_with(&[0xff, 0xd9]));
+    let jp2 = images[2].export(ImageFormat::Ppm)?;
+    assert_eq!(jp2.get_format(), ImageFormat::Jpeg2000);
+    assert_eq!(&jp2.get_data()[4..8], b"jP  ");
+
+    // PNG files of masked images have an alpha channel
+    let png = images[0].export(ImageFormat::Png)?;
+    assert_eq!(png.get_format(), ImageFormat::Png);
+    let data = png.get_data();
+    assert_eq!(&data[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&data[12..16], b"IHDR");
+    assert_eq!(&data[16..29], &[0, 0, 0, 2, 0, 0, 0, 2, 8, 6, 0, 0, 0]);
+    let idat_len = u32::from_be_bytes(data[33..37].try_into().unwrap()) as usize;
+    assert_eq!(&data[37..41], b"IDAT");
+    let mut scanlines = Vec::new();
+    ZlibDecoder::new(&data[41..41 + idat_len]).read_to_end(&mut scanlines)?;
+    assert_eq!(scanlines, [0, 255, 0, 0, 255, 0, 255, 0, 128, 0, 0, 0, 255, 0, 255, 255, 255, 255]);
+    assert!(data.ends_with(b"IEND\xae\x42\x60\x82"));
+    // Opaque images are RGB
+    let png = images[6].export(ImageFormat::Png)?;
+    assert_eq!(png.get_data()[25], 2);
+
+    // PPM files are composited onto white
+    let ppm = images[7].export(ImageFormat::Ppm)?;
+    assert_eq!(ppm.get_data(), b"P6\n2 1\n255\n\x00\x00\xff\xff\xff\xff");
+    let path = std::env::temp_dir().join(format!("pdf-rs-image.{}", ppm.get_format().get_extension()));
+    ppm.save(&path)?;
+    assert_eq!(std::fs::read(&path)?, ppm.get_data());
+    std::fs::remove_file(path)?;
+    assert!(images[0].export(ImageFormat::Jpeg).is_err());
+    Ok(())
+}
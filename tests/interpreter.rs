@@ -0,0 +1,107 @@
+use pdf_rs::error::Result;
+use pdf_rs::graphics::{FillRule, GraphicsState, Matrix, Path};
+use pdf_rs::interpreter::{ContentInterpreter, ContentVisitor, Glyph, Image};
+use pdf_rs::objects::Dictionary;
+mod common;
+
+type ImageRecord = (Option<String>, Option<(u32, u16)>, Matrix);
+
+/// Records what the interpreter reports, rounded to keep comparisons exact.
+#[derive(Default)]
+struct Recorder {
+    fills: Vec<([f64; 4], FillRule, Vec<f64>, usize)>,
+    strokes: Vec<([f64; 4], f64, f64, Vec<f64>)>,
+    glyphs: Vec<(u32, (f64, f64), usize)>,
+    images: Vec<ImageRecord>,
+    marked: Vec<(String, Option<i64>)>,
+    depth: i32,
+}
+
+impl ContentVisitor for Recorder {
+    fn fill_path(&mut self, path: &Path, rule: FillRule, state: &GraphicsState) -> Result<()> {
+        let components = state.get_fill_color().get_components().to_vec();
+        self.fills.push((path.get_bounds().unwrap(), rule, components, state.get_clip().len()));
+        Ok(())
+    }
+
+    fn stroke_path(&mut self, path: &Path, state: &GraphicsState) -> Result<()> {
+        let components = state.get_stroke_color().get_components().to_vec();
+        self.strokes.push((path.get_bounds().unwrap(), state.get_line_width(), state.get_stroke_alpha(), components));
+        Ok(())
+    }
+
+    fn show_text(&mut self, glyphs: &[Glyph], state: &GraphicsState) -> Result<()> {
+        for glyph in glyphs {
+            let (x, y) = glyph.get_origin();
+            self.glyphs.push((glyph.get_code(), ((x * 1000.0).round() / 1000.0, (y * 1000.0).round() / 1000.0), state.get_clip().len()));
+        }
+        Ok(())
+    }
+
+    fn draw_image(&mut self, image: &Image, state: &GraphicsState) -> Result<()> {
+        self.images.push((image.get_name().map(|name| name.to_string()), image.get_obj_ref(), *state.get_ctm()));
+        Ok(())
+    }
+
+    fn begin_marked_content(&mut self, tag: &str, properties: Option<&Dictionary>) -> Result<()> {
+        let mcid = properties.and_then(|properties| properties.get("MCID")).and_then(|mcid| mcid.as_i64());
+        self.marked.push((tag.to_string(), mcid));
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn end_marked_content(&mut self) -> Result<()> {
+        self.depth -= 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_interpret_page() -> Result<()> {
+    for document in common::open_all("document/interpreter.pdf")? {
+        let page = document.get_page(0).unwrap();
+        let mut recorder = Recorder::default();
+        let mut interpreter = ContentInterpreter::new(&document, Matrix::identity());
+        interpreter.run_page(&page, &mut recorder)?;
+
+        // The rectangle is transformed by the CTM, the form's path by the form matrix too
+        // and clipped to the form's bounding box. The form painting itself is skipped.
+        assert_eq!(recorder.fills, [
+            ([10.0, 20.0, 30.0, 30.0], FillRule::NonZero, vec![0.0], 0),
+            ([50.0, 50.0, 70.0, 70.0], FillRule::NonZero, vec![0.0, 0.0, 1.0], 1),
+        ]);
+        // Line width and alpha set by the ExtGState
+        assert_eq!(recorder.strokes, [([0.0, 0.0, 50.0, 0.0], 3.0, 0.5, vec![1.0, 0.0, 0.0])]);
+        // Widths 500 and 600 at 10pt with a character spacing of 1, and a TJ adjustment of -500
+        assert_eq!(recorder.glyphs, [
+            (65, (5.0, 5.0), 1),
+            (66, (11.0, 5.0), 1),
+            (65, (18.0, 5.0), 1),
+            (66, (29.0, 5.0), 1),
+        ]);
+        assert_eq!(recorder.images.len(), 2);
+        assert_eq!(recorder.images[0], (Some("Im1".to_string()), Some((7, 0)), Matrix::new(20.0, 0.0, 0.0, 10.0, 0.0, 0.0)));
+        assert_eq!(recorder.images[1].0, None);
+        assert_eq!(recorder.marked, [("Span".to_string(), Some(3)), ("Tag".to_string(), Some(7))]);
+        assert_eq!(recorder.depth, 0);
+
+        // Saved states are restored, the ExtGState was applied outside of q/Q
+        let state = interpreter.get_state();
+        assert_eq!(*state.get_ctm(), Matrix::identity());
+        assert!(state.get_clip().is_empty());
+        assert_eq!(state.get_stroke_alpha(), 0.5);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_interpret_reference() -> Result<()> {
+    let document = common::open_all("document/pdfreference1.0.pdf")?.remove(0);
+    let mut recorder = Recorder::default();
+    for page in document.get_pages() {
+        page.interpret(&mut recorder)?;
+    }
+    assert!(!recorder.glyphs.is_empty());
+    assert_eq!(recorder.depth, 0);
+    Ok(())
+}
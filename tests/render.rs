@@ -0,0 +1,91 @@
+use pdf_rs::error::Result;
+mod common;
+
+const WHITE: [u8; 4] = [255, 255, 255, 255];
+const BLACK: [u8; 4] = [0, 0, 0, 255];
+const RED: [u8; 4] = [255, 0, 0, 255];
+const GREEN: [u8; 4] = [0, 255, 0, 255];
+const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+#[test]
+fn test_render_graphics() -> Result<()> {
+    for document in common::open_all("document/render.pdf")? {
+        let bitmap = document.get_page(0).unwrap().render(72.0)?;
+        assert_eq!((bitmap.get_width(), bitmap.get_height()), (100, 100));
+        let pixels = [
+            // Filled square, with y pointing down in the bitmap
+            (25, 75), (5, 75),
+            // Solid and dashed lines, and a dash pattern too dense to draw
+            (70, 20), (52, 30), (56, 30), (45, 5),
+            // Even-odd ring
+            (52, 60), (60, 60),
+            // Fill clipped to a smaller rectangle
+            (80, 85), (72, 85),
+            // RGB image, its first row at the top
+            (15, 35), (25, 35), (15, 45), (25, 45),
+            // Stencil mask painted with the fill colour
+            (82, 37), (87, 37), (87, 42),
+            // Half-transparent black
+            (85, 60),
+        ].map(|(x, y)| bitmap.get_pixel(x, y));
+        assert_eq!(pixels, [
+            RED, WHITE,
+            BLUE, BLUE, WHITE, BLUE,
+            BLACK, WHITE,
+            GREEN, WHITE,
+            RED, GREEN, BLUE, WHITE,
+            [255, 255, 0, 255], WHITE, [255, 255, 0, 255],
+            [128, 128, 128, 255],
+        ]);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_render_text() -> Result<()> {
+    let document = common::open_all("document/render.pdf")?.remove(0);
+    let bitmap = document.get_page(1).unwrap().render(72.0)?;
+    // The stems of the embedded TrueType glyph and of the built-in glyph replacing Helvetica
+    assert_eq!([(40, 50), (123, 50), (100, 50), (40, 95)].map(|(x, y)| bitmap.get_pixel(x, y)), [BLACK, BLACK, WHITE, WHITE]);
+    // Glyph edges are anti-aliased
+    assert!(bitmap.get_pixels().chunks_exact(4).any(|pixel| pixel[0] > 0 && pixel[0] < 255));
+    Ok(())
+}
+
+#[test]
+fn test_render_size() -> Result<()> {
+    let document = common::open_all("document/render.pdf")?.remove(0);
+    // The page is rotated clockwise, its left half showing at the top
+    let bitmap = document.get_page(2).unwrap().render(144.0)?;
+    assert_eq!((bitmap.get_width(), bitmap.get_height()), (100, 200));
+    assert_eq!([bitmap.get_pixel(50, 20), bitmap.get_pixel(50, 150)], [RED, WHITE]);
+    let png = bitmap.to_png()?;
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!((&png[16..20], &png[20..24]), (&100u32.to_be_bytes()[..], &200u32.to_be_bytes()[..]));
+    let page = document.get_page(0).unwrap();
+    assert!(page.render(0.0).is_err());
+    assert!(page.render(f64::NAN).is_err());
+    assert!(page.render(1e6).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_render_skipped_images() -> Result<()> {
+    let document = common::open_all("document/jpeg.pdf")?.remove(0);
+    let (bitmap, skipped) = document.get_page(0).unwrap().render_with_skipped(72.0)?;
+    // The JPEG 2000 image and the image too large for its data are drawn as gray boxes and reported
+    assert_eq!(bitmap.get_pixel(25, 75), [192, 192, 192, 255]);
+    assert_eq!(bitmap.get_pixel(75, 75), [192, 192, 192, 255]);
+    let skipped = skipped.iter().map(|image| (image.get_name().unwrap(), image.get_obj_ref())).collect::<Vec<_>>();
+    #[cfg(feature = "jpeg")]
+    {
+        assert_eq!(skipped, [("Im3", Some((12, 0))), ("Im4", Some((13, 0)))]);
+        // The quadrants of the JPEG image, its first row at the top
+        let pixels = [(20, 20), (40, 20), (20, 40), (40, 40)].map(|(x, y)| bitmap.get_pixel(x, y));
+        assert_eq!(pixels, [RED, BLUE, GREEN, WHITE]);
+        assert_eq!([bitmap.get_pixel(60, 35), bitmap.get_pixel(80, 35)], [BLACK, [128, 128, 128, 255]]);
+    }
+    #[cfg(not(feature = "jpeg"))]
+    assert_eq!(skipped, [("Im1", Some((10, 0))), ("Im2", Some((11, 0))), ("Im3", Some((12, 0))), ("Im4", Some((13, 0)))]);
+    Ok(())
+}
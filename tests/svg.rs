@@ -0,0 +1,49 @@
+use pdf_rs::error::Result;
+mod common;
+
+#[test]
+fn test_svg_graphics() -> Result<()> {
+    for document in common::open_all("document/render.pdf")? {
+        let svg = document.get_page(0).unwrap().to_svg()?;
+        assert!(svg.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<svg "));
+        assert!(svg.contains(" width=\"100\" height=\"100\" viewBox=\"0 0 100 100\">"));
+        assert!(svg.ends_with("</svg>\n"));
+        // Fills are written in device space, with y pointing down
+        assert!(svg.contains("<path d=\"M10 90 L40 90 L40 60 L10 60 Z\" fill=\"#ff0000\"/>"));
+        assert!(svg.contains("<path d=\"M50 70 L70 70 L70 50 L50 50 Z M55 65 L65 65 L65 55 L55 55 Z\" fill=\"#000000\" fill-rule=\"evenodd\"/>"));
+        assert!(svg.contains("<path d=\"M80 65 L90 65 L90 55 L80 55 Z\" fill=\"#000000\" fill-opacity=\"0.5\"/>"));
+        // Strokes are written in user space, keeping the line width and dashes
+        assert!(svg.contains(
+            "<path d=\"M50 70 L90 70\" transform=\"matrix(1 0 0 -1 0 100)\" fill=\"none\" stroke=\"#0000ff\" \
+             stroke-width=\"4\" stroke-miterlimit=\"10\" stroke-dasharray=\"4 4\"/>"
+        ));
+        // The clipped fill is grouped under its clipping path
+        assert!(svg.contains(
+            "<defs><clipPath id=\"clip1\"><path d=\"M75 90 L85 90 L85 80 L75 80 Z\" clip-rule=\"nonzero\"/></clipPath></defs>\n\
+             <g clip-path=\"url(#clip1)\">\n<path d=\"M70 100 L100 100 L100 50 L70 50 Z\" fill=\"#00ff00\"/>\n</g>\n"
+        ));
+        // Images are defined once as PNG data URIs and flipped to the unit square
+        assert_eq!(svg.matches("xlink:href=\"data:image/png;base64,iVBORw0KGgo").count(), 2);
+        assert!(svg.contains("<use xlink:href=\"#image2\" transform=\"matrix(20 0 0 20 10 30)\"/>"));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_svg_text() -> Result<()> {
+    let document = common::open_all("document/render.pdf")?.remove(0);
+    let svg = document.get_page(3).unwrap().to_svg()?;
+    // Runs of a line are merged, with a space between words positioned apart
+    assert!(svg.contains(
+        "<text transform=\"matrix(1 0 0 1 10 50)\" x=\"0 14.44 25.56 29.8 34.24 45.36 55.36 74.24 85.36 92.02 96.46\" y=\"0\" \
+         font-family=\"'Helvetica', sans-serif\" font-size=\"20\" fill=\"#000000\" xml:space=\"preserve\">Hello World</text>"
+    ));
+    // Glyphs without Unicode text are drawn from their outlines, here a TrueType I
+    assert!(svg.contains("<path d=\"M11.9629 65.4199 L20.0586 65.4199 L20.0586 67.0801 "));
+    assert_eq!(svg.matches("<text ").count(), 1);
+    assert_eq!(svg.matches("clip-path=\"url(#clip1)\"").count(), 1);
+    // Rotated pages are written upright
+    let svg = document.get_page(2).unwrap().to_svg()?;
+    assert!(svg.contains(" width=\"50\" height=\"100\" viewBox=\"0 0 50 100\">\n<path d=\"M0 0 L0 50 L50 50 L50 0 Z\" fill=\"#ff0000\"/>"));
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use pdf_rs::error::Result;
+mod common;
+
+#[test]
+fn test_extract_text() -> Result<()> {
+    for document in common::open_all("document/text.pdf")? {
+        let page = document.get_page(0).unwrap();
+        // The right column is shown first but read after the left one, including the
+        // paragraph below it. A line shown in two pieces is joined, and TJ adjustments
+        // wider than a space become spaces.
+        assert_eq!(page.extract_text()?, "Title Line\n\nLeft one\nLeft two\nCafé au lait\n\nLeft four\n\nRight one\nRight two\n\nHifi");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_extract_reference_text() -> Result<()> {
+    let document = common::open_all("document/pdfreference1.0.pdf")?.remove(0);
+    let text = document.get_page(0).unwrap().extract_text()?;
+    assert!(text.starts_with("PDF"));
+    assert!(text.contains("Addison-Wesley Publishing Company"));
+    Ok(())
+}
+
+#[test]
+fn test_extract_positions() -> Result<()> {
+    for document in common::open_all("document/text.pdf")? {
+        // The content is rotated to be upright on a page displayed rotated by 90 degrees
+        let page = document.get_page(1).unwrap();
+        assert_eq!(page.get_page_matrix()?.transform(0.0, 0.0), (0.0, 200.0));
+        let glyphs = page.extract_glyphs()?;
+        let texts = glyphs.iter().map(|glyph| glyph.get_text()).collect::<Vec<&str>>();
+        assert_eq!(texts, ["A", "B", " ", "C"]);
+        let a = &glyphs[0];
+        assert_eq!(a.get_code(), 65);
+        assert_eq!(a.get_font_name(), Some("ABCDEF+Times"));
+        assert_eq!(a.get_font_size(), 20.0);
+        assert_eq!(a.get_fill_color().get_components(), [1.0, 0.0, 0.0]);
+        // Tz 50 halves the advance, Ts 3 raises the baseline, ascent and descent come from the descriptor
+        assert_eq!(a.get_bbox(), [10.0, 110.0, 16.0, 130.0]);
+        assert_eq!(a.get_origin(), (10.0, 116.0));
+        // Tc 2 is added to every advance, scaled by Tz and the CTM
+        assert_eq!(glyphs[1].get_bbox(), [18.0, 110.0, 22.0, 130.0]);
+        assert_eq!(glyphs[3].get_bbox(), [28.5, 110.0, 33.5, 130.0]);
+
+        let lines = page.extract_lines()?;
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].get_text(), "AB C");
+        assert_eq!(lines[0].get_bbox(), [10.0, 110.0, 33.5, 130.0]);
+        let words = page.extract_words()?;
+        let words = words.iter().map(|word| (word.get_text(), word.get_bbox())).collect::<Vec<(&str, [f64; 4])>>();
+        assert_eq!(words, [("AB", [10.0, 110.0, 22.0, 130.0]), ("C", [28.5, 110.0, 33.5, 130.0])]);
+        assert_eq!(page.extract_text()?, "AB C");
+
+        // Lines of the first page are split into words at spaces and kerning gaps
+        let lines = document.get_page(0).unwrap().extract_lines()?;
+        let words = lines[3].get_words().iter().map(|word| word.get_text()).collect::<Vec<&str>>();
+        assert_eq!(words, ["Café", "au", "lait"]);
+    }
+    Ok(())
+}
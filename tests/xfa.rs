@@ -0,0 +1,44 @@
+use pdf_rs::error::Result;
+mod common;
+
+#[test]
+fn test_dynamic_xfa() -> Result<()> {
+    for document in common::open_all("document/xfa.pdf")? {
+        let xfa = document.get_xfa()?.unwrap();
+        assert!(xfa.is_dynamic());
+        let names = xfa.get_packets().iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        assert_eq!(names, ["preamble", "config", "template", "datasets", "postamble"]);
+        // The packets are decoded and concatenated into the XDP document
+        let xml = String::from_utf8_lossy(xfa.get_xml()).into_owned();
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<xdp:xdp"));
+        assert!(xml.ends_with("</xdp:xdp>\n"));
+        assert!(String::from_utf8_lossy(xfa.get_packet("template").unwrap()).starts_with("<template "));
+        let datasets = xfa.get_datasets().unwrap();
+        assert!(datasets.starts_with("<xfa:datasets "));
+        assert!(datasets.contains("<name>Zoë</name><amount>12.50</amount>"));
+        assert!(xfa.get_packet("localeSet").is_none());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_static_xfa() -> Result<()> {
+    for document in common::open_all("document/xfa-static.pdf")? {
+        let xfa = document.get_xfa()?.unwrap();
+        assert!(!xfa.is_dynamic());
+        // A single stream is split at the children of the root element,
+        // skipping comments and CDATA sections
+        let names = xfa.get_packets().iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        assert_eq!(names, ["config", "template", "datasets"]);
+        let template = String::from_utf8_lossy(xfa.get_packet("template").unwrap()).into_owned();
+        assert!(template.starts_with("<template ") && template.ends_with("</subform></template>"));
+        let datasets = xfa.get_datasets().unwrap();
+        assert!(datasets.starts_with("<xfa:datasets ") && datasets.ends_with("</xfa:data></xfa:datasets>"));
+    }
+
+    // Forms without XFA
+    let document = common::open_all("document/forms.pdf")?.remove(0);
+    assert!(document.get_xfa()?.is_none());
+    assert!(common::open_all("document/links.pdf")?.remove(0).get_xfa()?.is_none());
+    Ok(())
+}